@@ -1,68 +1,442 @@
 use crate::{
-    api::{epoch, validate, Api},
-    scheduler, storage,
+    api::{tokens, validate, Api},
+    storage,
 };
-use futures::stream::StreamExt;
-use gofer_models::{common_task, event};
+use anyhow::{anyhow, Result};
+use gofer_models::{common_task, event, token::Token};
 use gofer_proto::{
     DisableCommonTaskRequest, DisableCommonTaskResponse, EnableCommonTaskRequest,
-    EnableCommonTaskResponse, GetCommonTaskInstallInstructionsRequest,
-    GetCommonTaskInstallInstructionsResponse, GetCommonTaskRequest, GetCommonTaskResponse,
+    EnableCommonTaskResponse, GetCommonTaskRequest, GetCommonTaskResponse,
     InstallCommonTaskRequest, InstallCommonTaskResponse, ListCommonTasksRequest,
     ListCommonTasksResponse, UninstallCommonTaskRequest, UninstallCommonTaskResponse,
 };
-use nanoid::nanoid;
-use slog_scope::info;
-use std::collections::HashMap;
+use slog_scope::{error, info};
 use tonic::{Response, Status};
 
+impl Api {
+    /// Loads all common task registrations from storage into the in-memory common task map. Unlike triggers
+    /// common tasks have no long running component, so there is nothing to start; they're simply launched as
+    /// task runs when a pipeline references them.
+    pub async fn load_common_tasks(&self) -> Result<()> {
+        let mut conn = match self.storage.conn().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("could not load common tasks"; "error" => format!("{:?}", e));
+                return Err(anyhow!(
+                    "could not load common tasks; connection error; {:?}",
+                    e
+                ));
+            }
+        };
+
+        let registrations = storage::common_task_registrations::list(&mut conn, 0, 0).await?;
+
+        for registration in registrations {
+            info!("Registered common task"; "name" => &registration.name, "image" => &registration.image);
+            self.common_tasks
+                .insert(registration.name.clone(), registration.into());
+        }
+
+        Ok(())
+    }
+
+    /// Updates the status of a common task both in-memory and on disk.
+    async fn set_common_task_status(
+        &self,
+        name: &str,
+        status: common_task::Status,
+    ) -> Result<(), Status> {
+        if !self.common_tasks.contains_key(name) {
+            return Err(Status::failed_precondition("common task does not exist"));
+        }
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        if let Err(e) = storage::common_task_registrations::update(
+            &mut conn,
+            name,
+            storage::common_task_registrations::UpdatableFields {
+                status: Some(status.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            return Err(Status::internal(format!(
+                "could not update common task registration status; {:?}",
+                e
+            )));
+        };
+
+        self.common_tasks.alter(name, |_, mut value| {
+            value.status = status;
+            value
+        });
+
+        Ok(())
+    }
+}
+
 impl Api {
     pub async fn install_common_task_handler(
         &self,
+        token: &Token,
         args: InstallCommonTaskRequest,
     ) -> Result<Response<InstallCommonTaskResponse>, Status> {
-        todo!()
-    }
+        tokens::require_management_token(token)?;
 
-    pub async fn get_common_task_install_instructions_handler(
-        &self,
-        args: GetCommonTaskInstallInstructionsRequest,
-    ) -> Result<Response<GetCommonTaskInstallInstructionsResponse>, Status> {
-        todo!()
+        validate::arg(
+            "name",
+            args.name.clone(),
+            vec![validate::is_valid_identifier, validate::not_empty_str],
+        )?;
+        validate::arg("image", args.image.clone(), vec![validate::not_empty_str])?;
+
+        // Check to see if this common task has been registered already.
+        if self.common_tasks.contains_key(&args.name) {
+            return Err(Status::already_exists(format!(
+                "common task '{}' already exists",
+                &args.name
+            )));
+        }
+
+        let registration: common_task::Registration = args.clone().into();
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        storage::common_task_registrations::insert(&mut conn, &registration)
+            .await
+            .map_err(|e| match e {
+                storage::StorageError::Exists => Status::already_exists(format!(
+                    "common task with name '{}' already exists",
+                    &args.name
+                )),
+                _ => Status::internal(e.to_string()),
+            })?;
+
+        self.common_tasks
+            .insert(args.name.clone(), registration.into());
+
+        self.event_bus
+            .publish(event::Kind::InstalledCommonTask {
+                name: args.name.clone(),
+                image: args.image.clone(),
+            })
+            .await;
+
+        info!("installed common task"; "name" => &args.name, "image" => &args.image);
+
+        Ok(Response::new(InstallCommonTaskResponse {}))
     }
 
     pub async fn get_common_task_handler(
         &self,
         args: GetCommonTaskRequest,
     ) -> Result<Response<GetCommonTaskResponse>, Status> {
-        todo!()
+        validate::arg(
+            "name",
+            args.name.clone(),
+            vec![validate::is_valid_identifier, validate::not_empty_str],
+        )?;
+
+        match self.common_tasks.get(&args.name) {
+            Some(entry) => {
+                let common_task = entry.value();
+                Ok(Response::new(GetCommonTaskResponse {
+                    common_task: Some(common_task.clone().into()),
+                }))
+            }
+            None => Err(Status::failed_precondition("common task does not exist")),
+        }
     }
 
     pub async fn list_common_tasks_handler(
         &self,
         _: ListCommonTasksRequest,
     ) -> Result<Response<ListCommonTasksResponse>, Status> {
-        todo!()
+        let common_tasks: Vec<gofer_proto::CommonTask> = self
+            .common_tasks
+            .iter()
+            .map(|common_task| common_task.value().clone().into())
+            .collect();
+
+        Ok(Response::new(ListCommonTasksResponse { common_tasks }))
     }
 
     pub async fn uninstall_common_task_handler(
         &self,
+        token: &Token,
         args: UninstallCommonTaskRequest,
     ) -> Result<Response<UninstallCommonTaskResponse>, Status> {
-        todo!()
+        tokens::require_management_token(token)?;
+
+        validate::arg(
+            "name",
+            args.name.clone(),
+            vec![validate::is_valid_identifier, validate::not_empty_str],
+        )?;
+
+        let common_task = match self.common_tasks.get(&args.name) {
+            Some(common_task) => common_task.value().clone(),
+            None => return Err(Status::failed_precondition("common task does not exist")),
+        };
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        if let Err(e) = storage::common_task_registrations::delete(&mut conn, &args.name).await {
+            return Err(Status::internal(format!(
+                "could not remove common task registration {:?}",
+                e
+            )));
+        };
+
+        self.common_tasks.remove(&args.name);
+
+        self.event_bus
+            .publish(event::Kind::UninstalledCommonTask {
+                name: common_task.name,
+                image: common_task.image,
+            })
+            .await;
+
+        Ok(Response::new(UninstallCommonTaskResponse {}))
     }
 
     pub async fn enable_common_task_handler(
         &self,
+        token: &Token,
         args: EnableCommonTaskRequest,
     ) -> Result<Response<EnableCommonTaskResponse>, Status> {
-        todo!()
+        tokens::require_management_token(token)?;
+
+        validate::arg(
+            "name",
+            args.name.clone(),
+            vec![validate::is_valid_identifier, validate::not_empty_str],
+        )?;
+
+        self.set_common_task_status(&args.name, common_task::Status::Enabled)
+            .await?;
+
+        if let Some(common_task) = self.common_tasks.get(&args.name) {
+            self.event_bus
+                .publish(event::Kind::EnabledCommonTask {
+                    name: common_task.name.clone(),
+                    image: common_task.image.clone(),
+                })
+                .await;
+        }
+
+        Ok(Response::new(EnableCommonTaskResponse {}))
     }
 
     pub async fn disable_common_task_handler(
         &self,
+        token: &Token,
         args: DisableCommonTaskRequest,
     ) -> Result<Response<DisableCommonTaskResponse>, Status> {
-        todo!()
+        tokens::require_management_token(token)?;
+
+        validate::arg(
+            "name",
+            args.name.clone(),
+            vec![validate::is_valid_identifier, validate::not_empty_str],
+        )?;
+
+        self.set_common_task_status(&args.name, common_task::Status::Disabled)
+            .await?;
+
+        if let Some(common_task) = self.common_tasks.get(&args.name) {
+            self.event_bus
+                .publish(event::Kind::DisabledCommonTask {
+                    name: common_task.name.clone(),
+                    image: common_task.image.clone(),
+                })
+                .await;
+        }
+
+        Ok(Response::new(DisableCommonTaskResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::TestHarness;
+    use std::collections::HashMap;
+
+    fn install_request(name: &str) -> InstallCommonTaskRequest {
+        InstallCommonTaskRequest {
+            name: name.to_string(),
+            image: "test_image".to_string(),
+            user: "registry_user".to_string(),
+            pass: "registry_pass".to_string(),
+            variables: HashMap::from([("key".to_string(), "value".to_string())]),
+        }
+    }
+
+    async fn stored_status(harness: &TestHarness, name: &str) -> common_task::Status {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::common_task_registrations::get(&mut conn, name)
+            .await
+            .unwrap()
+            .status
+    }
+
+    #[tokio::test]
+    /// Common tasks can be installed, disabled, enabled and uninstalled, with the in-memory map and storage
+    /// kept in step.
+    async fn common_task_lifecycle() {
+        let harness = TestHarness::new().await;
+        let token = harness.management_token();
+        let api = &harness.api;
+
+        api.install_common_task_handler(&token, install_request("test_common_task"))
+            .await
+            .unwrap();
+
+        let common_task = api.common_tasks.get("test_common_task").unwrap().clone();
+        assert_eq!(common_task.image, "test_image");
+        assert_eq!(common_task.status, common_task::Status::Enabled);
+        assert_eq!(
+            common_task.registry_auth.map(|auth| auth.pass),
+            Some("registry_pass".to_string())
+        );
+        assert_eq!(
+            stored_status(&harness, "test_common_task").await,
+            common_task::Status::Enabled
+        );
+
+        let err = api
+            .install_common_task_handler(&token, install_request("test_common_task"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+        api.disable_common_task_handler(
+            &token,
+            DisableCommonTaskRequest {
+                name: "test_common_task".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            api.common_tasks.get("test_common_task").unwrap().status,
+            common_task::Status::Disabled
+        );
+        assert_eq!(
+            stored_status(&harness, "test_common_task").await,
+            common_task::Status::Disabled
+        );
+
+        api.enable_common_task_handler(
+            &token,
+            EnableCommonTaskRequest {
+                name: "test_common_task".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            api.common_tasks.get("test_common_task").unwrap().status,
+            common_task::Status::Enabled
+        );
+        assert_eq!(
+            stored_status(&harness, "test_common_task").await,
+            common_task::Status::Enabled
+        );
+
+        api.uninstall_common_task_handler(
+            &token,
+            UninstallCommonTaskRequest {
+                name: "test_common_task".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(!api.common_tasks.contains_key("test_common_task"));
+
+        let mut conn = api.storage.conn().await.unwrap();
+        let err = storage::common_task_registrations::get(&mut conn, "test_common_task")
+            .await
+            .unwrap_err();
+        assert_eq!(err, storage::StorageError::NotFound);
+    }
+
+    #[tokio::test]
+    /// Common tasks that were never installed can't be changed.
+    async fn missing_common_task() {
+        let harness = TestHarness::new().await;
+        let token = harness.management_token();
+        let api = &harness.api;
+
+        let err = api
+            .enable_common_task_handler(
+                &token,
+                EnableCommonTaskRequest {
+                    name: "missing".to_string(),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+        let err = api
+            .disable_common_task_handler(
+                &token,
+                DisableCommonTaskRequest {
+                    name: "missing".to_string(),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+        let err = api
+            .uninstall_common_task_handler(
+                &token,
+                UninstallCommonTaskRequest {
+                    name: "missing".to_string(),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    /// Only management tokens can manage common tasks.
+    async fn common_tasks_require_management_token() {
+        let harness = TestHarness::new().await;
+        let token = Token::new(
+            "client_hash",
+            gofer_models::token::Kind::Client,
+            HashMap::new(),
+            vec!["default".to_string()],
+        );
+
+        let err = harness
+            .api
+            .install_common_task_handler(&token, install_request("test_common_task"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        assert!(!harness.api.common_tasks.contains_key("test_common_task"));
     }
 }
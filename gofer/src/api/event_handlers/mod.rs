@@ -1,7 +1,7 @@
-use crate::api::{validate, Api};
+use crate::api::{tokens, validate, Api};
 use crate::storage;
 use futures::Stream;
-use gofer_models::event::KindDiscriminant;
+use gofer_models::{event::KindDiscriminant, token::Token};
 use gofer_proto::{GetEventRequest, GetEventResponse, ListEventsRequest, ListEventsResponse};
 use slog_scope::error;
 use std::sync::Arc;
@@ -15,6 +15,7 @@ type ListEventsStream = Pin<Box<dyn Stream<Item = Result<ListEventsResponse, Sta
 impl Api {
     pub async fn get_event_handler(
         &self,
+        token: &Token,
         args: GetEventRequest,
     ) -> Result<Response<GetEventResponse>, Status> {
         validate::arg("id", args.id, vec![validate::not_zero_num])?;
@@ -34,27 +35,41 @@ impl Api {
                 _ => Status::internal(e.to_string()),
             })?;
 
+        if !tokens::has_event_access(token, &event) {
+            return Err(Status::permission_denied(format!(
+                "token does not have access to event '{}'",
+                args.id
+            )));
+        }
+
         Ok(Response::new(GetEventResponse {
             event: Some(event.into()),
         }))
     }
 
+    /// Streams all events the token is allowed to see; the rest are left out.
     pub async fn list_events_handler(
         self: Arc<Self>,
+        token: &Token,
         args: ListEventsRequest,
     ) -> Result<Response<ListEventsStream>, Status> {
         // We create a channel we will eventually turn into
         // a stream we can use and pass back to the client.
         let (tx, rx) = mpsc::channel(128);
         let output_stream = ReceiverStream::new(rx);
+        let token = token.clone();
 
-        tokio::spawn(async move { self.stream_events(args.reverse, args.follow, tx).await });
+        tokio::spawn(async move {
+            self.stream_events(&token, args.reverse, args.follow, tx)
+                .await
+        });
 
         Ok(Response::new(Box::pin(output_stream)))
     }
 
     async fn stream_events(
         &self,
+        token: &Token,
         reverse: bool,
         follow: bool,
         input: Sender<Result<ListEventsResponse, Status>>,
@@ -101,6 +116,12 @@ impl Api {
             }
 
             for event in events {
+                last_event_id = event.id;
+
+                if !tokens::has_event_access(token, &event) {
+                    continue;
+                }
+
                 if let Err(e) = input
                     .send(Result::<ListEventsResponse, Status>::Ok(
                         ListEventsResponse {
@@ -112,7 +133,6 @@ impl Api {
                     error!("could not stream event to client; error in send; {:?}", e);
                     return;
                 }
-                last_event_id = event.id;
             }
 
             offset += 10
@@ -133,6 +153,10 @@ impl Api {
                 continue;
             }
 
+            if !tokens::has_event_access(token, &event) {
+                continue;
+            }
+
             if let Err(e) = input
                 .send(Result::<ListEventsResponse, Status>::Ok(
                     ListEventsResponse {
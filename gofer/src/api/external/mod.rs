@@ -0,0 +1,245 @@
+use crate::api::Api;
+use axum::{
+    body::Bytes,
+    extract::{ContentLengthLimit, Extension, Path},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use gofer_models::trigger;
+use gofer_proto::TriggerExternalEventRequest;
+use slog_scope::{debug, error};
+use std::{collections::HashMap, sync::Arc};
+
+/// The largest body an external event may have, in bytes. Events are passed to triggers over gRPC, which limits
+/// messages to 4MB by default, so this leaves room for the headers sent along with the body.
+const EXTERNAL_EVENT_BODY_LIMIT: u64 = 3 * 1024 * 1024;
+
+/// Converts the headers of the incoming request into a map that can be passed to a trigger. Headers
+/// that appear multiple times are joined by a comma and headers with non-visible ASCII values are dropped.
+fn headers_to_map(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = HashMap::new();
+
+    for (name, value) in headers {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        map.entry(name.to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+
+    map
+}
+
+/// Maps the GRPC status returned from a trigger to an HTTP status code that makes sense to external
+/// callers.
+fn grpc_code_to_http(code: tonic::Code) -> StatusCode {
+    match code {
+        tonic::Code::Ok => StatusCode::OK,
+        tonic::Code::InvalidArgument
+        | tonic::Code::FailedPrecondition
+        | tonic::Code::OutOfRange => StatusCode::BAD_REQUEST,
+        tonic::Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+        tonic::Code::NotFound => StatusCode::NOT_FOUND,
+        tonic::Code::AlreadyExists | tonic::Code::Aborted => StatusCode::CONFLICT,
+        tonic::Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        tonic::Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        tonic::Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        tonic::Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Builds the request that passes an external event's raw body and headers on to a trigger.
+fn external_event_request(headers: &HeaderMap, payload: &Bytes) -> TriggerExternalEventRequest {
+    TriggerExternalEventRequest {
+        payload: payload.to_vec(),
+        headers: headers_to_map(headers),
+    }
+}
+
+/// An axum compliant handler that passes external requests(usually webhooks) to the trigger named in the
+/// path. The raw body and headers are passed along untouched so that triggers can perform their own
+/// verification. Requests for triggers that aren't installed or are disabled are rejected, as are requests
+/// without a Content-Length or with a body over [`EXTERNAL_EVENT_BODY_LIMIT`].
+pub async fn external_event_handler(
+    Extension(api): Extension<Arc<Api>>,
+    Path(trigger_name): Path<String>,
+    headers: HeaderMap,
+    ContentLengthLimit(payload): ContentLengthLimit<Bytes, EXTERNAL_EVENT_BODY_LIMIT>,
+) -> Response {
+    let trigger = match api.triggers.get(&trigger_name) {
+        Some(trigger) => trigger.value().clone(),
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                format!("trigger '{}' does not exist", trigger_name),
+            )
+                .into_response()
+        }
+    };
+
+    if trigger.status != trigger::Status::Enabled {
+        return (
+            StatusCode::FORBIDDEN,
+            format!("trigger '{}' is not enabled", trigger_name),
+        )
+            .into_response();
+    }
+
+    let mut client = match api
+        .trigger_client(
+            &trigger.url.unwrap_or_default(),
+            &trigger.key.unwrap_or_default(),
+        )
+        .await
+    {
+        Ok(client) => client,
+        Err(e) => {
+            error!("could not connect to trigger to pass external event";
+                "name" => &trigger_name, "error" => format!("{:?}", e));
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("could not connect to trigger '{}'", trigger_name),
+            )
+                .into_response();
+        }
+    };
+
+    let request = tonic::Request::new(external_event_request(&headers, &payload));
+
+    match client.external_event(request).await {
+        Ok(response) => {
+            debug!("passed external event to trigger"; "name" => &trigger_name);
+            (StatusCode::OK, response.into_inner().payload).into_response()
+        }
+        Err(status) => {
+            debug!("trigger rejected external event"; "name" => &trigger_name,
+                "code" => format!("{:?}", status.code()), "message" => status.message());
+            (
+                grpc_code_to_http(status.code()),
+                status.message().to_string(),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::TestHarness;
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use tower::ServiceExt;
+
+    /// Registers a trigger named "test_trigger" with the given status.
+    fn register_trigger(harness: &TestHarness, status: trigger::Status) {
+        harness.api.triggers.insert(
+            "test_trigger".to_string(),
+            trigger::Trigger {
+                registration: trigger::Registration {
+                    name: "test_trigger".to_string(),
+                    image: "test_image".to_string(),
+                    user: None,
+                    pass: None,
+                    variables: HashMap::new(),
+                    created: 0,
+                    status: status.clone(),
+                },
+                url: None,
+                scheduler_id: None,
+                started: 0,
+                state: trigger::State::Running,
+                status,
+                documentation: None,
+                key: None,
+            },
+        );
+    }
+
+    /// Posts an external event to the trigger through the same route the service uses.
+    async fn post_event(harness: &TestHarness, trigger_name: &str, body: Vec<u8>) -> StatusCode {
+        let router = Router::new()
+            .route("/api/external/:trigger_name", post(external_event_handler))
+            .layer(Extension(harness.api.clone()));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/api/external/{}", trigger_name))
+            .header("content-length", body.len())
+            .body(Body::from(body))
+            .unwrap();
+
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    /// Events for triggers that aren't installed are rejected as not found.
+    async fn external_event_unknown_trigger() {
+        let harness = TestHarness::new().await;
+
+        assert_eq!(
+            post_event(&harness, "test_trigger", b"{}".to_vec()).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    /// Events for disabled triggers are rejected as forbidden.
+    async fn external_event_disabled_trigger() {
+        let harness = TestHarness::new().await;
+        register_trigger(&harness, trigger::Status::Disabled);
+
+        assert_eq!(
+            post_event(&harness, "test_trigger", b"{}".to_vec()).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    /// Bodies over the limit are rejected before the trigger is looked up.
+    async fn external_event_body_too_large() {
+        let harness = TestHarness::new().await;
+        register_trigger(&harness, trigger::Status::Enabled);
+
+        assert_eq!(
+            post_event(
+                &harness,
+                "test_trigger",
+                vec![0; EXTERNAL_EVENT_BODY_LIMIT as usize + 1]
+            )
+            .await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[test]
+    /// The body is passed to the trigger byte for byte along with every visible header. Repeated headers are
+    /// joined.
+    fn external_event_passed_through() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-hub-signature-256", "sha256=test".parse().unwrap());
+        headers.append("x-repeated", "first".parse().unwrap());
+        headers.append("x-repeated", "second".parse().unwrap());
+        headers.insert("x-invisible", "caf\u{e9}".as_bytes().try_into().unwrap());
+
+        let payload = Bytes::from_static(b"{\"ref\": \"refs/heads/main\"}\n");
+
+        assert_eq!(
+            external_event_request(&headers, &payload),
+            TriggerExternalEventRequest {
+                payload: payload.to_vec(),
+                headers: HashMap::from([
+                    ("x-hub-signature-256".to_string(), "sha256=test".to_string()),
+                    ("x-repeated".to_string(), "first, second".to_string()),
+                ]),
+            }
+        );
+    }
+}
@@ -1,9 +1,22 @@
 use gofer_models::task_run;
 
+/// Task runs from before retries existed have attempt 0 and keep the names they were given back then.
 pub fn task_run_log_path(log_dir: &str, task_run: &task_run::TaskRun) -> String {
+    if task_run.attempt == 0 {
+        return format!(
+            "{}/{}_{}_{}_{}",
+            log_dir, &task_run.namespace, task_run.pipeline, task_run.run, task_run.id
+        );
+    }
+
     return format!(
-        "{}/{}_{}_{}_{}",
-        log_dir, &task_run.namespace, task_run.pipeline, task_run.run, task_run.id
+        "{}/{}_{}_{}_{}_{}",
+        log_dir,
+        &task_run.namespace,
+        task_run.pipeline,
+        task_run.run,
+        task_run.id,
+        task_run.attempt
     );
 }
 
@@ -19,10 +32,66 @@ pub fn run_object_key(namespace: &str, pipeline: &str, run: u64, key: &str) -> S
     return format!("{}_{}_{}_{}", namespace, pipeline, run, key);
 }
 
-pub fn task_container_id(namespace: &str, pipeline: &str, run: u64, task_run: &str) -> String {
-    return format!("{}_{}_{}_{}", namespace, pipeline, run, task_run);
+pub fn task_container_id(
+    namespace: &str,
+    pipeline: &str,
+    run: u64,
+    task_run: &str,
+    attempt: u64,
+) -> String {
+    if attempt == 0 {
+        return format!("{}_{}_{}_{}", namespace, pipeline, run, task_run);
+    }
+
+    return format!(
+        "{}_{}_{}_{}_{}",
+        namespace, pipeline, run, task_run, attempt
+    );
 }
 
 pub fn trigger_container_id(name: &str) -> String {
     return format!("trigger_{}", name);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gofer_models::task;
+
+    fn test_task_run(attempt: u64) -> task_run::TaskRun {
+        let mut test_task_run = task_run::TaskRun::new(
+            "test_namespace",
+            "test_pipeline",
+            1,
+            task::Task::new("test_task", "ubuntu:latest"),
+        );
+        test_task_run.attempt = attempt;
+        test_task_run
+    }
+
+    #[test]
+    /// Task runs from before retries existed keep their original log path and container name.
+    fn names_without_attempt() {
+        assert_eq!(
+            task_run_log_path("/tmp", &test_task_run(0)),
+            "/tmp/test_namespace_test_pipeline_1_test_task"
+        );
+        assert_eq!(
+            task_container_id("test_namespace", "test_pipeline", 1, "test_task", 0),
+            "test_namespace_test_pipeline_1_test_task"
+        );
+    }
+
+    #[test]
+    /// Every attempt of a task run gets its own log path and container name.
+    fn names_with_attempt() {
+        assert_eq!(
+            task_run_log_path("/tmp", &test_task_run(2)),
+            "/tmp/test_namespace_test_pipeline_1_test_task_2"
+        );
+        assert_eq!(
+            task_container_id("test_namespace", "test_pipeline", 1, "test_task", 2),
+            "test_namespace_test_pipeline_1_test_task_2"
+        );
+    }
+}
@@ -1,9 +1,12 @@
 use futures::Stream;
 use gofer_proto::{gofer_server::Gofer, *};
 use std::{ops::Deref, pin::Pin};
-use tonic::{Request, Response, Status};
+use tonic::{Request, Response, Status, Streaming};
 
-use super::ApiWrapper;
+use super::{
+    tokens::{request_run_token, request_token},
+    ApiWrapper,
+};
 
 // Since we can't implement this trait over many files each function here just calls out to a clone function
 // located in other, more neatly organized files.
@@ -11,177 +14,228 @@ use super::ApiWrapper;
 impl Gofer for ApiWrapper {
     async fn get_system_info(
         &self,
-        _: Request<GetSystemInfoRequest>,
+        request: Request<GetSystemInfoRequest>,
     ) -> Result<Response<GetSystemInfoResponse>, Status> {
+        request_token(&request)?;
         self.get_system_info_handler()
     }
 
+    async fn rotate_secret_store_keys(
+        &self,
+        request: Request<RotateSecretStoreKeysRequest>,
+    ) -> Result<Response<RotateSecretStoreKeysResponse>, Status> {
+        let token = request_token(&request)?;
+        self.rotate_secret_store_keys_handler(&token).await
+    }
+
     async fn list_namespaces(
         &self,
         request: Request<ListNamespacesRequest>,
     ) -> Result<Response<ListNamespacesResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.list_namespaces_handler(args).await
+        self.list_namespaces_handler(&token, args).await
     }
 
     async fn create_namespace(
         &self,
         request: Request<CreateNamespaceRequest>,
     ) -> Result<Response<CreateNamespaceResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().create_namespace_handler(args).await
+        self.deref()
+            .clone()
+            .create_namespace_handler(&token, args)
+            .await
     }
 
     async fn get_namespace(
         &self,
         request: Request<GetNamespaceRequest>,
     ) -> Result<Response<GetNamespaceResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.get_namespace_handler(args).await
+        self.get_namespace_handler(&token, args).await
     }
 
     async fn update_namespace(
         &self,
         request: Request<UpdateNamespaceRequest>,
     ) -> Result<Response<UpdateNamespaceResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.update_namespace_handler(args).await
+        self.update_namespace_handler(&token, args).await
     }
 
     async fn delete_namespace(
         &self,
         request: Request<DeleteNamespaceRequest>,
     ) -> Result<Response<DeleteNamespaceResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().delete_namespace_handler(args).await
+        self.deref()
+            .clone()
+            .delete_namespace_handler(&token, args)
+            .await
     }
 
     async fn list_pipelines(
         &self,
         request: Request<ListPipelinesRequest>,
     ) -> Result<Response<ListPipelinesResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.list_pipelines_handler(args).await
+        self.list_pipelines_handler(&token, args).await
     }
 
     async fn create_pipeline(
         &self,
         request: Request<CreatePipelineRequest>,
     ) -> Result<Response<CreatePipelineResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().create_pipeline_handler(args).await
+        self.deref()
+            .clone()
+            .create_pipeline_handler(&token, args)
+            .await
     }
 
     async fn get_pipeline(
         &self,
         request: Request<GetPipelineRequest>,
     ) -> Result<Response<GetPipelineResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.get_pipeline_handler(args).await
+        self.get_pipeline_handler(&token, args).await
     }
 
     async fn enable_pipeline(
         &self,
         request: Request<EnablePipelineRequest>,
     ) -> Result<Response<EnablePipelineResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().enable_pipeline_handler(args).await
+        self.deref()
+            .clone()
+            .enable_pipeline_handler(&token, args)
+            .await
     }
 
     async fn disable_pipeline(
         &self,
         request: Request<DisablePipelineRequest>,
     ) -> Result<Response<DisablePipelineResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().disable_pipeline_handler(args).await
+        self.deref()
+            .clone()
+            .disable_pipeline_handler(&token, args)
+            .await
     }
 
     async fn update_pipeline(
         &self,
         request: Request<UpdatePipelineRequest>,
     ) -> Result<Response<UpdatePipelineResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.update_pipeline_handler(args).await
+        self.update_pipeline_handler(&token, args).await
     }
 
     async fn delete_pipeline(
         &self,
         request: Request<DeletePipelineRequest>,
     ) -> Result<Response<DeletePipelineResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().delete_pipeline_handler(args).await
+        self.deref()
+            .clone()
+            .delete_pipeline_handler(&token, args)
+            .await
     }
 
     async fn get_run(
         &self,
         request: Request<GetRunRequest>,
     ) -> Result<Response<GetRunResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.get_run_handler(args).await
+        self.get_run_handler(&token, args).await
     }
 
     async fn list_runs(
         &self,
         request: Request<ListRunsRequest>,
     ) -> Result<Response<ListRunsResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.list_runs_handler(args).await
+        self.list_runs_handler(&token, args).await
     }
 
     async fn start_run(
         &self,
         request: Request<StartRunRequest>,
     ) -> Result<Response<StartRunResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().start_run_handler(args).await
+        self.deref().clone().start_run_handler(&token, args).await
     }
 
     async fn retry_run(
         &self,
         request: Request<RetryRunRequest>,
     ) -> Result<Response<RetryRunResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().retry_run_handler(args).await
+        self.deref().clone().retry_run_handler(&token, args).await
     }
 
     async fn cancel_run(
         &self,
         request: Request<CancelRunRequest>,
     ) -> Result<Response<CancelRunResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().cancel_run_handler(args).await
+        self.deref().clone().cancel_run_handler(&token, args).await
     }
 
     async fn cancel_all_runs(
         &self,
         request: Request<CancelAllRunsRequest>,
     ) -> Result<Response<CancelAllRunsResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().cancel_all_runs_handler(args).await
+        self.deref()
+            .clone()
+            .cancel_all_runs_handler(&token, args)
+            .await
     }
 
     async fn get_task_run(
         &self,
         request: Request<GetTaskRunRequest>,
     ) -> Result<Response<GetTaskRunResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.get_task_run_handler(args).await
+        self.get_task_run_handler(&token, args).await
     }
 
     async fn list_task_runs(
         &self,
         request: Request<ListTaskRunsRequest>,
     ) -> Result<Response<ListTaskRunsResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.list_task_runs_handler(args).await
+        self.list_task_runs_handler(&token, args).await
     }
 
     async fn cancel_task_run(
         &self,
         request: Request<CancelTaskRunRequest>,
     ) -> Result<Response<CancelTaskRunResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.cancel_task_run_handler(args).await
+        self.cancel_task_run_handler(&token, args).await
     }
 
     type GetTaskRunLogsStream =
@@ -191,22 +245,142 @@ impl Gofer for ApiWrapper {
         &self,
         request: Request<GetTaskRunLogsRequest>,
     ) -> Result<Response<Self::GetTaskRunLogsStream>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().get_task_run_logs_handler(args).await
+        self.deref()
+            .clone()
+            .get_task_run_logs_handler(&token, args)
+            .await
     }
 
     async fn delete_task_run_logs(
         &self,
         request: Request<DeleteTaskRunLogsRequest>,
     ) -> Result<Response<DeleteTaskRunLogsResponse>, Status> {
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.delete_task_run_logs_handler(&token, args).await
+    }
+
+    type GetPipelineObjectStream =
+        Pin<Box<dyn Stream<Item = Result<GetPipelineObjectResponse, Status>> + Send>>;
+
+    async fn get_pipeline_object(
+        &self,
+        request: Request<GetPipelineObjectRequest>,
+    ) -> Result<Response<Self::GetPipelineObjectStream>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.get_pipeline_object_handler(&token, args).await
+    }
+
+    async fn list_pipeline_objects(
+        &self,
+        request: Request<ListPipelineObjectsRequest>,
+    ) -> Result<Response<ListPipelineObjectsResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.list_pipeline_objects_handler(&token, args).await
+    }
+
+    async fn put_pipeline_object(
+        &self,
+        request: Request<Streaming<PutPipelineObjectRequest>>,
+    ) -> Result<Response<PutPipelineObjectResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let requests = request.into_inner();
+        self.put_pipeline_object_handler(&token, requests).await
+    }
+
+    async fn delete_pipeline_object(
+        &self,
+        request: Request<DeletePipelineObjectRequest>,
+    ) -> Result<Response<DeletePipelineObjectResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.delete_pipeline_object_handler(&token, args).await
+    }
+
+    type GetRunObjectStream =
+        Pin<Box<dyn Stream<Item = Result<GetRunObjectResponse, Status>> + Send>>;
+
+    async fn get_run_object(
+        &self,
+        request: Request<GetRunObjectRequest>,
+    ) -> Result<Response<Self::GetRunObjectStream>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.get_run_object_handler(&token, args).await
+    }
+
+    async fn list_run_objects(
+        &self,
+        request: Request<ListRunObjectsRequest>,
+    ) -> Result<Response<ListRunObjectsResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.list_run_objects_handler(&token, args).await
+    }
+
+    async fn put_run_object(
+        &self,
+        request: Request<Streaming<PutRunObjectRequest>>,
+    ) -> Result<Response<PutRunObjectResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let requests = request.into_inner();
+        self.put_run_object_handler(&token, requests).await
+    }
+
+    async fn delete_run_object(
+        &self,
+        request: Request<DeleteRunObjectRequest>,
+    ) -> Result<Response<DeleteRunObjectResponse>, Status> {
+        let token = request_run_token(&request)?;
         let args = request.into_inner();
-        self.delete_task_run_logs_handler(args).await
+        self.delete_run_object_handler(&token, args).await
+    }
+
+    async fn get_pipeline_secret(
+        &self,
+        request: Request<GetPipelineSecretRequest>,
+    ) -> Result<Response<GetPipelineSecretResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.get_pipeline_secret_handler(&token, args).await
+    }
+
+    async fn list_pipeline_secrets(
+        &self,
+        request: Request<ListPipelineSecretsRequest>,
+    ) -> Result<Response<ListPipelineSecretsResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.list_pipeline_secrets_handler(&token, args).await
+    }
+
+    async fn put_pipeline_secret(
+        &self,
+        request: Request<PutPipelineSecretRequest>,
+    ) -> Result<Response<PutPipelineSecretResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.put_pipeline_secret_handler(&token, args).await
+    }
+
+    async fn delete_pipeline_secret(
+        &self,
+        request: Request<DeletePipelineSecretRequest>,
+    ) -> Result<Response<DeletePipelineSecretResponse>, Status> {
+        let token = request_run_token(&request)?;
+        let args = request.into_inner();
+        self.delete_pipeline_secret_handler(&token, args).await
     }
 
     async fn get_trigger(
         &self,
         request: Request<GetTriggerRequest>,
     ) -> Result<Response<GetTriggerResponse>, Status> {
+        request_token(&request)?;
         let args = request.into_inner();
         self.get_trigger_handler(args).await
     }
@@ -215,6 +389,7 @@ impl Gofer for ApiWrapper {
         &self,
         request: Request<ListTriggersRequest>,
     ) -> Result<Response<ListTriggersResponse>, Status> {
+        request_token(&request)?;
         let args = request.into_inner();
         self.list_triggers_handler(args).await
     }
@@ -223,38 +398,49 @@ impl Gofer for ApiWrapper {
         &self,
         request: Request<InstallTriggerRequest>,
     ) -> Result<Response<InstallTriggerResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.install_trigger_handler(args).await
+        self.deref()
+            .clone()
+            .install_trigger_handler(&token, args)
+            .await
     }
 
     async fn uninstall_trigger(
         &self,
         request: Request<UninstallTriggerRequest>,
     ) -> Result<Response<UninstallTriggerResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.uninstall_trigger_handler(args).await
+        self.uninstall_trigger_handler(&token, args).await
     }
 
     async fn enable_trigger(
         &self,
         request: Request<EnableTriggerRequest>,
     ) -> Result<Response<EnableTriggerResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.enable_trigger_handler(args).await
+        self.deref()
+            .clone()
+            .enable_trigger_handler(&token, args)
+            .await
     }
 
     async fn disable_trigger(
         &self,
         request: Request<DisableTriggerRequest>,
     ) -> Result<Response<DisableTriggerResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.disable_trigger_handler(args).await
+        self.disable_trigger_handler(&token, args).await
     }
 
     async fn get_trigger_install_instructions(
         &self,
         request: Request<GetTriggerInstallInstructionsRequest>,
     ) -> Result<Response<GetTriggerInstallInstructionsResponse>, Status> {
+        request_token(&request)?;
         let args = request.into_inner();
         self.get_trigger_install_instructions_handler(args).await
     }
@@ -263,50 +449,63 @@ impl Gofer for ApiWrapper {
         &self,
         request: Request<GetCommonTaskRequest>,
     ) -> Result<Response<GetCommonTaskResponse>, Status> {
-        todo!()
+        request_token(&request)?;
+        let args = request.into_inner();
+        self.get_common_task_handler(args).await
     }
 
     async fn list_common_tasks(
         &self,
         request: Request<ListCommonTasksRequest>,
     ) -> Result<Response<ListCommonTasksResponse>, Status> {
-        todo!()
+        request_token(&request)?;
+        let args = request.into_inner();
+        self.list_common_tasks_handler(args).await
     }
 
     async fn install_common_task(
         &self,
         request: Request<InstallCommonTaskRequest>,
     ) -> Result<Response<InstallCommonTaskResponse>, Status> {
-        todo!()
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.install_common_task_handler(&token, args).await
     }
 
     async fn uninstall_common_task(
         &self,
         request: Request<UninstallCommonTaskRequest>,
     ) -> Result<Response<UninstallCommonTaskResponse>, Status> {
-        todo!()
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.uninstall_common_task_handler(&token, args).await
     }
 
     async fn enable_common_task(
         &self,
         request: Request<EnableCommonTaskRequest>,
     ) -> Result<Response<EnableCommonTaskResponse>, Status> {
-        todo!()
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.enable_common_task_handler(&token, args).await
     }
 
     async fn disable_common_task(
         &self,
         request: Request<DisableCommonTaskRequest>,
     ) -> Result<Response<DisableCommonTaskResponse>, Status> {
-        todo!()
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.disable_common_task_handler(&token, args).await
     }
 
     async fn get_event(
         &self,
         request: Request<GetEventRequest>,
     ) -> Result<Response<GetEventResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.get_event_handler(args).await
+        self.get_event_handler(&token, args).await
     }
 
     type ListEventsStream = Pin<Box<dyn Stream<Item = Result<ListEventsResponse, Status>> + Send>>;
@@ -315,7 +514,35 @@ impl Gofer for ApiWrapper {
         &self,
         request: Request<ListEventsRequest>,
     ) -> Result<Response<Self::ListEventsStream>, Status> {
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.deref().clone().list_events_handler(&token, args).await
+    }
+
+    async fn create_token(
+        &self,
+        request: Request<CreateTokenRequest>,
+    ) -> Result<Response<CreateTokenResponse>, Status> {
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.create_token_handler(&token, args).await
+    }
+
+    async fn list_tokens(
+        &self,
+        request: Request<ListTokensRequest>,
+    ) -> Result<Response<ListTokensResponse>, Status> {
+        let token = request_token(&request)?;
+        let args = request.into_inner();
+        self.list_tokens_handler(&token, args).await
+    }
+
+    async fn delete_token(
+        &self,
+        request: Request<DeleteTokenRequest>,
+    ) -> Result<Response<DeleteTokenResponse>, Status> {
+        let token = request_token(&request)?;
         let args = request.into_inner();
-        self.deref().clone().list_events_handler(args).await
+        self.delete_token_handler(&token, args).await
     }
 }
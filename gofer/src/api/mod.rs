@@ -1,20 +1,27 @@
 mod common_tasks;
 mod event_handlers;
+mod external;
 mod fmt;
 mod gofer_impl;
 mod namespaces;
+mod objects;
 mod pipelines;
 mod runs;
+mod secrets;
 mod system;
 mod task_runs;
+mod tokens;
 mod triggers;
 mod validate;
 
+#[cfg(test)]
+mod tests;
+
 use crate::{conf, events, frontend, object_store, scheduler, secret_store, storage};
 use anyhow::anyhow;
 use axum_server::Handle;
-use dashmap::DashMap;
-use gofer_models::{common_task, event, namespace, trigger};
+use dashmap::{DashMap, DashSet};
+use gofer_models::{common_task, event, namespace, token, trigger};
 use gofer_proto::gofer_server::GoferServer;
 use http::header::CONTENT_TYPE;
 use slog_scope::info;
@@ -109,17 +116,39 @@ pub struct Api {
     /// updated and maintained.
     triggers: DashMap<String, trigger::Trigger>,
 
+    /// Cancels the watch loop of each trigger by name. A trigger's watch is cancelled whenever it is uninstalled
+    /// or disabled so that a replacement never ends up running alongside the old one.
+    trigger_watches: DashMap<String, CancellationToken>,
+
     /// An in-memory map of currently registered common_tasks. These common_tasks are registered on startup
     /// and launched as requested in the user's pipeline run. Gofer refers to this cache as a way
     /// to quickly look up which container is needed to be launched.
     common_tasks: DashMap<String, common_task::CommonTask>,
+
+    /// Signalled every time a run finishes. Used by anything that needs to wait for a run to end (the run
+    /// dispatcher, expiry jobs) without repeatedly querying the database.
+    run_completions: tokio::sync::watch::Sender<()>,
+
+    /// Signalled every time a new run is placed in the run queue so the run dispatcher can pick it up.
+    queued_runs: tokio::sync::watch::Sender<()>,
+
+    /// Task runs (namespace, pipeline, run, task run) that have been cancelled while their run is still in
+    /// progress. Cancelling a container looks like any other failure, so this is how the run state machine
+    /// knows not to retry them.
+    cancelled_task_runs: DashSet<(String, String, u64, String)>,
+
+    /// An in-memory map of all API tokens, keyed by their hash. Every request is authenticated against it so
+    /// that it doesn't cost a database lookup.
+    tokens: DashMap<String, token::Token>,
 }
 
 impl Api {
     /// Create a new instance of API with all services started.
     pub async fn start(conf: conf::api::Config) {
         let shutdown = CancellationToken::new();
-        let storage = storage::Db::new(&conf.server.storage_path).await.unwrap();
+        let storage = storage::Db::new(&conf.server.storage_path, &conf.general.encryption_key)
+            .await
+            .unwrap();
         let scheduler = scheduler::init_scheduler(&conf.scheduler).await.unwrap();
         let object_store = object_store::init_object_store(&conf.object_store)
             .await
@@ -142,13 +171,23 @@ impl Api {
             secret_store,
             event_bus,
             triggers: DashMap::new(),
+            trigger_watches: DashMap::new(),
             common_tasks: DashMap::new(),
+            run_completions: tokio::sync::watch::channel(()).0,
+            queued_runs: tokio::sync::watch::channel(()).0,
+            cancelled_task_runs: DashSet::new(),
+            tokens: DashMap::new(),
         };
 
         let api = Arc::new(api);
 
         api.create_default_namespace().await.unwrap();
+        api.load_tokens().await.unwrap();
+        api.create_bootstrap_token().await.unwrap();
         api.clone().start_triggers().await.unwrap();
+        api.load_common_tasks().await.unwrap();
+        api.clone().restore_runs().await.unwrap();
+        tokio::spawn(api.clone().dispatch_runs());
 
         // Launch a thread that waits for ctrl-c and runs cleanup.
         let server_handle = axum_server::Handle::new();
@@ -217,12 +256,21 @@ impl Api {
         let key = config.server.tls_key.clone().into_bytes();
 
         let http = axum::Router::new()
-            .route("/*path", axum::routing::any(frontend::frontend_handler))
+            .route(
+                "/api/external/:trigger_name",
+                axum::routing::post(external::external_event_handler),
+            )
+            .fallback(axum::routing::any(frontend::frontend_handler))
+            .layer(axum::extract::Extension(self.clone()))
             .map_err(tower::BoxError::from)
             .boxed_clone();
 
+        let auth_api = self.clone();
         let grpc = tonic::transport::Server::builder()
-            .add_service(GoferServer::new(ApiWrapper(self)))
+            .add_service(GoferServer::with_interceptor(
+                ApiWrapper(self),
+                move |request| auth_api.authenticate(request),
+            ))
             .into_service()
             .map_response(|r| r.map(axum::body::boxed))
             .boxed_clone();
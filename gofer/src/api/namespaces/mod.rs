@@ -1,6 +1,6 @@
-use crate::api::{epoch, validate, Api};
+use crate::api::{epoch, tokens, validate, Api};
 use crate::storage;
-use gofer_models::{event, namespace};
+use gofer_models::{event, namespace, token::Token};
 use gofer_proto::{
     CreateNamespaceRequest, CreateNamespaceResponse, DeleteNamespaceRequest,
     DeleteNamespaceResponse, GetNamespaceRequest, GetNamespaceResponse, ListNamespacesRequest,
@@ -12,6 +12,7 @@ use tonic::{Response, Status};
 impl Api {
     pub async fn list_namespaces_handler(
         &self,
+        token: &Token,
         args: ListNamespacesRequest,
     ) -> Result<Response<ListNamespacesResponse>, Status> {
         let mut conn = self
@@ -24,7 +25,11 @@ impl Api {
             .await
             .map(|namespaces| {
                 Response::new(ListNamespacesResponse {
-                    namespaces: namespaces.into_iter().map(Namespace::from).collect(),
+                    namespaces: namespaces
+                        .into_iter()
+                        .filter(|namespace| token.has_namespace_access(&namespace.id))
+                        .map(Namespace::from)
+                        .collect(),
                 })
             })
             .map_err(|e| Status::internal(e.to_string()))
@@ -32,8 +37,11 @@ impl Api {
 
     pub async fn create_namespace_handler(
         self: Arc<Self>,
+        token: &Token,
         args: CreateNamespaceRequest,
     ) -> Result<Response<CreateNamespaceResponse>, Status> {
+        tokens::require_management_token(token)?;
+
         validate::arg("id", args.id.clone(), vec![validate::is_valid_identifier])?;
         validate::arg("name", args.name.clone(), vec![validate::not_empty_str])?;
 
@@ -70,8 +78,11 @@ impl Api {
 
     pub async fn get_namespace_handler(
         &self,
+        token: &Token,
         args: GetNamespaceRequest,
     ) -> Result<Response<GetNamespaceResponse>, Status> {
+        tokens::require_namespace_access(token, &args.id)?;
+
         validate::arg("id", args.id.clone(), vec![validate::is_valid_identifier])?;
 
         let mut conn = self
@@ -97,8 +108,11 @@ impl Api {
 
     pub async fn update_namespace_handler(
         &self,
+        token: &Token,
         args: UpdateNamespaceRequest,
     ) -> Result<Response<UpdateNamespaceResponse>, Status> {
+        tokens::require_full_namespace_access(token, &args.id)?;
+
         validate::arg("id", args.id.clone(), vec![validate::is_valid_identifier])?;
 
         let mut conn = self
@@ -129,8 +143,11 @@ impl Api {
 
     pub async fn delete_namespace_handler(
         self: Arc<Self>,
+        token: &Token,
         args: DeleteNamespaceRequest,
     ) -> Result<Response<DeleteNamespaceResponse>, Status> {
+        tokens::require_management_token(token)?;
+
         validate::arg("id", args.id.clone(), vec![validate::is_valid_identifier])?;
 
         let mut conn = self
@@ -139,6 +156,29 @@ impl Api {
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
+        // Deleting the namespace removes its pipelines' secret and object keys along with it, so the values have
+        // to be removed from the secret and object stores first.
+        let mut offset = 0;
+        loop {
+            let pipelines = storage::pipelines::list(&mut conn, offset, 0, &args.id)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+            if pipelines.is_empty() {
+                break;
+            }
+            offset += pipelines.len() as u64;
+
+            for pipeline in pipelines {
+                self.delete_all_pipeline_secrets(&mut conn, &args.id, &pipeline.id)
+                    .await
+                    .map_err(|e| Status::internal(e.to_string()))?;
+
+                self.delete_all_pipeline_objects(&mut conn, &args.id, &pipeline.id)
+                    .await
+                    .map_err(|e| Status::internal(e.to_string()))?;
+            }
+        }
+
         storage::namespaces::delete(&mut conn, &args.id)
             .await
             .map_err(|e| match e {
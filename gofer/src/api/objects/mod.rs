@@ -0,0 +1,669 @@
+use crate::{
+    api::{fmt, tokens, validate, Api},
+    object_store, storage,
+};
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
+use gofer_models::{object, token::Token};
+use gofer_proto::{
+    DeletePipelineObjectRequest, DeletePipelineObjectResponse, DeleteRunObjectRequest,
+    DeleteRunObjectResponse, GetPipelineObjectRequest, GetPipelineObjectResponse,
+    GetRunObjectRequest, GetRunObjectResponse, ListPipelineObjectsRequest,
+    ListPipelineObjectsResponse, ListRunObjectsRequest, ListRunObjectsResponse,
+    PutPipelineObjectRequest, PutPipelineObjectResponse, PutRunObjectRequest, PutRunObjectResponse,
+};
+use slog_scope::{debug, error};
+use sqlx::SqliteConnection;
+use std::pin::Pin;
+use tonic::{Response, Status};
+
+type GetPipelineObjectStream =
+    Pin<Box<dyn Stream<Item = Result<GetPipelineObjectResponse, Status>> + Send>>;
+type GetRunObjectStream = Pin<Box<dyn Stream<Item = Result<GetRunObjectResponse, Status>> + Send>>;
+
+fn object_store_error_to_status(key: &str, e: object_store::ObjectStoreError) -> Status {
+    match e {
+        object_store::ObjectStoreError::NotFound => {
+            Status::not_found(format!("object with key '{}' does not exist", key))
+        }
+        object_store::ObjectStoreError::Exists => {
+            Status::already_exists(format!("object with key '{}' already exists", key))
+        }
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+/// Objects are uploaded as a stream of requests. The first carries the object's details and every request,
+/// including the first, carries the next chunk of its content.
+trait UploadRequest {
+    fn take_content(&mut self) -> Vec<u8>;
+}
+
+impl UploadRequest for PutPipelineObjectRequest {
+    fn take_content(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.content)
+    }
+}
+
+impl UploadRequest for PutRunObjectRequest {
+    fn take_content(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.content)
+    }
+}
+
+/// Reads the first request of an upload, returning it along with a stream of the object's content that can be
+/// handed straight to the object store.
+async fn read_upload<R, S>(mut requests: S) -> Result<(R, object_store::ObjectStream), Status>
+where
+    R: UploadRequest + Send + 'static,
+    S: Stream<Item = Result<R, Status>> + Send + Unpin + 'static,
+{
+    let mut details = requests.next().await.ok_or_else(|| {
+        Status::invalid_argument("no object was sent; the first request must contain its details")
+    })??;
+
+    let first_chunk = Bytes::from(details.take_content());
+    let content = stream::once(async move { Ok(first_chunk) }).chain(requests.map(|request| {
+        request
+            .map(|mut request| Bytes::from(request.take_content()))
+            .map_err(|e| {
+                object_store::ObjectStoreError::Unknown(format!(
+                    "could not receive object content; {}",
+                    e.message()
+                ))
+            })
+    }));
+
+    Ok((details, Box::pin(content)))
+}
+
+impl Api {
+    /// Removes all objects belonging to a pipeline and its runs from the object store. The object keys kept
+    /// in storage are removed along with the pipeline itself.
+    pub async fn delete_all_pipeline_objects(
+        &self,
+        conn: &mut SqliteConnection,
+        namespace_id: &str,
+        pipeline_id: &str,
+    ) -> Result<(), storage::StorageError> {
+        let pipeline_keys =
+            storage::objects::list_pipeline_keys(conn, namespace_id, pipeline_id).await?;
+
+        for object_key in pipeline_keys {
+            if let Err(e) = self
+                .object_store
+                .delete_object(&fmt::pipeline_object_key(
+                    namespace_id,
+                    pipeline_id,
+                    &object_key.key,
+                ))
+                .await
+            {
+                error!("could not remove pipeline object from object store";
+                    "namespace" => namespace_id, "pipeline" => pipeline_id,
+                    "key" => &object_key.key, "error" => e.to_string());
+            }
+        }
+
+        let run_keys =
+            storage::objects::list_pipeline_run_keys(conn, namespace_id, pipeline_id).await?;
+
+        for (run_id, object_key) in run_keys {
+            if let Err(e) = self
+                .object_store
+                .delete_object(&fmt::run_object_key(
+                    namespace_id,
+                    pipeline_id,
+                    run_id,
+                    &object_key.key,
+                ))
+                .await
+            {
+                error!("could not remove run object from object store";
+                    "namespace" => namespace_id, "pipeline" => pipeline_id, "run" => run_id,
+                    "key" => &object_key.key, "error" => e.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the oldest pipeline objects until the pipeline is back within its object limit. The key that
+    /// was just stored is never evicted. Returns the keys of the objects removed.
+    async fn evict_pipeline_objects(
+        &self,
+        conn: &mut SqliteConnection,
+        namespace_id: &str,
+        pipeline_id: &str,
+        stored_key: &str,
+    ) -> Result<Vec<String>, Status> {
+        let limit = self.conf.object_store.pipeline_object_limit;
+
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+
+        let object_keys = storage::objects::list_pipeline_keys(conn, namespace_id, pipeline_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        if object_keys.len() as u64 <= limit {
+            return Ok(vec![]);
+        }
+
+        let excess = object_keys.len() - limit as usize;
+        let mut evicted = vec![];
+
+        for object_key in object_keys
+            .into_iter()
+            .filter(|object_key| object_key.key != stored_key)
+            .take(excess)
+        {
+            self.object_store
+                .delete_object(&fmt::pipeline_object_key(
+                    namespace_id,
+                    pipeline_id,
+                    &object_key.key,
+                ))
+                .await
+                .map_err(|e| object_store_error_to_status(&object_key.key, e))?;
+
+            storage::objects::delete_pipeline_key(conn, namespace_id, pipeline_id, &object_key.key)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+            evicted.push(object_key.key);
+        }
+
+        debug!("evicted pipeline objects over object limit";
+            "namespace" => namespace_id, "pipeline" => pipeline_id,
+            "object_limit" => limit, "evicted" => format!("{:?}", evicted));
+
+        Ok(evicted)
+    }
+}
+
+impl Api {
+    pub async fn get_pipeline_object_handler(
+        &self,
+        token: &Token,
+        args: GetPipelineObjectRequest,
+    ) -> Result<Response<GetPipelineObjectStream>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let content = self
+            .object_store
+            .get_object_stream(&fmt::pipeline_object_key(
+                &args.namespace_id,
+                &args.pipeline_id,
+                &args.key,
+            ))
+            .await
+            .map_err(|e| object_store_error_to_status(&args.key, e))?;
+
+        let output_stream = content.map(move |chunk| {
+            chunk
+                .map(|chunk| GetPipelineObjectResponse {
+                    content: chunk.to_vec(),
+                })
+                .map_err(|e| object_store_error_to_status(&args.key, e))
+        });
+
+        Ok(Response::new(Box::pin(output_stream)))
+    }
+
+    pub async fn list_pipeline_objects_handler(
+        &self,
+        token: &Token,
+        args: ListPipelineObjectsRequest,
+    ) -> Result<Response<ListPipelineObjectsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let keys =
+            storage::objects::list_pipeline_keys(&mut conn, &args.namespace_id, &args.pipeline_id)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListPipelineObjectsResponse {
+            keys: keys.into_iter().map(|key| key.into()).collect(),
+        }))
+    }
+
+    pub async fn put_pipeline_object_handler<S>(
+        &self,
+        token: &Token,
+        requests: S,
+    ) -> Result<Response<PutPipelineObjectResponse>, Status>
+    where
+        S: Stream<Item = Result<PutPipelineObjectRequest, Status>> + Send + Unpin + 'static,
+    {
+        let (args, content) = read_upload(requests).await?;
+
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        storage::pipelines::get(&mut conn, &args.namespace_id, &args.pipeline_id)
+            .await
+            .map_err(|e| match e {
+                storage::StorageError::NotFound => Status::not_found(format!(
+                    "pipeline with id '{}' does not exist",
+                    &args.pipeline_id
+                )),
+                _ => Status::internal(e.to_string()),
+            })?;
+
+        let metadata = self
+            .object_store
+            .put_object_stream(
+                &fmt::pipeline_object_key(&args.namespace_id, &args.pipeline_id, &args.key),
+                content,
+                args.force,
+            )
+            .await
+            .map_err(|e| object_store_error_to_status(&args.key, e))?;
+
+        // Overwriting an object keeps its original key record, and therefore its place in the eviction
+        // order.
+        match storage::objects::insert_pipeline_key(
+            &mut conn,
+            &args.namespace_id,
+            &args.pipeline_id,
+            &object::ObjectKey::new(&args.key),
+        )
+        .await
+        {
+            Ok(_) => {}
+            Err(storage::StorageError::Exists) if args.force => {}
+            Err(storage::StorageError::Exists) => {
+                return Err(Status::already_exists(format!(
+                    "object with key '{}' already exists",
+                    &args.key
+                )))
+            }
+            Err(e) => return Err(Status::internal(e.to_string())),
+        };
+
+        let objects_evicted = self
+            .evict_pipeline_objects(&mut conn, &args.namespace_id, &args.pipeline_id, &args.key)
+            .await?;
+
+        Ok(Response::new(PutPipelineObjectResponse {
+            bytes: metadata.size,
+            object_limit: self.conf.object_store.pipeline_object_limit,
+            objects_evicted,
+        }))
+    }
+
+    pub async fn delete_pipeline_object_handler(
+        &self,
+        token: &Token,
+        args: DeletePipelineObjectRequest,
+    ) -> Result<Response<DeletePipelineObjectResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.object_store
+            .delete_object(&fmt::pipeline_object_key(
+                &args.namespace_id,
+                &args.pipeline_id,
+                &args.key,
+            ))
+            .await
+            .map_err(|e| object_store_error_to_status(&args.key, e))?;
+
+        storage::objects::delete_pipeline_key(
+            &mut conn,
+            &args.namespace_id,
+            &args.pipeline_id,
+            &args.key,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(DeletePipelineObjectResponse {}))
+    }
+
+    pub async fn get_run_object_handler(
+        &self,
+        token: &Token,
+        args: GetRunObjectRequest,
+    ) -> Result<Response<GetRunObjectStream>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("run_id", args.run_id, vec![validate::not_zero_num])?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let content = self
+            .object_store
+            .get_object_stream(&fmt::run_object_key(
+                &args.namespace_id,
+                &args.pipeline_id,
+                args.run_id,
+                &args.key,
+            ))
+            .await
+            .map_err(|e| object_store_error_to_status(&args.key, e))?;
+
+        let output_stream = content.map(move |chunk| {
+            chunk
+                .map(|chunk| GetRunObjectResponse {
+                    content: chunk.to_vec(),
+                })
+                .map_err(|e| object_store_error_to_status(&args.key, e))
+        });
+
+        Ok(Response::new(Box::pin(output_stream)))
+    }
+
+    pub async fn list_run_objects_handler(
+        &self,
+        token: &Token,
+        args: ListRunObjectsRequest,
+    ) -> Result<Response<ListRunObjectsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("run_id", args.run_id, vec![validate::not_zero_num])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let keys = storage::objects::list_run_keys(
+            &mut conn,
+            &args.namespace_id,
+            &args.pipeline_id,
+            args.run_id,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListRunObjectsResponse {
+            keys: keys.into_iter().map(|key| key.into()).collect(),
+        }))
+    }
+
+    pub async fn put_run_object_handler<S>(
+        &self,
+        token: &Token,
+        requests: S,
+    ) -> Result<Response<PutRunObjectResponse>, Status>
+    where
+        S: Stream<Item = Result<PutRunObjectRequest, Status>> + Send + Unpin + 'static,
+    {
+        let (args, content) = read_upload(requests).await?;
+
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("run_id", args.run_id, vec![validate::not_zero_num])?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let run = storage::runs::get(
+            &mut conn,
+            &args.namespace_id,
+            &args.pipeline_id,
+            args.run_id,
+        )
+        .await
+        .map_err(|e| match e {
+            storage::StorageError::NotFound => {
+                Status::not_found(format!("run with id '{}' does not exist", args.run_id))
+            }
+            _ => Status::internal(e.to_string()),
+        })?;
+
+        if run.store_info.is_expired {
+            return Err(Status::failed_precondition(format!(
+                "objects for run '{}' have expired; new objects can no longer be stored",
+                args.run_id
+            )));
+        }
+
+        let metadata = self
+            .object_store
+            .put_object_stream(
+                &fmt::run_object_key(
+                    &args.namespace_id,
+                    &args.pipeline_id,
+                    args.run_id,
+                    &args.key,
+                ),
+                content,
+                args.force,
+            )
+            .await
+            .map_err(|e| object_store_error_to_status(&args.key, e))?;
+
+        match storage::objects::insert_run_key(
+            &mut conn,
+            &args.namespace_id,
+            &args.pipeline_id,
+            args.run_id,
+            &object::ObjectKey::new(&args.key),
+        )
+        .await
+        {
+            Ok(_) => {}
+            Err(storage::StorageError::Exists) if args.force => {}
+            Err(storage::StorageError::Exists) => {
+                return Err(Status::already_exists(format!(
+                    "object with key '{}' already exists",
+                    &args.key
+                )))
+            }
+            Err(e) => return Err(Status::internal(e.to_string())),
+        };
+
+        Ok(Response::new(PutRunObjectResponse {
+            bytes: metadata.size,
+        }))
+    }
+
+    pub async fn delete_run_object_handler(
+        &self,
+        token: &Token,
+        args: DeleteRunObjectRequest,
+    ) -> Result<Response<DeleteRunObjectResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("run_id", args.run_id, vec![validate::not_zero_num])?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.object_store
+            .delete_object(&fmt::run_object_key(
+                &args.namespace_id,
+                &args.pipeline_id,
+                args.run_id,
+                &args.key,
+            ))
+            .await
+            .map_err(|e| object_store_error_to_status(&args.key, e))?;
+
+        storage::objects::delete_run_key(
+            &mut conn,
+            &args.namespace_id,
+            &args.pipeline_id,
+            args.run_id,
+            &args.key,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(DeleteRunObjectResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &str) -> Result<PutPipelineObjectRequest, Status> {
+        Ok(PutPipelineObjectRequest {
+            content: content.as_bytes().to_vec(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    /// The first request of an upload holds the object's details while the content of every request, including
+    /// the first, makes up the object.
+    async fn read_upload_content() {
+        let details = PutPipelineObjectRequest {
+            namespace_id: "default".to_string(),
+            pipeline_id: "simple_test_pipeline".to_string(),
+            key: "test_key".to_string(),
+            content: b"first ".to_vec(),
+            force: true,
+        };
+        let requests = stream::iter(vec![Ok(details), chunk("second "), chunk("third")]);
+
+        let (args, content) = read_upload(requests).await.unwrap();
+
+        assert_eq!(args.key, "test_key");
+        assert!(args.force);
+        assert!(args.content.is_empty());
+
+        let content: Vec<Bytes> = content.map(|chunk| chunk.unwrap()).collect().await;
+        assert_eq!(content.concat(), b"first second third".to_vec());
+    }
+
+    #[tokio::test]
+    /// An upload must at least send the object's details, and a failure partway through is passed on to the
+    /// object store so the object is never stored.
+    async fn read_upload_failures() {
+        let requests = stream::iter(Vec::<Result<PutPipelineObjectRequest, Status>>::new());
+        match read_upload(requests).await {
+            Err(e) => assert_eq!(e.code(), tonic::Code::InvalidArgument),
+            Ok(_) => panic!("upload without any requests should fail"),
+        }
+
+        let requests = stream::iter(vec![
+            chunk("first"),
+            Err(Status::cancelled("client went away")),
+        ]);
+        let (_, content) = read_upload(requests).await.unwrap();
+        let content: Vec<Result<Bytes, object_store::ObjectStoreError>> = content.collect().await;
+
+        assert_eq!(content[0], Ok(Bytes::from("first")));
+        assert!(matches!(
+            content[1],
+            Err(object_store::ObjectStoreError::Unknown(_))
+        ));
+    }
+}
@@ -1,6 +1,6 @@
-use crate::api::{epoch, validate, Api};
+use crate::api::{epoch, tokens, validate, Api};
 use crate::storage;
-use gofer_models::{event, pipeline};
+use gofer_models::{event, pipeline, token::Token};
 use gofer_models::{Variable, VariableOwner, VariableSensitivity};
 use gofer_proto::{
     CreatePipelineRequest, CreatePipelineResponse, DeletePipelineRequest, DeletePipelineResponse,
@@ -8,14 +8,137 @@ use gofer_proto::{
     GetPipelineRequest, GetPipelineResponse, ListPipelinesRequest, ListPipelinesResponse, Pipeline,
     UpdatePipelineRequest, UpdatePipelineResponse,
 };
-use std::{ops::Not, sync::Arc};
+use slog_scope::error;
+use std::{collections::HashMap, ops::Not, sync::Arc};
 use tonic::{Response, Status};
 
+impl Api {
+    /// Subscribes the pipeline to each of its triggers. Failures are recorded on the pipeline's trigger
+    /// settings rather than returned since a single misbehaving trigger shouldn't prevent the pipeline
+    /// from being used.
+    async fn subscribe_all_triggers(&self, pipeline: &mut pipeline::Pipeline) {
+        let namespace_id = &pipeline.namespace;
+        let pipeline_id = &pipeline.id;
+
+        for settings in pipeline.triggers.values_mut() {
+            if let Err(e) = self
+                .sync_trigger_subscription(namespace_id, pipeline_id, settings)
+                .await
+            {
+                error!("could not record trigger subscription"; "namespace" => namespace_id,
+                    "pipeline" => pipeline_id, "label" => &settings.label, "error" => format!("{:?}", e));
+            }
+        }
+    }
+
+    /// Compares the pipeline's current trigger settings with the updated ones, unsubscribing from triggers
+    /// that were removed or changed and subscribing to triggers that were added or changed. Subscriptions
+    /// are only sent to triggers if the pipeline is active; disabled pipelines are subscribed when they
+    /// are next enabled.
+    async fn update_trigger_subscriptions(
+        &self,
+        current_pipeline: &pipeline::Pipeline,
+        mut updated_triggers: HashMap<String, pipeline::TriggerSettings>,
+    ) -> Result<(), Status> {
+        let is_active = current_pipeline.state == pipeline::State::Active;
+        let is_unchanged = |current: &pipeline::TriggerSettings,
+                            updated: &pipeline::TriggerSettings| {
+            current.name == updated.name && current.settings == updated.settings
+        };
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        for current in current_pipeline.triggers.values() {
+            if let Some(updated) = updated_triggers.get(&current.label) {
+                if is_unchanged(current, updated) {
+                    continue;
+                }
+            }
+
+            if is_active {
+                if let Err(e) = self
+                    .unsubscribe_pipeline_trigger(
+                        &current_pipeline.namespace,
+                        &current_pipeline.id,
+                        current,
+                    )
+                    .await
+                {
+                    error!("could not unsubscribe pipeline from trigger"; "namespace" => &current_pipeline.namespace,
+                        "pipeline" => &current_pipeline.id, "label" => &current.label, "error" => format!("{:?}", e));
+                }
+            }
+
+            storage::pipelines::delete_trigger_settings(
+                &mut conn,
+                &current_pipeline.namespace,
+                &current_pipeline.id,
+                &current.label,
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        }
+
+        for updated in updated_triggers.values_mut() {
+            match current_pipeline.triggers.get(&updated.label) {
+                Some(current) if is_unchanged(current, updated) => {
+                    // Subscriptions that failed previously are given another attempt.
+                    if current.error.is_none() {
+                        continue;
+                    }
+                }
+                _ => {
+                    storage::pipelines::insert_trigger_settings(
+                        &mut conn,
+                        &current_pipeline.namespace,
+                        &current_pipeline.id,
+                        updated,
+                    )
+                    .await
+                    .map_err(|e| Status::internal(e.to_string()))?;
+                }
+            }
+
+            if is_active {
+                self.sync_trigger_subscription(
+                    &current_pipeline.namespace,
+                    &current_pipeline.id,
+                    updated,
+                )
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the pipeline's subscription from each of its triggers.
+    async fn unsubscribe_all_triggers(&self, pipeline: &pipeline::Pipeline) {
+        for settings in pipeline.triggers.values() {
+            if let Err(e) = self
+                .unsubscribe_pipeline_trigger(&pipeline.namespace, &pipeline.id, settings)
+                .await
+            {
+                error!("could not unsubscribe pipeline from trigger"; "namespace" => &pipeline.namespace,
+                    "pipeline" => &pipeline.id, "label" => &settings.label, "error" => format!("{:?}", e));
+            }
+        }
+    }
+}
+
 impl Api {
     pub async fn list_pipelines_handler(
         &self,
+        token: &Token,
         args: ListPipelinesRequest,
     ) -> Result<Response<ListPipelinesResponse>, Status> {
+        tokens::require_namespace_access(token, &args.namespace_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -45,8 +168,11 @@ impl Api {
 
     pub async fn create_pipeline_handler(
         self: Arc<Self>,
+        token: &Token,
         args: CreatePipelineRequest,
     ) -> Result<Response<CreatePipelineResponse>, Status> {
+        tokens::require_namespace_access(token, &args.namespace_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -62,8 +188,14 @@ impl Api {
             }
         };
 
-        let new_pipeline =
-            pipeline::Pipeline::new(&args.namespace_id, pipeline_config.to_owned().into());
+        let pipeline_config: gofer_sdk::config::Pipeline = pipeline_config.to_owned().into();
+        tokens::require_pipeline_access(token, &args.namespace_id, &pipeline_config.id)?;
+
+        pipeline_config
+            .validate()
+            .map_err(|e| Status::failed_precondition(format!("invalid pipeline config; {}", e)))?;
+
+        let mut new_pipeline = pipeline::Pipeline::new(&args.namespace_id, pipeline_config);
 
         let mut conn = self
             .storage
@@ -81,6 +213,8 @@ impl Api {
                 _ => Status::internal(e.to_string()),
             })?;
 
+        self.subscribe_all_triggers(&mut new_pipeline).await;
+
         let namespace_id = new_pipeline.namespace.clone();
         let pipeline_id = new_pipeline.id.clone();
 
@@ -100,8 +234,11 @@ impl Api {
 
     pub async fn get_pipeline_handler(
         &self,
+        token: &Token,
         args: GetPipelineRequest,
     ) -> Result<Response<GetPipelineResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -132,8 +269,11 @@ impl Api {
 
     pub async fn enable_pipeline_handler(
         self: Arc<Self>,
+        token: &Token,
         args: EnablePipelineRequest,
     ) -> Result<Response<EnablePipelineResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -164,6 +304,12 @@ impl Api {
             _ => Status::internal(e.to_string()),
         })?;
 
+        let mut pipeline = storage::pipelines::get(&mut conn, &args.namespace_id, &args.id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.subscribe_all_triggers(&mut pipeline).await;
+
         tokio::spawn(async move {
             self.event_bus
                 .publish(event::Kind::EnabledPipeline {
@@ -178,8 +324,11 @@ impl Api {
 
     pub async fn disable_pipeline_handler(
         self: Arc<Self>,
+        token: &Token,
         args: DisablePipelineRequest,
     ) -> Result<Response<DisablePipelineResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -210,9 +359,15 @@ impl Api {
             _ => Status::internal(e.to_string()),
         })?;
 
+        let pipeline = storage::pipelines::get(&mut conn, &args.namespace_id, &args.id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.unsubscribe_all_triggers(&pipeline).await;
+
         tokio::spawn(async move {
             self.event_bus
-                .publish(event::Kind::DeletedPipeline {
+                .publish(event::Kind::DisabledPipeline {
                     namespace_id: args.namespace_id.clone(),
                     pipeline_id: args.id.clone(),
                 })
@@ -224,8 +379,11 @@ impl Api {
 
     pub async fn update_pipeline_handler(
         &self,
+        token: &Token,
         args: UpdatePipelineRequest,
     ) -> Result<Response<UpdatePipelineResponse>, Status> {
+        tokens::require_namespace_access(token, &args.namespace_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -241,8 +399,14 @@ impl Api {
             }
         };
 
-        let new_pipeline =
-            pipeline::Pipeline::new(&args.namespace_id, pipeline_config.to_owned().into());
+        let pipeline_config: gofer_sdk::config::Pipeline = pipeline_config.to_owned().into();
+        tokens::require_pipeline_access(token, &args.namespace_id, &pipeline_config.id)?;
+
+        pipeline_config
+            .validate()
+            .map_err(|e| Status::failed_precondition(format!("invalid pipeline config; {}", e)))?;
+
+        let new_pipeline = pipeline::Pipeline::new(&args.namespace_id, pipeline_config);
 
         let mut conn = self
             .storage
@@ -250,6 +414,17 @@ impl Api {
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
+        let current_pipeline =
+            storage::pipelines::get(&mut conn, &args.namespace_id, &new_pipeline.id)
+                .await
+                .map_err(|e| match e {
+                    storage::StorageError::NotFound => Status::not_found(format!(
+                        "pipeline with id '{}' does not exist",
+                        &new_pipeline.id
+                    )),
+                    _ => Status::internal(e.to_string()),
+                })?;
+
         storage::pipelines::update(
             &mut conn,
             &args.namespace_id,
@@ -270,6 +445,8 @@ impl Api {
                     .eq(&0)
                     .not()
                     .then(|| new_pipeline.parallelism),
+                // Unlike parallelism, a run timeout of 0 is meaningful: it removes the pipeline's timeout.
+                run_timeout: Some(new_pipeline.run_timeout),
                 modified: Some(epoch()),
                 ..Default::default()
             },
@@ -283,15 +460,26 @@ impl Api {
             _ => Status::internal(e.to_string()),
         })?;
 
+        self.update_trigger_subscriptions(&current_pipeline, new_pipeline.triggers)
+            .await?;
+
+        let updated_pipeline =
+            storage::pipelines::get(&mut conn, &args.namespace_id, &current_pipeline.id)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+
         Ok(Response::new(UpdatePipelineResponse {
-            pipeline: Some(new_pipeline.into()),
+            pipeline: Some(updated_pipeline.into()),
         }))
     }
 
     pub async fn delete_pipeline_handler(
         self: Arc<Self>,
+        token: &Token,
         args: DeletePipelineRequest,
     ) -> Result<Response<DeletePipelineResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -305,7 +493,7 @@ impl Api {
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
-        storage::pipelines::get(&mut conn, &args.namespace_id, &args.id)
+        let pipeline = storage::pipelines::get(&mut conn, &args.namespace_id, &args.id)
             .await
             .map_err(|e| match e {
                 storage::StorageError::NotFound => {
@@ -314,6 +502,17 @@ impl Api {
                 _ => Status::internal(e.to_string()),
             })?;
 
+        self.unsubscribe_all_triggers(&pipeline).await;
+
+        // The secret and object keys are removed along with the pipeline, so the values have to go first.
+        self.delete_all_pipeline_secrets(&mut conn, &args.namespace_id, &args.id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.delete_all_pipeline_objects(&mut conn, &args.namespace_id, &args.id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
         storage::pipelines::delete(&mut conn, &args.namespace_id, &args.id)
             .await
             .map_err(|e| match e {
@@ -335,3 +534,206 @@ impl Api {
         Ok(Response::new(DeletePipelineResponse {}))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{
+        fmt,
+        tests::{next_event, TestHarness},
+    };
+    use gofer_models::{event::KindDiscriminant, object::ObjectKey, run};
+    use gofer_proto::{DeleteNamespaceRequest, PutPipelineSecretRequest};
+
+    /// Stores a secret, a pipeline object and a run object for the pipeline.
+    async fn store_pipeline_data(harness: &TestHarness, namespace_id: &str, pipeline_id: &str) {
+        harness
+            .api
+            .put_pipeline_secret_handler(
+                &harness.management_token(),
+                PutPipelineSecretRequest {
+                    namespace_id: namespace_id.to_string(),
+                    pipeline_id: pipeline_id.to_string(),
+                    key: "test_secret".to_string(),
+                    value: "test_value".to_string(),
+                    force: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+
+        let test_run = run::Run::new(
+            namespace_id,
+            pipeline_id,
+            run::TriggerInfo {
+                name: "test_trigger".to_string(),
+                label: "test_label".to_string(),
+            },
+            vec![],
+        );
+        let run_id = storage::runs::insert(&mut conn, &test_run).await.unwrap();
+
+        let object_key = ObjectKey::new("test_object");
+        storage::objects::insert_pipeline_key(&mut conn, namespace_id, pipeline_id, &object_key)
+            .await
+            .unwrap();
+        storage::objects::insert_run_key(&mut conn, namespace_id, pipeline_id, run_id, &object_key)
+            .await
+            .unwrap();
+
+        for key in [
+            fmt::pipeline_object_key(namespace_id, pipeline_id, "test_object"),
+            fmt::run_object_key(namespace_id, pipeline_id, run_id, "test_object"),
+        ] {
+            harness
+                .api
+                .object_store
+                .put_object(&key, b"test_value".to_vec(), false)
+                .await
+                .unwrap();
+        }
+    }
+
+    /// Reports whether the secret, pipeline object and run object stored for the pipeline are still there.
+    async fn stored_pipeline_data(
+        harness: &TestHarness,
+        namespace_id: &str,
+        pipeline_id: &str,
+    ) -> [bool; 3] {
+        let api = &harness.api;
+
+        [
+            api.secret_store
+                .get_secret(&fmt::secret_key(namespace_id, pipeline_id, "test_secret"))
+                .await
+                .is_ok(),
+            api.object_store
+                .get_object(&fmt::pipeline_object_key(
+                    namespace_id,
+                    pipeline_id,
+                    "test_object",
+                ))
+                .await
+                .is_ok(),
+            api.object_store
+                .get_object(&fmt::run_object_key(
+                    namespace_id,
+                    pipeline_id,
+                    1,
+                    "test_object",
+                ))
+                .await
+                .is_ok(),
+        ]
+    }
+
+    #[tokio::test]
+    /// Disabling a pipeline is temporary and leaves its secrets and objects alone, while deleting it removes them
+    /// from the secret and object stores.
+    async fn pipeline_data_kept_on_disable_and_removed_on_delete() {
+        let harness = TestHarness::new().await;
+        let token = harness.management_token();
+        harness
+            .create_pipeline(
+                "test_namespace",
+                gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline"),
+            )
+            .await;
+        store_pipeline_data(&harness, "test_namespace", "test_pipeline").await;
+
+        let subscription = harness
+            .api
+            .event_bus
+            .subscribe(KindDiscriminant::Any)
+            .await
+            .unwrap();
+
+        harness
+            .api
+            .clone()
+            .disable_pipeline_handler(
+                &token,
+                DisablePipelineRequest {
+                    namespace_id: "test_namespace".to_string(),
+                    id: "test_pipeline".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        next_event(&subscription).await;
+
+        harness
+            .api
+            .clone()
+            .enable_pipeline_handler(
+                &token,
+                EnablePipelineRequest {
+                    namespace_id: "test_namespace".to_string(),
+                    id: "test_pipeline".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        next_event(&subscription).await;
+
+        assert_eq!(
+            stored_pipeline_data(&harness, "test_namespace", "test_pipeline").await,
+            [true; 3]
+        );
+
+        harness
+            .api
+            .clone()
+            .delete_pipeline_handler(
+                &token,
+                DeletePipelineRequest {
+                    namespace_id: "test_namespace".to_string(),
+                    id: "test_pipeline".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stored_pipeline_data(&harness, "test_namespace", "test_pipeline").await,
+            [false; 3]
+        );
+    }
+
+    #[tokio::test]
+    /// Deleting a namespace removes the secrets and objects of every pipeline in it from the secret and object
+    /// stores.
+    async fn namespace_delete_removes_pipeline_data() {
+        let harness = TestHarness::new().await;
+        for pipeline_id in ["first_pipeline", "second_pipeline"] {
+            harness
+                .create_pipeline(
+                    "test_namespace",
+                    gofer_sdk::config::Pipeline::new(pipeline_id, "Test Pipeline"),
+                )
+                .await;
+            store_pipeline_data(&harness, "test_namespace", pipeline_id).await;
+        }
+
+        harness
+            .api
+            .clone()
+            .delete_namespace_handler(
+                &harness.management_token(),
+                DeleteNamespaceRequest {
+                    id: "test_namespace".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+        for pipeline_id in ["first_pipeline", "second_pipeline"] {
+            assert_eq!(
+                stored_pipeline_data(&harness, "test_namespace", pipeline_id).await,
+                [false; 3]
+            );
+        }
+    }
+}
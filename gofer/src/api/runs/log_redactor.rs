@@ -0,0 +1,136 @@
+/// What each sensitive value is replaced with in task run logs.
+const REDACTED: &[u8] = b"[redacted]";
+
+/// Removes sensitive values from a task run's log stream as it is written. Logs arrive in arbitrarily sized
+/// chunks, so the end of a chunk that could be the start of a sensitive value is held back until the next
+/// chunk shows whether it is one.
+#[derive(Debug)]
+pub struct LogRedactor {
+    /// Longest first, so that a value that contains another is redacted whole.
+    values: Vec<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl LogRedactor {
+    pub fn new(values: &[String]) -> Self {
+        let mut values: Vec<Vec<u8>> = values
+            .iter()
+            .filter(|value| !value.is_empty())
+            .map(|value| value.as_bytes().to_vec())
+            .collect();
+        values.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        values.dedup();
+
+        Self {
+            values,
+            pending: vec![],
+        }
+    }
+
+    /// Returns the chunk with all sensitive values redacted, minus anything held back for the next chunk.
+    pub fn redact(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.values.is_empty() {
+            return chunk.to_vec();
+        }
+
+        self.pending.extend_from_slice(chunk);
+        self.drain_pending(false)
+    }
+
+    /// Returns whatever was held back, redacted. Called once the log stream has ended.
+    pub fn finish(&mut self) -> Vec<u8> {
+        self.drain_pending(true)
+    }
+
+    fn drain_pending(&mut self, finished: bool) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.pending.len());
+        let mut position = 0;
+
+        'outer: while position < self.pending.len() {
+            let remaining = &self.pending[position..];
+
+            for value in &self.values {
+                if remaining.starts_with(value) {
+                    output.extend_from_slice(REDACTED);
+                    position += value.len();
+                    continue 'outer;
+                }
+
+                // The rest of the stream might complete this value.
+                if !finished && value.starts_with(remaining) {
+                    break 'outer;
+                }
+            }
+
+            output.push(remaining[0]);
+            position += 1;
+        }
+
+        self.pending.drain(..position);
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact_chunks(values: &[&str], chunks: &[&str]) -> String {
+        let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        let mut redactor = LogRedactor::new(&values);
+
+        let mut output = vec![];
+        for chunk in chunks {
+            output.extend(redactor.redact(chunk.as_bytes()));
+        }
+        output.extend(redactor.finish());
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    /// Values that arrive within a single chunk are redacted.
+    fn redact_whole_values() {
+        assert_eq!(
+            "token is [redacted] and [redacted]\n",
+            redact_chunks(&["hunter2"], &["token is hunter2 and hunter2\n"])
+        );
+    }
+
+    #[test]
+    /// Values split across several chunks are still redacted.
+    fn redact_values_split_across_chunks() {
+        assert_eq!(
+            "token is [redacted]\n",
+            redact_chunks(&["hunter2"], &["token is hun", "te", "r2\n"])
+        );
+    }
+
+    #[test]
+    /// Text held back because it might have been a value is released once it turns out not to be.
+    fn release_held_back_partial_values() {
+        assert_eq!(
+            "token is hunt\nhun",
+            redact_chunks(&["hunter2"], &["token is hun", "t\nhun"])
+        );
+    }
+
+    #[test]
+    /// When one value contains another the longer value is redacted whole.
+    fn redact_overlapping_values() {
+        assert_eq!(
+            "[redacted] [redacted]",
+            redact_chunks(&["abc", "abcdef"], &["abcd", "ef ab", "c"])
+        );
+    }
+
+    #[test]
+    /// Logs pass through untouched when there is nothing to redact.
+    fn no_values_passes_logs_through() {
+        assert_eq!(
+            "nothing to see",
+            redact_chunks(&[""], &["nothing ", "to see"])
+        );
+    }
+}
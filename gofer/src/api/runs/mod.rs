@@ -1,22 +1,26 @@
+mod log_redactor;
 mod state_machine;
 
-use crate::api::{validate, Api};
+use crate::api::{epoch, tokens, validate, Api};
 use crate::storage;
 use anyhow::Result;
-use gofer_models::{event, pipeline, run, task, task_run};
+use gofer_models::{event, pipeline, run, task, task_run, token::Token};
 use gofer_models::{Variable, VariableOwner, VariableSensitivity};
 use gofer_proto::{
     CancelAllRunsRequest, CancelAllRunsResponse, CancelRunRequest, CancelRunResponse,
     GetRunRequest, GetRunResponse, ListRunsRequest, ListRunsResponse, RetryRunRequest,
     RetryRunResponse, Run, StartRunRequest, StartRunResponse,
 };
-use slog_scope::debug;
-use sqlx::Acquire;
+use slog_scope::{debug, error, info};
 use state_machine::RunStateMachine;
 use std::{collections::HashMap, sync::Arc};
 use strum::{Display, EnumString};
 use tonic::{Response, Status};
 
+/// How often the run dispatcher re-checks the run queue when nothing else has woken it up. This mostly exists
+/// so that the dispatcher recovers from errors encountered while reading the queue.
+const DISPATCH_RECHECK_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(30);
+
 /// Gofer allows users to enter special interpolation strings such that
 /// special functionality is substituted when Gofer reads these strings
 /// in a user's pipeline configuration.
@@ -72,10 +76,32 @@ pub fn parse_interpolation_syntax(kind: InterpolationKind, input: &str) -> Optio
     None
 }
 
+/// Removes the values of private variables so that they aren't handed out by the API. Values that only
+/// reference a secret are kept since they reveal nothing; the secret itself is only resolved when a task run
+/// starts.
+pub fn redact_private_variables(variables: &mut [gofer_proto::Variable]) {
+    for variable in variables {
+        if variable.sensitivity != gofer_proto::variable::VariableSensitivity::Private as i32 {
+            continue;
+        }
+
+        if parse_interpolation_syntax(InterpolationKind::Secret, &variable.value).is_some() {
+            continue;
+        }
+
+        variable.value = String::new();
+        variable.redacted = true;
+    }
+}
+
 /// On every run Gofer injects some vars that are determined by the system.
 /// These are usually meant to give the user some basic information that they can pull
 /// into their program about the details of the run.
-fn system_injected_vars(run: &run::Run, task: &task::Task) -> HashMap<String, Variable> {
+fn system_injected_vars(
+    run: &run::Run,
+    task: &task::Task,
+    api_token: &str,
+) -> HashMap<String, Variable> {
     HashMap::from([
         (
             "GOFER_PIPELINE_ID".to_string(),
@@ -117,7 +143,7 @@ fn system_injected_vars(run: &run::Run, task: &task::Task) -> HashMap<String, Va
             "GOFER_API_TOKEN".to_string(),
             Variable {
                 key: "GOFER_API_TOKEN".to_string(),
-                value: "".to_string(), //TODO(clintjedwards): token needed here.
+                value: api_token.to_string(),
                 owner: VariableOwner::System,
                 sensitivity: VariableSensitivity::Private,
             },
@@ -142,8 +168,8 @@ fn system_injected_vars(run: &run::Run, task: &task::Task) -> HashMap<String, Va
 /// 3) Lastly we pass in the run specific defined envvars. These are usually provided by either a trigger
 /// or the user when they attempt to start a new run manually. Since these are the most likely to be
 /// edited adhoc they are treated as the most important.
-pub fn combine_variables(run: &run::Run, task: &task::Task) -> Vec<Variable> {
-    let system_injected_vars = system_injected_vars(run, task);
+pub fn combine_variables(run: &run::Run, task: &task::Task, api_token: &str) -> Vec<Variable> {
+    let system_injected_vars = system_injected_vars(run, task, api_token);
 
     let task_vars: HashMap<String, Variable> = task
         .variables
@@ -184,12 +210,31 @@ impl Api {
         pipeline_id: String,
         run_id: u64,
     ) -> Result<()> {
+        // Subscribe before we cancel anything so that we can't miss the run being marked complete.
+        let mut run_completions = self.run_completions.subscribe();
+
         let mut conn = self
             .storage
             .conn()
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
+        let run = storage::runs::get(&mut conn, &namespace_id, &pipeline_id, run_id).await?;
+
+        // Runs still waiting in the queue have no task runs yet, so we can just remove them from the queue.
+        if run.state == run::State::Pending {
+            self.finish_queued_run(
+                &run,
+                run::Status::Cancelled,
+                run::StatusReason {
+                    reason: run::Reason::UserCancelled,
+                    description: "The run was cancelled before it could be started".to_string(),
+                },
+            )
+            .await;
+            return Ok(());
+        }
+
         let task_runs =
             storage::task_runs::list(&mut conn, 0, 0, &namespace_id, &pipeline_id, run_id).await?;
 
@@ -200,38 +245,53 @@ impl Api {
 
         let mut cancelled_task_runs: Vec<task_run::TaskRun> = vec![];
 
+        // Earlier attempts of retried task runs have already finished and are left alone.
         for task_run in task_runs {
-            if self
-                .cancel_task_run(&namespace_id, &pipeline_id, run_id, &task_run.id, timeout)
-                .await
-                .is_ok()
-            {
+            if task_run.state == task_run::State::Complete {
+                continue;
+            }
+
+            if self.cancel_task_run(&task_run, timeout).await.is_ok() {
                 cancelled_task_runs.push(task_run)
             }
         }
 
+        // The connection is given back while we wait so that it isn't held for as long as the task runs
+        // take to stop.
+        drop(conn);
+
         // wait for the run to be marked complete due to the failures in task_runs.
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            let mut conn = self.storage.conn().await?;
             let run = storage::runs::get(&mut conn, &namespace_id, &pipeline_id, run_id).await?;
+            drop(conn);
 
-            if run.state != run::State::Complete {
-                continue;
+            if run.state == run::State::Complete {
+                break;
             }
 
-            for task_run in cancelled_task_runs {
-                let _ = storage::task_runs::update(
-                    &mut conn,
-                    &task_run,
-                    storage::task_runs::UpdatableFields {
-                        status: Some(task_run::Status::Cancelled),
-                        ..Default::default()
-                    },
-                )
-                .await;
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return Ok(()),
+                result = run_completions.changed() => {
+                    if result.is_err() {
+                        return Ok(());
+                    }
+                },
             }
+        }
 
-            break;
+        let mut conn = self.storage.conn().await?;
+
+        for task_run in cancelled_task_runs {
+            let _ = storage::task_runs::update(
+                &mut conn,
+                &task_run,
+                storage::task_runs::UpdatableFields {
+                    status: Some(task_run::Status::Cancelled),
+                    ..Default::default()
+                },
+            )
+            .await;
         }
 
         Ok(())
@@ -241,8 +301,15 @@ impl Api {
 impl Api {
     pub async fn get_run_handler(
         &self,
+        token: &Token,
         args: GetRunRequest,
     ) -> Result<Response<GetRunResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        if args.reveal_private_variables {
+            tokens::require_management_token(token)?;
+        }
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -267,9 +334,12 @@ impl Api {
         storage::runs::get(&mut conn, &args.namespace_id, &args.pipeline_id, args.id)
             .await
             .map(|run| {
-                Response::new(GetRunResponse {
-                    run: Some(run.into()),
-                })
+                let mut run = Run::from(run);
+                if !args.reveal_private_variables {
+                    redact_private_variables(&mut run.variables);
+                }
+
+                Response::new(GetRunResponse { run: Some(run) })
             })
             .map_err(|e| match e {
                 storage::StorageError::NotFound => {
@@ -281,8 +351,11 @@ impl Api {
 
     pub async fn list_runs_handler(
         &self,
+        token: &Token,
         args: ListRunsRequest,
     ) -> Result<Response<ListRunsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -310,7 +383,14 @@ impl Api {
         .await
         .map(|runs| {
             Response::new(ListRunsResponse {
-                runs: runs.into_iter().map(Run::from).collect(),
+                runs: runs
+                    .into_iter()
+                    .map(|run| {
+                        let mut run = Run::from(run);
+                        redact_private_variables(&mut run.variables);
+                        run
+                    })
+                    .collect(),
             })
         })
         .map_err(|e| Status::internal(e.to_string()))
@@ -318,8 +398,11 @@ impl Api {
 
     pub async fn retry_run_handler(
         self: Arc<Self>,
+        token: &Token,
         args: RetryRunRequest,
     ) -> Result<Response<RetryRunResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -353,15 +436,19 @@ impl Api {
         })?;
 
         let resp = self
-            .start_run_handler(StartRunRequest {
-                namespace_id: run.namespace,
-                pipeline_id: run.pipeline,
-                variables: run
-                    .variables
-                    .into_iter()
-                    .map(|variable| (variable.key, variable.value))
-                    .collect(),
-            })
+            .start_run_handler(
+                token,
+                StartRunRequest {
+                    namespace_id: run.namespace,
+                    pipeline_id: run.pipeline,
+                    variables: run
+                        .variables
+                        .into_iter()
+                        .map(|variable| (variable.key, variable.value))
+                        .collect(),
+                    priority: run.priority,
+                },
+            )
             .await?;
 
         Ok(Response::new(RetryRunResponse {
@@ -370,8 +457,11 @@ impl Api {
     }
     pub async fn cancel_run_handler(
         self: Arc<Self>,
+        token: &Token,
         args: CancelRunRequest,
     ) -> Result<Response<CancelRunResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -397,8 +487,11 @@ impl Api {
     }
     pub async fn cancel_all_runs_handler(
         self: Arc<Self>,
+        token: &Token,
         args: CancelAllRunsRequest,
     ) -> Result<Response<CancelAllRunsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -444,8 +537,11 @@ impl Api {
 
     pub async fn start_run_handler(
         self: Arc<Self>,
+        token: &Token,
         args: StartRunRequest,
     ) -> Result<Response<StartRunResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -457,6 +553,44 @@ impl Api {
             vec![validate::is_valid_identifier],
         )?;
 
+        let new_run = self
+            .start_run(
+                &args.namespace_id,
+                &args.pipeline_id,
+                run::TriggerInfo {
+                    name: "manual".to_string(),
+                    label: "api".to_string(),
+                },
+                variables_to_vec(
+                    args.variables,
+                    VariableOwner::User,
+                    VariableSensitivity::Public,
+                ),
+                args.priority,
+            )
+            .await?;
+
+        let mut new_run = Run::from(new_run);
+        redact_private_variables(&mut new_run.variables);
+
+        Ok(Response::new(StartRunResponse { run: Some(new_run) }))
+    }
+}
+
+impl Api {
+    /// Creates a new run for the given pipeline and places it in the run queue. The run is returned in the
+    /// pending state; the run dispatcher is responsible for actually starting it once the parallelism limits
+    /// allow. This is shared between runs started manually through the API and runs
+    /// started by triggers; the caller is responsible for describing where the run came from via the trigger
+    /// info and providing any run level variables.
+    pub async fn start_run(
+        self: Arc<Self>,
+        namespace_id: &str,
+        pipeline_id: &str,
+        trigger: run::TriggerInfo,
+        variables: Vec<Variable>,
+        priority: i64,
+    ) -> Result<run::Run, Status> {
         let mut conn = self
             .storage
             .conn()
@@ -471,13 +605,12 @@ impl Api {
             .map_err(|e| Status::internal(e.to_string()))?;
 
         // Make sure the pipeline is ready to take new runs.
-        let pipeline = storage::pipelines::get(&mut tx, &args.namespace_id, &args.pipeline_id)
+        let pipeline = storage::pipelines::get(&mut tx, namespace_id, pipeline_id)
             .await
             .map_err(|e| match e {
-                storage::StorageError::NotFound => Status::not_found(format!(
-                    "pipeline with id '{}' does not exist",
-                    &args.pipeline_id
-                )),
+                storage::StorageError::NotFound => {
+                    Status::not_found(format!("pipeline with id '{}' does not exist", pipeline_id))
+                }
                 _ => Status::internal(e.to_string()),
             })?;
 
@@ -488,19 +621,8 @@ impl Api {
         }
 
         // Create the new run and retrieve it's ID.
-        let mut new_run = run::Run::new(
-            &pipeline.namespace,
-            &pipeline.id,
-            run::TriggerInfo {
-                name: "manual".to_string(),
-                label: "api".to_string(),
-            },
-            variables_to_vec(
-                args.variables,
-                VariableOwner::User,
-                VariableSensitivity::Public,
-            ),
-        );
+        let mut new_run = run::Run::new(&pipeline.namespace, &pipeline.id, trigger, variables);
+        new_run.priority = priority;
 
         let id = storage::runs::insert(&mut tx, &new_run)
             .await
@@ -528,22 +650,537 @@ impl Api {
                 .await
         });
 
-        let run_state_machine = RunStateMachine::new(self, pipeline.clone(), new_run.clone()).await;
+        // Wake up the dispatcher so it can start the run as soon as the parallelism limits allow.
+        self.queued_runs.send_replace(());
+
+        Ok(new_run)
+    }
+
+    /// Finds all runs that were still in progress when Gofer last shut down and resumes them.
+    pub async fn restore_runs(self: Arc<Self>) -> Result<()> {
+        let mut conn = self.storage.conn().await?;
+
+        let runs = storage::runs::list_running(&mut conn).await?;
+
+        for run in runs {
+            let pipeline =
+                match storage::pipelines::get(&mut conn, &run.namespace, &run.pipeline).await {
+                    Ok(pipeline) => pipeline,
+                    Err(e) => {
+                        error!("could not resume run; could not get pipeline";
+                            "namespace" => &run.namespace, "pipeline" => &run.pipeline,
+                            "run" => run.id, "error" => format!("{:?}", e));
+                        continue;
+                    }
+                };
+
+            info!("resuming run"; "namespace" => &run.namespace, "pipeline" => &run.pipeline, "run" => run.id);
+
+            let run_state_machine = RunStateMachine::new(self.clone(), pipeline, run).await;
+            tokio::spawn(run_state_machine.resume_task_tree());
+        }
+
+        Ok(())
+    }
+
+    /// Runs for the lifetime of the server, starting queued runs whenever a new run is queued or a running
+    /// run finishes and frees up room under the parallelism limits.
+    pub async fn dispatch_runs(self: Arc<Self>) {
+        let mut queued_runs = self.queued_runs.subscribe();
+        let mut run_completions = self.run_completions.subscribe();
+
+        loop {
+            if let Err(e) = self.clone().dispatch_queued_runs().await {
+                error!("could not dispatch queued runs"; "error" => format!("{:?}", e));
+            }
+
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return,
+                _ = queued_runs.changed() => {},
+                _ = run_completions.changed() => {},
+                _ = tokio::time::sleep(DISPATCH_RECHECK_INTERVAL) => {},
+            }
+        }
+    }
+
+    /// Starts as many pending runs as the parallelism limits currently allow. Pending runs are considered
+    /// in order of highest priority first and then in the order they were queued. Runs that are over the
+    /// limits stay queued, while the runs of pipelines that were disabled are still failed.
+    async fn dispatch_queued_runs(self: Arc<Self>) -> Result<()> {
+        let mut conn = self.storage.conn().await?;
+
+        let pending_runs = storage::runs::list_pending(&mut conn).await?;
+
+        // The global limit also caps the amount of runs in progress across all pipelines.
+        let global_limit = self.conf.general.run_parallelism_limit;
+        let mut total_running = storage::runs::count_all_running(&mut conn).await?;
+
+        // Keeps track of the pipeline and the amount of runs in progress for every pipeline we've
+        // come across during this pass so we don't have to keep querying them for every run.
+        let mut pipelines: HashMap<(String, String), (pipeline::Pipeline, u64)> = HashMap::new();
+
+        for mut queued_run in pending_runs {
+            let key = (queued_run.namespace.clone(), queued_run.pipeline.clone());
+
+            // A run we can't look up or start is skipped for this pass so that it doesn't hold up the runs
+            // queued behind it. It is tried again on the next pass.
+            if !pipelines.contains_key(&key) {
+                let pipeline_and_running = async {
+                    let pipeline = storage::pipelines::get(
+                        &mut conn,
+                        &queued_run.namespace,
+                        &queued_run.pipeline,
+                    )
+                    .await?;
+                    let running =
+                        storage::runs::count_running(&mut conn, &pipeline.namespace, &pipeline.id)
+                            .await?;
+
+                    Ok::<_, storage::StorageError>((pipeline, running))
+                }
+                .await;
 
-        // Make sure the pipeline is ready for a new run.
-        while run_state_machine
-            .parallelism_limit_exceeded(&mut conn)
+                match pipeline_and_running {
+                    Ok(pipeline_and_running) => {
+                        pipelines.insert(key.clone(), pipeline_and_running);
+                    }
+                    Err(e) => {
+                        error!("could not get pipeline for queued run; skipping"; "namespace" => &queued_run.namespace,
+                            "pipeline" => &queued_run.pipeline, "run" => queued_run.id, "error" => format!("{:?}", e));
+                        continue;
+                    }
+                }
+            }
+
+            let (pipeline, running) = pipelines.get_mut(&key).unwrap();
+
+            if pipeline.state != pipeline::State::Active {
+                self.finish_queued_run(
+                    &queued_run,
+                    run::Status::Failed,
+                    run::StatusReason {
+                        reason: run::Reason::FailedPrecondition,
+                        description: "The pipeline was disabled before the run could be started"
+                            .to_string(),
+                    },
+                )
+                .await;
+                continue;
+            }
+
+            if global_limit != 0 && total_running >= global_limit {
+                continue;
+            }
+
+            let limit = self.parallelism_limit(pipeline);
+            if limit != 0 && *running >= limit {
+                continue;
+            }
+
+            // The run's start time is reset so that time spent waiting in the queue doesn't count towards
+            // the run's timeout.
+            let started = epoch();
+
+            if let Err(e) = storage::runs::update(
+                &mut conn,
+                &queued_run,
+                storage::runs::UpdatableFields {
+                    state: Some(run::State::Running),
+                    started: Some(started),
+                    ..Default::default()
+                },
+            )
             .await
+            {
+                error!("could not start queued run; skipping"; "namespace" => &queued_run.namespace,
+                    "pipeline" => &queued_run.pipeline, "run" => queued_run.id, "error" => format!("{:?}", e));
+                continue;
+            }
+
+            queued_run.state = run::State::Running;
+            queued_run.started = started;
+            *running += 1;
+            total_running += 1;
+
+            debug!("dispatching queued run"; "namespace" => &queued_run.namespace,
+                "pipeline" => &queued_run.pipeline, "run" => queued_run.id, "priority" => queued_run.priority);
+
+            let run_state_machine =
+                RunStateMachine::new(self.clone(), pipeline.clone(), queued_run).await;
+            tokio::spawn(run_state_machine.execute_task_tree());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the maximum amount of runs a pipeline is allowed to have in progress at once. The global
+    /// limit is used when the pipeline does not define one and otherwise caps the pipeline's own limit.
+    /// 0 means there is no limit.
+    fn parallelism_limit(&self, pipeline: &pipeline::Pipeline) -> u64 {
+        let global_limit = self.conf.general.run_parallelism_limit;
+
+        match (pipeline.parallelism, global_limit) {
+            (0, global_limit) => global_limit,
+            (limit, 0) => limit,
+            (limit, global_limit) => limit.min(global_limit),
+        }
+    }
+
+    /// Completes a run that is still waiting in the run queue without ever starting it.
+    async fn finish_queued_run(
+        &self,
+        queued_run: &run::Run,
+        status: run::Status,
+        reason: run::StatusReason,
+    ) {
+        let mut conn = match self.storage.conn().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("could not finish queued run"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+
+        if let Err(e) = storage::runs::update(
+            &mut conn,
+            queued_run,
+            storage::runs::UpdatableFields {
+                state: Some(run::State::Complete),
+                status: Some(status.clone()),
+                failure_info: Some(reason),
+                ended: Some(epoch()),
+                ..Default::default()
+            },
+        )
+        .await
         {
-            debug!("parallelism limit exceeded; waiting for runs to end before launching new run"; "run" => &new_run.id, "limit" => &pipeline.parallelism);
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            error!("could not finish queued run"; "error" => format!("{:?}", e));
+            return;
+        };
+
+        self.run_completions.send_replace(());
+
+        self.event_bus
+            .publish(event::Kind::CompletedRun {
+                namespace_id: queued_run.namespace.clone(),
+                pipeline_id: queued_run.pipeline.clone(),
+                run_id: queued_run.id,
+                status,
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::{FakeScheduler, TestHarness};
+    use gofer_proto::variable::VariableSensitivity as ProtoSensitivity;
+
+    fn proto_variable(
+        key: &str,
+        value: &str,
+        sensitivity: ProtoSensitivity,
+    ) -> gofer_proto::Variable {
+        gofer_proto::Variable {
+            key: key.to_string(),
+            value: value.to_string(),
+            sensitivity: sensitivity as i32,
+            ..Default::default()
         }
+    }
 
-        // Finally, launch the thread that will launch all the task runs for a job.
-        tokio::spawn(run_state_machine.execute_task_tree());
+    #[test]
+    /// Private values are redacted while public values and secret references are left alone.
+    fn redact_private_variable_values() {
+        let mut variables = vec![
+            proto_variable("PUBLIC", "public_value", ProtoSensitivity::Public),
+            proto_variable("PRIVATE", "private_value", ProtoSensitivity::Private),
+            proto_variable("SECRET", "secret{{ my_secret }}", ProtoSensitivity::Private),
+        ];
 
-        Ok(Response::new(StartRunResponse {
-            run: Some(new_run.into()),
-        }))
+        redact_private_variables(&mut variables);
+
+        assert_eq!(
+            variables,
+            vec![
+                proto_variable("PUBLIC", "public_value", ProtoSensitivity::Public),
+                gofer_proto::Variable {
+                    redacted: true,
+                    ..proto_variable("PRIVATE", "", ProtoSensitivity::Private)
+                },
+                proto_variable("SECRET", "secret{{ my_secret }}", ProtoSensitivity::Private),
+            ]
+        );
+    }
+
+    /// Returns a harness whose task runs keep running until the returned scheduler exits their containers.
+    async fn dispatcher_harness(run_parallelism_limit: u64) -> (TestHarness, Arc<FakeScheduler>) {
+        let mut conf = crate::conf::api::Config::default();
+        conf.general.run_parallelism_limit = run_parallelism_limit;
+
+        let scheduler = Arc::new(FakeScheduler::default());
+        let harness = TestHarness::with_scheduler(conf, scheduler.clone()).await;
+
+        (harness, scheduler)
+    }
+
+    /// Stores a pipeline with a single task and the given parallelism limit.
+    async fn create_pipeline(harness: &TestHarness, pipeline_id: &str, parallelism: u64) {
+        harness
+            .create_pipeline(
+                "test_namespace",
+                gofer_sdk::config::Pipeline::new(pipeline_id, "Test Pipeline")
+                    .parallelism(parallelism)
+                    .tasks(vec![gofer_sdk::config::Task::new(
+                        "test_task",
+                        "ubuntu:latest",
+                    )]),
+            )
+            .await;
+    }
+
+    /// Places a new run in the run queue, returning its id.
+    async fn queue_run(harness: &TestHarness, pipeline_id: &str, priority: i64) -> u64 {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+
+        let mut new_run = run::Run::new(
+            "test_namespace",
+            pipeline_id,
+            run::TriggerInfo {
+                name: "test_trigger".to_string(),
+                label: "test_label".to_string(),
+            },
+            vec![],
+        );
+        new_run.priority = priority;
+
+        storage::runs::insert(&mut conn, &new_run).await.unwrap()
+    }
+
+    async fn get_run(harness: &TestHarness, pipeline_id: &str, id: u64) -> run::Run {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::runs::get(&mut conn, "test_namespace", pipeline_id, id)
+            .await
+            .unwrap()
+    }
+
+    /// Returns the ids of the given pipeline's runs that are currently running.
+    async fn running_runs(harness: &TestHarness, pipeline_id: &str) -> Vec<u64> {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        let mut running: Vec<u64> =
+            storage::runs::list(&mut conn, 0, 0, "test_namespace", pipeline_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|run| run.state == run::State::Running)
+                .map(|run| run.id)
+                .collect();
+        running.sort_unstable();
+        running
+    }
+
+    /// Waits for the run to reach the given state.
+    async fn wait_for_run_state(
+        harness: &TestHarness,
+        pipeline_id: &str,
+        id: u64,
+        state: run::State,
+    ) {
+        for _ in 0..1000 {
+            if get_run(harness, pipeline_id, id).await.state == state {
+                return;
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+
+        panic!("run {} never reached state {}", id, state);
+    }
+
+    /// Exits the containers of every run in progress and waits for those runs to finish.
+    async fn finish_running_runs(harness: &TestHarness, scheduler: &FakeScheduler) {
+        // The containers of dispatched runs are started in the background.
+        for _ in 0..1000 {
+            if !scheduler.running().is_empty() {
+                break;
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+
+        let runs = {
+            let mut conn = harness.api.storage.conn().await.unwrap();
+            storage::runs::list_running(&mut conn).await.unwrap()
+        };
+
+        for container in scheduler.running() {
+            scheduler.exit(&container, 0);
+        }
+
+        for run in runs {
+            wait_for_run_state(harness, &run.pipeline, run.id, run::State::Complete).await;
+        }
+    }
+
+    #[tokio::test]
+    /// Queued runs are started highest priority first and then in the order they were queued, no more than the
+    /// pipeline's parallelism limit at a time.
+    async fn dispatch_queued_runs_by_priority() {
+        let (harness, scheduler) = dispatcher_harness(0).await;
+        create_pipeline(&harness, "test_pipeline", 1).await;
+
+        for priority in [0, 5, 5, 0] {
+            queue_run(&harness, "test_pipeline", priority).await;
+        }
+
+        for expected in [2, 3, 1, 4] {
+            harness.api.clone().dispatch_queued_runs().await.unwrap();
+            assert_eq!(running_runs(&harness, "test_pipeline").await, vec![expected]);
+
+            finish_running_runs(&harness, &scheduler).await;
+        }
+
+        harness.api.clone().dispatch_queued_runs().await.unwrap();
+        assert!(running_runs(&harness, "test_pipeline").await.is_empty());
+    }
+
+    #[tokio::test]
+    /// Dispatching a run resets its start time, which must not affect how the next run is numbered.
+    async fn new_run_numbered_after_dispatched_runs() {
+        let (harness, _scheduler) = dispatcher_harness(0).await;
+        create_pipeline(&harness, "test_pipeline", 1).await;
+
+        queue_run(&harness, "test_pipeline", 5).await;
+        queue_run(&harness, "test_pipeline", 0).await;
+
+        // The first run ends up with the latest start time.
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+        harness.api.clone().dispatch_queued_runs().await.unwrap();
+        assert_eq!(running_runs(&harness, "test_pipeline").await, vec![1]);
+
+        assert_eq!(queue_run(&harness, "test_pipeline", 0).await, 3);
+    }
+
+    #[tokio::test]
+    /// The global parallelism limit caps the runs in progress across all pipelines, not just within each one.
+    async fn dispatch_queued_runs_global_limit() {
+        let (harness, _scheduler) = dispatcher_harness(2).await;
+        create_pipeline(&harness, "test_pipeline_a", 0).await;
+        create_pipeline(&harness, "test_pipeline_b", 0).await;
+
+        for _ in 0..2 {
+            queue_run(&harness, "test_pipeline_a", 0).await;
+            queue_run(&harness, "test_pipeline_b", 1).await;
+        }
+
+        harness.api.clone().dispatch_queued_runs().await.unwrap();
+
+        assert!(running_runs(&harness, "test_pipeline_a").await.is_empty());
+        assert_eq!(running_runs(&harness, "test_pipeline_b").await, vec![1, 2]);
+
+        // Nothing else is started until a run finishes.
+        harness.api.clone().dispatch_queued_runs().await.unwrap();
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        assert_eq!(storage::runs::count_all_running(&mut conn).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    /// Runs still queued when their pipeline is disabled are failed instead of started.
+    async fn dispatch_queued_runs_disabled_pipeline() {
+        let (harness, scheduler) = dispatcher_harness(0).await;
+        create_pipeline(&harness, "test_pipeline", 0).await;
+
+        let id = queue_run(&harness, "test_pipeline", 0).await;
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::pipelines::update(
+            &mut conn,
+            "test_namespace",
+            "test_pipeline",
+            storage::pipelines::UpdatableFields {
+                state: Some(pipeline::State::Disabled),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        harness.api.clone().dispatch_queued_runs().await.unwrap();
+
+        let queued_run = get_run(&harness, "test_pipeline", id).await;
+        assert_eq!(queued_run.state, run::State::Complete);
+        assert_eq!(queued_run.status, run::Status::Failed);
+        assert_eq!(
+            queued_run.status_reason.unwrap().reason,
+            run::Reason::FailedPrecondition
+        );
+        assert!(scheduler.running().is_empty());
+    }
+
+    #[tokio::test]
+    /// Runs left in the queue by a previous instance of Gofer are picked up when the dispatcher starts, and the
+    /// rest are started as running runs finish.
+    async fn dispatch_runs_restores_queue() {
+        let (harness, scheduler) = dispatcher_harness(0).await;
+        create_pipeline(&harness, "test_pipeline", 1).await;
+
+        queue_run(&harness, "test_pipeline", 0).await;
+        queue_run(&harness, "test_pipeline", 0).await;
+
+        tokio::spawn(harness.api.clone().dispatch_runs());
+
+        wait_for_run_state(&harness, "test_pipeline", 1, run::State::Running).await;
+        assert_eq!(
+            get_run(&harness, "test_pipeline", 2).await.state,
+            run::State::Pending
+        );
+
+        finish_running_runs(&harness, &scheduler).await;
+
+        wait_for_run_state(&harness, "test_pipeline", 2, run::State::Running).await;
+    }
+
+    #[tokio::test]
+    /// Cancelling a run returns once the run has finished and marks its stopped task runs as cancelled.
+    async fn cancel_run_waits_for_run() {
+        let (harness, scheduler) = dispatcher_harness(0).await;
+        create_pipeline(&harness, "test_pipeline", 0).await;
+
+        let id = queue_run(&harness, "test_pipeline", 0).await;
+        harness.api.clone().dispatch_queued_runs().await.unwrap();
+
+        let container = crate::api::fmt::task_container_id(
+            "test_namespace",
+            "test_pipeline",
+            id,
+            "test_task",
+            1,
+        );
+        scheduler.wait_for_start(&container).await;
+
+        tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            harness
+                .api
+                .cancel_run("test_namespace".into(), "test_pipeline".into(), id),
+        )
+        .await
+        .expect("cancel_run did not return once the run finished")
+        .unwrap();
+
+        assert_eq!(
+            get_run(&harness, "test_pipeline", id).await.state,
+            run::State::Complete
+        );
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        let task_runs =
+            storage::task_runs::list(&mut conn, 0, 0, "test_namespace", "test_pipeline", id)
+                .await
+                .unwrap();
+        assert_eq!(task_runs.len(), 1);
+        assert_eq!(task_runs[0].status, task_run::Status::Cancelled);
     }
 }
@@ -1,15 +1,61 @@
+use super::log_redactor::LogRedactor;
 use super::{combine_variables, parse_interpolation_syntax, InterpolationKind};
 use crate::api::{epoch, fmt, Api, GOFER_EOF};
 use crate::{scheduler, storage};
 use anyhow::Result;
 use dashmap::DashMap;
 use futures::StreamExt;
-use gofer_models::Variable;
-use gofer_models::{event, pipeline, run, task, task_run};
+use gofer_models::{common_task, event, pipeline, run, task, task_run};
+use gofer_models::{Variable, VariableOwner, VariableSensitivity};
 use slog_scope::{debug, error};
-use sqlx::SqliteConnection;
 use std::{collections::HashMap, sync::Arc};
-use tokio::io::AsyncWriteExt;
+use tokio::{io::AsyncWriteExt, sync::watch};
+
+/// Combines a task's requested resources with the server's configured default and maximum limit,
+/// returning the final request and limit to pass to the scheduler. A limit is always capped by the maximum
+/// and a request is never allowed to exceed the final limit.
+fn resolve_resources(
+    request: u64,
+    limit: u64,
+    default_limit: u64,
+    max_limit: u64,
+) -> (Option<u64>, Option<u64>) {
+    let mut limit = if limit == 0 { default_limit } else { limit };
+
+    if max_limit != 0 && (limit == 0 || limit > max_limit) {
+        limit = max_limit;
+    }
+
+    let mut request = request;
+
+    if limit != 0 && request > limit {
+        request = limit;
+    }
+
+    (
+        (request != 0).then_some(request),
+        (limit != 0).then_some(limit),
+    )
+}
+
+/// Returns the values of variables that should never show up in task run logs; those that were interpolated
+/// from secrets and those marked private. Takes the variables before and after interpolation.
+fn sensitive_values(variables: &[Variable], interpolated: &[Variable]) -> Vec<String> {
+    variables
+        .iter()
+        .zip(interpolated)
+        .filter(|(variable, _)| {
+            variable.sensitivity == VariableSensitivity::Private
+                || parse_interpolation_syntax(InterpolationKind::Secret, &variable.value).is_some()
+        })
+        .map(|(_, interpolated)| interpolated.value.clone())
+        .collect()
+}
+
+/// How long run log expiry first waits before trying again after failing to read the run it's expiring. The wait
+/// doubles with each consecutive failure up to the max.
+const LOG_EXPIRY_MIN_RETRY_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(1);
+const LOG_EXPIRY_MAX_RETRY_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(60);
 
 /// Used to keep track of a run as it progresses through the necessary states.
 #[derive(Debug, Clone)]
@@ -18,6 +64,14 @@ pub struct RunStateMachine {
     pipeline: pipeline::Pipeline,
     run: run::Run,
     task_runs: DashMap<String, task_run::TaskRun>,
+    /// Signalled every time a task run in the task_runs map changes state. Anything that needs to wait on
+    /// task runs (dependent tasks, the run monitor) subscribes to this instead of polling the map.
+    task_run_updates: Arc<watch::Sender<()>>,
+    /// The resolved values of each task run's secret and private variables, by task run id. These are
+    /// redacted from the task run's logs.
+    task_run_sensitive_values: DashMap<String, Vec<String>>,
+    /// The token task runs are given to call back into Gofer with. Revoked once the run finishes.
+    api_token: String,
 }
 
 impl RunStateMachine {
@@ -27,51 +81,12 @@ impl RunStateMachine {
             pipeline,
             run,
             task_runs: DashMap::new(),
+            task_run_updates: Arc::new(watch::channel(()).0),
+            task_run_sensitive_values: DashMap::new(),
+            api_token: String::new(),
         }
     }
 
-    /// Returns true if there are more runs in progress than the parallelism limit
-    /// of a pipeline allows.
-    /// If there was an error getting the current number of runs, we fail closed as the
-    /// functionality of failing a parallelism_limit is usually retrying until it succeeds.
-    pub async fn parallelism_limit_exceeded(&self, conn: &mut SqliteConnection) -> bool {
-        let mut limit = self.pipeline.parallelism;
-
-        if limit == 0 && self.api.conf.general.run_parallelism_limit == 0 {
-            return false;
-        }
-
-        if limit > self.api.conf.general.run_parallelism_limit {
-            limit = self.api.conf.general.run_parallelism_limit
-        }
-
-        let runs = match storage::runs::list(
-            conn,
-            0,
-            0,
-            &self.pipeline.namespace,
-            &self.pipeline.id,
-        )
-        .await
-        {
-            Ok(runs) => runs,
-            Err(_) => return true,
-        };
-
-        let mut runs_in_progress = 0;
-        for run in runs {
-            if run.state != run::State::Complete {
-                runs_in_progress += 1;
-            }
-        }
-
-        if runs_in_progress >= limit {
-            return true;
-        }
-
-        false
-    }
-
     /// Mark a task run object as finished.
     pub async fn set_task_run_finished(
         &self,
@@ -80,55 +95,65 @@ impl RunStateMachine {
         status: task_run::Status,
         failure: Option<task_run::StatusReason>,
     ) {
-        // Update the task run's status inside the map first.
-        self.task_runs.alter(id, |_, mut task_run| {
-            task_run.state = task_run::State::Complete;
-            task_run.status = status.clone();
-
-            task_run
-        });
+        // A task run that is going to be retried is left incomplete inside the map so that nothing waiting on
+        // it moves on before the final attempt has finished. The attempt itself is still recorded as complete.
+        let retrying = self.should_retry(id, &status, &failure);
 
         let task_run = match self.task_runs.get(id) {
-            Some(task_run) => task_run,
+            Some(task_run) => task_run.value().clone(),
             None => {
                 error!("could not set task run finished; task_run does not exist");
                 return;
             }
         };
 
-        let mut conn = match self.api.storage.conn().await {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!("could not set task run finished; database connection error"; "error" => format!("{:?}", e));
-                return;
-            }
+        // The task run's status is updated on disk first so that nothing woken up by the change in the map,
+        // like the run finishing, can get ahead of it.
+        let stored = match self.api.storage.conn().await {
+            Ok(mut conn) => storage::task_runs::update(
+                &mut conn,
+                &task_run,
+                storage::task_runs::UpdatableFields {
+                    exit_code: code,
+                    status: Some(status.clone()),
+                    state: Some(task_run::State::Complete),
+                    ended: Some(epoch()),
+                    failure: failure.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| format!("database error; {:?}", e)),
+            Err(e) => Err(format!("database connection error; {:?}", e)),
         };
 
-        // Then update the task_run's status on disk.
-        if let Err(e) = storage::task_runs::update(
-            &mut conn,
-            &task_run,
-            storage::task_runs::UpdatableFields {
-                exit_code: code,
-                status: Some(status.clone()),
-                state: Some(task_run::State::Complete),
-                ended: Some(epoch()),
-                failure,
-                ..Default::default()
-            },
-        )
-        .await
-        {
-            error!("could not set task run finished; database error"; "error" => format!("{:?}", e));
+        if let Err(e) = &stored {
+            error!("could not set task run finished"; "error" => e);
+        }
+
+        // Then update the task run's status inside the map. This happens even if it couldn't be stored, so that
+        // the run doesn't wait on it forever.
+        self.task_runs.alter(id, |_, mut task_run| {
+            if !retrying {
+                task_run.state = task_run::State::Complete;
+            }
+            task_run.status = status.clone();
+            task_run.status_reason = failure.clone();
+
+            task_run
+        });
+        self.task_run_updates.send_replace(());
+
+        if stored.is_err() {
             return;
-        };
+        }
 
         // Lastly publish to the event bus that the task_run has finished.
         let namespace_id = self.run.namespace.clone();
         let pipeline_id = self.run.pipeline.clone();
         let run_id = self.run.id;
         let task_run_id = task_run.task.id.clone();
-        let status = task_run.status.clone();
+        let attempt = task_run.attempt;
         let api = self.api.clone();
 
         tokio::spawn(async move {
@@ -138,6 +163,7 @@ impl RunStateMachine {
                     pipeline_id,
                     run_id,
                     task_run_id,
+                    attempt,
                     status,
                 })
                 .await;
@@ -170,6 +196,19 @@ impl RunStateMachine {
             error!("could not not update run during run monitoring"; "error" => format!("{:?}", e));
         };
 
+        self.api
+            .cancelled_task_runs
+            .retain(|(namespace, pipeline, run, _)| {
+                !(namespace == &self.run.namespace
+                    && pipeline == &self.run.pipeline
+                    && *run == self.run.id)
+            });
+
+        self.api.revoke_run_tokens(&self.run).await;
+
+        // Wake up anything waiting on a run to finish, such as the run dispatcher.
+        self.api.run_completions.send_replace(());
+
         // Lastly publish to the event bus that the task_run has finished.
         let namespace_id = self.run.namespace.clone();
         let pipeline_id = self.run.pipeline.clone();
@@ -188,9 +227,36 @@ impl RunStateMachine {
         });
     }
 
+    /// Creates the token task runs are given to call back into Gofer with. Fails the run if it can't, returning
+    /// false.
+    async fn create_api_token(&mut self) -> bool {
+        match self.api.create_run_token(&self.run).await {
+            Ok(api_token) => {
+                self.api_token = api_token;
+                true
+            }
+            Err(e) => {
+                error!("could not create run token"; "error" => format!("{:?}", e));
+                self.set_run_finished(
+                    run::Status::Failed,
+                    Some(run::StatusReason {
+                        reason: run::Reason::Unknown,
+                        description: "Could not create API token for run".to_string(),
+                    }),
+                )
+                .await;
+                false
+            }
+        }
+    }
+
     /// Creates all child task_runs for a given run. After creating all task runs it then
     /// blocks and monitors the run until it is finished.
-    pub async fn execute_task_tree(self) {
+    pub async fn execute_task_tree(mut self) {
+        if !self.create_api_token().await {
+            return;
+        }
+
         let state_machine = Arc::new(self);
         let object_expiry_clone = state_machine.clone();
         let log_expiry_clone = state_machine.clone();
@@ -199,14 +265,33 @@ impl RunStateMachine {
         tokio::spawn(object_expiry_clone.handle_run_object_expiry());
         tokio::spawn(log_expiry_clone.handle_run_log_expiry());
 
-        // TODO(clintjedwards): create token here.
-
-        // TODO(clintjedwards): include notifiers here.
+        // Resolve all common tasks up front so that a misconfigured common task fails the run before any
+        // task runs are started.
+        let common_tasks = match state_machine.resolve_common_tasks() {
+            Ok(common_tasks) => common_tasks,
+            Err(description) => {
+                state_machine
+                    .set_run_finished(
+                        run::Status::Failed,
+                        Some(run::StatusReason {
+                            reason: run::Reason::FailedPrecondition,
+                            description,
+                        }),
+                    )
+                    .await;
+                return;
+            }
+        };
 
         // Launch a new task run for each task found.
-        for task in state_machine.pipeline.tasks.values() {
+        for task in state_machine
+            .pipeline
+            .tasks
+            .values()
+            .cloned()
+            .chain(common_tasks)
+        {
             let task_clone = state_machine.clone();
-            let task = task.clone();
             tokio::spawn(async move { task_clone.launch_task_run(task).await });
         }
 
@@ -214,6 +299,266 @@ impl RunStateMachine {
         state_machine.wait_run_finish().await;
     }
 
+    /// Picks a run back up after Gofer has been restarted. Task runs that already finished are left alone unless
+    /// their last attempt still has to be retried, task runs that had their container scheduled are reattached to
+    /// and everything left in the DAG is launched as it normally would be. Like
+    /// [`execute_task_tree`](Self::execute_task_tree) it blocks until the run is finished.
+    pub async fn resume_task_tree(mut self) {
+        // The token given to task runs before the restart is still valid and is kept, encrypted, in the variables
+        // of each task run it was handed to, so task runs picked back up below keep using it. Task runs started
+        // from here on get a new one instead of it being read back out of another task run.
+        if !self.create_api_token().await {
+            return;
+        }
+
+        let state_machine = Arc::new(self);
+
+        let mut conn = match state_machine.api.storage.conn().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("could not resume run; database connection error"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+
+        let task_runs = match storage::task_runs::list(
+            &mut conn,
+            0,
+            0,
+            &state_machine.pipeline.namespace,
+            &state_machine.pipeline.id,
+            state_machine.run.id,
+        )
+        .await
+        {
+            Ok(task_runs) => task_runs,
+            Err(e) => {
+                error!("could not resume run; could not get task runs"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+
+        for task_run in task_runs {
+            state_machine
+                .task_runs
+                .insert(task_run.id.clone(), task_run);
+        }
+
+        tokio::spawn(state_machine.clone().handle_run_object_expiry());
+        tokio::spawn(state_machine.clone().handle_run_log_expiry());
+
+        let common_tasks = match state_machine.resolve_common_tasks() {
+            Ok(common_tasks) => common_tasks,
+            Err(description) => {
+                state_machine
+                    .set_run_finished(
+                        run::Status::Failed,
+                        Some(run::StatusReason {
+                            reason: run::Reason::FailedPrecondition,
+                            description,
+                        }),
+                    )
+                    .await;
+                return;
+            }
+        };
+
+        for task in state_machine
+            .pipeline
+            .tasks
+            .values()
+            .cloned()
+            .chain(common_tasks)
+        {
+            let existing_task_run = state_machine
+                .task_runs
+                .get(&task.id)
+                .map(|task_run| task_run.value().clone());
+
+            let task_clone = state_machine.clone();
+
+            match existing_task_run {
+                None => {
+                    tokio::spawn(async move { task_clone.launch_task_run(task).await });
+                }
+                Some(task_run) => match task_run.state {
+                    task_run::State::Complete => {
+                        // Gofer may have stopped after a failed attempt was recorded but before the attempt
+                        // that retries it was created, so the retry is picked up here instead.
+                        if !state_machine.should_retry(
+                            &task_run.id,
+                            &task_run.status,
+                            &task_run.status_reason,
+                        ) {
+                            continue;
+                        }
+
+                        // Like set_task_run_finished, the attempt is left incomplete in the map until its
+                        // retry has finished.
+                        state_machine
+                            .task_runs
+                            .alter(&task_run.id, |_, mut task_run| {
+                                task_run.state = task_run::State::Running;
+                                task_run
+                            });
+
+                        tokio::spawn(async move {
+                            if let Some(next_task_run) =
+                                task_clone.next_task_run_attempt(&task_run).await
+                            {
+                                task_clone.run_task_run_attempts(next_task_run).await;
+                            }
+                        });
+                    }
+                    task_run::State::Running => {
+                        tokio::spawn(async move { task_clone.reattach_task_run(task_run).await });
+                    }
+                    task_run::State::Unknown
+                    | task_run::State::Processing
+                    | task_run::State::Waiting => {
+                        let env_vars = if task_run.variables.is_empty() {
+                            combine_variables(&state_machine.run, &task, &state_machine.api_token)
+                        } else {
+                            task_run.variables.clone()
+                        };
+
+                        tokio::spawn(async move {
+                            task_clone.execute_task_run(task_run, env_vars).await
+                        });
+                    }
+                },
+            }
+        }
+
+        state_machine.wait_run_finish().await;
+    }
+
+    /// Reattaches log collection and monitoring to a task run whose container was started before a restart.
+    /// If the scheduler no longer knows about the container the task run is marked as orphaned.
+    async fn reattach_task_run(self: Arc<Self>, task_run: task_run::TaskRun) {
+        // The values to redact from the task run's logs were lost on restart so they have to be resolved again.
+        let mut variables = task_run.variables.clone();
+        if let Err(e) = self.interpolate_vars(&mut variables).await {
+            error!("could not resolve task run secrets; only private variables will be redacted from logs";
+                "task_run" => &task_run.id, "error" => format!("{:?}", e));
+            variables = task_run.variables.clone();
+        }
+        self.task_run_sensitive_values.insert(
+            task_run.id.clone(),
+            sensitive_values(&task_run.variables, &variables),
+        );
+
+        let container_name = fmt::task_container_id(
+            &self.pipeline.namespace,
+            &self.pipeline.id,
+            self.run.id,
+            &task_run.id,
+            task_run.attempt,
+        );
+
+        if let Err(e) = self
+            .api
+            .scheduler
+            .get_state(scheduler::GetStateRequest {
+                name: container_name.clone(),
+            })
+            .await
+        {
+            self.set_task_run_finished(
+                &task_run.id,
+                None,
+                task_run::Status::Unknown,
+                Some(task_run::StatusReason {
+                    reason: task_run::Reason::Orphaned,
+                    description: format!(
+                        "Task run's container could not be found after Gofer was restarted; {}",
+                        e
+                    ),
+                }),
+            )
+            .await;
+        } else {
+            self.clone()
+                .monitor_task_run(container_name, task_run.id.clone())
+                .await;
+        }
+
+        if let Some(next_task_run) = self.next_task_run_attempt(&task_run).await {
+            self.run_task_run_attempts(next_task_run).await;
+        }
+    }
+
+    /// Turns each common task the pipeline references into a regular task so it can be launched
+    /// alongside the pipeline's own tasks. The task id is the label given to the common task by the pipeline.
+    /// Variables set at registration time are overridden by the pipeline's settings for that label.
+    fn resolve_common_tasks(&self) -> Result<Vec<task::Task>, String> {
+        let mut tasks = vec![];
+
+        for (label, settings) in &self.pipeline.common_tasks {
+            if self.pipeline.tasks.contains_key(label) {
+                return Err(format!(
+                    "common task label '{label}' conflicts with a task of the same id"
+                ));
+            }
+
+            let common_task = match self.api.common_tasks.get(&settings.name) {
+                Some(common_task) => common_task.value().clone(),
+                None => {
+                    return Err(format!(
+                        "common task '{}' used by label '{label}' is not installed",
+                        settings.name
+                    ))
+                }
+            };
+
+            if common_task.status != common_task::Status::Enabled {
+                return Err(format!(
+                    "common task '{}' used by label '{label}' is not enabled",
+                    settings.name
+                ));
+            }
+
+            let mut variables: HashMap<String, Variable> = common_task
+                .variables
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        key.clone(),
+                        Variable {
+                            key,
+                            value,
+                            owner: VariableOwner::System,
+                            sensitivity: VariableSensitivity::Private,
+                        },
+                    )
+                })
+                .collect();
+
+            for (key, value) in &settings.settings {
+                variables.insert(
+                    key.clone(),
+                    Variable {
+                        key: key.clone(),
+                        value: value.clone(),
+                        owner: VariableOwner::User,
+                        sensitivity: VariableSensitivity::Public,
+                    },
+                );
+            }
+
+            let mut task = task::Task::new(label, &common_task.image);
+            task.registry_auth = common_task.registry_auth.map(|auth| task::RegistryAuth {
+                user: auth.user,
+                pass: auth.pass,
+            });
+            task.variables = variables.into_values().collect();
+
+            tasks.push(task);
+        }
+
+        Ok(tasks)
+    }
+
     /// Check a dependency tree to see if all parents tasks have been finished.
     fn parent_tasks_finished(
         &self,
@@ -289,6 +634,17 @@ impl RunStateMachine {
         Ok(())
     }
 
+    /// Returns true once a task run exists for every task in the pipeline and all of them have completed.
+    fn all_task_runs_complete(&self) -> bool {
+        if self.task_runs.len() != self.pipeline.tasks.len() + self.pipeline.common_tasks.len() {
+            return false;
+        }
+
+        self.task_runs
+            .iter()
+            .all(|item| item.value().state == task_run::State::Complete)
+    }
+
     /// Monitors all task run statuses and determines the final run status based on all
     /// finished task runs. It will block until all task runs have finished.
     pub async fn wait_run_finish(&self) {
@@ -315,27 +671,39 @@ impl RunStateMachine {
             return;
         }
 
-        // If the task_run map hasn't had all the entries come in we should wait until it does.
-        loop {
-            if self.task_runs.len() != self.pipeline.tasks.len() {
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                continue;
+        // Wait until every task run has been created and all of them have completed. We re-check
+        // each time a task run changes state.
+        let mut task_run_updates = self.task_run_updates.subscribe();
+        while !self.all_task_runs_complete() {
+            if task_run_updates.changed().await.is_err() {
+                return;
             }
-
-            break;
         }
 
-        // We loop over the task_runs to make sure all are complete.
-        'outer: loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            for item in &self.task_runs {
-                let task_run = item.value();
-                if task_run.state != task_run::State::Complete {
-                    continue 'outer;
-                }
-            }
+        // A timeout takes precedence over any other failure since it's usually the root cause of the
+        // other task runs failing or being skipped.
+        let timed_out = self.task_runs.iter().any(|item| {
+            matches!(
+                &item.value().status_reason,
+                Some(task_run::StatusReason {
+                    reason: task_run::Reason::Timeout,
+                    ..
+                })
+            )
+        });
 
-            break;
+        if timed_out {
+            self.set_run_finished(
+                run::Status::Failed,
+                Some(run::StatusReason {
+                    reason: run::Reason::Timeout,
+                    description:
+                        "One or more task runs exceeded their allotted time and were stopped"
+                            .to_string(),
+                }),
+            )
+            .await;
+            return;
         }
 
         // When all are finished we now need to get a final tallying of what the run's result is.
@@ -376,7 +744,100 @@ impl RunStateMachine {
         self.set_run_finished(run::Status::Successful, None).await;
     }
 
+    /// Returns the timeout (in seconds) of the run: the shorter of the pipeline's own run timeout and the
+    /// global run timeout. 0 means neither is set.
+    fn run_timeout(&self) -> u64 {
+        match (self.pipeline.run_timeout, self.api.conf.general.run_timeout) {
+            (0, global_timeout) => global_timeout,
+            (pipeline_timeout, 0) => pipeline_timeout,
+            (pipeline_timeout, global_timeout) => pipeline_timeout.min(global_timeout),
+        }
+    }
+
+    /// Returns the time (in epoch milliseconds) at which a task run started at `started` must be stopped
+    /// along with a description of the timeout that will be reached. The task's own timeout can only ever
+    /// shorten the overall run timeout. Returns None if neither timeout is set.
+    fn task_run_deadline(&self, task: &task::Task, started: u64) -> Option<(u64, String)> {
+        let run_deadline = match self.run_timeout() {
+            0 => None,
+            timeout => Some((
+                self.run
+                    .started
+                    .saturating_add(timeout.saturating_mul(1000)),
+                format!("Run exceeded its timeout of {} seconds.", timeout),
+            )),
+        };
+
+        let task_deadline = match task.timeout {
+            0 => None,
+            timeout => Some((
+                started.saturating_add(timeout.saturating_mul(1000)),
+                format!("Task run exceeded its timeout of {} seconds.", timeout),
+            )),
+        };
+
+        match (run_deadline, task_deadline) {
+            (Some(run_deadline), Some(task_deadline)) => {
+                if task_deadline.0 < run_deadline.0 {
+                    Some(task_deadline)
+                } else {
+                    Some(run_deadline)
+                }
+            }
+            (run_deadline, task_deadline) => run_deadline.or(task_deadline),
+        }
+    }
+
+    /// Returns true if the run has already exceeded its run timeout.
+    fn run_timed_out(&self) -> bool {
+        let timeout = self.run_timeout();
+        let deadline = self
+            .run
+            .started
+            .saturating_add(timeout.saturating_mul(1000));
+
+        timeout != 0 && epoch() >= deadline
+    }
+
+    /// Stops a task run's container that has exceeded its deadline and marks the task run as failed.
+    async fn stop_timed_out_task_run(&self, container_name: String, id: &str, description: String) {
+        if let Err(e) = self
+            .api
+            .scheduler
+            .stop_container(scheduler::StopContainerRequest {
+                name: container_name,
+                timeout: self.api.conf.general.task_run_stop_timeout as i64,
+            })
+            .await
+        {
+            error!("could not stop timed out task run"; "task_run" => id, "error" => format!("{:?}", e));
+        }
+
+        self.set_task_run_finished(
+            id,
+            None,
+            task_run::Status::Failed,
+            Some(task_run::StatusReason {
+                reason: task_run::Reason::Timeout,
+                description,
+            }),
+        )
+        .await;
+    }
+
     pub async fn wait_task_run_finish(&self, container_name: String, id: String) -> Result<()> {
+        let deadline = match self.task_runs.get(&id) {
+            Some(task_run) => {
+                let started = if task_run.started == 0 {
+                    epoch()
+                } else {
+                    task_run.started
+                };
+                self.task_run_deadline(&task_run.task, started)
+            }
+            None => None,
+        };
+
         loop {
             let resp = match self
                 .api
@@ -424,6 +885,14 @@ impl RunStateMachine {
                 scheduler::ContainerState::Running
                 | scheduler::ContainerState::Restarting
                 | scheduler::ContainerState::Paused => {
+                    if let Some((deadline, description)) = &deadline {
+                        if epoch() >= *deadline {
+                            self.stop_timed_out_task_run(container_name, &id, description.clone())
+                                .await;
+                            return Ok(());
+                        }
+                    }
+
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     continue;
                 }
@@ -495,6 +964,10 @@ impl RunStateMachine {
     pub async fn handle_run_object_expiry(self: Arc<Self>) {
         let limit = self.api.conf.object_store.run_object_expiry;
 
+        // Subscribe before we look anything up so that we can't miss the completion of the run we end up
+        // waiting on.
+        let mut run_completions = self.api.run_completions.subscribe();
+
         let mut conn = match self.api.storage.conn().await {
             Ok(conn) => conn,
             Err(e) => {
@@ -533,7 +1006,14 @@ impl RunStateMachine {
 
         // If the run is still in progress wait for it to be done.
         while expired_run.state != run::State::Complete {
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            tokio::select! {
+                _ = self.api.shutdown.cancelled() => return,
+                result = run_completions.changed() => {
+                    if result.is_err() {
+                        return;
+                    }
+                },
+            }
 
             expired_run = match storage::runs::get(
                 &mut conn,
@@ -551,17 +1031,42 @@ impl RunStateMachine {
             };
         }
 
-        if expired_run.store_info.is_none() {
+        if expired_run.store_info.is_expired {
             return;
         };
 
-        let mut store_info = expired_run.store_info.clone().unwrap();
-
-        if store_info.is_expired {
+        // The run is marked expired before anything is removed so that no new objects can be added to it
+        // while we're cleaning up.
+        if let Err(e) = storage::runs::update(
+            &mut conn,
+            &expired_run,
+            storage::runs::UpdatableFields {
+                store_objects_expired: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        {
+            error!("could not not update run for expiry processing"; "error" => format!("{:?}", e));
             return;
+        }
+
+        let object_keys = match storage::objects::list_run_keys(
+            &mut conn,
+            &self.pipeline.namespace,
+            &self.pipeline.id,
+            expired_run.id,
+        )
+        .await
+        {
+            Ok(object_keys) => object_keys,
+            Err(e) => {
+                error!("could not get run objects for expiry processing"; "error" => format!("{:?}", e));
+                return;
+            }
         };
 
-        for object_key in &store_info.keys {
+        for object_key in &object_keys {
             if let Err(e) = self
                 .api
                 .object_store
@@ -569,7 +1074,7 @@ impl RunStateMachine {
                     &self.pipeline.namespace,
                     &self.pipeline.id,
                     expired_run.id,
-                    object_key,
+                    &object_key.key,
                 ))
                 .await
             {
@@ -577,32 +1082,58 @@ impl RunStateMachine {
             }
         }
 
-        store_info.is_expired = true;
-
-        expired_run.store_info = Some(store_info.clone());
-        if let Err(e) = storage::runs::update(
-            &mut conn,
-            &expired_run,
-            storage::runs::UpdatableFields {
-                store_info: Some(store_info.clone()),
-                ..Default::default()
-            },
-        )
-        .await
-        {
-            error!("could not not update run for expiry processing"; "error" => format!("{:?}", e));
-        }
+        let removed_keys: Vec<String> = object_keys
+            .into_iter()
+            .map(|object_key| object_key.key)
+            .collect();
 
         debug!("old run objects removed";
             "run_age_limit" => limit,
             "run_id" => expired_run.id,
-            "removed_objects" => format!("{:?}", store_info.keys),
+            "removed_objects" => format!("{:?}", removed_keys),
         );
     }
 
+    /// Returns the task runs of the given run if the run and all of its task runs are complete.
+    async fn completed_task_runs(
+        &self,
+        conn: &mut storage::Conn<'_>,
+        run_id: u64,
+    ) -> Result<Option<Vec<task_run::TaskRun>>, storage::StorageError> {
+        let run =
+            storage::runs::get(conn, &self.pipeline.namespace, &self.pipeline.id, run_id).await?;
+
+        if run.state != run::State::Complete {
+            return Ok(None);
+        }
+
+        let task_runs = storage::task_runs::list(
+            conn,
+            0,
+            0,
+            &self.pipeline.namespace,
+            &self.pipeline.id,
+            run_id,
+        )
+        .await?;
+
+        if task_runs
+            .iter()
+            .any(|task_run| task_run.state != task_run::State::Complete)
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(task_runs))
+    }
+
     pub async fn handle_run_log_expiry(self: Arc<Self>) {
         let limit = self.api.conf.general.task_run_log_expiry;
 
+        // Subscribe before we look anything up so that we can't miss the completion of the run we end up
+        // waiting on.
+        let mut run_completions = self.api.run_completions.subscribe();
+
         let mut conn = match self.api.storage.conn().await {
             Ok(conn) => conn,
             Err(e) => {
@@ -637,57 +1168,40 @@ impl RunStateMachine {
             return;
         }
 
-        let mut expired_run = runs.last().unwrap().to_owned();
-
-        // If the run is still in progress wait for it to be done.
-        while expired_run.state != run::State::Complete {
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-
-            expired_run = match storage::runs::get(
-                &mut conn,
-                &self.pipeline.namespace,
-                &self.pipeline.id,
-                expired_run.id,
-            )
-            .await
-            {
-                Ok(run) => run,
-                Err(e) => {
-                    error!("could not get run while performing run log expiry"; "error" => format!("{:?}", e));
-                    continue;
+        let expired_run = runs.last().unwrap().to_owned();
+        let mut retry_interval = LOG_EXPIRY_MIN_RETRY_INTERVAL;
+
+        // Logs are only removed once the run and all of its task runs are done writing to them. Task runs
+        // normally finish before their run does, so we re-check whenever a run completes.
+        let task_runs = loop {
+            match self.completed_task_runs(&mut conn, expired_run.id).await {
+                Ok(Some(task_runs)) => break task_runs,
+                Ok(None) => {
+                    retry_interval = LOG_EXPIRY_MIN_RETRY_INTERVAL;
+
+                    tokio::select! {
+                        _ = self.api.shutdown.cancelled() => return,
+                        result = run_completions.changed() => {
+                            if result.is_err() {
+                                return;
+                            }
+                        },
+                    }
                 }
-            };
-        }
-
-        let mut task_runs: Vec<task_run::TaskRun>;
-
-        'outer: loop {
-            task_runs = match storage::task_runs::list(
-                &mut conn,
-                0,
-                0,
-                &self.pipeline.namespace,
-                &self.pipeline.id,
-                expired_run.id,
-            )
-            .await
-            {
-                Ok(task_runs) => task_runs,
                 Err(e) => {
-                    error!("could not get task run while performing run log expiry"; "error" => format!("{:?}", e));
-                    continue;
-                }
-            };
+                    error!("could not get run while performing run log expiry";
+                        "run_id" => expired_run.id, "retry_in" => format!("{:?}", retry_interval),
+                        "error" => format!("{:?}", e));
 
-            for task_run in &task_runs {
-                if task_run.state != task_run::State::Complete {
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                    continue 'outer;
+                    tokio::select! {
+                        _ = self.api.shutdown.cancelled() => return,
+                        _ = tokio::time::sleep(retry_interval) => {},
+                    }
+
+                    retry_interval = (retry_interval * 2).min(LOG_EXPIRY_MAX_RETRY_INTERVAL);
                 }
             }
-
-            break;
-        }
+        };
 
         let mut removed_files = vec![];
 
@@ -748,6 +1262,14 @@ impl RunStateMachine {
 
         drop(task_run);
 
+        let mut redactor = LogRedactor::new(
+            &self
+                .task_run_sensitive_values
+                .get(&task_run_id)
+                .map(|values| values.clone())
+                .unwrap_or_default(),
+        );
+
         let mut log_file = match tokio::fs::File::create(&log_path).await {
             Ok(log_file) => log_file,
             Err(e) => {
@@ -773,7 +1295,7 @@ impl RunStateMachine {
                     return;
                 }
                 scheduler::Log::Stderr(log) | scheduler::Log::Stdout(log) => {
-                    if let Err(e) = log_file.write_all(&log).await {
+                    if let Err(e) = log_file.write_all(&redactor.redact(&log)).await {
                         error!("encountered error while writing log file;";
                                 "file_path" => log_path, "error" => format!("{:?}", e));
                         return;
@@ -782,6 +1304,12 @@ impl RunStateMachine {
             }
         }
 
+        if let Err(e) = log_file.write_all(&redactor.finish()).await {
+            error!("encountered error while writing log file;";
+            "file_path" => log_path, "error" => format!("{:?}", e));
+            return;
+        }
+
         if let Err(e) = log_file.write_all(GOFER_EOF.as_bytes()).await {
             error!("encountered error while writing log file;";
             "file_path" => log_path, "error" => format!("{:?}", e));
@@ -873,7 +1401,7 @@ impl RunStateMachine {
 
     pub async fn set_task_run_state(
         &self,
-        conn: &mut SqliteConnection,
+        conn: &mut storage::Conn<'_>,
         task_run: &task_run::TaskRun,
         state: task_run::State,
     ) {
@@ -896,6 +1424,7 @@ impl RunStateMachine {
 
             taskrun
         });
+        self.task_run_updates.send_replace(());
     }
 
     /// Launches a brand new task run as part of a larger run for a specific task.
@@ -919,6 +1448,7 @@ impl RunStateMachine {
 
         self.task_runs
             .insert(new_task_run.id.clone(), new_task_run.clone());
+        self.task_run_updates.send_replace(());
 
         if let Err(e) = storage::task_runs::insert(&mut conn, &new_task_run).await {
             error!("could not add task run to storage"; "error" => format!("{:?}", e));
@@ -931,6 +1461,7 @@ impl RunStateMachine {
         let pipeline_id = self.pipeline.id.to_string();
         let run_id = self.run.id;
         let task_run_id = task.id.clone();
+        let attempt = new_task_run.attempt;
 
         tokio::spawn(async move {
             self_clone
@@ -941,11 +1472,12 @@ impl RunStateMachine {
                     pipeline_id,
                     run_id,
                     task_run_id,
+                    attempt,
                 })
                 .await;
         });
 
-        let mut env_vars = combine_variables(&self.run, &task);
+        let env_vars = combine_variables(&self.run, &task, &self.api_token);
 
         // Determine the task run's final variable set and pass them in.
         if let Err(e) = storage::task_runs::update(
@@ -962,6 +1494,30 @@ impl RunStateMachine {
             return;
         }
 
+        // The task run can wait on its parents for a long time, so the connection goes back to the pool first.
+        drop(conn);
+
+        self.execute_task_run(new_task_run, env_vars).await;
+    }
+
+    /// Waits for a task run's parents to finish and then schedules and monitors its container. It blocks until
+    /// the task run has completed. This is split from [`launch_task_run`](Self::launch_task_run) so that task runs
+    /// which were created but never started before a restart can be picked back up.
+    async fn execute_task_run(
+        self: Arc<Self>,
+        mut new_task_run: task_run::TaskRun,
+        env_vars: Vec<Variable>,
+    ) {
+        new_task_run.variables = env_vars;
+
+        let mut conn = match self.api.storage.conn().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("could not launch task; database connection error"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+
         // Now we examine the validity of the task run to be started and wait for it's dependents to
         // finish running.
 
@@ -969,8 +1525,11 @@ impl RunStateMachine {
             .await;
 
         // First we need to make sure all the parents of the current task are in a finished state.
+        let mut task_run_updates = self.task_run_updates.subscribe();
         while !self.parent_tasks_finished(&new_task_run.task.depends_on) {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            if task_run_updates.changed().await.is_err() {
+                return;
+            }
         }
 
         self.set_task_run_state(&mut conn, &new_task_run, task_run::State::Processing)
@@ -993,6 +1552,182 @@ impl RunStateMachine {
             return;
         }
 
+        // Each attempt opens its own connection.
+        drop(conn);
+
+        self.run_task_run_attempts(new_task_run).await;
+    }
+
+    /// Runs attempts of a task run until one of them finishes without needing to be retried. It blocks until
+    /// the final attempt has completed.
+    async fn run_task_run_attempts(self: Arc<Self>, mut task_run: task_run::TaskRun) {
+        loop {
+            self.clone().run_task_run_attempt(task_run.clone()).await;
+
+            task_run = match self.next_task_run_attempt(&task_run).await {
+                Some(next_task_run) => next_task_run,
+                None => return,
+            };
+        }
+    }
+
+    /// Creates the next attempt of a task run if the previous attempt failed in a way that should be retried.
+    /// It waits out the task's retry backoff before returning the new attempt, which is left for the caller to
+    /// run. Returns None if the task run is finished.
+    async fn next_task_run_attempt(
+        &self,
+        previous: &task_run::TaskRun,
+    ) -> Option<task_run::TaskRun> {
+        // set_task_run_finished leaves task runs that need to be retried incomplete.
+        let retrying = match self.task_runs.get(&previous.id) {
+            Some(task_run) => task_run.state != task_run::State::Complete,
+            None => false,
+        };
+
+        if !retrying {
+            return None;
+        }
+
+        let mut next_task_run = task_run::TaskRun::new(
+            &self.pipeline.namespace,
+            &self.pipeline.id,
+            self.run.id,
+            previous.task.clone(),
+        );
+        next_task_run.attempt = previous.attempt + 1;
+        next_task_run.variables = previous.variables.clone();
+
+        let inserted = match self.api.storage.conn().await {
+            Ok(mut conn) => storage::task_runs::insert(&mut conn, &next_task_run).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = inserted {
+            error!("could not add task run retry to storage"; "task_run" => &previous.id, "error" => format!("{:?}", e));
+
+            // The previous attempt is already recorded as failed; all that's left is to stop waiting on it.
+            self.task_runs.alter(&previous.id, |_, mut task_run| {
+                task_run.state = task_run::State::Complete;
+                task_run
+            });
+            self.task_run_updates.send_replace(());
+            return None;
+        }
+
+        self.task_runs
+            .insert(next_task_run.id.clone(), next_task_run.clone());
+        self.task_run_updates.send_replace(());
+
+        self.api
+            .event_bus
+            .publish(event::Kind::CreatedTaskRun {
+                namespace_id: self.pipeline.namespace.clone(),
+                pipeline_id: self.pipeline.id.clone(),
+                run_id: self.run.id,
+                task_run_id: next_task_run.id.clone(),
+                attempt: next_task_run.attempt,
+            })
+            .await;
+
+        let delay = next_task_run
+            .task
+            .retry_policy
+            .delay_before(next_task_run.attempt);
+
+        debug!("retrying task run"; "run" => self.run.id, "task_run" => &next_task_run.id,
+            "attempt" => next_task_run.attempt, "delay" => delay);
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(delay)).await;
+
+        if self.task_run_cancelled(&next_task_run.id) {
+            self.set_task_run_finished(
+                &next_task_run.id,
+                None,
+                task_run::Status::Cancelled,
+                Some(task_run::StatusReason {
+                    reason: task_run::Reason::Cancelled,
+                    description: "Task run was cancelled before it could be retried.".to_string(),
+                }),
+            )
+            .await;
+            return None;
+        }
+
+        Some(next_task_run)
+    }
+
+    /// Returns true if the task run was cancelled by a user while the run is in progress.
+    fn task_run_cancelled(&self, id: &str) -> bool {
+        self.api.cancelled_task_runs.contains(&(
+            self.run.namespace.clone(),
+            self.run.pipeline.clone(),
+            self.run.id,
+            id.to_string(),
+        ))
+    }
+
+    /// Returns true if a task run that finished with the given status should be attempted again. Only failures
+    /// that could plausibly be transient are retried, and only as many times as the task's retry policy allows.
+    fn should_retry(
+        &self,
+        id: &str,
+        status: &task_run::Status,
+        failure: &Option<task_run::StatusReason>,
+    ) -> bool {
+        match self.task_runs.get(id) {
+            Some(task_run) => {
+                if task_run.attempt > task_run.task.retry_policy.retries {
+                    return false;
+                }
+            }
+            None => return false,
+        };
+
+        if *status != task_run::Status::Failed && *status != task_run::Status::Unknown {
+            return false;
+        }
+
+        let retryable_failure = matches!(
+            failure,
+            Some(task_run::StatusReason {
+                reason: task_run::Reason::AbnormalExit
+                    | task_run::Reason::SchedulerError
+                    | task_run::Reason::Orphaned,
+                ..
+            })
+        );
+
+        retryable_failure && !self.task_run_cancelled(id) && !self.run_timed_out()
+    }
+
+    /// Schedules and monitors the container for a single attempt of a task run. It blocks until the attempt
+    /// has completed.
+    async fn run_task_run_attempt(self: Arc<Self>, new_task_run: task_run::TaskRun) {
+        let mut conn = match self.api.storage.conn().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("could not launch task; database connection error"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+
+        // There's no point in starting a task run if the run it belongs to has already run out of time.
+        if self.run_timed_out() {
+            self.set_task_run_finished(
+                &new_task_run.id,
+                None,
+                task_run::Status::Failed,
+                Some(task_run::StatusReason {
+                    reason: task_run::Reason::Timeout,
+                    description: "Task run could not be started; run exceeded its timeout."
+                        .to_string(),
+                }),
+            )
+            .await;
+
+            return;
+        }
+
         // After this point we're sure the task is in a state to be run. So we attempt to
         // contact the scheduler and start the container.
 
@@ -1003,6 +1738,7 @@ impl RunStateMachine {
 
         // We create a copy of variables so that we can substitute in secrets and objects.
         // to eventually pass them into the start container function.
+        let mut env_vars = new_task_run.variables.clone();
         if let Err(e) = self.interpolate_vars(&mut env_vars).await {
             self.set_task_run_finished(&new_task_run.id, None,
                 task_run::Status::Failed,
@@ -1018,16 +1754,36 @@ impl RunStateMachine {
             return;
         };
 
+        self.task_run_sensitive_values.insert(
+            new_task_run.id.clone(),
+            sensitive_values(&new_task_run.variables, &env_vars),
+        );
+
         let env_vars: HashMap<String, String> = env_vars
             .into_iter()
             .map(|variable| (variable.key, variable.value))
             .collect();
 
+        let (cpu_request, cpu_limit) = resolve_resources(
+            new_task_run.task.cpu_request,
+            new_task_run.task.cpu_limit,
+            self.api.conf.scheduler.default_cpu_limit,
+            self.api.conf.scheduler.max_cpu_limit,
+        );
+
+        let (memory_request, memory_limit) = resolve_resources(
+            new_task_run.task.memory_request,
+            new_task_run.task.memory_limit,
+            self.api.conf.scheduler.default_memory_limit,
+            self.api.conf.scheduler.max_memory_limit,
+        );
+
         let container_name = fmt::task_container_id(
             &self.pipeline.namespace,
             &self.pipeline.id,
             self.run.id,
             &new_task_run.id,
+            new_task_run.attempt,
         );
 
         if let Err(e) = self
@@ -1054,6 +1810,10 @@ impl RunStateMachine {
                 enable_networking: false,
                 entrypoint: new_task_run.task.entrypoint.clone(),
                 command: new_task_run.task.command.clone(),
+                cpu_request,
+                cpu_limit,
+                memory_request,
+                memory_limit,
             })
             .await
         {
@@ -1073,12 +1833,14 @@ impl RunStateMachine {
             return;
         };
 
+        let started = epoch();
+
         if let Err(e) = storage::task_runs::update(
             &mut conn,
             &new_task_run,
             storage::task_runs::UpdatableFields {
                 state: Some(task_run::State::Running),
-                started: Some(epoch()),
+                started: Some(started),
                 ..Default::default()
             },
         )
@@ -1093,7 +1855,8 @@ impl RunStateMachine {
         let namespace_id = self.pipeline.namespace.to_string();
         let pipeline_id = self.pipeline.id.to_string();
         let run_id = self.run.id;
-        let task_run_id = task.id.clone();
+        let task_run_id = new_task_run.task.id.clone();
+        let attempt = new_task_run.attempt;
 
         tokio::spawn(async move {
             self_clone
@@ -1104,6 +1867,7 @@ impl RunStateMachine {
                     pipeline_id,
                     run_id,
                     task_run_id,
+                    attempt,
                 })
                 .await;
         });
@@ -1111,11 +1875,673 @@ impl RunStateMachine {
         // Update the task run's status inside the map first.
         self.task_runs.alter(&new_task_run.id, |_, mut task_run| {
             task_run.state = task_run::State::Running;
+            task_run.started = started;
 
             task_run
         });
+        self.task_run_updates.send_replace(());
 
         // Block until task_run is finished and log results.
         self.monitor_task_run(container_name, new_task_run.id).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::{next_event, FakeScheduler, TestHarness};
+    use gofer_models::event::KindDiscriminant;
+
+    /// Returns a state machine for a pipeline using the common task "test_common_task" under the label
+    /// "common". The common task itself is installed with the given status, if any.
+    async fn common_task_state_machine(
+        harness: &TestHarness,
+        status: Option<common_task::Status>,
+    ) -> RunStateMachine {
+        if let Some(status) = status {
+            harness.api.common_tasks.insert(
+                "test_common_task".to_string(),
+                common_task::CommonTask {
+                    name: "test_common_task".to_string(),
+                    image: "test_image".to_string(),
+                    registry_auth: Some(gofer_models::RegistryAuth {
+                        user: "registry_user".to_string(),
+                        pass: "registry_pass".to_string(),
+                    }),
+                    variables: HashMap::from([
+                        ("registered".to_string(), "registered_value".to_string()),
+                        ("overridden".to_string(), "registered_value".to_string()),
+                    ]),
+                    documentation: None,
+                    status,
+                },
+            );
+        }
+
+        let mut common_task_settings =
+            pipeline::CommonTaskSettings::new("test_common_task", "common");
+        common_task_settings.settings =
+            HashMap::from([("overridden".to_string(), "pipeline_value".to_string())]);
+
+        let mut pipeline = pipeline::Pipeline::new(
+            "test_namespace",
+            gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline"),
+        );
+        pipeline.common_tasks = HashMap::from([("common".to_string(), common_task_settings)]);
+
+        let run = run::Run::new(
+            "test_namespace",
+            "test_pipeline",
+            run::TriggerInfo {
+                name: "manual".to_string(),
+                label: "cli".to_string(),
+            },
+            vec![],
+        );
+
+        RunStateMachine::new(harness.api.clone(), pipeline, run).await
+    }
+
+    /// Returns a state machine for a run that started at 1000ms.
+    async fn timeout_state_machine(harness: &TestHarness) -> RunStateMachine {
+        pipeline_timeout_state_machine(harness, 0).await
+    }
+
+    /// Returns a state machine for a run that started at 1000ms, belonging to a pipeline with the given
+    /// run timeout.
+    async fn pipeline_timeout_state_machine(
+        harness: &TestHarness,
+        run_timeout: u64,
+    ) -> RunStateMachine {
+        let pipeline = pipeline::Pipeline::new(
+            "test_namespace",
+            gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline")
+                .run_timeout(std::time::Duration::from_secs(run_timeout)),
+        );
+
+        let mut run = run::Run::new(
+            "test_namespace",
+            "test_pipeline",
+            run::TriggerInfo {
+                name: "manual".to_string(),
+                label: "cli".to_string(),
+            },
+            vec![],
+        );
+        run.started = 1000;
+
+        RunStateMachine::new(harness.api.clone(), pipeline, run).await
+    }
+
+    fn task_with_timeout(timeout: u64) -> task::Task {
+        let mut task = task::Task::new("test_task", "test_image");
+        task.timeout = timeout;
+        task
+    }
+
+    fn run_timeout_conf(run_timeout: u64) -> crate::conf::api::Config {
+        let mut conf = crate::conf::api::Config::default();
+        conf.general.run_timeout = run_timeout;
+        conf
+    }
+
+    #[tokio::test]
+    /// A task timeout can only shorten the run timeout, never extend it.
+    async fn task_run_deadline_shortest_wins() {
+        let harness = TestHarness::with_conf(run_timeout_conf(60)).await;
+        let state_machine = timeout_state_machine(&harness).await;
+
+        // The task run starts 10s into the run, so a 120s task timeout would outlast the 60s run timeout.
+        let (deadline, description) = state_machine
+            .task_run_deadline(&task_with_timeout(120), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 61_000);
+        assert!(description.starts_with("Run exceeded"), "{}", description);
+
+        let (deadline, description) = state_machine
+            .task_run_deadline(&task_with_timeout(30), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 41_000);
+        assert!(
+            description.starts_with("Task run exceeded"),
+            "{}",
+            description
+        );
+    }
+
+    #[tokio::test]
+    /// A timeout of 0 means that timeout isn't enforced, leaving only the other one.
+    async fn task_run_deadline_unset_timeouts() {
+        let harness = TestHarness::with_conf(run_timeout_conf(60)).await;
+        let state_machine = timeout_state_machine(&harness).await;
+
+        let (deadline, _) = state_machine
+            .task_run_deadline(&task_with_timeout(0), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 61_000);
+
+        let harness = TestHarness::with_conf(run_timeout_conf(0)).await;
+        let state_machine = timeout_state_machine(&harness).await;
+
+        let (deadline, _) = state_machine
+            .task_run_deadline(&task_with_timeout(120), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 131_000);
+
+        assert!(state_machine
+            .task_run_deadline(&task_with_timeout(0), 11_000)
+            .is_none());
+    }
+
+    #[tokio::test]
+    /// A pipeline's run timeout can only shorten the global run timeout, and applies on its own when there is no
+    /// global run timeout.
+    async fn task_run_deadline_pipeline_timeout() {
+        let harness = TestHarness::with_conf(run_timeout_conf(60)).await;
+
+        let state_machine = pipeline_timeout_state_machine(&harness, 30).await;
+        let (deadline, _) = state_machine
+            .task_run_deadline(&task_with_timeout(0), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 31_000);
+
+        let state_machine = pipeline_timeout_state_machine(&harness, 120).await;
+        let (deadline, _) = state_machine
+            .task_run_deadline(&task_with_timeout(0), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 61_000);
+
+        let harness = TestHarness::with_conf(run_timeout_conf(0)).await;
+
+        let state_machine = pipeline_timeout_state_machine(&harness, 120).await;
+        let (deadline, description) = state_machine
+            .task_run_deadline(&task_with_timeout(0), 11_000)
+            .unwrap();
+        assert_eq!(deadline, 121_000);
+        assert!(description.starts_with("Run exceeded"), "{}", description);
+        assert!(state_machine.run_timed_out());
+    }
+
+    #[tokio::test]
+    /// Timeouts too large to represent as a deadline never expire instead of overflowing.
+    async fn task_run_deadline_large_timeout() {
+        let harness = TestHarness::with_conf(run_timeout_conf(0)).await;
+        let state_machine = timeout_state_machine(&harness).await;
+
+        let (deadline, _) = state_machine
+            .task_run_deadline(&task_with_timeout(u64::MAX), 11_000)
+            .unwrap();
+        assert_eq!(deadline, u64::MAX);
+    }
+
+    #[tokio::test]
+    /// Common tasks become regular tasks named after their label, with the pipeline's settings taking
+    /// precedence over those given at registration.
+    async fn resolve_common_tasks_enabled() {
+        let harness = TestHarness::new().await;
+        let state_machine =
+            common_task_state_machine(&harness, Some(common_task::Status::Enabled)).await;
+
+        let tasks = state_machine.resolve_common_tasks().unwrap();
+        assert_eq!(tasks.len(), 1);
+
+        let task = &tasks[0];
+        assert_eq!(task.id, "common");
+        assert_eq!(task.image, "test_image");
+        assert_eq!(
+            task.registry_auth.as_ref().map(|auth| auth.pass.as_str()),
+            Some("registry_pass")
+        );
+
+        let variables: HashMap<&str, (&str, &VariableOwner)> = task
+            .variables
+            .iter()
+            .map(|variable| {
+                (
+                    variable.key.as_str(),
+                    (variable.value.as_str(), &variable.owner),
+                )
+            })
+            .collect();
+        assert_eq!(
+            variables,
+            HashMap::from([
+                ("registered", ("registered_value", &VariableOwner::System)),
+                ("overridden", ("pipeline_value", &VariableOwner::User)),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    /// A pipeline can't run when a common task it uses was never installed.
+    async fn resolve_common_tasks_missing() {
+        let harness = TestHarness::new().await;
+        let state_machine = common_task_state_machine(&harness, None).await;
+
+        let err = state_machine.resolve_common_tasks().unwrap_err();
+        assert!(err.contains("is not installed"), "{}", err);
+    }
+
+    #[tokio::test]
+    /// A pipeline can't run when a common task it uses is disabled.
+    async fn resolve_common_tasks_disabled() {
+        let harness = TestHarness::new().await;
+        let state_machine =
+            common_task_state_machine(&harness, Some(common_task::Status::Disabled)).await;
+
+        let err = state_machine.resolve_common_tasks().unwrap_err();
+        assert!(err.contains("is not enabled"), "{}", err);
+    }
+
+    #[test]
+    /// Unset task resources fall back to the server default.
+    fn resolve_resources_default_limit() {
+        assert_eq!(resolve_resources(0, 0, 1000, 2000), (None, Some(1000)));
+        assert_eq!(
+            resolve_resources(500, 0, 1000, 2000),
+            (Some(500), Some(1000))
+        );
+    }
+
+    #[test]
+    /// Limits are capped by the server maximum and requests by the final limit.
+    fn resolve_resources_max_limit() {
+        assert_eq!(resolve_resources(0, 4000, 1000, 2000), (None, Some(2000)));
+        assert_eq!(
+            resolve_resources(3000, 4000, 1000, 2000),
+            (Some(2000), Some(2000))
+        );
+        assert_eq!(resolve_resources(0, 0, 0, 2000), (None, Some(2000)));
+    }
+
+    #[test]
+    /// Without a default or maximum the task's own values are passed through untouched.
+    fn resolve_resources_unbounded() {
+        assert_eq!(resolve_resources(0, 0, 0, 0), (None, None));
+        assert_eq!(resolve_resources(500, 0, 0, 0), (Some(500), None));
+        assert_eq!(
+            resolve_resources(500, u64::MAX, 0, 0),
+            (Some(500), Some(u64::MAX))
+        );
+    }
+
+    /// Returns a state machine for a stored, running run of a pipeline with the given tasks.
+    async fn stored_state_machine(
+        harness: &TestHarness,
+        tasks: Vec<gofer_sdk::config::Task>,
+    ) -> RunStateMachine {
+        let pipeline = harness
+            .create_pipeline(
+                "test_namespace",
+                gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline").tasks(tasks),
+            )
+            .await;
+
+        let mut run = run::Run::new(
+            "test_namespace",
+            "test_pipeline",
+            run::TriggerInfo {
+                name: "manual".to_string(),
+                label: "cli".to_string(),
+            },
+            vec![],
+        );
+        run.state = run::State::Running;
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        run.id = storage::runs::insert(&mut conn, &run).await.unwrap();
+
+        RunStateMachine::new(harness.api.clone(), pipeline, run).await
+    }
+
+    /// Returns a state machine for a stored run of a pipeline with a single task using the given retry policy.
+    async fn retry_state_machine(
+        harness: &TestHarness,
+        retry_policy: gofer_sdk::config::RetryPolicy,
+    ) -> RunStateMachine {
+        stored_state_machine(
+            harness,
+            vec![gofer_sdk::config::Task::new("test_task", "test_image").retry_policy(retry_policy)],
+        )
+        .await
+    }
+
+    /// Stores an attempt of the task's task run, as it was left when Gofer stopped.
+    async fn store_task_run(
+        harness: &TestHarness,
+        state_machine: &RunStateMachine,
+        task_id: &str,
+        state: task_run::State,
+        status: task_run::Status,
+        reason: Option<task_run::Reason>,
+    ) {
+        let mut task_run = task_run::TaskRun::new(
+            "test_namespace",
+            "test_pipeline",
+            state_machine.run.id,
+            state_machine.pipeline.tasks.get(task_id).unwrap().clone(),
+        );
+        task_run.state = state;
+        task_run.status = status;
+        task_run.status_reason = reason.map(|reason| task_run::StatusReason {
+            reason,
+            description: "test_description".to_string(),
+        });
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::task_runs::insert(&mut conn, &task_run)
+            .await
+            .unwrap();
+    }
+
+    fn container(task_id: &str, attempt: u64) -> String {
+        fmt::task_container_id("test_namespace", "test_pipeline", 1, task_id, attempt)
+    }
+
+    async fn stored_run(harness: &TestHarness) -> run::Run {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::runs::get(&mut conn, "test_namespace", "test_pipeline", 1)
+            .await
+            .unwrap()
+    }
+
+    /// Returns the attempt and status of every stored task run, in order.
+    async fn task_run_attempts(harness: &TestHarness) -> Vec<(String, u64, task_run::Status)> {
+        stored_task_runs(harness)
+            .await
+            .into_iter()
+            .map(|task_run| (task_run.id, task_run.attempt, task_run.status))
+            .collect()
+    }
+
+    async fn stored_task_runs(harness: &TestHarness) -> Vec<task_run::TaskRun> {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::task_runs::list(&mut conn, 0, 0, "test_namespace", "test_pipeline", 1)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    /// A task run that keeps failing is attempted once more for every retry, waiting out the backoff before each
+    /// one. Every attempt is stored as its own task run and completes with its own event.
+    async fn task_run_retried_until_retries_run_out() {
+        let harness = TestHarness::new().await;
+        let state_machine = retry_state_machine(
+            &harness,
+            gofer_sdk::config::RetryPolicy::exponential(
+                2,
+                std::time::Duration::from_secs(1),
+                std::time::Duration::from_secs(0),
+            ),
+        )
+        .await;
+
+        let subscription = harness
+            .api
+            .event_bus
+            .subscribe(KindDiscriminant::CompletedTaskRun)
+            .await
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        state_machine.execute_task_tree().await;
+
+        // Exponential backoff waits 1 second before the first retry and 2 seconds before the second.
+        assert!(started.elapsed() >= std::time::Duration::from_secs(3));
+
+        let task_runs = stored_task_runs(&harness).await;
+        assert_eq!(
+            task_runs
+                .iter()
+                .map(|task_run| task_run.attempt)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        for task_run in task_runs {
+            assert_eq!(task_run.state, task_run::State::Complete);
+            assert_eq!(task_run.status, task_run::Status::Failed);
+            assert_eq!(
+                task_run.status_reason.unwrap().reason,
+                task_run::Reason::SchedulerError
+            );
+        }
+
+        let mut attempts = vec![];
+        for _ in 0..3 {
+            match next_event(&subscription).await.kind {
+                event::Kind::CompletedTaskRun { attempt, .. } => attempts.push(attempt),
+                kind => panic!("unexpected event {:?}", kind),
+            }
+        }
+        attempts.sort_unstable();
+        assert_eq!(attempts, vec![1, 2, 3]);
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        assert!(subscription.receiver.try_recv().is_err());
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        let finished_run = storage::runs::get(&mut conn, "test_namespace", "test_pipeline", 1)
+            .await
+            .unwrap();
+        assert_eq!(finished_run.status, run::Status::Failed);
+    }
+
+    #[tokio::test]
+    /// Retries stop at the first attempt that succeeds, and the run takes on the status of that final attempt.
+    async fn task_run_retried_until_success() {
+        let scheduler = Arc::new(FakeScheduler::default());
+        let harness =
+            TestHarness::with_scheduler(crate::conf::api::Config::default(), scheduler.clone())
+                .await;
+        let state_machine = retry_state_machine(
+            &harness,
+            gofer_sdk::config::RetryPolicy::constant(3, std::time::Duration::from_secs(0)),
+        )
+        .await;
+
+        let execution = tokio::spawn(state_machine.execute_task_tree());
+
+        for (attempt, exit_code) in [(1, 1), (2, 0)] {
+            scheduler
+                .wait_for_start(&container("test_task", attempt))
+                .await;
+            scheduler.exit(&container("test_task", attempt), exit_code);
+        }
+
+        tokio::time::timeout(tokio::time::Duration::from_secs(10), execution)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let task_runs = stored_task_runs(&harness).await;
+        assert_eq!(
+            task_runs
+                .iter()
+                .map(|task_run| (task_run.attempt, task_run.status.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, task_run::Status::Failed),
+                (2, task_run::Status::Successful)
+            ]
+        );
+
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        let finished_run = storage::runs::get(&mut conn, "test_namespace", "test_pipeline", 1)
+            .await
+            .unwrap();
+        assert_eq!(finished_run.status, run::Status::Successful);
+    }
+
+    #[tokio::test]
+    /// Task runs whose container was running when Gofer stopped are monitored again rather than started over.
+    async fn resume_reattaches_running_task_run() {
+        let scheduler = Arc::new(FakeScheduler::default());
+        let harness =
+            TestHarness::with_scheduler(crate::conf::api::Config::default(), scheduler.clone())
+                .await;
+        let state_machine = stored_state_machine(
+            &harness,
+            vec![gofer_sdk::config::Task::new("test_task", "test_image")],
+        )
+        .await;
+        store_task_run(
+            &harness,
+            &state_machine,
+            "test_task",
+            task_run::State::Running,
+            task_run::Status::Unknown,
+            None,
+        )
+        .await;
+        scheduler.start(&container("test_task", 1));
+
+        let resumed = tokio::spawn(state_machine.resume_task_tree());
+        scheduler.exit(&container("test_task", 1), 0);
+
+        tokio::time::timeout(tokio::time::Duration::from_secs(10), resumed)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            task_run_attempts(&harness).await,
+            vec![("test_task".to_string(), 1, task_run::Status::Successful)]
+        );
+        assert_eq!(stored_run(&harness).await.status, run::Status::Successful);
+    }
+
+    #[tokio::test]
+    /// Task runs whose container the scheduler no longer knows about are marked as orphaned.
+    async fn resume_orphans_missing_container() {
+        let scheduler = Arc::new(FakeScheduler::default());
+        let harness =
+            TestHarness::with_scheduler(crate::conf::api::Config::default(), scheduler.clone())
+                .await;
+        let state_machine = stored_state_machine(
+            &harness,
+            vec![gofer_sdk::config::Task::new("test_task", "test_image")],
+        )
+        .await;
+        store_task_run(
+            &harness,
+            &state_machine,
+            "test_task",
+            task_run::State::Running,
+            task_run::Status::Unknown,
+            None,
+        )
+        .await;
+
+        tokio::time::timeout(
+            tokio::time::Duration::from_secs(10),
+            state_machine.resume_task_tree(),
+        )
+        .await
+        .unwrap();
+
+        let task_runs = stored_task_runs(&harness).await;
+        assert_eq!(task_runs.len(), 1);
+        assert_eq!(task_runs[0].status, task_run::Status::Unknown);
+        assert_eq!(
+            task_runs[0].status_reason.as_ref().unwrap().reason,
+            task_run::Reason::Orphaned
+        );
+        assert_eq!(stored_run(&harness).await.status, run::Status::Failed);
+    }
+
+    #[tokio::test]
+    /// Finished task runs are left alone while tasks that never got a task run are launched.
+    async fn resume_launches_remaining_tasks() {
+        let scheduler = Arc::new(FakeScheduler::default());
+        let harness =
+            TestHarness::with_scheduler(crate::conf::api::Config::default(), scheduler.clone())
+                .await;
+        let state_machine = stored_state_machine(
+            &harness,
+            vec![
+                gofer_sdk::config::Task::new("first_task", "test_image"),
+                gofer_sdk::config::Task::new("second_task", "test_image").depends_on_one(
+                    "first_task",
+                    gofer_sdk::config::RequiredParentStatus::Success,
+                ),
+            ],
+        )
+        .await;
+        store_task_run(
+            &harness,
+            &state_machine,
+            "first_task",
+            task_run::State::Complete,
+            task_run::Status::Successful,
+            None,
+        )
+        .await;
+
+        let resumed = tokio::spawn(state_machine.resume_task_tree());
+        scheduler.wait_for_start(&container("second_task", 1)).await;
+        scheduler.exit(&container("second_task", 1), 0);
+
+        tokio::time::timeout(tokio::time::Duration::from_secs(10), resumed)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            task_run_attempts(&harness).await,
+            vec![
+                ("first_task".to_string(), 1, task_run::Status::Successful),
+                ("second_task".to_string(), 1, task_run::Status::Successful)
+            ]
+        );
+        assert!(harness
+            .api
+            .scheduler
+            .get_state(scheduler::GetStateRequest {
+                name: container("first_task", 1),
+            })
+            .await
+            .is_err());
+        assert_eq!(stored_run(&harness).await.status, run::Status::Successful);
+    }
+
+    #[tokio::test]
+    /// A failed attempt whose retry hadn't been created yet when Gofer stopped is still retried.
+    async fn resume_retries_failed_attempt() {
+        let scheduler = Arc::new(FakeScheduler::default());
+        let harness =
+            TestHarness::with_scheduler(crate::conf::api::Config::default(), scheduler.clone())
+                .await;
+        let state_machine = retry_state_machine(
+            &harness,
+            gofer_sdk::config::RetryPolicy::constant(1, std::time::Duration::from_secs(0)),
+        )
+        .await;
+        store_task_run(
+            &harness,
+            &state_machine,
+            "test_task",
+            task_run::State::Complete,
+            task_run::Status::Failed,
+            Some(task_run::Reason::AbnormalExit),
+        )
+        .await;
+
+        let resumed = tokio::spawn(state_machine.resume_task_tree());
+        scheduler.wait_for_start(&container("test_task", 2)).await;
+        scheduler.exit(&container("test_task", 2), 0);
+
+        tokio::time::timeout(tokio::time::Duration::from_secs(10), resumed)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            task_run_attempts(&harness).await,
+            vec![
+                ("test_task".to_string(), 1, task_run::Status::Failed),
+                ("test_task".to_string(), 2, task_run::Status::Successful)
+            ]
+        );
+        assert_eq!(stored_run(&harness).await.status, run::Status::Successful);
+    }
+}
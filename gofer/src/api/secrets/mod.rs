@@ -0,0 +1,282 @@
+use crate::{
+    api::{fmt, tokens, validate, Api},
+    secret_store, storage,
+};
+use gofer_models::{event, secret, token::Token};
+use gofer_proto::{
+    DeletePipelineSecretRequest, DeletePipelineSecretResponse, GetPipelineSecretRequest,
+    GetPipelineSecretResponse, ListPipelineSecretsRequest, ListPipelineSecretsResponse,
+    PutPipelineSecretRequest, PutPipelineSecretResponse,
+};
+use slog_scope::error;
+use sqlx::SqliteConnection;
+use tonic::{Response, Status};
+
+impl Api {
+    /// Removes all secrets belonging to a pipeline from the secret store. The secret keys kept in storage
+    /// are removed along with the pipeline itself.
+    pub async fn delete_all_pipeline_secrets(
+        &self,
+        conn: &mut SqliteConnection,
+        namespace_id: &str,
+        pipeline_id: &str,
+    ) -> Result<(), storage::StorageError> {
+        let secrets = storage::secrets::list(conn, namespace_id, pipeline_id).await?;
+
+        for secret in secrets {
+            if let Err(e) = self
+                .secret_store
+                .delete_secret(&fmt::secret_key(namespace_id, pipeline_id, &secret.key))
+                .await
+            {
+                error!("could not remove pipeline secret from secret store";
+                    "namespace" => namespace_id, "pipeline" => pipeline_id,
+                    "key" => &secret.key, "error" => e.to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies the pipeline exists, so that secrets can't be stored for pipelines that were never created.
+async fn pipeline_exists(
+    conn: &mut storage::Conn<'_>,
+    namespace_id: &str,
+    pipeline_id: &str,
+) -> Result<(), Status> {
+    storage::pipelines::get(conn, namespace_id, pipeline_id)
+        .await
+        .map_err(|e| match e {
+            storage::StorageError::NotFound => {
+                Status::not_found(format!("pipeline with id '{}' does not exist", pipeline_id))
+            }
+            _ => Status::internal(e.to_string()),
+        })?;
+
+    Ok(())
+}
+
+impl Api {
+    pub async fn get_pipeline_secret_handler(
+        &self,
+        token: &Token,
+        args: GetPipelineSecretRequest,
+    ) -> Result<Response<GetPipelineSecretResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let secret =
+            storage::secrets::get(&mut conn, &args.namespace_id, &args.pipeline_id, &args.key)
+                .await
+                .map_err(|e| match e {
+                    storage::StorageError::NotFound => {
+                        Status::not_found(format!("secret with key '{}' does not exist", &args.key))
+                    }
+                    _ => Status::internal(e.to_string()),
+                })?;
+
+        let mut value = String::new();
+
+        if args.include_secret {
+            let secret_value = self
+                .secret_store
+                .get_secret(&fmt::secret_key(
+                    &args.namespace_id,
+                    &args.pipeline_id,
+                    &args.key,
+                ))
+                .await
+                .map_err(|e| match e {
+                    secret_store::SecretStoreError::NotFound => {
+                        Status::not_found(format!("secret with key '{}' does not exist", &args.key))
+                    }
+                    _ => Status::internal(e.to_string()),
+                })?;
+
+            value = String::from_utf8_lossy(&secret_value).to_string();
+        }
+
+        Ok(Response::new(GetPipelineSecretResponse {
+            secret: Some(secret.into()),
+            value,
+        }))
+    }
+
+    pub async fn list_pipeline_secrets_handler(
+        &self,
+        token: &Token,
+        args: ListPipelineSecretsRequest,
+    ) -> Result<Response<ListPipelineSecretsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let secrets = storage::secrets::list(&mut conn, &args.namespace_id, &args.pipeline_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListPipelineSecretsResponse {
+            secrets: secrets.into_iter().map(|secret| secret.into()).collect(),
+        }))
+    }
+
+    pub async fn put_pipeline_secret_handler(
+        &self,
+        token: &Token,
+        args: PutPipelineSecretRequest,
+    ) -> Result<Response<PutPipelineSecretResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+        validate::arg("value", args.value.clone(), vec![validate::not_empty_str])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        pipeline_exists(&mut conn, &args.namespace_id, &args.pipeline_id).await?;
+
+        self.secret_store
+            .put_secret(
+                &fmt::secret_key(&args.namespace_id, &args.pipeline_id, &args.key),
+                &args.value,
+                args.force,
+            )
+            .await
+            .map_err(|e| match e {
+                secret_store::SecretStoreError::Exists => Status::already_exists(format!(
+                    "secret with key '{}' already exists",
+                    &args.key
+                )),
+                _ => Status::internal(e.to_string()),
+            })?;
+
+        let secret = secret::Secret::new(&args.namespace_id, &args.pipeline_id, &args.key);
+
+        // Overwriting a secret keeps its original key record in place.
+        match storage::secrets::insert(&mut conn, &secret).await {
+            Ok(_) => {}
+            Err(storage::StorageError::Exists) if args.force => {}
+            Err(storage::StorageError::Exists) => {
+                return Err(Status::already_exists(format!(
+                    "secret with key '{}' already exists",
+                    &args.key
+                )))
+            }
+            Err(e) => return Err(Status::internal(e.to_string())),
+        };
+
+        self.event_bus
+            .publish(event::Kind::PutPipelineSecret {
+                namespace_id: args.namespace_id.clone(),
+                pipeline_id: args.pipeline_id.clone(),
+                key: args.key.clone(),
+            })
+            .await;
+
+        Ok(Response::new(PutPipelineSecretResponse {}))
+    }
+
+    pub async fn delete_pipeline_secret_handler(
+        &self,
+        token: &Token,
+        args: DeletePipelineSecretRequest,
+    ) -> Result<Response<DeletePipelineSecretResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        validate::arg(
+            "namespace_id",
+            args.namespace_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg(
+            "pipeline_id",
+            args.pipeline_id.clone(),
+            vec![validate::is_valid_identifier],
+        )?;
+        validate::arg("key", args.key.clone(), vec![validate::is_valid_identifier])?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        storage::secrets::get(&mut conn, &args.namespace_id, &args.pipeline_id, &args.key)
+            .await
+            .map_err(|e| match e {
+                storage::StorageError::NotFound => {
+                    Status::not_found(format!("secret with key '{}' does not exist", &args.key))
+                }
+                _ => Status::internal(e.to_string()),
+            })?;
+
+        self.secret_store
+            .delete_secret(&fmt::secret_key(
+                &args.namespace_id,
+                &args.pipeline_id,
+                &args.key,
+            ))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        storage::secrets::delete(&mut conn, &args.namespace_id, &args.pipeline_id, &args.key)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.event_bus
+            .publish(event::Kind::DeletedPipelineSecret {
+                namespace_id: args.namespace_id.clone(),
+                pipeline_id: args.pipeline_id.clone(),
+                key: args.key.clone(),
+            })
+            .await;
+
+        Ok(Response::new(DeletePipelineSecretResponse {}))
+    }
+}
@@ -1,5 +1,8 @@
-use crate::api::{Api, BUILD_COMMIT, BUILD_SEMVER};
-use gofer_proto::GetSystemInfoResponse;
+use crate::api::{tokens, Api, BUILD_COMMIT, BUILD_SEMVER};
+use crate::secret_store::SecretStoreError;
+use gofer_models::token::Token;
+use gofer_proto::{GetSystemInfoResponse, RotateSecretStoreKeysResponse};
+use slog_scope::info;
 use tonic::{Response, Status};
 
 impl Api {
@@ -10,4 +13,28 @@ impl Api {
             semver: BUILD_SEMVER.to_string(),
         }))
     }
+
+    pub async fn rotate_secret_store_keys_handler(
+        &self,
+        token: &Token,
+    ) -> Result<Response<RotateSecretStoreKeysResponse>, Status> {
+        tokens::require_management_token(token)?;
+
+        let secrets_reencrypted =
+            self.secret_store
+                .rotate_encryption_keys()
+                .await
+                .map_err(|e| match e {
+                    SecretStoreError::FailedPrecondition => Status::failed_precondition(
+                        "secret store engine does not support key rotation",
+                    ),
+                    _ => Status::internal(e.to_string()),
+                })?;
+
+        info!("re-encrypted secrets with active encryption key"; "count" => secrets_reencrypted);
+
+        Ok(Response::new(RotateSecretStoreKeysResponse {
+            secrets_reencrypted,
+        }))
+    }
 }
@@ -1,8 +1,8 @@
-use crate::api::{fmt, validate, Api, GOFER_EOF};
+use crate::api::{fmt, runs::redact_private_variables, tokens, validate, Api, GOFER_EOF};
 use crate::{scheduler, storage};
 use anyhow::Result;
 use futures::Stream;
-use gofer_models::task_run;
+use gofer_models::{task_run, token::Token};
 use gofer_proto::{
     CancelTaskRunRequest, CancelTaskRunResponse, DeleteTaskRunLogsRequest,
     DeleteTaskRunLogsResponse, GetTaskRunLogsRequest, GetTaskRunLogsResponse, GetTaskRunRequest,
@@ -113,19 +113,42 @@ pub async fn stream_task_run_logs(
     }
 }
 
+/// Converts the task run for an API response, redacting its private variables unless asked not to.
+fn to_task_run_response(task_run: task_run::TaskRun, reveal_private_variables: bool) -> TaskRun {
+    let mut task_run = TaskRun::from(task_run);
+
+    if !reveal_private_variables {
+        redact_private_variables(&mut task_run.variables);
+        if let Some(task) = task_run.task.as_mut() {
+            redact_private_variables(&mut task.variables);
+        }
+    }
+
+    task_run
+}
+
 impl Api {
-    /// Calls upon the scheduler to terminate a specific container.
-    pub async fn cancel_task_run(
-        &self,
-        namespace_id: &str,
-        pipeline_id: &str,
-        run_id: u64,
-        task_run_id: &str,
-        timeout: u64,
-    ) -> Result<()> {
+    /// Calls upon the scheduler to terminate the container of a specific task run attempt. Task runs
+    /// that are still in progress are also marked as cancelled so they won't be retried.
+    pub async fn cancel_task_run(&self, task_run: &task_run::TaskRun, timeout: u64) -> Result<()> {
+        if task_run.state != task_run::State::Complete {
+            self.cancelled_task_runs.insert((
+                task_run.namespace.clone(),
+                task_run.pipeline.clone(),
+                task_run.run,
+                task_run.id.clone(),
+            ));
+        }
+
         self.scheduler
             .stop_container(scheduler::StopContainerRequest {
-                name: fmt::task_container_id(namespace_id, pipeline_id, run_id, task_run_id),
+                name: fmt::task_container_id(
+                    &task_run.namespace,
+                    &task_run.pipeline,
+                    task_run.run,
+                    &task_run.id,
+                    task_run.attempt,
+                ),
                 timeout: timeout as i64,
             })
             .await?;
@@ -135,8 +158,15 @@ impl Api {
 
     pub async fn list_task_runs_handler(
         &self,
+        token: &Token,
         args: ListTaskRunsRequest,
     ) -> Result<Response<ListTaskRunsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        if args.reveal_private_variables {
+            tokens::require_management_token(token)?;
+        }
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -168,7 +198,10 @@ impl Api {
         .await
         .map(|task_runs| {
             Response::new(ListTaskRunsResponse {
-                task_runs: task_runs.into_iter().map(TaskRun::from).collect(),
+                task_runs: task_runs
+                    .into_iter()
+                    .map(|task_run| to_task_run_response(task_run, args.reveal_private_variables))
+                    .collect(),
             })
         })
         .map_err(|e| Status::internal(e.to_string()))
@@ -176,8 +209,15 @@ impl Api {
 
     pub async fn get_task_run_handler(
         &self,
+        token: &Token,
         args: GetTaskRunRequest,
     ) -> Result<Response<GetTaskRunResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
+        if args.reveal_private_variables {
+            tokens::require_management_token(token)?;
+        }
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -210,11 +250,15 @@ impl Api {
             &args.pipeline_id,
             args.run_id,
             &args.id,
+            args.attempt,
         )
         .await
         .map(|task_run| {
             Response::new(GetTaskRunResponse {
-                task_run: Some(task_run.into()),
+                task_run: Some(to_task_run_response(
+                    task_run,
+                    args.reveal_private_variables,
+                )),
             })
         })
         .map_err(|e| match e {
@@ -227,8 +271,11 @@ impl Api {
 
     pub async fn cancel_task_run_handler(
         &self,
+        token: &Token,
         args: CancelTaskRunRequest,
     ) -> Result<Response<CancelTaskRunResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -260,12 +307,13 @@ impl Api {
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
-        storage::task_runs::get(
+        let task_run = storage::task_runs::get(
             &mut conn,
             &args.namespace_id,
             &args.pipeline_id,
             args.run_id,
             &args.id,
+            0,
         )
         .await
         .map_err(|e| match e {
@@ -275,16 +323,7 @@ impl Api {
             _ => Status::internal(e.to_string()),
         })?;
 
-        if let Err(e) = self
-            .cancel_task_run(
-                &args.namespace_id,
-                &args.pipeline_id,
-                args.run_id,
-                &args.id,
-                timeout,
-            )
-            .await
-        {
+        if let Err(e) = self.cancel_task_run(&task_run, timeout).await {
             return Err(Status::internal(e.to_string()));
         }
 
@@ -293,8 +332,11 @@ impl Api {
 
     pub async fn get_task_run_logs_handler(
         self: Arc<Self>,
+        token: &Token,
         args: GetTaskRunLogsRequest,
     ) -> Result<Response<GetTaskRunLogsStream>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -327,6 +369,7 @@ impl Api {
             &args.pipeline_id,
             args.run_id,
             &args.id,
+            args.attempt,
         )
         .await
         .map_err(|e| match e {
@@ -361,8 +404,11 @@ impl Api {
 
     pub async fn delete_task_run_logs_handler(
         &self,
+        token: &Token,
         args: DeleteTaskRunLogsRequest,
     ) -> Result<Response<DeleteTaskRunLogsResponse>, Status> {
+        tokens::require_pipeline_access(token, &args.namespace_id, &args.pipeline_id)?;
+
         validate::arg(
             "namespace_id",
             args.namespace_id.clone(),
@@ -395,6 +441,7 @@ impl Api {
             &args.pipeline_id,
             args.run_id,
             &args.id,
+            args.attempt,
         )
         .await
         .map_err(|e| match e {
@@ -0,0 +1,285 @@
+use super::*;
+use async_trait::async_trait;
+use futures::Stream;
+use gofer_models::pipeline;
+use std::{collections::HashMap, pin::Pin};
+
+const TEST_ENCRYPTION_KEY: &str = "changemechangemechangemechangeme";
+
+/// Stands in for a container scheduler in tests that don't launch containers. Every call fails.
+#[derive(Debug)]
+struct NoopScheduler;
+
+#[async_trait]
+impl scheduler::Scheduler for NoopScheduler {
+    async fn start_container(
+        &self,
+        _: scheduler::StartContainerRequest,
+    ) -> Result<scheduler::StartContainerResponse, scheduler::SchedulerError> {
+        Err(scheduler::SchedulerError::Unknown(
+            "no scheduler in tests".to_string(),
+        ))
+    }
+
+    async fn stop_container(
+        &self,
+        _: scheduler::StopContainerRequest,
+    ) -> Result<(), scheduler::SchedulerError> {
+        Err(scheduler::SchedulerError::Unknown(
+            "no scheduler in tests".to_string(),
+        ))
+    }
+
+    async fn get_state(
+        &self,
+        _: scheduler::GetStateRequest,
+    ) -> Result<scheduler::GetStateResponse, scheduler::SchedulerError> {
+        Err(scheduler::SchedulerError::Unknown(
+            "no scheduler in tests".to_string(),
+        ))
+    }
+
+    fn get_logs(
+        &self,
+        _: scheduler::GetLogsRequest,
+    ) -> Pin<Box<dyn Stream<Item = Result<scheduler::Log, scheduler::SchedulerError>> + Send>> {
+        Box::pin(futures::stream::empty())
+    }
+}
+
+/// Stands in for a container scheduler in tests that need task runs to be in progress. Containers start
+/// successfully and keep running until the test exits them.
+#[derive(Debug, Default)]
+pub struct FakeScheduler {
+    /// The exit code of every container started, keyed by container name. Containers without one are still running.
+    containers: DashMap<String, Option<u8>>,
+}
+
+impl FakeScheduler {
+    /// Has a container running as if it had been started before the test.
+    pub fn start(&self, name: &str) {
+        self.containers.insert(name.to_string(), None);
+    }
+
+    /// Waits for the container to be started.
+    pub async fn wait_for_start(&self, name: &str) {
+        for _ in 0..1000 {
+            if self.containers.contains_key(name) {
+                return;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        panic!("container {} was never started", name);
+    }
+
+    /// Has the container exit with the given code.
+    pub fn exit(&self, name: &str, exit_code: u8) {
+        self.containers.insert(name.to_string(), Some(exit_code));
+    }
+
+    /// Returns the names of all containers that are still running.
+    pub fn running(&self) -> Vec<String> {
+        let mut running: Vec<String> = self
+            .containers
+            .iter()
+            .filter(|container| container.value().is_none())
+            .map(|container| container.key().clone())
+            .collect();
+        running.sort();
+        running
+    }
+}
+
+#[async_trait]
+impl scheduler::Scheduler for FakeScheduler {
+    async fn start_container(
+        &self,
+        request: scheduler::StartContainerRequest,
+    ) -> Result<scheduler::StartContainerResponse, scheduler::SchedulerError> {
+        self.containers.insert(request.name, None);
+
+        Ok(scheduler::StartContainerResponse {
+            scheduler_id: None,
+            url: None,
+        })
+    }
+
+    async fn stop_container(
+        &self,
+        request: scheduler::StopContainerRequest,
+    ) -> Result<(), scheduler::SchedulerError> {
+        match self.containers.get_mut(&request.name) {
+            Some(mut exit_code) => {
+                exit_code.get_or_insert(137);
+                Ok(())
+            }
+            None => Err(scheduler::SchedulerError::NoSuchContainer(request.name)),
+        }
+    }
+
+    async fn get_state(
+        &self,
+        request: scheduler::GetStateRequest,
+    ) -> Result<scheduler::GetStateResponse, scheduler::SchedulerError> {
+        match self.containers.get(&request.name) {
+            Some(exit_code) => Ok(scheduler::GetStateResponse {
+                exit_code: *exit_code,
+                state: match *exit_code {
+                    Some(_) => scheduler::ContainerState::Exited,
+                    None => scheduler::ContainerState::Running,
+                },
+            }),
+            None => Err(scheduler::SchedulerError::NoSuchContainer(request.name)),
+        }
+    }
+
+    fn get_logs(
+        &self,
+        _: scheduler::GetLogsRequest,
+    ) -> Pin<Box<dyn Stream<Item = Result<scheduler::Log, scheduler::SchedulerError>> + Send>> {
+        Box::pin(futures::stream::empty())
+    }
+}
+
+/// An API backed by throwaway embedded stores, for testing handlers without starting the service.
+pub struct TestHarness {
+    pub api: Arc<Api>,
+    storage_path: String,
+    object_store_path: String,
+    secret_store_path: String,
+}
+
+impl TestHarness {
+    pub async fn new() -> Self {
+        Self::with_conf(conf::api::Config::default()).await
+    }
+
+    /// Builds the API from the given config. Its store settings are replaced with throwaway embedded stores.
+    pub async fn with_conf(conf: conf::api::Config) -> Self {
+        Self::with_scheduler(conf, Arc::new(NoopScheduler)).await
+    }
+
+    /// Builds the API from the given config and scheduler. Its store settings are replaced with throwaway
+    /// embedded stores.
+    pub async fn with_scheduler(
+        mut conf: conf::api::Config,
+        scheduler: Arc<dyn scheduler::Scheduler + Sync + Send>,
+    ) -> Self {
+        let id = nanoid::nanoid!(10);
+        let storage_path = format!("/tmp/gofer_tests_api{}.db", id);
+        let object_store_path = format!("/tmp/gofer_tests_api_object_store{}", id);
+        let secret_store_path = format!("/tmp/gofer_tests_api_secret_store{}", id);
+
+        conf.object_store.embedded = Some(conf::api::EmbeddedObjectStore {
+            path: object_store_path.clone(),
+        });
+        conf.secret_store.embedded = Some(conf::api::EmbeddedSecretStore {
+            path: secret_store_path.clone(),
+            active_key: "default".to_string(),
+            encryption_keys: HashMap::from([(
+                "default".to_string(),
+                TEST_ENCRYPTION_KEY.to_string(),
+            )]),
+            encryption_key: None,
+        });
+
+        let storage = storage::Db::new(&storage_path, TEST_ENCRYPTION_KEY)
+            .await
+            .unwrap();
+        let object_store = object_store::init_object_store(&conf.object_store)
+            .await
+            .unwrap();
+        let secret_store = secret_store::init_secret_store(&conf.secret_store)
+            .await
+            .unwrap();
+        let event_bus = Arc::new(events::EventBus::new(storage.clone(), 7889238, 604800));
+
+        let api = Api {
+            shutdown: CancellationToken::new(),
+            conf,
+            storage,
+            scheduler,
+            object_store,
+            secret_store,
+            event_bus,
+            triggers: DashMap::new(),
+            trigger_watches: DashMap::new(),
+            common_tasks: DashMap::new(),
+            run_completions: tokio::sync::watch::channel(()).0,
+            queued_runs: tokio::sync::watch::channel(()).0,
+            cancelled_task_runs: DashSet::new(),
+            tokens: DashMap::new(),
+        };
+
+        Self {
+            api: Arc::new(api),
+            storage_path,
+            object_store_path,
+            secret_store_path,
+        }
+    }
+
+    /// Stores a pipeline built from the given config, creating its namespace first if it doesn't exist yet.
+    pub async fn create_pipeline(
+        &self,
+        namespace_id: &str,
+        config: gofer_sdk::config::Pipeline,
+    ) -> pipeline::Pipeline {
+        let mut conn = self.api.storage.conn().await.unwrap();
+
+        if storage::namespaces::get(&mut conn, namespace_id)
+            .await
+            .is_err()
+        {
+            let test_namespace = namespace::Namespace::new(namespace_id, "Test Namespace", "");
+            storage::namespaces::insert(&mut conn, &test_namespace)
+                .await
+                .unwrap();
+        }
+
+        let new_pipeline = pipeline::Pipeline::new(namespace_id, config);
+        storage::pipelines::insert(&mut conn, &new_pipeline)
+            .await
+            .unwrap();
+
+        new_pipeline
+    }
+
+    /// A token allowed to call every handler.
+    pub fn management_token(&self) -> token::Token {
+        token::Token::new("test_hash", token::Kind::Management, HashMap::new(), vec![])
+    }
+}
+
+/// Waits for the next event on the subscription. Handlers publish their events in the background, so tests
+/// that need those events to have been stored wait on them with this.
+pub async fn next_event(subscription: &events::Subscription<'_>) -> event::Event {
+    for _ in 0..500 {
+        if let Ok(event) = subscription.receiver.try_recv() {
+            return event;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    panic!("no event was published");
+}
+
+impl Drop for TestHarness {
+    fn drop(&mut self) {
+        self.api.shutdown.cancel();
+
+        for path in [
+            self.storage_path.clone(),
+            format!("{}-shm", self.storage_path),
+            format!("{}-wal", self.storage_path),
+        ] {
+            let _ = std::fs::remove_file(path);
+        }
+
+        let _ = std::fs::remove_dir_all(&self.object_store_path);
+        let _ = std::fs::remove_dir_all(&self.secret_store_path);
+    }
+}
@@ -0,0 +1,661 @@
+use crate::{
+    api::{epoch, validate, Api},
+    storage,
+};
+use gofer_models::{
+    event::{Event, Kind as EventKind},
+    run::Run,
+    token::{Kind, Token},
+};
+use gofer_proto::{
+    CreateTokenRequest, CreateTokenResponse, DeleteTokenRequest, DeleteTokenResponse,
+    ListTokensRequest, ListTokensResponse,
+};
+use slog_scope::{error, info, warn};
+use std::collections::HashMap;
+use tonic::{Request, Response, Status};
+
+/// Generates a new random token, returning it along with its hash.
+fn generate_token() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    orion::util::secure_rand_bytes(&mut bytes).expect("could not generate random token");
+
+    let token = base64::encode_config(bytes, base64::URL_SAFE_NO_PAD);
+    let hash = hash_token(&token);
+
+    (token, hash)
+}
+
+/// Tokens are only ever stored hashed. They are random enough that a fast unsalted hash is fine.
+fn hash_token(token: &str) -> String {
+    let digest = orion::hash::digest(token.as_bytes()).expect("could not hash token");
+
+    digest
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns the token the request was authenticated with. The authentication interceptor attaches it to every
+/// request it lets through. Run tokens are rejected; only the object and secret endpoints accept them, through
+/// [`request_run_token`].
+pub fn request_token<T>(request: &Request<T>) -> Result<Token, Status> {
+    let token = request_run_token(request)?;
+
+    if token.kind == Kind::Run {
+        return Err(Status::permission_denied(
+            "run tokens can only access pipeline objects, run objects and pipeline secrets",
+        ));
+    }
+
+    Ok(token)
+}
+
+/// Returns the token the request was authenticated with, including run tokens. Run tokens carry the run's
+/// namespace and pipeline, so the usual pipeline access check keeps them to that pipeline.
+pub fn request_run_token<T>(request: &Request<T>) -> Result<Token, Status> {
+    request
+        .extensions()
+        .get::<Token>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("request is not authenticated"))
+}
+
+/// Actions that affect all of Gofer, rather than a single namespace, are reserved for management tokens.
+pub fn require_management_token(token: &Token) -> Result<(), Status> {
+    if token.kind != Kind::Management {
+        return Err(Status::permission_denied(
+            "this action requires a management token",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn require_namespace_access(token: &Token, namespace_id: &str) -> Result<(), Status> {
+    if !token.has_namespace_access(namespace_id) {
+        return Err(Status::permission_denied(format!(
+            "token does not have access to namespace '{}'",
+            namespace_id
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn require_pipeline_access(
+    token: &Token,
+    namespace_id: &str,
+    pipeline_id: &str,
+) -> Result<(), Status> {
+    require_namespace_access(token, namespace_id)?;
+
+    if !token.has_pipeline_access(namespace_id, pipeline_id) {
+        return Err(Status::permission_denied(format!(
+            "token does not have access to pipeline '{}'",
+            pipeline_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// Actions on the namespace itself, rather than on its pipelines, need access to the whole namespace. Tokens
+/// limited to specific pipelines within it are turned away.
+pub fn require_full_namespace_access(token: &Token, namespace_id: &str) -> Result<(), Status> {
+    require_namespace_access(token, namespace_id)?;
+
+    if token.kind != Kind::Management && !token.pipelines.is_empty() {
+        return Err(Status::permission_denied(format!(
+            "token is limited to specific pipelines within namespace '{}'",
+            namespace_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns whether the token is allowed to see the given event. Events about a namespace or pipeline follow
+/// the same access rules as acting on them. Events about triggers and common tasks are visible to every token,
+/// just as the triggers and common tasks themselves are.
+pub fn has_event_access(token: &Token, event: &Event) -> bool {
+    match &event.kind {
+        EventKind::Any => false,
+        EventKind::CreatedNamespace { namespace_id }
+        | EventKind::DeletedNamespace { namespace_id } => token.has_namespace_access(namespace_id),
+        EventKind::DisabledPipeline {
+            namespace_id,
+            pipeline_id,
+        }
+        | EventKind::EnabledPipeline {
+            namespace_id,
+            pipeline_id,
+        }
+        | EventKind::CreatedPipeline {
+            namespace_id,
+            pipeline_id,
+        }
+        | EventKind::DeletedPipeline {
+            namespace_id,
+            pipeline_id,
+        }
+        | EventKind::PutPipelineSecret {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::DeletedPipelineSecret {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::StartedRun {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::CompletedRun {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::CreatedTaskRun {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::StartedTaskRun {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::CompletedTaskRun {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::FiredTriggerEvent {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::ProcessedTriggerEvent {
+            namespace_id,
+            pipeline_id,
+            ..
+        }
+        | EventKind::ResolvedTriggerEvent {
+            namespace_id,
+            pipeline_id,
+            ..
+        } => token.has_pipeline_access(namespace_id, pipeline_id),
+        EventKind::InstalledTrigger { .. }
+        | EventKind::UninstalledTrigger { .. }
+        | EventKind::EnabledTrigger { .. }
+        | EventKind::DisabledTrigger { .. }
+        | EventKind::InstalledCommonTask { .. }
+        | EventKind::UninstalledCommonTask { .. }
+        | EventKind::EnabledCommonTask { .. }
+        | EventKind::DisabledCommonTask { .. } => true,
+    }
+}
+
+/// Run tokens record the run they belong to so they can be found again once it finishes.
+fn run_token_metadata(run: &Run) -> HashMap<String, String> {
+    HashMap::from([
+        ("namespace".to_string(), run.namespace.clone()),
+        ("pipeline".to_string(), run.pipeline.clone()),
+        ("run".to_string(), run.id.to_string()),
+    ])
+}
+
+impl Api {
+    /// Loads all tokens into the in-memory cache the authentication interceptor checks against.
+    pub async fn load_tokens(&self) -> Result<(), storage::StorageError> {
+        let mut conn = self.storage.conn().await?;
+        let tokens = storage::tokens::list(&mut conn).await?;
+
+        for token in tokens {
+            if token.is_expired() {
+                storage::tokens::delete(&mut conn, token.id).await?;
+                continue;
+            }
+
+            self.tokens.insert(token.hash.clone(), token);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a token for tasks within the run to call back into Gofer with. It can only access the objects and
+    /// secrets of the run's pipeline and expires once the run would have timed out.
+    pub async fn create_run_token(&self, run: &Run) -> Result<String, storage::StorageError> {
+        let (token, hash) = generate_token();
+
+        let expires = match self.conf.general.run_timeout {
+            0 => 0,
+            run_timeout => epoch() + run_timeout * 1000,
+        };
+
+        let mut run_token = Token::new(
+            &hash,
+            Kind::Run,
+            run_token_metadata(run),
+            vec![run.namespace.clone()],
+        )
+        .pipelines(vec![run.pipeline.clone()])
+        .expires(expires);
+
+        let mut conn = self.storage.conn().await?;
+        run_token.id = storage::tokens::insert(&mut conn, &run_token).await?;
+        self.tokens.insert(hash, run_token);
+
+        Ok(token)
+    }
+
+    /// Removes all tokens created for the run. A run resumed after a restart gets a new token while the task
+    /// runs started before it keep using the old one, so there can be more than one.
+    pub async fn revoke_run_tokens(&self, run: &Run) {
+        let metadata = run_token_metadata(run);
+
+        let run_tokens: Vec<Token> = self
+            .tokens
+            .iter()
+            .filter(|token| token.kind == Kind::Run && token.metadata == metadata)
+            .map(|token| token.clone())
+            .collect();
+
+        let mut conn = match self.storage.conn().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("could not revoke run tokens"; "error" => format!("{:?}", e));
+                return;
+            }
+        };
+
+        for run_token in run_tokens {
+            if let Err(e) = storage::tokens::delete(&mut conn, run_token.id).await {
+                error!("could not revoke run token"; "id" => run_token.id, "error" => format!("{:?}", e));
+                continue;
+            }
+
+            self.tokens.remove(&run_token.hash);
+        }
+    }
+
+    /// Gofer needs at least one management token to be administered at all, so the first time it starts it
+    /// creates one. The token is printed to stderr once and is never shown again.
+    pub async fn create_bootstrap_token(&self) -> Result<(), storage::StorageError> {
+        if !self.tokens.is_empty() {
+            return Ok(());
+        }
+
+        let (token, hash) = generate_token();
+        let mut bootstrap_token = Token::new(
+            &hash,
+            Kind::Management,
+            HashMap::from([("bootstrap_token".to_string(), "true".to_string())]),
+            vec![],
+        );
+
+        let mut conn = self.storage.conn().await?;
+        bootstrap_token.id = storage::tokens::insert(&mut conn, &bootstrap_token).await?;
+        self.tokens.insert(hash, bootstrap_token);
+
+        // The token is printed outside of the logger so it never ends up wherever the logs are shipped.
+        warn!("Created bootstrap management token; it has been printed to stderr and will not be shown again");
+        eprintln!(
+            "Bootstrap management token: {}\nIt will not be shown again, so store it somewhere safe.",
+            token
+        );
+
+        Ok(())
+    }
+
+    /// Checks that every request carries a valid token in its `authorization` metadata, in the form
+    /// `Bearer <token>`. The token is attached to the request so handlers can check what it is allowed to do.
+    pub fn authenticate(&self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let header = request
+            .metadata()
+            .get("authorization")
+            .ok_or_else(|| Status::unauthenticated("authorization metadata not found"))?;
+
+        let header = header
+            .to_str()
+            .map_err(|_| Status::unauthenticated("authorization metadata is malformed"))?;
+
+        let presented_token = header.strip_prefix("Bearer ").ok_or_else(|| {
+            Status::unauthenticated("authorization metadata must be in the form 'Bearer <token>'")
+        })?;
+
+        let token = self
+            .tokens
+            .get(&hash_token(presented_token))
+            .map(|token| token.clone())
+            .ok_or_else(|| Status::unauthenticated("invalid token"))?;
+
+        if token.is_expired() {
+            return Err(Status::unauthenticated("token has expired"));
+        }
+
+        request.extensions_mut().insert(token);
+
+        Ok(request)
+    }
+
+    pub async fn create_token_handler(
+        &self,
+        token: &Token,
+        args: CreateTokenRequest,
+    ) -> Result<Response<CreateTokenResponse>, Status> {
+        require_management_token(token)?;
+
+        let kind = Kind::from(
+            gofer_proto::token::Kind::from_i32(args.kind)
+                .ok_or_else(|| Status::failed_precondition("arg 'kind' invalid; unknown kind"))?,
+        );
+
+        if kind == Kind::Unknown || kind == Kind::Run {
+            return Err(Status::failed_precondition(
+                "arg 'kind' invalid; must be management or client",
+            ));
+        }
+
+        for namespace in &args.namespaces {
+            validate::arg(
+                "namespaces",
+                namespace.clone(),
+                vec![validate::is_valid_identifier],
+            )?;
+        }
+
+        for pipeline in &args.pipelines {
+            validate::arg(
+                "pipelines",
+                pipeline.clone(),
+                vec![validate::is_valid_identifier],
+            )?;
+        }
+
+        let (new_token, hash) = generate_token();
+        let mut details =
+            Token::new(&hash, kind, args.metadata, args.namespaces).pipelines(args.pipelines);
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        details.id = storage::tokens::insert(&mut conn, &details)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.tokens.insert(hash, details.clone());
+
+        info!("Created new token"; "id" => details.id, "kind" => details.kind.to_string(),
+            "created_by" => token.id);
+
+        Ok(Response::new(CreateTokenResponse {
+            details: Some(details.into()),
+            token: new_token,
+        }))
+    }
+
+    pub async fn list_tokens_handler(
+        &self,
+        token: &Token,
+        _: ListTokensRequest,
+    ) -> Result<Response<ListTokensResponse>, Status> {
+        require_management_token(token)?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let tokens = storage::tokens::list(&mut conn)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListTokensResponse {
+            tokens: tokens.into_iter().map(gofer_proto::Token::from).collect(),
+        }))
+    }
+
+    pub async fn delete_token_handler(
+        &self,
+        token: &Token,
+        args: DeleteTokenRequest,
+    ) -> Result<Response<DeleteTokenResponse>, Status> {
+        require_management_token(token)?;
+
+        let mut conn = self
+            .storage
+            .conn()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let deleted_token =
+            storage::tokens::get(&mut conn, args.id)
+                .await
+                .map_err(|e| match e {
+                    storage::StorageError::NotFound => {
+                        Status::not_found(format!("token with id '{}' does not exist", args.id))
+                    }
+                    _ => Status::internal(e.to_string()),
+                })?;
+
+        storage::tokens::delete(&mut conn, args.id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        self.tokens.remove(&deleted_token.hash);
+
+        info!("Deleted token"; "id" => deleted_token.id, "deleted_by" => token.id);
+
+        Ok(Response::new(DeleteTokenResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Client tokens can only act on the namespaces they were granted while management tokens can act on all
+    /// of them.
+    fn namespace_access() {
+        let client = Token::new(
+            "client_hash",
+            Kind::Client,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        );
+        let management = Token::new("management_hash", Kind::Management, HashMap::new(), vec![]);
+
+        assert!(require_namespace_access(&client, "granted").is_ok());
+        assert_eq!(
+            require_namespace_access(&client, "other")
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            require_management_token(&client).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+
+        assert!(require_namespace_access(&management, "other").is_ok());
+        assert!(require_management_token(&management).is_ok());
+    }
+
+    #[test]
+    /// Client tokens granted specific pipelines can only act on those pipelines within their namespaces.
+    fn pipeline_access() {
+        let pipeline_token = Token::new(
+            "pipeline_hash",
+            Kind::Client,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        )
+        .pipelines(vec!["granted_pipeline".to_string()]);
+        let namespace_token = Token::new(
+            "namespace_hash",
+            Kind::Client,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        );
+
+        assert!(require_pipeline_access(&pipeline_token, "granted", "granted_pipeline").is_ok());
+        assert_eq!(
+            require_pipeline_access(&pipeline_token, "granted", "other_pipeline")
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            require_pipeline_access(&pipeline_token, "other", "granted_pipeline")
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+
+        assert!(require_pipeline_access(&namespace_token, "granted", "other_pipeline").is_ok());
+    }
+
+    #[test]
+    /// Only tokens with access to the whole namespace can act on the namespace itself.
+    fn full_namespace_access() {
+        let namespace_token = Token::new(
+            "namespace_hash",
+            Kind::Client,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        );
+        let pipeline_token = namespace_token
+            .clone()
+            .pipelines(vec!["granted_pipeline".to_string()]);
+        let management = Token::new("management_hash", Kind::Management, HashMap::new(), vec![]);
+
+        assert!(require_full_namespace_access(&namespace_token, "granted").is_ok());
+        assert!(require_full_namespace_access(&management, "granted").is_ok());
+        assert_eq!(
+            require_full_namespace_access(&pipeline_token, "granted")
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            require_full_namespace_access(&namespace_token, "other")
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+    }
+
+    #[test]
+    /// Tokens only see events about the namespaces and pipelines they can access, along with events about
+    /// triggers and common tasks.
+    fn event_access() {
+        let pipeline_token = Token::new(
+            "pipeline_hash",
+            Kind::Client,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        )
+        .pipelines(vec!["granted_pipeline".to_string()]);
+        let management = Token::new("management_hash", Kind::Management, HashMap::new(), vec![]);
+
+        let started_run = |namespace_id: &str, pipeline_id: &str| {
+            Event::new(EventKind::StartedRun {
+                namespace_id: namespace_id.to_string(),
+                pipeline_id: pipeline_id.to_string(),
+                run_id: 1,
+            })
+        };
+        let installed_trigger = Event::new(EventKind::InstalledTrigger {
+            name: "cron".to_string(),
+            image: "ghcr.io/clintjedwards/gofer/trigger_cron:latest".to_string(),
+        });
+        let created_namespace = Event::new(EventKind::CreatedNamespace {
+            namespace_id: "other".to_string(),
+        });
+
+        assert!(has_event_access(
+            &pipeline_token,
+            &started_run("granted", "granted_pipeline")
+        ));
+        assert!(!has_event_access(
+            &pipeline_token,
+            &started_run("granted", "other_pipeline")
+        ));
+        assert!(!has_event_access(
+            &pipeline_token,
+            &started_run("other", "granted_pipeline")
+        ));
+        assert!(!has_event_access(&pipeline_token, &created_namespace));
+        assert!(has_event_access(&pipeline_token, &installed_trigger));
+
+        assert!(has_event_access(
+            &management,
+            &started_run("other", "other_pipeline")
+        ));
+        assert!(has_event_access(&management, &created_namespace));
+    }
+
+    #[test]
+    /// Run tokens are turned away by every endpoint except the object and secret ones, which still keep them
+    /// to the run's pipeline.
+    fn run_token_access() {
+        let run_token = Token::new(
+            "run_hash",
+            Kind::Run,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        )
+        .pipelines(vec!["granted_pipeline".to_string()]);
+
+        let mut request = Request::new(());
+        request.extensions_mut().insert(run_token.clone());
+
+        assert_eq!(
+            request_token(&request).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(request_run_token(&request).unwrap(), run_token);
+
+        assert!(require_pipeline_access(&run_token, "granted", "granted_pipeline").is_ok());
+        assert_eq!(
+            require_pipeline_access(&run_token, "granted", "other_pipeline")
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            require_management_token(&run_token).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+
+        let client_token = Token::new(
+            "client_hash",
+            Kind::Client,
+            HashMap::new(),
+            vec!["granted".to_string()],
+        );
+        let mut request = Request::new(());
+        request.extensions_mut().insert(client_token.clone());
+
+        assert_eq!(request_token(&request).unwrap(), client_token);
+    }
+
+    #[test]
+    fn expired_token() {
+        let token = Token::new("hash", Kind::Client, HashMap::new(), vec![]);
+        assert!(!token.is_expired());
+        assert!(token.clone().expires(1).is_expired());
+        assert!(!token.expires(epoch() + 60_000).is_expired());
+    }
+}
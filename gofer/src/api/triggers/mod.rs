@@ -1,11 +1,11 @@
 mod utils;
 
 use crate::{
-    api::{epoch, validate, Api},
+    api::{epoch, tokens, validate, Api},
     scheduler, storage,
 };
 use futures::stream::StreamExt;
-use gofer_models::{event, trigger};
+use gofer_models::{event, token::Token, trigger};
 use gofer_proto::{
     DisableTriggerRequest, DisableTriggerResponse, EnableTriggerRequest, EnableTriggerResponse,
     GetTriggerInstallInstructionsRequest, GetTriggerInstallInstructionsResponse, GetTriggerRequest,
@@ -13,15 +13,18 @@ use gofer_proto::{
     ListTriggersResponse, UninstallTriggerRequest, UninstallTriggerResponse,
 };
 use nanoid::nanoid;
-use slog_scope::info;
-use std::collections::HashMap;
+use slog_scope::{error, info};
+use std::{collections::HashMap, sync::Arc};
 use tonic::{Response, Status};
 
 impl Api {
     pub async fn install_trigger_handler(
-        &self,
+        self: Arc<Self>,
+        token: &Token,
         args: InstallTriggerRequest,
     ) -> Result<Response<InstallTriggerResponse>, Status> {
+        tokens::require_management_token(token)?;
+
         validate::arg("name", args.name.clone(), vec![validate::not_empty_str])?;
         validate::arg("image", args.image.clone(), vec![validate::not_empty_str])?;
 
@@ -74,6 +77,13 @@ impl Api {
             },
         );
 
+        // Pipelines might have subscribed to a previous installation of this trigger.
+        if let Err(e) = self.restore_trigger_subscriptions(&args.name).await {
+            error!("could not restore trigger subscriptions"; "name" => &args.name, "error" => format!("{:?}", e));
+        }
+
+        self.clone().start_trigger_watch(&args.name);
+
         self.event_bus
             .publish(event::Kind::InstalledTrigger {
                 name: args.name.clone(),
@@ -132,6 +142,10 @@ impl Api {
                 enable_networking: false,
                 entrypoint: vec!["./trigger".into(), "installer".into()],
                 command: vec![],
+                cpu_request: None,
+                cpu_limit: None,
+                memory_request: None,
+                memory_limit: None,
             })
             .await
             .map_err(|e| Status::internal(format!("could not start container: {}", e)))?;
@@ -207,14 +221,19 @@ impl Api {
 
     pub async fn uninstall_trigger_handler(
         &self,
+        token: &Token,
         args: UninstallTriggerRequest,
     ) -> Result<Response<UninstallTriggerResponse>, Status> {
+        tokens::require_management_token(token)?;
+
         validate::arg(
             "name",
             args.name.clone(),
             vec![validate::is_valid_identifier, validate::not_empty_str],
         )?;
 
+        self.stop_trigger_watch(&args.name);
+
         // We create an inner scope here so we don't deadlock on the remove call
         // because we have a reference into the map.
         {
@@ -248,20 +267,34 @@ impl Api {
     }
 
     pub async fn enable_trigger_handler(
-        &self,
+        self: Arc<Self>,
+        token: &Token,
         args: EnableTriggerRequest,
     ) -> Result<Response<EnableTriggerResponse>, Status> {
+        tokens::require_management_token(token)?;
+
         validate::arg(
             "name",
             args.name.clone(),
             vec![validate::is_valid_identifier, validate::not_empty_str],
         )?;
 
+        let was_disabled = self
+            .triggers
+            .get(&args.name)
+            .map(|trigger| trigger.value().status != trigger::Status::Enabled)
+            .unwrap_or_default();
+
         self.triggers.alter(&args.name, |_, mut value| {
             value.status = trigger::Status::Enabled;
             value
         });
 
+        // Disabling a trigger stops its watch, so it needs to be started back up.
+        if was_disabled {
+            self.clone().start_trigger_watch(&args.name);
+        }
+
         let mut conn = self
             .storage
             .conn()
@@ -289,8 +322,11 @@ impl Api {
 
     pub async fn disable_trigger_handler(
         &self,
+        token: &Token,
         args: DisableTriggerRequest,
     ) -> Result<Response<DisableTriggerResponse>, Status> {
+        tokens::require_management_token(token)?;
+
         validate::arg(
             "name",
             args.name.clone(),
@@ -302,6 +338,8 @@ impl Api {
             value
         });
 
+        self.stop_trigger_watch(&args.name);
+
         let mut conn = self
             .storage
             .conn()
@@ -1,16 +1,26 @@
-use crate::api::{epoch, fmt, get_tls_client_config, Api};
+use crate::api::{epoch, fmt, get_tls_client_config, runs::variables_to_vec, Api};
 use crate::{scheduler, storage};
 use anyhow::{anyhow, Result};
-use futures::StreamExt;
-use gofer_models::trigger::Trigger;
+use futures::{Future, StreamExt};
+use gofer_models::{event, pipeline, run, trigger::Trigger, VariableOwner, VariableSensitivity};
 use gofer_proto::{
-    trigger_service_client::TriggerServiceClient, TriggerInfoRequest, TriggerInfoResponse,
-    TriggerShutdownRequest,
+    trigger_service_client::TriggerServiceClient, trigger_watch_response, TriggerInfoRequest,
+    TriggerInfoResponse, TriggerShutdownRequest, TriggerSubscribeRequest,
+    TriggerUnsubscribeRequest, TriggerWatchRequest, TriggerWatchResponse,
 };
 use nanoid::nanoid;
 use slog_scope::{debug, error, info};
 use std::{collections::HashMap, sync::Arc};
-use tonic::{metadata::MetadataValue, transport::Channel, Request};
+use tokio_util::sync::CancellationToken;
+use tonic::{
+    metadata::MetadataValue,
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::Channel,
+    Request,
+};
+
+/// How long to wait before attempting to reconnect to a trigger whose watch call failed.
+const WATCH_RETRY_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(5);
 
 pub struct TriggerInfo {
     pub scheduler_id: Option<String>,
@@ -95,6 +105,10 @@ impl Api {
                 enable_networking: true,
                 entrypoint: vec![],
                 command: vec![],
+                cpu_request: None,
+                cpu_limit: None,
+                memory_request: None,
+                memory_limit: None,
             })
             .await?;
 
@@ -167,6 +181,12 @@ impl Api {
                     .await
             });
 
+            if let Err(e) = self.restore_trigger_subscriptions(&trigger.name).await {
+                error!("could not restore trigger subscriptions"; "name" => &trigger.name, "error" => format!("{:?}", e));
+            }
+
+            self.clone().start_trigger_watch(&trigger.name);
+
             info!("Started trigger"; "name" => trigger.name, "image" => trigger.image, "url" => trigger_info.url);
         }
 
@@ -186,19 +206,7 @@ impl Api {
             None => return Err(anyhow::anyhow!("trigger has no auth key")),
         };
 
-        let tls_config = get_tls_client_config(url, self.conf.triggers.tls_ca.clone())?;
-        let channel = Channel::from_shared(url.clone())?
-            .tls_config(tls_config.clone())?
-            .connect()
-            .await?;
-
-        let token: MetadataValue<_> = format!("Bearer {}", key).parse()?;
-
-        let mut client =
-            TriggerServiceClient::with_interceptor(channel, move |mut req: Request<()>| {
-                req.metadata_mut().insert("authorization", token.clone());
-                Ok(req)
-            });
+        let mut client = self.trigger_client(url, key).await?;
 
         let request = tonic::Request::new(TriggerShutdownRequest {});
         if let Err(e) = client.shutdown(request).await {
@@ -248,23 +256,9 @@ impl Api {
             break;
         }
 
-        let tls_config = get_tls_client_config(url, self.conf.triggers.tls_ca.clone())?;
-
         let mut attempts: u8 = 0;
         loop {
-            let url = url.to_owned();
-            let channel = Channel::from_shared(url.clone())?
-                .tls_config(tls_config.clone())?
-                .connect()
-                .await?;
-
-            let token: MetadataValue<_> = format!("Bearer {}", trigger_key).parse()?;
-
-            let mut client =
-                TriggerServiceClient::with_interceptor(channel, move |mut req: Request<()>| {
-                    req.metadata_mut().insert("authorization", token.clone());
-                    Ok(req)
-                });
+            let mut client = self.trigger_client(url, trigger_key).await?;
 
             let request = tonic::Request::new(TriggerInfoRequest {});
             match client.info(request).await {
@@ -289,6 +283,346 @@ impl Api {
         }
     }
 
+    /// Returns a GRPC client for the trigger found at the given url. Every request made through the client
+    /// is authenticated with the key that was handed to the trigger on startup.
+    pub async fn trigger_client(
+        &self,
+        url: &str,
+        key: &str,
+    ) -> Result<TriggerServiceClient<InterceptedService<Channel, impl Interceptor>>> {
+        let tls_config = get_tls_client_config(url, self.conf.triggers.tls_ca.clone())?;
+        let channel = Channel::from_shared(url.to_string())?
+            .tls_config(tls_config)?
+            .connect()
+            .await?;
+
+        let token: MetadataValue<_> = format!("Bearer {}", key).parse()?;
+
+        Ok(TriggerServiceClient::with_interceptor(
+            channel,
+            move |mut req: Request<()>| {
+                req.metadata_mut().insert("authorization", token.clone());
+                Ok(req)
+            },
+        ))
+    }
+
+    /// Looks up a registered trigger by name and returns a client connected to it.
+    async fn registered_trigger_client(
+        &self,
+        name: &str,
+    ) -> Result<TriggerServiceClient<InterceptedService<Channel, impl Interceptor>>> {
+        let (url, key) = match self.triggers.get(name) {
+            Some(trigger) => {
+                let trigger = trigger.value();
+                (
+                    trigger.url.clone().unwrap_or_default(),
+                    trigger.key.clone().unwrap_or_default(),
+                )
+            }
+            None => return Err(anyhow!("trigger '{}' is not installed", name)),
+        };
+
+        self.trigger_client(&url, &key).await
+    }
+
+    /// Tells the trigger named in the settings that the given pipeline would like to receive events for
+    /// the given label.
+    pub async fn subscribe_pipeline_trigger(
+        &self,
+        namespace_id: &str,
+        pipeline_id: &str,
+        settings: &pipeline::TriggerSettings,
+    ) -> Result<()> {
+        let mut client = self.registered_trigger_client(&settings.name).await?;
+
+        client
+            .subscribe(tonic::Request::new(TriggerSubscribeRequest {
+                namespace_id: namespace_id.to_string(),
+                pipeline_id: pipeline_id.to_string(),
+                pipeline_trigger_label: settings.label.clone(),
+                config: settings.settings.clone(),
+            }))
+            .await
+            .map_err(|e| anyhow!("{}", e.message()))?;
+
+        Ok(())
+    }
+
+    /// Tells the trigger named in the settings that the given pipeline no longer needs events for the
+    /// given label.
+    pub async fn unsubscribe_pipeline_trigger(
+        &self,
+        namespace_id: &str,
+        pipeline_id: &str,
+        settings: &pipeline::TriggerSettings,
+    ) -> Result<()> {
+        let mut client = self.registered_trigger_client(&settings.name).await?;
+
+        client
+            .unsubscribe(tonic::Request::new(TriggerUnsubscribeRequest {
+                namespace_id: namespace_id.to_string(),
+                pipeline_id: pipeline_id.to_string(),
+                pipeline_trigger_label: settings.label.clone(),
+            }))
+            .await
+            .map_err(|e| anyhow!("{}", e.message()))?;
+
+        Ok(())
+    }
+
+    /// Subscribes the pipeline to the trigger and records the outcome on the settings, both in the struct
+    /// passed in and in storage, so that users can see why a trigger isn't firing for their pipeline.
+    pub async fn sync_trigger_subscription(
+        &self,
+        namespace_id: &str,
+        pipeline_id: &str,
+        settings: &mut pipeline::TriggerSettings,
+    ) -> Result<()> {
+        settings.error = match self
+            .subscribe_pipeline_trigger(namespace_id, pipeline_id, settings)
+            .await
+        {
+            Ok(_) => None,
+            Err(e) => {
+                error!("could not subscribe pipeline to trigger"; "namespace" => namespace_id,
+                    "pipeline" => pipeline_id, "trigger" => &settings.name, "label" => &settings.label,
+                    "error" => format!("{:?}", e));
+                Some(format!("could not subscribe to trigger; {}", e))
+            }
+        };
+
+        let mut conn = self.storage.conn().await?;
+        storage::pipelines::update_trigger_settings(&mut conn, namespace_id, pipeline_id, settings)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Triggers keep no permanent state, so every time one is (re)started we need to send it all the
+    /// subscriptions that active pipelines have made to it.
+    pub async fn restore_trigger_subscriptions(&self, name: &str) -> Result<()> {
+        let mut conn = self.storage.conn().await?;
+        let subscriptions =
+            storage::pipelines::list_active_trigger_subscriptions(&mut conn, name).await?;
+
+        for (namespace_id, pipeline_id, mut settings) in subscriptions {
+            self.sync_trigger_subscription(&namespace_id, &pipeline_id, &mut settings)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts watching the trigger for events, replacing any watch that is already running for it.
+    pub fn start_trigger_watch(self: Arc<Self>, name: &str) {
+        let cancel = self.shutdown.child_token();
+
+        if let Some(previous) = self
+            .trigger_watches
+            .insert(name.to_string(), cancel.clone())
+        {
+            previous.cancel();
+        }
+
+        tokio::spawn(self.watch_trigger(name.to_string(), cancel));
+    }
+
+    /// Stops watching the trigger for events. Events that were already received are still processed.
+    pub fn stop_trigger_watch(&self, name: &str) {
+        if let Some((_, cancel)) = self.trigger_watches.remove(name) {
+            cancel.cancel();
+        }
+    }
+
+    /// Continuously calls the watch endpoint for a trigger, starting a new run for each event the
+    /// trigger passes back. Watch is a blocking call, so once a response comes back we simply call it
+    /// again. If the connection to the trigger fails we wait a bit and try again, looking up the
+    /// trigger fresh each time in case it was restarted at a new address.
+    ///
+    /// The loop exits when the watch is cancelled, either by Gofer shutting down or by the trigger being
+    /// disabled or uninstalled.
+    async fn watch_trigger(self: Arc<Self>, name: String, cancel: CancellationToken) {
+        let api = self.clone();
+        self.watch_trigger_with(name, cancel, move |url, key| {
+            let api = api.clone();
+            async move { api.watch_trigger_once(&url, &key).await }
+        })
+        .await
+    }
+
+    /// The loop behind [`watch_trigger`](Self::watch_trigger), with the call to the trigger's watch endpoint
+    /// passed in so that tests don't need a trigger to talk to.
+    async fn watch_trigger_with<F, Fut>(
+        self: Arc<Self>,
+        name: String,
+        cancel: CancellationToken,
+        watch: F,
+    ) where
+        F: Fn(String, String) -> Fut,
+        Fut: Future<Output = Result<TriggerWatchResponse>>,
+    {
+        loop {
+            let (url, key) = match self.triggers.get(&name) {
+                Some(trigger) => {
+                    let trigger = trigger.value();
+                    (
+                        trigger.url.clone().unwrap_or_default(),
+                        trigger.key.clone().unwrap_or_default(),
+                    )
+                }
+                None => {
+                    debug!("trigger no longer registered; stopping watch"; "name" => &name);
+                    return;
+                }
+            };
+
+            let response = tokio::select! {
+                _ = cancel.cancelled() => return,
+                response = watch(url, key) => response,
+            };
+
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    error!("could not watch trigger for events; retrying";
+                        "name" => &name, "error" => format!("{:?}", e));
+                    tokio::select! {
+                        _ = cancel.cancelled() => return,
+                        _ = tokio::time::sleep(WATCH_RETRY_INTERVAL) => continue,
+                    }
+                }
+            };
+
+            let self_clone = self.clone();
+            let name_clone = name.clone();
+            tokio::spawn(
+                async move { self_clone.handle_trigger_event(&name_clone, response).await },
+            );
+        }
+    }
+
+    async fn watch_trigger_once(&self, url: &str, key: &str) -> Result<TriggerWatchResponse> {
+        let mut client = self.trigger_client(url, key).await?;
+        let response = client
+            .watch(tonic::Request::new(TriggerWatchRequest {}))
+            .await?;
+
+        Ok(response.into_inner())
+    }
+
+    /// Processes a single event returned from a trigger's watch endpoint. Successful events result in
+    /// a new run for the pipeline that subscribed, with the trigger's metadata passed in as run variables.
+    /// Events from triggers that aren't enabled, or for pipelines that aren't subscribed to the trigger under
+    /// the event's label, are discarded.
+    async fn handle_trigger_event(self: Arc<Self>, name: &str, event: TriggerWatchResponse) {
+        let is_enabled = match self.triggers.get(name) {
+            Some(trigger) => trigger.value().status == gofer_models::trigger::Status::Enabled,
+            None => false,
+        };
+
+        if !is_enabled {
+            debug!("trigger event discarded; trigger is not enabled"; "name" => name,
+                "namespace" => &event.namespace_id, "pipeline" => &event.pipeline_id);
+            return;
+        }
+
+        let result = trigger_watch_response::Result::from_i32(event.result)
+            .unwrap_or(trigger_watch_response::Result::Unknown);
+
+        if result != trigger_watch_response::Result::Success {
+            debug!("trigger event did not result in a run"; "name" => name, "result" => format!("{:?}", result),
+                "details" => &event.details, "namespace" => &event.namespace_id, "pipeline" => &event.pipeline_id);
+            return;
+        }
+
+        match self
+            .pipeline_subscribed_to_trigger(
+                &event.namespace_id,
+                &event.pipeline_id,
+                name,
+                &event.pipeline_trigger_label,
+            )
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                error!("trigger event discarded; pipeline is not subscribed to trigger"; "name" => name,
+                    "label" => &event.pipeline_trigger_label, "namespace" => &event.namespace_id,
+                    "pipeline" => &event.pipeline_id);
+                return;
+            }
+            Err(e) => {
+                error!("trigger event discarded; could not get pipeline"; "name" => name,
+                    "namespace" => &event.namespace_id, "pipeline" => &event.pipeline_id,
+                    "error" => format!("{:?}", e));
+                return;
+            }
+        }
+
+        self.event_bus
+            .publish(event::Kind::FiredTriggerEvent {
+                namespace_id: event.namespace_id.clone(),
+                pipeline_id: event.pipeline_id.clone(),
+                label: event.pipeline_trigger_label.clone(),
+            })
+            .await;
+
+        if let Err(e) = self
+            .clone()
+            .start_run(
+                &event.namespace_id,
+                &event.pipeline_id,
+                run::TriggerInfo {
+                    name: name.to_string(),
+                    label: event.pipeline_trigger_label.clone(),
+                },
+                variables_to_vec(
+                    event.metadata.clone(),
+                    VariableOwner::System,
+                    VariableSensitivity::Public,
+                ),
+                0,
+            )
+            .await
+        {
+            error!("could not start run from trigger event"; "name" => name,
+                "namespace" => &event.namespace_id, "pipeline" => &event.pipeline_id,
+                "error" => e.message());
+            return;
+        }
+
+        self.event_bus
+            .publish(event::Kind::ProcessedTriggerEvent {
+                namespace_id: event.namespace_id,
+                pipeline_id: event.pipeline_id,
+                label: event.pipeline_trigger_label,
+            })
+            .await;
+    }
+
+    /// Returns true if the pipeline has subscribed to the trigger under the given label.
+    async fn pipeline_subscribed_to_trigger(
+        &self,
+        namespace_id: &str,
+        pipeline_id: &str,
+        name: &str,
+        label: &str,
+    ) -> Result<bool> {
+        let mut conn = self.storage.conn().await?;
+
+        let pipeline = match storage::pipelines::get(&mut conn, namespace_id, pipeline_id).await {
+            Ok(pipeline) => pipeline,
+            Err(storage::StorageError::NotFound) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(pipeline
+            .triggers
+            .get(label)
+            .is_some_and(|settings| settings.name == name))
+    }
+
     /// Take a container and print the logs to stdout. Used to allow triggers to print into server logs.
     pub async fn echo_logs_to_console(&self, container_name: String) {
         let mut log_stream = self.scheduler.get_logs(scheduler::GetLogsRequest {
@@ -316,3 +650,227 @@ impl Api {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::{next_event, TestHarness};
+    use gofer_models::{event::KindDiscriminant, trigger};
+    use std::{pin::Pin, sync::Mutex};
+
+    /// Registers a running trigger named "test_trigger" with the given status.
+    fn register_trigger(harness: &TestHarness, status: trigger::Status) {
+        harness.api.triggers.insert(
+            "test_trigger".to_string(),
+            trigger::Trigger {
+                registration: trigger::Registration {
+                    name: "test_trigger".to_string(),
+                    image: "test_image".to_string(),
+                    user: None,
+                    pass: None,
+                    variables: HashMap::new(),
+                    created: 0,
+                    status: status.clone(),
+                },
+                url: Some("first_url".to_string()),
+                scheduler_id: None,
+                started: 0,
+                state: trigger::State::Running,
+                status,
+                documentation: None,
+                key: Some("test_key".to_string()),
+            },
+        );
+    }
+
+    /// Stores a pipeline subscribed to "test_trigger" under the label "test_label".
+    async fn create_subscribed_pipeline(harness: &TestHarness) {
+        harness
+            .create_pipeline(
+                "test_namespace",
+                gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline").triggers(vec![
+                    gofer_sdk::config::PipelineTriggerConfig::new("test_trigger", "test_label"),
+                ]),
+            )
+            .await;
+    }
+
+    fn watch_response(label: &str, result: trigger_watch_response::Result) -> TriggerWatchResponse {
+        TriggerWatchResponse {
+            details: "test_details".to_string(),
+            namespace_id: "test_namespace".to_string(),
+            pipeline_id: "test_pipeline".to_string(),
+            pipeline_trigger_label: label.to_string(),
+            result: result as i32,
+            metadata: HashMap::from([("TEST_KEY".to_string(), "test_value".to_string())]),
+        }
+    }
+
+    async fn stored_runs(harness: &TestHarness) -> Vec<run::Run> {
+        let mut conn = harness.api.storage.conn().await.unwrap();
+        storage::runs::list(&mut conn, 0, 0, "test_namespace", "test_pipeline")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    /// A successful event queues a run for the subscribed pipeline with the event's metadata as variables.
+    async fn trigger_event_starts_run() {
+        let harness = TestHarness::new().await;
+        register_trigger(&harness, trigger::Status::Enabled);
+        create_subscribed_pipeline(&harness).await;
+
+        let subscription = harness
+            .api
+            .event_bus
+            .subscribe(KindDiscriminant::Any)
+            .await
+            .unwrap();
+
+        harness
+            .api
+            .clone()
+            .handle_trigger_event(
+                "test_trigger",
+                watch_response("test_label", trigger_watch_response::Result::Success),
+            )
+            .await;
+
+        let mut kinds = vec![];
+        for _ in 0..3 {
+            kinds.push(KindDiscriminant::from(next_event(&subscription).await.kind));
+        }
+        assert_eq!(kinds[0], KindDiscriminant::FiredTriggerEvent);
+        assert!(kinds.contains(&KindDiscriminant::StartedRun));
+        assert!(kinds.contains(&KindDiscriminant::ProcessedTriggerEvent));
+
+        let runs = stored_runs(&harness).await;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].state, run::State::Pending);
+        assert_eq!(
+            runs[0].trigger,
+            run::TriggerInfo {
+                name: "test_trigger".to_string(),
+                label: "test_label".to_string(),
+            }
+        );
+        assert!(runs[0]
+            .variables
+            .iter()
+            .any(|variable| variable.key == "TEST_KEY" && variable.value == "test_value"));
+    }
+
+    #[tokio::test]
+    /// Events are discarded without firing when the trigger is disabled, the event didn't succeed or the
+    /// pipeline isn't subscribed to the trigger under the event's label.
+    async fn trigger_event_discarded() {
+        let harness = TestHarness::new().await;
+        create_subscribed_pipeline(&harness).await;
+
+        let subscription = harness
+            .api
+            .event_bus
+            .subscribe(KindDiscriminant::Any)
+            .await
+            .unwrap();
+
+        for (status, response) in [
+            (
+                trigger::Status::Disabled,
+                watch_response("test_label", trigger_watch_response::Result::Success),
+            ),
+            (
+                trigger::Status::Enabled,
+                watch_response("test_label", trigger_watch_response::Result::Skipped),
+            ),
+            (
+                trigger::Status::Enabled,
+                watch_response("other_label", trigger_watch_response::Result::Success),
+            ),
+            (
+                trigger::Status::Enabled,
+                TriggerWatchResponse {
+                    pipeline_id: "other_pipeline".to_string(),
+                    ..watch_response("test_label", trigger_watch_response::Result::Success)
+                },
+            ),
+        ] {
+            register_trigger(&harness, status);
+
+            harness
+                .api
+                .clone()
+                .handle_trigger_event("test_trigger", response)
+                .await;
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        assert!(subscription.receiver.try_recv().is_err());
+        assert!(stored_runs(&harness).await.is_empty());
+    }
+
+    #[tokio::test]
+    /// A failed watch call is retried against the trigger's current address, and the events that come back
+    /// once it is reachable again start runs.
+    async fn watch_trigger_reconnects() {
+        let harness = TestHarness::new().await;
+        register_trigger(&harness, trigger::Status::Enabled);
+        create_subscribed_pipeline(&harness).await;
+
+        let calls = Arc::new(Mutex::new(vec![]));
+        let watch_calls = calls.clone();
+        let api = harness.api.clone();
+
+        let watch = move |url: String,
+                          _key: String|
+              -> Pin<Box<dyn Future<Output = Result<TriggerWatchResponse>> + Send>> {
+            let call = {
+                let mut calls = watch_calls.lock().unwrap();
+                calls.push(url);
+                calls.len()
+            };
+
+            match call {
+                // The trigger went away and came back up at a new address.
+                1 => {
+                    api.triggers.alter("test_trigger", |_, mut trigger| {
+                        trigger.url = Some("second_url".to_string());
+                        trigger
+                    });
+                    Box::pin(async { Err(anyhow!("connection refused")) })
+                }
+                2 => Box::pin(async {
+                    Ok(watch_response(
+                        "test_label",
+                        trigger_watch_response::Result::Success,
+                    ))
+                }),
+                _ => Box::pin(futures::future::pending()),
+            }
+        };
+
+        let cancel = CancellationToken::new();
+        let watching = tokio::spawn(harness.api.clone().watch_trigger_with(
+            "test_trigger".to_string(),
+            cancel.clone(),
+            watch,
+        ));
+
+        for _ in 0..1000 {
+            if !stored_runs(&harness).await.is_empty() {
+                break;
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+
+        cancel.cancel();
+        watching.await.unwrap();
+
+        assert_eq!(stored_runs(&harness).await.len(), 1);
+        assert_eq!(
+            calls.lock().unwrap()[..2],
+            ["first_url".to_string(), "second_url".to_string()]
+        );
+    }
+}
@@ -4,6 +4,7 @@ mod pipeline;
 mod run;
 mod service;
 mod spinner;
+mod token;
 mod trigger;
 mod utils;
 
@@ -24,7 +25,11 @@ use std::{
     str::FromStr,
     time::{SystemTime, UNIX_EPOCH},
 };
-use tonic::transport::channel::Channel;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::channel::Channel,
+};
 
 #[derive(Debug, Parser)]
 #[clap(name = "gofer")]
@@ -47,6 +52,30 @@ struct Cli {
 
 const DEFAULT_NAMESPACE: &str = "default";
 
+/// Attaches the configured API token to every request.
+#[derive(Debug, Clone)]
+struct AuthInterceptor {
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+/// A Gofer client that authenticates every request.
+type Client = GoferClient<InterceptedService<Channel, AuthInterceptor>>;
+
+impl Interceptor for AuthInterceptor {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+
+        Ok(request)
+    }
+}
+
 struct CliHarness {
     config: Config,
 }
@@ -60,7 +89,16 @@ impl CliHarness {
         self.config.namespace = Some(namespace.to_string());
     }
 
-    async fn connect(&self) -> Result<GoferClient<Channel>, Box<dyn Error>> {
+    async fn connect(&self) -> Result<Client, Box<dyn Error>> {
+        let authorization = match &self.config.token {
+            Some(token) => Some(
+                format!("Bearer {}", token)
+                    .parse()
+                    .map_err(|_| "token contains invalid characters")?,
+            ),
+            None => None,
+        };
+
         let tls_config = get_tls_config(&self.config.server, self.config.tls_ca.clone())?;
 
         let channel = Channel::from_shared(self.config.server.to_string())?
@@ -75,7 +113,10 @@ impl CliHarness {
                 }
             })?;
 
-        Ok(GoferClient::new(channel))
+        Ok(GoferClient::with_interceptor(
+            channel,
+            AuthInterceptor { authorization },
+        ))
     }
 }
 
@@ -98,6 +139,9 @@ enum Commands {
 
     /// List and get information about Gofer events.
     Event(event::EventSubcommands),
+
+    /// Manages API tokens. Requires a management token.
+    Token(token::TokenSubcommands),
 }
 
 fn init_logging(severity: Severity) -> slog_scope::GlobalLoggerGuard {
@@ -198,6 +242,9 @@ pub async fn init() {
                 service::ServiceCommands::Info => {
                     cli.service_info().await;
                 }
+                service::ServiceCommands::RotateSecretKeys => {
+                    cli.service_rotate_secret_keys().await;
+                }
             }
         }
         Commands::Namespace(namespace) => {
@@ -230,16 +277,96 @@ pub async fn init() {
                 pipeline::PipelineCommands::List => cli.pipeline_list().await,
                 pipeline::PipelineCommands::Create { path } => cli.pipeline_create(&path).await,
                 pipeline::PipelineCommands::Get { id } => cli.pipeline_get(&id).await,
-                pipeline::PipelineCommands::Run { id, variables } => {
-                    cli.pipeline_run(&id, variables).await
-                }
+                pipeline::PipelineCommands::Run {
+                    id,
+                    variables,
+                    priority,
+                } => cli.pipeline_run(&id, variables, priority).await,
                 pipeline::PipelineCommands::Update { path } => cli.pipeline_update(&path).await,
                 pipeline::PipelineCommands::Delete { id } => cli.pipeline_delete(&id).await,
+                pipeline::PipelineCommands::Secret(secret) => match secret.command {
+                    pipeline::secret::SecretCommands::List { pipeline_id } => {
+                        cli.pipeline_secret_list(&pipeline_id).await
+                    }
+                    pipeline::secret::SecretCommands::Get {
+                        pipeline_id,
+                        key,
+                        include_secret,
+                    } => {
+                        cli.pipeline_secret_get(&pipeline_id, &key, include_secret)
+                            .await
+                    }
+                    pipeline::secret::SecretCommands::Put {
+                        pipeline_id,
+                        key,
+                        file,
+                        force,
+                    } => {
+                        cli.pipeline_secret_put(&pipeline_id, &key, file, force)
+                            .await
+                    }
+                    pipeline::secret::SecretCommands::Delete { pipeline_id, key } => {
+                        cli.pipeline_secret_delete(&pipeline_id, &key).await
+                    }
+                },
+                pipeline::PipelineCommands::Store(store) => match store.command {
+                    pipeline::store::StoreCommands::List { pipeline_id } => {
+                        cli.pipeline_store_list(&pipeline_id).await
+                    }
+                    pipeline::store::StoreCommands::Get {
+                        pipeline_id,
+                        key,
+                        file,
+                    } => cli.pipeline_store_get(&pipeline_id, &key, file).await,
+                    pipeline::store::StoreCommands::Put {
+                        pipeline_id,
+                        key,
+                        file,
+                        force,
+                    } => {
+                        cli.pipeline_store_put(&pipeline_id, &key, file, force)
+                            .await
+                    }
+                    pipeline::store::StoreCommands::Delete { pipeline_id, key } => {
+                        cli.pipeline_store_delete(&pipeline_id, &key).await
+                    }
+                },
             }
         }
         Commands::Run(run) => match run.command {
-            run::RunCommands::Get { pipeline_id, id } => cli.run_get(pipeline_id, id).await,
+            run::RunCommands::Get {
+                pipeline_id,
+                id,
+                reveal,
+            } => cli.run_get(pipeline_id, id, reveal).await,
             run::RunCommands::List { pipeline_id } => cli.run_list(pipeline_id).await,
+            run::RunCommands::Store(store) => match store.command {
+                run::store::StoreCommands::List {
+                    pipeline_id,
+                    run_id,
+                } => cli.run_store_list(&pipeline_id, run_id).await,
+                run::store::StoreCommands::Get {
+                    pipeline_id,
+                    run_id,
+                    key,
+                    file,
+                } => cli.run_store_get(&pipeline_id, run_id, &key, file).await,
+                run::store::StoreCommands::Put {
+                    pipeline_id,
+                    run_id,
+                    key,
+                    file,
+                    force,
+                } => {
+                    cli.run_store_put(&pipeline_id, run_id, &key, file, force)
+                        .await
+                }
+                run::store::StoreCommands::Delete {
+                    pipeline_id,
+                    run_id,
+                    key,
+                } => cli.run_store_delete(&pipeline_id, run_id, &key).await,
+            },
             _ => todo!(),
         },
         Commands::Trigger(trigger) => {
@@ -272,5 +399,19 @@ pub async fn init() {
                 }
             }
         }
+        Commands::Token(token) => {
+            let token_cmds = token.command;
+
+            match token_cmds {
+                token::TokenCommands::Create {
+                    kind,
+                    metadata,
+                    namespace,
+                    pipeline,
+                } => cli.token_create(&kind, metadata, namespace, pipeline).await,
+                token::TokenCommands::List => cli.token_list().await,
+                token::TokenCommands::Delete { id } => cli.token_delete(id).await,
+            }
+        }
     }
 }
@@ -3,6 +3,8 @@ mod delete;
 mod get;
 mod list;
 mod run;
+pub mod secret;
+pub mod store;
 mod update;
 
 use anyhow::{anyhow, Result};
@@ -48,6 +50,11 @@ pub enum PipelineCommands {
         /// Optional environment variables to pass to your run.
         #[clap(short, long, name = "KEY=VALUE")]
         variables: Vec<String>,
+
+        /// Runs with a higher priority are started first when the pipeline has more runs waiting than it
+        /// is allowed to run at once.
+        #[clap(short, long, default_value_t = 0)]
+        priority: i64,
     },
 
     /// Update to a new version of your pipeline.
@@ -65,6 +72,16 @@ pub enum PipelineCommands {
         /// Pipeline Identifier.
         id: String,
     },
+
+    /// Manage pipeline secrets.
+    ///
+    /// Secrets are stored per pipeline and can be referenced within a pipeline's task configuration.
+    Secret(secret::SecretSubcommands),
+
+    /// Manage pipeline objects.
+    ///
+    /// Pipeline objects are stored in Gofer's object store and can be referenced by any run of the pipeline.
+    Store(store::StoreSubcommands),
 }
 
 #[derive(Debug)]
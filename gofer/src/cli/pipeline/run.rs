@@ -4,7 +4,7 @@ use colored::Colorize;
 use std::process;
 
 impl CliHarness {
-    pub async fn pipeline_run(&self, id: &str, variables: Vec<String>) {
+    pub async fn pipeline_run(&self, id: &str, variables: Vec<String>, priority: i64) {
         let vars = parse_variables(variables);
 
         let mut client = self.connect().await.unwrap_or_else(|e| {
@@ -20,6 +20,7 @@ impl CliHarness {
                 .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
             pipeline_id: id.to_string(),
             variables: vars,
+            priority,
         });
         let response = client
             .start_run(request)
@@ -33,7 +34,7 @@ impl CliHarness {
         let new_run = response.run.unwrap();
 
         println!(
-            "{} Started new run ({}) for pipeline '{}'",
+            "{} Queued new run ({}) for pipeline '{}'",
             "✓".green(),
             new_run.id,
             id,
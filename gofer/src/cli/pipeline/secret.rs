@@ -0,0 +1,256 @@
+use super::super::CliHarness;
+use crate::cli::{humanize_relative_duration, DEFAULT_NAMESPACE};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use comfy_table::{presets::ASCII_MARKDOWN, Cell, CellAlignment, Color, ContentArrangement};
+use std::io::{stdin, Read};
+use std::process;
+
+#[derive(Debug, Args)]
+pub struct SecretSubcommands {
+    #[clap(subcommand)]
+    pub command: SecretCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SecretCommands {
+    /// List the keys of all secrets stored for a pipeline.
+    List {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+    },
+
+    /// Detail a pipeline secret by key.
+    Get {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Secret key.
+        key: String,
+
+        /// Print the secret value along with its details.
+        #[clap(short, long)]
+        include_secret: bool,
+    },
+
+    /// Store a secret for a pipeline.
+    ///
+    /// The secret value is read from stdin unless a file is given. A single trailing newline is removed
+    /// from values read from stdin.
+    Put {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Secret key; referenced within pipeline configurations as `secret{{ key }}`.
+        key: String,
+
+        /// Path to a file whose contents will be used as the secret value.
+        #[clap(short, long)]
+        file: Option<String>,
+
+        /// Replace the secret if it already exists.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Delete a pipeline secret by key.
+    Delete {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Secret key.
+        key: String,
+    },
+}
+
+/// Reads the secret value either from the file given or from stdin.
+fn read_secret_value(file: Option<String>) -> std::io::Result<String> {
+    if let Some(path) = file {
+        return std::fs::read_to_string(path);
+    }
+
+    let mut value = String::new();
+    stdin().read_to_string(&mut value)?;
+
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+
+    Ok(value)
+}
+
+impl CliHarness {
+    pub async fn pipeline_secret_list(&self, pipeline_id: &str) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::ListPipelineSecretsRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+        });
+        let response = client
+            .list_pipeline_secrets(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        if response.secrets.is_empty() {
+            println!("No secrets found.");
+            return;
+        }
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(ASCII_MARKDOWN)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                Cell::new("key")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("created")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+            ]);
+
+        for secret in response.secrets {
+            table.add_row(vec![
+                Cell::new(secret.key).fg(Color::Green),
+                Cell::new(
+                    humanize_relative_duration(secret.created)
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                ),
+            ]);
+        }
+
+        println!("{table}",);
+    }
+
+    pub async fn pipeline_secret_get(&self, pipeline_id: &str, key: &str, include_secret: bool) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::GetPipelineSecretRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            key: key.to_string(),
+            include_secret,
+        });
+        let response = client
+            .get_pipeline_secret(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        let secret: gofer_models::secret::Secret = response.secret.unwrap().into();
+
+        println!(
+            "[{}/{}] {} :: Created {}",
+            secret.namespace,
+            secret.pipeline,
+            secret.key,
+            humanize_relative_duration(secret.created).unwrap_or_else(|| "Unknown".to_string())
+        );
+
+        if include_secret {
+            println!("\n{}", response.value);
+        }
+    }
+
+    pub async fn pipeline_secret_put(
+        &self,
+        pipeline_id: &str,
+        key: &str,
+        file: Option<String>,
+        force: bool,
+    ) {
+        let value = read_secret_value(file).unwrap_or_else(|e| {
+            eprintln!("{} Could not read secret value; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::PutPipelineSecretRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            key: key.to_string(),
+            value,
+            force,
+        });
+
+        client
+            .put_pipeline_secret(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            });
+
+        println!(
+            "{} Stored secret '{}' for pipeline '{}'",
+            "✓".green(),
+            key,
+            pipeline_id
+        );
+    }
+
+    pub async fn pipeline_secret_delete(&self, pipeline_id: &str, key: &str) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::DeletePipelineSecretRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            key: key.to_string(),
+        });
+
+        client
+            .delete_pipeline_secret(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            });
+
+        println!(
+            "{} Deleted secret '{}' from pipeline '{}'",
+            "✓".green(),
+            key,
+            pipeline_id
+        );
+    }
+}
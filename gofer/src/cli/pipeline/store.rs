@@ -0,0 +1,246 @@
+use super::super::CliHarness;
+use crate::cli::{
+    download_object, humanize_relative_duration, open_object, upload_object, DEFAULT_NAMESPACE,
+};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use comfy_table::{presets::ASCII_MARKDOWN, Cell, CellAlignment, Color, ContentArrangement};
+use std::process;
+
+#[derive(Debug, Args)]
+pub struct StoreSubcommands {
+    #[clap(subcommand)]
+    pub command: StoreCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StoreCommands {
+    /// List the keys of all objects stored for a pipeline.
+    List {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+    },
+
+    /// Retrieve a pipeline object; written to stdout unless a file is given.
+    Get {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Object key.
+        key: String,
+
+        /// Path of a file to write the object to.
+        #[clap(short, long)]
+        file: Option<String>,
+    },
+
+    /// Store an object for a pipeline.
+    ///
+    /// The object is read from stdin unless a file is given. Pipelines can only hold a limited number of
+    /// objects; once that limit is reached the oldest object is removed.
+    Put {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Object key; referenced within pipeline configurations as `pipeline{{ key }}`.
+        key: String,
+
+        /// Path to a file whose contents will be stored.
+        #[clap(short, long)]
+        file: Option<String>,
+
+        /// Replace the object if it already exists.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Delete a pipeline object by key.
+    Delete {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Object key.
+        key: String,
+    },
+}
+
+impl CliHarness {
+    pub async fn pipeline_store_list(&self, pipeline_id: &str) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::ListPipelineObjectsRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+        });
+        let response = client
+            .list_pipeline_objects(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        if response.keys.is_empty() {
+            println!("No objects found.");
+            return;
+        }
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(ASCII_MARKDOWN)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                Cell::new("key")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("created")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+            ]);
+
+        for object_key in response.keys {
+            table.add_row(vec![
+                Cell::new(object_key.key).fg(Color::Green),
+                Cell::new(
+                    humanize_relative_duration(object_key.created)
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                ),
+            ]);
+        }
+
+        println!("{table}",);
+    }
+
+    pub async fn pipeline_store_get(&self, pipeline_id: &str, key: &str, file: Option<String>) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::GetPipelineObjectRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            key: key.to_string(),
+        });
+        let response = client
+            .get_pipeline_object(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        download_object(file, response, |chunk| chunk.content)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Could not write object; {}", "x".red(), e);
+                process::exit(1);
+            });
+    }
+
+    pub async fn pipeline_store_put(
+        &self,
+        pipeline_id: &str,
+        key: &str,
+        file: Option<String>,
+        force: bool,
+    ) {
+        let object = open_object(file).await.unwrap_or_else(|e| {
+            eprintln!("{} Could not read object; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let details = gofer_proto::PutPipelineObjectRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            key: key.to_string(),
+            content: vec![],
+            force,
+        };
+        let request = tonic::Request::new(upload_object(details, object, |content| {
+            gofer_proto::PutPipelineObjectRequest {
+                content,
+                ..Default::default()
+            }
+        }));
+        let response = client
+            .put_pipeline_object(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        println!(
+            "{} Stored object '{}' ({} bytes) for pipeline '{}'",
+            "✓".green(),
+            key,
+            response.bytes,
+            pipeline_id
+        );
+
+        for evicted in response.objects_evicted {
+            println!(
+                "{} Removed object '{}' to stay within the pipeline object limit of {}",
+                "!".yellow(),
+                evicted,
+                response.object_limit
+            );
+        }
+    }
+
+    pub async fn pipeline_store_delete(&self, pipeline_id: &str, key: &str) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::DeletePipelineObjectRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            key: key.to_string(),
+        });
+
+        client
+            .delete_pipeline_object(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            });
+
+        println!(
+            "{} Deleted object '{}' from pipeline '{}'",
+            "✓".green(),
+            key,
+            pipeline_id
+        );
+    }
+}
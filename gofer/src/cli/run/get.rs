@@ -15,6 +15,7 @@ struct Data {
     started: String,
     duration: String,
     task_runs: Vec<TaskRunData>,
+    variables: Vec<VariableData>,
     store_keys: Vec<StoreKeyData>,
 }
 
@@ -27,6 +28,12 @@ struct TaskRunData {
     status: String,
 }
 
+#[derive(Debug, serde::Serialize)]
+struct VariableData {
+    key: String,
+    value: String,
+}
+
 #[derive(Debug, serde::Serialize)]
 struct StoreKeyData {
     key: String,
@@ -42,6 +49,13 @@ Triggered via {trigger_label} ({trigger_name}) {started} and ran for {duration}
   {{- for task_run in task_runs}}
   • {task_run.id} :: Started {task_run.started} :: {task_run.duration} :: {task_run.status} | {task_run.state}
   {{- endfor -}}
+{{- if variables}}
+
+🔑 Variables:
+  {{- for variable in variables}}
+  • {variable.key} = {variable.value}
+  {{- endfor -}}
+{{- endif}}
 {{- if store_keys}}
 
 ☁︎ Store keys: [{store_keys}]
@@ -53,7 +67,7 @@ Triggered via {trigger_label} ({trigger_name}) {started} and ran for {duration}
 }
 
 impl CliHarness {
-    pub async fn run_get(&self, pipeline_id: String, id: u64) {
+    pub async fn run_get(&self, pipeline_id: String, id: u64, reveal: bool) {
         let mut client = self.connect().await.unwrap_or_else(|e| {
             eprintln!("{} Command failed; {}", "x".red(), e);
             process::exit(1);
@@ -67,6 +81,7 @@ impl CliHarness {
                 .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
             pipeline_id: pipeline_id.to_string(),
             id,
+            reveal_private_variables: reveal,
         });
         let run = client
             .get_run(request)
@@ -82,6 +97,21 @@ impl CliHarness {
                 process::exit(1);
             });
 
+        // Whether a variable was redacted is only known from the response, so variables are read before the
+        // run is converted.
+        let variables: Vec<VariableData> = run
+            .variables
+            .iter()
+            .map(|variable| VariableData {
+                key: variable.key.clone(),
+                value: if variable.redacted {
+                    "[redacted]".to_string()
+                } else {
+                    variable.value.clone()
+                },
+            })
+            .collect();
+
         let run: gofer_models::run::Run = run.into();
 
         let request = tonic::Request::new(gofer_proto::ListTaskRunsRequest {
@@ -92,6 +122,7 @@ impl CliHarness {
                 .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
             pipeline_id: pipeline_id.to_string(),
             run_id: id,
+            reveal_private_variables: reveal,
         });
         let task_runs = client
             .list_task_runs(request)
@@ -130,6 +161,7 @@ impl CliHarness {
                 .unwrap_or_else(|| "Not yet".to_string()),
             duration: humanize_absolute_duration(run.started, run.ended),
             task_runs,
+            variables,
             store_keys: vec![],
         };
 
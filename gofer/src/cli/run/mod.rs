@@ -1,5 +1,6 @@
 mod get;
 mod list;
+pub mod store;
 
 use super::CliHarness;
 use clap::{Args, Subcommand};
@@ -19,6 +20,10 @@ pub enum RunCommands {
 
         /// Run Identifier.
         id: u64,
+
+        /// Show the values of private variables instead of redacting them. Requires a management token.
+        #[clap(long)]
+        reveal: bool,
     },
 
     /// List all runs; defaults from oldest run to newest.
@@ -47,4 +52,9 @@ pub enum RunCommands {
         /// Pipeline Identifier.
         pipeline_id: String,
     },
+
+    /// Manage run objects.
+    ///
+    /// Run objects are stored in Gofer's object store and can be referenced by the tasks of that run.
+    Store(store::StoreSubcommands),
 }
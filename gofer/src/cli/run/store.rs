@@ -0,0 +1,257 @@
+use super::super::CliHarness;
+use crate::cli::{
+    download_object, humanize_relative_duration, open_object, upload_object, DEFAULT_NAMESPACE,
+};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use comfy_table::{presets::ASCII_MARKDOWN, Cell, CellAlignment, Color, ContentArrangement};
+use std::process;
+
+#[derive(Debug, Args)]
+pub struct StoreSubcommands {
+    #[clap(subcommand)]
+    pub command: StoreCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StoreCommands {
+    /// List the keys of all objects stored for a run.
+    List {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Run Identifier.
+        run_id: u64,
+    },
+
+    /// Retrieve a run object; written to stdout unless a file is given.
+    Get {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Run Identifier.
+        run_id: u64,
+
+        /// Object key.
+        key: String,
+
+        /// Path of a file to write the object to.
+        #[clap(short, long)]
+        file: Option<String>,
+    },
+
+    /// Store an object for a run.
+    ///
+    /// The object is read from stdin unless a file is given. Run objects are removed once the run is older
+    /// than the run object expiry.
+    Put {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Run Identifier.
+        run_id: u64,
+
+        /// Object key; referenced within pipeline configurations as `run{{ key }}`.
+        key: String,
+
+        /// Path to a file whose contents will be stored.
+        #[clap(short, long)]
+        file: Option<String>,
+
+        /// Replace the object if it already exists.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Delete a run object by key.
+    Delete {
+        /// Pipeline Identifier.
+        pipeline_id: String,
+
+        /// Run Identifier.
+        run_id: u64,
+
+        /// Object key.
+        key: String,
+    },
+}
+
+impl CliHarness {
+    pub async fn run_store_list(&self, pipeline_id: &str, run_id: u64) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::ListRunObjectsRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            run_id,
+        });
+        let response = client
+            .list_run_objects(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        if response.keys.is_empty() {
+            println!("No objects found.");
+            return;
+        }
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(ASCII_MARKDOWN)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                Cell::new("key")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("created")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+            ]);
+
+        for object_key in response.keys {
+            table.add_row(vec![
+                Cell::new(object_key.key).fg(Color::Green),
+                Cell::new(
+                    humanize_relative_duration(object_key.created)
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                ),
+            ]);
+        }
+
+        println!("{table}",);
+    }
+
+    pub async fn run_store_get(
+        &self,
+        pipeline_id: &str,
+        run_id: u64,
+        key: &str,
+        file: Option<String>,
+    ) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::GetRunObjectRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            run_id,
+            key: key.to_string(),
+        });
+        let response = client
+            .get_run_object(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        download_object(file, response, |chunk| chunk.content)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Could not write object; {}", "x".red(), e);
+                process::exit(1);
+            });
+    }
+
+    pub async fn run_store_put(
+        &self,
+        pipeline_id: &str,
+        run_id: u64,
+        key: &str,
+        file: Option<String>,
+        force: bool,
+    ) {
+        let object = open_object(file).await.unwrap_or_else(|e| {
+            eprintln!("{} Could not read object; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let details = gofer_proto::PutRunObjectRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            run_id,
+            key: key.to_string(),
+            content: vec![],
+            force,
+        };
+        let request = tonic::Request::new(upload_object(details, object, |content| {
+            gofer_proto::PutRunObjectRequest {
+                content,
+                ..Default::default()
+            }
+        }));
+        let response = client
+            .put_run_object(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        println!(
+            "{} Stored object '{}' ({} bytes) for run #{}",
+            "✓".green(),
+            key,
+            response.bytes,
+            run_id
+        );
+    }
+
+    pub async fn run_store_delete(&self, pipeline_id: &str, run_id: u64, key: &str) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(gofer_proto::DeleteRunObjectRequest {
+            namespace_id: self
+                .config
+                .namespace
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+            pipeline_id: pipeline_id.to_string(),
+            run_id,
+            key: key.to_string(),
+        });
+
+        client.delete_run_object(request).await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e.message());
+            process::exit(1);
+        });
+
+        println!(
+            "{} Deleted object '{}' from run #{}",
+            "✓".green(),
+            key,
+            run_id
+        );
+    }
+}
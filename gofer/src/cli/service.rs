@@ -1,6 +1,7 @@
 use crate::api;
 use crate::conf;
-use gofer_proto::GetSystemInfoRequest;
+use colored::Colorize;
+use gofer_proto::{GetSystemInfoRequest, RotateSecretStoreKeysRequest};
 
 use clap::{Args, Subcommand};
 use std::process;
@@ -25,6 +26,13 @@ pub enum ServiceCommands {
 
     /// Retrieve general information about Gofer's systems
     Info,
+
+    /// Re-encrypt all secrets with the secret store's active encryption key.
+    #[clap(
+        long_about = "Re-encrypts every secret in the secret store with the active encryption key. Run this after
+    changing the active key so that the previous keys can be removed from the configuration."
+    )]
+    RotateSecretKeys,
 }
 
 impl CliHarness {
@@ -46,4 +54,27 @@ impl CliHarness {
 
         println!("{:?}", response.into_inner());
     }
+
+    pub async fn service_rotate_secret_keys(&self) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(RotateSecretStoreKeysRequest {});
+        let response = client
+            .rotate_secret_store_keys(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        println!(
+            "{} Re-encrypted {} secrets",
+            "✓".green(),
+            response.secrets_reencrypted
+        );
+    }
 }
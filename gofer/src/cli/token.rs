@@ -0,0 +1,177 @@
+use super::CliHarness;
+use crate::cli::{humanize_relative_duration, parse_variables};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use comfy_table::{presets::ASCII_MARKDOWN, Cell, CellAlignment, Color, ContentArrangement};
+use gofer_proto::{token::Kind, CreateTokenRequest, DeleteTokenRequest, ListTokensRequest};
+use std::process;
+
+#[derive(Debug, Args)]
+pub struct TokenSubcommands {
+    #[clap(subcommand)]
+    pub command: TokenCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TokenCommands {
+    /// Create a new API token. The token is only shown once.
+    Create {
+        /// The kind of token; either "management" or "client".
+        #[clap(short, long, default_value = "client")]
+        kind: String,
+
+        /// Extra information about the token, like who or what it is for.
+        #[clap(short, long, name = "KEY=VALUE")]
+        metadata: Vec<String>,
+
+        /// Namespaces a client token is allowed to act on. Can be given multiple times.
+        #[clap(short, long)]
+        namespace: Vec<String>,
+
+        /// Pipelines a client token is allowed to act on within its namespaces. Can be given multiple times;
+        /// all pipelines if not given.
+        #[clap(short, long)]
+        pipeline: Vec<String>,
+    },
+
+    /// List API tokens.
+    List,
+
+    /// Delete an API token, revoking access for anyone using it.
+    Delete {
+        /// Token Identifier.
+        id: u64,
+    },
+}
+
+impl CliHarness {
+    pub async fn token_create(
+        &self,
+        kind: &str,
+        metadata: Vec<String>,
+        namespaces: Vec<String>,
+        pipelines: Vec<String>,
+    ) {
+        let kind = match kind.to_lowercase().as_str() {
+            "management" => Kind::Management,
+            "client" => Kind::Client,
+            _ => {
+                eprintln!(
+                    "{} Command failed; kind must be one of 'management' or 'client'",
+                    "x".red()
+                );
+                process::exit(1);
+            }
+        };
+
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(CreateTokenRequest {
+            kind: kind as i32,
+            metadata: parse_variables(metadata),
+            namespaces,
+            pipelines,
+        });
+        let response = client
+            .create_token(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        let details = response.details.unwrap();
+
+        println!("{} Created token ({})", "✓".green(), details.id);
+        println!(
+            "  Store this token somewhere safe, it will not be shown again: {}",
+            response.token.cyan()
+        );
+    }
+
+    pub async fn token_list(&self) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(ListTokensRequest {});
+        let response = client
+            .list_tokens(request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("{} Command failed; {}", "x".red(), e.message());
+                process::exit(1);
+            })
+            .into_inner();
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(ASCII_MARKDOWN)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                Cell::new("id")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("kind")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("namespaces")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("pipelines")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("metadata")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+                Cell::new("created")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Blue),
+            ]);
+
+        for token in response.tokens {
+            let kind = Kind::from_i32(token.kind).unwrap_or(Kind::Unknown);
+
+            let mut metadata: Vec<String> = token
+                .metadata
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            metadata.sort();
+
+            table.add_row(vec![
+                Cell::new(token.id).fg(Color::Green),
+                Cell::new(format!("{:?}", kind).to_lowercase()),
+                Cell::new(token.namespaces.join(", ")),
+                Cell::new(token.pipelines.join(", ")),
+                Cell::new(metadata.join(", ")),
+                Cell::new(
+                    humanize_relative_duration(token.created)
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                ),
+            ]);
+        }
+
+        println!("{table}",);
+    }
+
+    pub async fn token_delete(&self, id: u64) {
+        let mut client = self.connect().await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e);
+            process::exit(1);
+        });
+
+        let request = tonic::Request::new(DeleteTokenRequest { id });
+        client.delete_token(request).await.unwrap_or_else(|e| {
+            eprintln!("{} Command failed; {}", "x".red(), e.message());
+            process::exit(1);
+        });
+
+        println!("{} Deleted token ({})", "✓".green(), id);
+    }
+}
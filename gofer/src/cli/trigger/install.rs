@@ -1,15 +1,13 @@
 use super::super::CliHarness;
-use crate::cli::{parse_variables, printerr_and_finish, Spinner};
+use crate::cli::{parse_variables, printerr_and_finish, Client, Spinner};
 use colored::Colorize;
 use crossterm::{
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
-use gofer_proto::gofer_client::GoferClient;
 use indicatif::ProgressBar;
 use std::collections::HashMap;
 use std::io::{stdin, stdout, Write};
-use tonic::transport::Channel;
 
 fn to_title_case(s: &str) -> String {
     let mut c = s.chars();
@@ -22,7 +20,7 @@ fn to_title_case(s: &str) -> String {
 impl CliHarness {
     async fn run_trigger_installer(
         &self,
-        client: &mut GoferClient<Channel>,
+        client: &mut Client,
         name: &str,
         image: &str,
         user: Option<String>,
@@ -1,8 +1,16 @@
+use crate::object_store::CHUNK_SIZE;
 use anyhow::anyhow;
+use colored::Colorize;
+use futures::Stream;
 use std::collections::HashMap;
+use std::pin::Pin;
 use std::process;
 use std::str::FromStr;
-use tonic::transport::{Certificate, ClientTlsConfig, Uri};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tonic::{
+    transport::{Certificate, ClientTlsConfig, Uri},
+    Streaming,
+};
 
 pub fn printerr_and_finish(message: &str) -> ! {
     eprintln!("Command failed; {}", message);
@@ -30,6 +38,67 @@ pub fn parse_variables(vars: Vec<String>) -> HashMap<String, String> {
     v
 }
 
+/// Opens an object to be stored, either the file given or stdin.
+pub async fn open_object(file: Option<String>) -> std::io::Result<Pin<Box<dyn AsyncRead + Send>>> {
+    match file {
+        Some(path) => Ok(Box::pin(tokio::fs::File::open(path).await?)),
+        None => Ok(Box::pin(tokio::io::stdin())),
+    }
+}
+
+/// Turns an object into the stream of requests used to upload it. The request carrying the object's details
+/// is sent first, followed by one request per chunk of content built by `chunk_request`. If the object can't
+/// be read the command fails before the upload is completed, so a partial object is never stored.
+pub fn upload_object<T, F>(
+    details: T,
+    mut object: Pin<Box<dyn AsyncRead + Send>>,
+    chunk_request: F,
+) -> impl Stream<Item = T> + Send + 'static
+where
+    T: Send + 'static,
+    F: Fn(Vec<u8>) -> T + Send + 'static,
+{
+    async_stream::stream! {
+        yield details;
+
+        loop {
+            let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+
+            match (&mut object).take(CHUNK_SIZE as u64).read_to_end(&mut chunk).await {
+                Ok(0) => break,
+                Ok(_) => yield chunk_request(chunk),
+                Err(e) => {
+                    eprintln!("{} Could not read object; {}", "x".red(), e);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Writes a retrieved object as its chunks arrive, either to the file given or to stdout.
+pub async fn download_object<T, F>(
+    file: Option<String>,
+    mut chunks: Streaming<T>,
+    chunk_content: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(T) -> Vec<u8>,
+{
+    let mut output: Pin<Box<dyn AsyncWrite + Send>> = match file {
+        Some(path) => Box::pin(tokio::fs::File::create(path).await?),
+        None => Box::pin(tokio::io::stdout()),
+    };
+
+    while let Some(chunk) = chunks.message().await? {
+        output.write_all(&chunk_content(chunk)).await?;
+    }
+
+    output.flush().await?;
+
+    Ok(())
+}
+
 /// Returns a valid TLS configuration for GRPC connections. Most of this is only required to make
 /// self-signed cert usage easier. Rustls wont allow IP addresses in the url field and wont allow
 /// you to skip client-side issuer verification. So if the user enters 127.0.0.1 we replace
@@ -2,6 +2,7 @@ use crate::conf::{LOCALHOST_CA, LOCALHOST_CRT, LOCALHOST_KEY};
 use crate::{object_store, scheduler, secret_store};
 use econf::LoadEnv;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
 pub struct Config {
@@ -34,7 +35,8 @@ pub struct General {
     ///  puts the memory and storage footprint at about 9GB.
     pub event_retention: u64,
 
-    /// The limit automatically imposed if the pipeline does not define a limit. 0 is unlimited.
+    /// The limit automatically imposed if the pipeline does not define a limit. It also caps the total amount of
+    /// runs in progress across all pipelines. 0 is unlimited.
     pub run_parallelism_limit: u64,
 
     /// The total amount of runs before logs of the oldest run will be deleted.
@@ -49,6 +51,11 @@ pub struct General {
     /// scheduler should wait however long it takes the container to respond to the terminal signal.
     /// This is usually passed to the scheduler when a request to cancel a task run is being made.
     pub task_run_stop_timeout: u64,
+
+    /// The maximum amount of time (in seconds) a run is allowed to take. Any task runs still in progress when
+    /// the timeout is reached are stopped and marked as failed. Pipelines and individual tasks can set a
+    /// shorter timeout, but never a longer one. 0 means runs are only timed out by their pipeline's timeout.
+    pub run_timeout: u64,
 }
 
 #[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
@@ -66,6 +73,21 @@ pub struct Server {
 pub struct Scheduler {
     pub engine: scheduler::Engine,
     pub docker: Option<DockerScheduler>,
+    pub kubernetes: Option<KubernetesScheduler>,
+
+    /// The CPU limit (in millicores) given to task containers that don't define their own. 0 means no limit.
+    pub default_cpu_limit: u64,
+
+    /// The largest CPU limit (in millicores) a task container is allowed to have. Tasks asking for more are
+    /// capped to this value. 0 means no maximum.
+    pub max_cpu_limit: u64,
+
+    /// The memory limit (in megabytes) given to task containers that don't define their own. 0 means no limit.
+    pub default_memory_limit: u64,
+
+    /// The largest memory limit (in megabytes) a task container is allowed to have. Tasks asking for more are
+    /// capped to this value. 0 means no maximum.
+    pub max_memory_limit: u64,
 }
 
 #[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
@@ -74,6 +96,23 @@ pub struct DockerScheduler {
     pub prune_interval: u64, // in seconds
 }
 
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
+pub struct KubernetesScheduler {
+    /// The URL of the Kubernetes API server. Leave empty when Gofer runs inside the cluster to use the
+    /// service account Gofer's pod runs as.
+    pub api_server: String,
+
+    /// Bearer token used to authenticate with the API server. Leave empty to use the service account token.
+    pub token: String,
+
+    /// Path to the CA certificate the API server's certificate is verified against. Leave empty to use the
+    /// service account's CA certificate when in the cluster or the system trust store otherwise.
+    pub ca_cert: String,
+
+    /// The namespace containers are run in. Leave empty to use the namespace Gofer's pod runs in.
+    pub namespace: String,
+}
+
 #[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
 pub struct Triggers {
     pub tls_ca: Option<String>,
@@ -85,6 +124,8 @@ pub struct Triggers {
 pub struct ObjectStore {
     pub engine: object_store::Engine,
     pub embedded: Option<EmbeddedObjectStore>,
+    pub filesystem: Option<FilesystemObjectStore>,
+    pub s3: Option<S3ObjectStore>,
     pub pipeline_object_limit: u64,
     pub run_object_expiry: u64,
 }
@@ -94,17 +135,92 @@ pub struct EmbeddedObjectStore {
     pub path: String,
 }
 
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
+pub struct FilesystemObjectStore {
+    /// The directory objects are stored in. Objects are kept as plain files named after their keys.
+    pub path: String,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
+pub struct S3ObjectStore {
+    /// The bucket objects are stored in. It must already exist.
+    pub bucket: String,
+
+    /// Prepended to the key of every object Gofer stores. Allows a bucket to be shared with other
+    /// applications or Gofer instances.
+    pub prefix: String,
+
+    pub region: String,
+
+    /// The URL of the S3 compatible service (ex. "http://localhost:9000" for a local MinIO). Leave empty to
+    /// use AWS S3 in the configured region.
+    pub endpoint: String,
+
+    /// Address the bucket as part of the path (endpoint/bucket/key) instead of as a subdomain
+    /// (bucket.endpoint/key). Most self-hosted S3 compatible services require this.
+    pub path_style: bool,
+
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
 #[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
 pub struct SecretStore {
     pub engine: secret_store::Engine,
     pub embedded: Option<EmbeddedSecretStore>,
+    pub vault: Option<VaultSecretStore>,
 }
 
 #[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
 pub struct EmbeddedSecretStore {
     pub path: String,
-    /// The encryption key is used to store sensitive Gofer values. It MUST be 32 characters long.
-    pub encryption_key: String,
+
+    /// The id of the key in `encryption_keys` that secrets are encrypted with.
+    pub active_key: String,
+
+    /// Keys used to encrypt secrets, by id. Each MUST be 32 characters long. Keys other than the active key
+    /// are only used to decrypt secrets stored before the active key was changed. Once those secrets have
+    /// been re-encrypted with the active key the old key can be removed.
+    pub encryption_keys: HashMap<String, String>,
+
+    /// Deprecated: the single key secrets were encrypted with before keys had ids. If set it is used as the
+    /// encryption key with id "default". Use `active_key` and `encryption_keys` instead.
+    pub encryption_key: Option<String>,
+}
+
+impl EmbeddedSecretStore {
+    /// Returns the id of the active key along with all encryption keys by id, with the deprecated
+    /// `encryption_key` taking the place of the key with id "default".
+    pub fn keys(&self) -> (String, HashMap<String, String>) {
+        let mut encryption_keys = self.encryption_keys.clone();
+
+        if let Some(encryption_key) = &self.encryption_key {
+            encryption_keys.insert("default".to_string(), encryption_key.clone());
+        }
+
+        (self.active_key.clone(), encryption_keys)
+    }
+}
+
+#[derive(Deserialize, Default, Debug, Clone, PartialEq, Eq, LoadEnv)]
+pub struct VaultSecretStore {
+    /// The address of the Vault server (ex. "https://vault.example.com:8200").
+    pub address: String,
+
+    /// Where the KV version 2 secrets engine is mounted (ex. "secret").
+    pub mount: String,
+
+    /// Prepended to the key of every secret Gofer stores. Allows a mount to be shared with other
+    /// applications or Gofer instances.
+    pub prefix: String,
+
+    /// Token used to authenticate with Vault. Takes precedence over AppRole if both are set.
+    pub token: Option<String>,
+
+    /// Where the AppRole auth method is mounted. Defaults to "approle".
+    pub approle_mount: Option<String>,
+    pub role_id: Option<String>,
+    pub secret_id: Option<String>,
 }
 
 impl Config {
@@ -171,6 +287,7 @@ mod tests {
                 task_run_log_expiry: 20,
                 task_run_logs_dir: "/tmp".to_string(),
                 task_run_stop_timeout: 15,
+                run_timeout: 28800,
             },
             server: Server {
                 url: "127.0.0.1:8080".to_string(),
@@ -184,6 +301,11 @@ mod tests {
                     prune: true,
                     prune_interval: 604800,
                 }),
+                kubernetes: None,
+                default_cpu_limit: 0,
+                max_cpu_limit: 0,
+                default_memory_limit: 0,
+                max_memory_limit: 0,
             },
             triggers: Triggers {
                 ..Default::default()
@@ -193,6 +315,8 @@ mod tests {
                 embedded: Some(EmbeddedObjectStore {
                     path: "/tmp/gofer-object-store".to_string(),
                 }),
+                filesystem: None,
+                s3: None,
                 pipeline_object_limit: 10,
                 run_object_expiry: 20,
             },
@@ -200,8 +324,14 @@ mod tests {
                 engine: secret_store::Engine::Embedded,
                 embedded: Some(EmbeddedSecretStore {
                     path: "/tmp/gofer-secret-store".to_string(),
-                    encryption_key: "changemechangemechangemechangeme".to_string(),
+                    active_key: "default".to_string(),
+                    encryption_keys: HashMap::from([(
+                        "default".to_string(),
+                        "changemechangemechangemechangeme".to_string(),
+                    )]),
+                    encryption_key: None,
                 }),
+                vault: None,
             },
         };
 
@@ -237,6 +367,7 @@ mod tests {
                 task_run_log_expiry: 20,
                 task_run_logs_dir: "/tmp".to_string(),
                 task_run_stop_timeout: 15,
+                run_timeout: 28800,
             },
             server: Server {
                 url: "127.0.0.1:8080".to_string(),
@@ -251,6 +382,11 @@ mod tests {
                     prune: true,
                     prune_interval: 604800,
                 }),
+                kubernetes: None,
+                default_cpu_limit: 0,
+                max_cpu_limit: 0,
+                default_memory_limit: 0,
+                max_memory_limit: 0,
             },
             triggers: Triggers {
                 tls_ca: Some(LOCALHOST_CA.to_string()),
@@ -262,6 +398,8 @@ mod tests {
                 embedded: Some(EmbeddedObjectStore {
                     path: "/tmp/gofer-object-store".to_string(),
                 }),
+                filesystem: None,
+                s3: None,
                 pipeline_object_limit: 10,
                 run_object_expiry: 20,
             },
@@ -269,14 +407,54 @@ mod tests {
                 engine: secret_store::Engine::Embedded,
                 embedded: Some(EmbeddedSecretStore {
                     path: "/tmp/gofer-secret-store".to_string(),
-                    encryption_key: "changemechangemechangemechangeme".to_string(),
+                    active_key: "default".to_string(),
+                    encryption_keys: HashMap::from([(
+                        "default".to_string(),
+                        "changemechangemechangemechangeme".to_string(),
+                    )]),
+                    encryption_key: None,
                 }),
+                vault: None,
             },
         };
 
         assert_eq!(parsed_config, expected_config);
     }
 
+    #[test]
+    /// Configs from before secret store keys had ids keep working, with their key used as the "default" key.
+    fn parse_legacy_secret_store_encryption_key() {
+        let config = Kind::new_api_config();
+
+        let config_src = config::Config::builder()
+            .add_source(config::File::from_str(
+                config.default_config(),
+                config::FileFormat::Toml,
+            ))
+            .add_source(config::File::from_str(
+                r#"
+[secret_store.embedded]
+path = "/tmp/gofer-secret-store"
+encryption_key = "legacylegacylegacylegacylegacyle"
+"#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+
+        let parsed_config = config_src.try_deserialize::<Config>().unwrap();
+        let (active_key, encryption_keys) = parsed_config.secret_store.embedded.unwrap().keys();
+
+        assert_eq!(active_key, "default");
+        assert_eq!(
+            encryption_keys,
+            HashMap::from([(
+                "default".to_string(),
+                "legacylegacylegacylegacylegacyle".to_string()
+            )])
+        );
+    }
+
     #[test]
     /// Test that env vars correctly overwrite struct vars and are parsed correctly.
     fn parse_env_vars() {
@@ -7,6 +7,8 @@ pub struct Config {
     pub namespace: Option<String>,
     pub server: String,
     pub tls_ca: Option<String>,
+    /// The API token used to authenticate with the Gofer server.
+    pub token: Option<String>,
 }
 
 impl Config {
@@ -48,6 +50,7 @@ mod tests {
             server: "https://127.0.0.1:8080".to_string(),
             namespace: None,
             tls_ca: Some(LOCALHOST_CA.to_string()),
+            token: None,
         };
 
         assert_eq!(parsed_config, expected_config);
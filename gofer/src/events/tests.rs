@@ -14,7 +14,9 @@ impl TestHarness {
         let append_num: u8 = rng.gen();
         let storage_path = format!("/tmp/gofer_tests_events{}.db", append_num);
 
-        let db = Db::new(&storage_path).await.unwrap();
+        let db = Db::new(&storage_path, "changemechangemechangemechangeme")
+            .await
+            .unwrap();
 
         Self { db, storage_path }
     }
@@ -0,0 +1,65 @@
+//! Scaffolding shared by the in-memory stand-ins for the services Gofer talks to (S3, Vault and the Kubernetes
+//! API server). Each stand-in is a single axum handler over some shared state; this takes care of serving it
+//! and of building its responses.
+//!
+//! The stand-ins only understand the requests Gofer makes. The tests marked `#[ignore]` next to them run the
+//! same checks against the real services; see each of those tests for how to start the service locally.
+
+use axum::extract::Extension;
+use axum::handler::Handler;
+use bytes::Bytes;
+use http::StatusCode;
+use std::sync::{Arc, Mutex};
+
+/// Starts serving the handler on a random local port and returns the address it can be reached at. The handler
+/// can extract the state with `Extension<Arc<Mutex<S>>>`.
+pub fn start<H, T, S>(handler: H, state: Arc<Mutex<S>>) -> String
+where
+    H: Handler<T>,
+    T: 'static,
+    S: Send + 'static,
+{
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let app = axum::Router::new()
+        .fallback(axum::routing::any(handler))
+        .layer(Extension(state));
+
+    tokio::spawn(
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service()),
+    );
+
+    format!("http://{}", address)
+}
+
+pub fn response(
+    status: StatusCode,
+    headers: &[(&str, &str)],
+    body: impl Into<Bytes>,
+) -> axum::response::Response {
+    let mut response = http::Response::builder().status(status);
+    for (name, value) in headers {
+        response = response.header(*name, *value);
+    }
+
+    response
+        .body(axum::body::boxed(axum::body::Full::from(body.into())))
+        .unwrap()
+}
+
+pub fn json_response(status: StatusCode, body: serde_json::Value) -> axum::response::Response {
+    response(
+        status,
+        &[("content-type", "application/json")],
+        body.to_string(),
+    )
+}
+
+/// Reads the setting for a test that runs against a real service from the environment, falling back to the
+/// value the service uses when started locally as described by the test.
+pub fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
@@ -2,6 +2,8 @@ mod api;
 mod cli;
 mod conf;
 mod events;
+#[cfg(test)]
+mod fake_server;
 mod frontend;
 mod object_store;
 mod scheduler;
@@ -1,10 +1,29 @@
 use super::*;
+use crate::api::epoch;
 use async_trait::async_trait;
+use futures::StreamExt;
+use slog_scope::info;
 use std::{fs::create_dir_all, io, path::Path};
 
+/// Objects are split into two trees. The metadata tree maps each object key to an [`ObjectRecord`] and the
+/// chunks tree holds the object content in [`CHUNK_SIZE`] pieces.
+const METADATA_TREE: &str = "metadata";
+const CHUNKS_TREE: &str = "chunks";
+
+/// How an object is recorded in the metadata tree. Every write of an object gets its own upload id which
+/// its chunks are stored under. This lets a new version of an object be written in full before it replaces
+/// the old one, so readers never see a partially written object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ObjectRecord {
+    metadata: ObjectMetadata,
+    upload_id: String,
+    chunks: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct Engine {
-    db: sled::Db,
+    metadata: sled::Tree,
+    chunks: sled::Tree,
 }
 
 // Create folder if not exists.
@@ -16,29 +35,145 @@ fn touch_folder(path: &Path) -> io::Result<()> {
     Ok(())
 }
 
+fn chunk_key(upload_id: &str, index: u64) -> Vec<u8> {
+    let mut key = upload_id.as_bytes().to_vec();
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
 impl Engine {
     pub async fn new(path: &str) -> Result<Self, ObjectStoreError> {
         touch_folder(Path::new(path)).unwrap();
 
-        Ok(Self {
-            db: sled::open(path).expect("could not open object database"),
-        })
+        let db = sled::open(path)
+            .map_err(|e| ObjectStoreError::FailedInitPrecondition(e.to_string()))?;
+
+        let engine = Self {
+            metadata: db
+                .open_tree(METADATA_TREE)
+                .map_err(|e| ObjectStoreError::FailedInitPrecondition(e.to_string()))?,
+            chunks: db
+                .open_tree(CHUNKS_TREE)
+                .map_err(|e| ObjectStoreError::FailedInitPrecondition(e.to_string()))?,
+        };
+
+        engine
+            .migrate_legacy_objects(&db)
+            .await
+            .map_err(|e| ObjectStoreError::FailedInitPrecondition(e.to_string()))?;
+
+        Ok(engine)
+    }
+
+    /// Stores used to keep each object whole in the default tree, keyed by the object key. Those objects are
+    /// moved into the metadata and chunks trees so they can still be read and expired. An object is only
+    /// removed from the default tree once it has been written to the new trees, so an interrupted migration
+    /// picks up where it left off on the next start. The original store time wasn't recorded, so migrated
+    /// objects are marked as created when they are migrated.
+    async fn migrate_legacy_objects(&self, db: &sled::Db) -> Result<(), ObjectStoreError> {
+        let mut migrated = 0;
+
+        for entry in db.iter() {
+            let (key, value) = entry.map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            let object_key = String::from_utf8(key.to_vec())
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+
+            // If an interrupted migration already got as far as storing the object it just needs to be removed
+            // from the default tree.
+            if !self
+                .metadata
+                .contains_key(&key)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?
+            {
+                let content = futures::stream::once(async move { Ok(Bytes::from(value.to_vec())) });
+                self.put_object_stream(&object_key, Box::pin(content), false)
+                    .await?;
+            }
+
+            db.remove(&key)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            migrated += 1;
+        }
+
+        if migrated > 0 {
+            info!("migrated objects to the chunked embedded object store layout"; "objects" => migrated);
+        }
+
+        Ok(())
+    }
+
+    fn get_record(&self, key: &str) -> Result<ObjectRecord, ObjectStoreError> {
+        let record = self
+            .metadata
+            .get(key)
+            .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?
+            .ok_or(ObjectStoreError::NotFound)?;
+
+        serde_json::from_slice(&record).map_err(|e| ObjectStoreError::Unknown(e.to_string()))
+    }
+
+    /// Writes the stream into the chunks tree under the given upload id, hashing it as it goes. Returns the
+    /// total size, number of chunks written and the hex encoded hash.
+    async fn write_chunks(
+        &self,
+        upload_id: &str,
+        mut content: ObjectStream,
+    ) -> Result<(u64, u64, String), ObjectStoreError> {
+        let mut hasher = ContentHasher::new()?;
+        let mut buffer: Vec<u8> = Vec::with_capacity(CHUNK_SIZE);
+        let mut chunks = 0;
+
+        while let Some(chunk) = content.next().await {
+            let chunk = chunk?;
+
+            hasher.update(&chunk)?;
+            buffer.extend_from_slice(&chunk);
+
+            while buffer.len() >= CHUNK_SIZE {
+                let remainder = buffer.split_off(CHUNK_SIZE);
+                self.chunks
+                    .insert(chunk_key(upload_id, chunks), buffer)
+                    .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+                chunks += 1;
+                buffer = remainder;
+            }
+        }
+
+        if !buffer.is_empty() {
+            self.chunks
+                .insert(chunk_key(upload_id, chunks), buffer)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            chunks += 1;
+        }
+
+        let (size, hash) = hasher.finalize()?;
+
+        Ok((size, chunks, hash))
+    }
+
+    fn remove_chunks(&self, upload_id: &str) -> Result<(), ObjectStoreError> {
+        for entry in self.chunks.scan_prefix(upload_id.as_bytes()) {
+            let (key, _) = entry.map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            self.chunks
+                .remove(key)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+        }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl Store for Engine {
     async fn get_object(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
-        let value = self
-            .db
-            .get(key)
-            .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+        let mut stream = self.get_object_stream(key).await?;
+        let mut value = vec![];
 
-        if value.is_none() {
-            return Err(ObjectStoreError::NotFound);
-        };
+        while let Some(chunk) = stream.next().await {
+            value.extend_from_slice(&chunk?);
+        }
 
-        Ok(value.unwrap().to_vec())
+        Ok(value)
     }
 
     async fn put_object(
@@ -47,30 +182,139 @@ impl Store for Engine {
         value: Vec<u8>,
         force: bool,
     ) -> Result<(), ObjectStoreError> {
+        let content = futures::stream::once(async move { Ok(Bytes::from(value)) });
+        self.put_object_stream(key, Box::pin(content), force)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ObjectStoreError> {
+        let record = self
+            .metadata
+            .remove(key)
+            .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+
+        if let Some(record) = record {
+            let record: ObjectRecord = serde_json::from_slice(&record)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            self.remove_chunks(&record.upload_id)?;
+        }
+
+        Ok(())
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<ObjectMetadata>, ObjectStoreError> {
+        let mut objects = vec![];
+
+        for entry in self.metadata.scan_prefix(prefix) {
+            let (_, record) = entry.map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            let record: ObjectRecord = serde_json::from_slice(&record)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+            objects.push(record.metadata);
+        }
+
+        Ok(objects)
+    }
+
+    async fn get_object_metadata(&self, key: &str) -> Result<ObjectMetadata, ObjectStoreError> {
+        Ok(self.get_record(key)?.metadata)
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ObjectStoreError> {
+        let record = self.get_record(key)?;
+        let chunks = self.chunks.clone();
+
+        let stream = async_stream::stream! {
+            for index in 0..record.chunks {
+                match chunks.get(chunk_key(&record.upload_id, index)) {
+                    Ok(Some(chunk)) => yield Ok(Bytes::copy_from_slice(&chunk)),
+                    // The object was removed or replaced while we were reading it.
+                    Ok(None) => {
+                        yield Err(ObjectStoreError::NotFound);
+                        break;
+                    }
+                    Err(e) => {
+                        yield Err(ObjectStoreError::Unknown(e.to_string()));
+                        break;
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        content: ObjectStream,
+        force: bool,
+    ) -> Result<ObjectMetadata, ObjectStoreError> {
         if key.is_empty() {
             return Err(ObjectStoreError::FailedPrecondition);
         };
 
+        // Checked up front so we don't bother writing an object that can't be stored. The check that counts
+        // is the compare and swap below.
+        if !force
+            && self
+                .metadata
+                .contains_key(key)
+                .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?
+        {
+            return Err(ObjectStoreError::Exists);
+        }
+
+        let upload_id = nanoid::nanoid!();
+
+        let (size, chunks, hash) = match self.write_chunks(&upload_id, content).await {
+            Ok(written) => written,
+            Err(e) => {
+                self.remove_chunks(&upload_id)?;
+                return Err(e);
+            }
+        };
+
+        let record = ObjectRecord {
+            metadata: ObjectMetadata {
+                key: key.to_string(),
+                size,
+                created: epoch(),
+                hash,
+            },
+            upload_id: upload_id.clone(),
+            chunks,
+        };
+        let record_json =
+            serde_json::to_vec(&record).map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+
         if force {
-            self.db
-                .insert(key, value)
+            let previous = self
+                .metadata
+                .insert(key, record_json)
                 .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
-            return Ok(());
-        }
 
-        self.db.compare_and_swap::<_, Vec<u8>, _>(key, None, Some(value)).
-            map_err(|e| ObjectStoreError::Unknown(e.to_string()))?.
-            // If we've reached an error at this level it can only be because the value exists.
-            map_err(|_| ObjectStoreError::Exists)?;
+            if let Some(previous) = previous {
+                let previous: ObjectRecord = serde_json::from_slice(&previous)
+                    .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+                self.remove_chunks(&previous.upload_id)?;
+            }
 
-        Ok(())
-    }
+            return Ok(record.metadata);
+        }
 
-    async fn delete_object(&self, key: &str) -> Result<(), ObjectStoreError> {
-        self.db
-            .remove(key)
+        let swapped = self
+            .metadata
+            .compare_and_swap::<_, Vec<u8>, _>(key, None, Some(record_json))
             .map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
 
-        Ok(())
+        // If the swap failed it can only be because the value exists.
+        if swapped.is_err() {
+            self.remove_chunks(&upload_id)?;
+            return Err(ObjectStoreError::Exists);
+        }
+
+        Ok(record.metadata)
     }
 }
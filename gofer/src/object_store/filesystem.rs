@@ -0,0 +1,325 @@
+use super::*;
+use crate::api::epoch;
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Object content is stored as plain files under the objects directory, at the path given by the object's
+/// key. Metadata for each object is stored as JSON at the same path under the metadata directory.
+/// Everything is first written to the tmp directory and then moved into place so that objects are never
+/// seen half written.
+const OBJECTS_DIR: &str = "objects";
+const METADATA_DIR: &str = "metadata";
+const TMP_DIR: &str = "tmp";
+
+#[derive(Debug, Clone)]
+pub struct Engine {
+    objects: PathBuf,
+    metadata: PathBuf,
+    tmp: PathBuf,
+}
+
+fn io_error(e: io::Error) -> ObjectStoreError {
+    match e.kind() {
+        io::ErrorKind::NotFound => ObjectStoreError::NotFound,
+        io::ErrorKind::AlreadyExists => ObjectStoreError::Exists,
+        _ => ObjectStoreError::Unknown(e.to_string()),
+    }
+}
+
+/// Keys are used as paths relative to the store's directories, so they must only be made up of normal
+/// path segments. Anything that could point outside of the store (like "..") is rejected.
+fn validate_key(key: &str) -> Result<(), ObjectStoreError> {
+    if key.is_empty() || key.contains('\0') || key.contains('\\') {
+        return Err(ObjectStoreError::FailedPrecondition);
+    }
+
+    // Components silently drops empty segments, so "a//b" and "a/" are checked for separately.
+    if key.split('/').any(|segment| segment.is_empty()) {
+        return Err(ObjectStoreError::FailedPrecondition);
+    }
+
+    if !Path::new(key)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(ObjectStoreError::FailedPrecondition);
+    }
+
+    Ok(())
+}
+
+/// Returns the keys of all files under the given directory, relative to the root directory.
+fn walk_keys(root: &Path, dir: &Path, keys: &mut Vec<String>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            walk_keys(root, &path, keys)?;
+            continue;
+        }
+
+        if let Ok(relative) = path.strip_prefix(root) {
+            let segments: Vec<String> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            keys.push(segments.join("/"));
+        }
+    }
+
+    Ok(())
+}
+
+impl Engine {
+    pub async fn new(path: &str) -> Result<Self, ObjectStoreError> {
+        let root = PathBuf::from(path);
+
+        let engine = Self {
+            objects: root.join(OBJECTS_DIR),
+            metadata: root.join(METADATA_DIR),
+            tmp: root.join(TMP_DIR),
+        };
+
+        for dir in [&engine.objects, &engine.metadata, &engine.tmp] {
+            fs::create_dir_all(dir)
+                .map_err(|e| ObjectStoreError::FailedInitPrecondition(e.to_string()))?;
+        }
+
+        Ok(engine)
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        self.tmp.join(nanoid::nanoid!())
+    }
+
+    /// Writes the stream to a new temporary file, returning its path along with the content's size and
+    /// hash.
+    async fn write_tmp(
+        &self,
+        mut content: ObjectStream,
+    ) -> Result<(PathBuf, u64, String), ObjectStoreError> {
+        let path = self.tmp_path();
+        let mut file = tokio::fs::File::create(&path).await.map_err(io_error)?;
+        let mut hasher = ContentHasher::new()?;
+
+        let result: Result<(), ObjectStoreError> = async {
+            while let Some(chunk) = content.next().await {
+                let chunk = chunk?;
+                hasher.update(&chunk)?;
+                file.write_all(&chunk).await.map_err(io_error)?;
+            }
+
+            file.sync_all().await.map_err(io_error)
+        }
+        .await;
+
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Err(e);
+        }
+
+        let (size, hash) = hasher.finalize()?;
+
+        Ok((path, size, hash))
+    }
+
+    async fn write_metadata(&self, metadata: &ObjectMetadata) -> Result<(), ObjectStoreError> {
+        let metadata_json =
+            serde_json::to_vec(metadata).map_err(|e| ObjectStoreError::Unknown(e.to_string()))?;
+
+        let tmp_path = self.tmp_path();
+        tokio::fs::write(&tmp_path, metadata_json)
+            .await
+            .map_err(io_error)?;
+
+        let path = self.metadata.join(&metadata.key);
+        create_parent_dir(&path).await?;
+
+        if let Err(e) = tokio::fs::rename(&tmp_path, &path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(io_error(e));
+        }
+
+        Ok(())
+    }
+}
+
+async fn create_parent_dir(path: &Path) -> Result<(), ObjectStoreError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl Store for Engine {
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        validate_key(key)?;
+
+        let path = self.objects.join(key);
+        if !path.is_file() {
+            return Err(ObjectStoreError::NotFound);
+        }
+
+        tokio::fs::read(path).await.map_err(io_error)
+    }
+
+    async fn put_object(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        force: bool,
+    ) -> Result<(), ObjectStoreError> {
+        let content = futures::stream::once(async move { Ok(Bytes::from(value)) });
+        self.put_object_stream(key, Box::pin(content), force)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ObjectStoreError> {
+        validate_key(key)?;
+
+        // Metadata goes first so that the object stops being listed before its content is removed.
+        for path in [self.metadata.join(key), self.objects.join(key)] {
+            if !path.is_file() {
+                continue;
+            }
+
+            match tokio::fs::remove_file(path).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<ObjectMetadata>, ObjectStoreError> {
+        // Only the directory the prefix points into needs to be walked.
+        let start = match prefix.rsplit_once('/') {
+            Some((dir, _)) if validate_key(dir).is_ok() => self.metadata.join(dir),
+            _ => self.metadata.clone(),
+        };
+
+        let mut keys = vec![];
+        walk_keys(&self.metadata, &start, &mut keys).map_err(io_error)?;
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+
+        let mut objects = vec![];
+        for key in keys {
+            match self.get_object_metadata(&key).await {
+                Ok(metadata) => objects.push(metadata),
+                // Removed while we were listing.
+                Err(ObjectStoreError::NotFound) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn get_object_metadata(&self, key: &str) -> Result<ObjectMetadata, ObjectStoreError> {
+        validate_key(key)?;
+
+        let path = self.metadata.join(key);
+        if !path.is_file() {
+            return Err(ObjectStoreError::NotFound);
+        }
+
+        let metadata = tokio::fs::read(path).await.map_err(io_error)?;
+
+        serde_json::from_slice(&metadata).map_err(|e| ObjectStoreError::Unknown(e.to_string()))
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ObjectStoreError> {
+        validate_key(key)?;
+
+        let path = self.objects.join(key);
+        if !path.is_file() {
+            return Err(ObjectStoreError::NotFound);
+        }
+
+        let mut file = tokio::fs::File::open(path).await.map_err(io_error)?;
+
+        let stream = async_stream::stream! {
+            loop {
+                let mut buffer = vec![0; CHUNK_SIZE];
+                match file.read(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(read) => {
+                        buffer.truncate(read);
+                        yield Ok(Bytes::from(buffer));
+                    }
+                    Err(e) => {
+                        yield Err(io_error(e));
+                        break;
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        content: ObjectStream,
+        force: bool,
+    ) -> Result<ObjectMetadata, ObjectStoreError> {
+        validate_key(key)?;
+
+        let path = self.objects.join(key);
+
+        // Checked up front so we don't bother writing an object that can't be stored. The check that counts
+        // is the hard link below, which fails if the file already exists.
+        if !force && path.exists() {
+            return Err(ObjectStoreError::Exists);
+        }
+
+        let (tmp_path, size, hash) = self.write_tmp(content).await?;
+
+        let result = async {
+            create_parent_dir(&path).await?;
+
+            if force {
+                tokio::fs::rename(&tmp_path, &path).await.map_err(io_error)
+            } else {
+                tokio::fs::hard_link(&tmp_path, &path)
+                    .await
+                    .map_err(io_error)
+            }
+        }
+        .await;
+
+        // Once renamed the temporary file is already gone, otherwise it is no longer needed.
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        result?;
+
+        let metadata = ObjectMetadata {
+            key: key.to_string(),
+            size,
+            created: epoch(),
+            hash,
+        };
+
+        self.write_metadata(&metadata).await?;
+
+        Ok(metadata)
+    }
+}
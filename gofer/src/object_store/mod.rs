@@ -1,12 +1,17 @@
 mod embedded;
+mod filesystem;
+mod s3;
 
 use crate::conf;
 use async_trait::async_trait;
+use bytes::Bytes;
 use econf::LoadEnv;
-use serde::Deserialize;
+use futures::Stream;
+use orion::hazardous::hash::blake2::blake2b::Blake2b;
+use serde::{Deserialize, Serialize};
 use slog_scope::error;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::{pin::Pin, sync::Arc};
 use strum::{Display, EnumString};
 
 #[cfg(test)]
@@ -31,6 +36,65 @@ pub enum ObjectStoreError {
     FailedInitPrecondition(String),
 }
 
+/// The size of the chunks objects are read and written in. Streaming in chunks means large objects never
+/// have to be held in memory all at once.
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A stream of object content, split into chunks.
+pub type ObjectStream = Pin<Box<dyn Stream<Item = Result<Bytes, ObjectStoreError>> + Send>>;
+
+/// Hex encodes the given bytes. Used for content hashes.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Tracks the size and hash of an object's content as it is written so they can be recorded in its
+/// metadata.
+struct ContentHasher {
+    hasher: Blake2b,
+    size: u64,
+}
+
+impl ContentHasher {
+    fn new() -> Result<Self, ObjectStoreError> {
+        Ok(Self {
+            hasher: Blake2b::new(32).map_err(|e| ObjectStoreError::Unknown(format!("{:?}", e)))?,
+            size: 0,
+        })
+    }
+
+    fn update(&mut self, chunk: &[u8]) -> Result<(), ObjectStoreError> {
+        self.hasher
+            .update(chunk)
+            .map_err(|e| ObjectStoreError::Unknown(format!("{:?}", e)))?;
+        self.size += chunk.len() as u64;
+
+        Ok(())
+    }
+
+    /// Returns the total size and hex encoded hash of the content written.
+    fn finalize(mut self) -> Result<(u64, String), ObjectStoreError> {
+        let digest = self
+            .hasher
+            .finalize()
+            .map_err(|e| ObjectStoreError::Unknown(format!("{:?}", e)))?;
+
+        Ok((self.size, to_hex(digest.as_ref())))
+    }
+}
+
+/// Details about a stored object.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ObjectMetadata {
+    pub key: String,
+    /// Size of the object in bytes.
+    pub size: u64,
+    /// Time the object was stored in epoch milli.
+    pub created: u64,
+    /// Hex encoded blake2b-256 hash of the object's content.
+    pub hash: String,
+}
+
 /// The store trait defines what the interface between Gofer and an Object store should adhere to.
 #[async_trait]
 pub trait Store: Debug {
@@ -42,11 +106,29 @@ pub trait Store: Debug {
         force: bool,
     ) -> Result<(), ObjectStoreError>;
     async fn delete_object(&self, key: &str) -> Result<(), ObjectStoreError>;
+
+    /// Returns the metadata of all objects whose key starts with the given prefix, ordered by key.
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<ObjectMetadata>, ObjectStoreError>;
+    async fn get_object_metadata(&self, key: &str) -> Result<ObjectMetadata, ObjectStoreError>;
+
+    /// Returns the object's content as a stream of chunks.
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ObjectStoreError>;
+
+    /// Stores an object from a stream of chunks. The object only becomes visible once the entire stream
+    /// has been written.
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        content: ObjectStream,
+        force: bool,
+    ) -> Result<ObjectMetadata, ObjectStoreError>;
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq, Display, EnumString, LoadEnv)]
 pub enum Engine {
     Embedded,
+    Filesystem,
+    S3,
 }
 
 impl Default for Engine {
@@ -58,7 +140,6 @@ impl Default for Engine {
 pub async fn init_object_store(
     config: &conf::api::ObjectStore,
 ) -> Result<Arc<dyn Store + Send + Sync>, ObjectStoreError> {
-    #[allow(clippy::match_single_binding)]
     match config.engine {
         Engine::Embedded => {
             if let Some(config) = &config.embedded {
@@ -70,5 +151,25 @@ pub async fn init_object_store(
                 ))
             }
         }
+        Engine::Filesystem => {
+            if let Some(config) = &config.filesystem {
+                let engine = filesystem::Engine::new(&config.path).await?;
+                Ok(Arc::new(engine))
+            } else {
+                Err(ObjectStoreError::FailedInitPrecondition(
+                    "engine settings not found in config".into(),
+                ))
+            }
+        }
+        Engine::S3 => {
+            if let Some(config) = &config.s3 {
+                let engine = s3::Engine::new(config).await?;
+                Ok(Arc::new(engine))
+            } else {
+                Err(ObjectStoreError::FailedInitPrecondition(
+                    "engine settings not found in config".into(),
+                ))
+            }
+        }
     }
 }
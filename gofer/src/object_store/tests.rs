@@ -1,28 +1,513 @@
-use rand::prelude::*;
+use crate::conf;
+use crate::fake_server;
+use crate::object_store::{ObjectStoreError, Store, CHUNK_SIZE};
+use axum::extract::{Extension, Query};
+use bytes::Bytes;
 use defer_lite::defer;
-use crate::object_store::Store;
+use futures::StreamExt;
+use http::{HeaderMap, Method, StatusCode, Uri};
+use rand::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Basic CRUD that every object store engine should support.
+async fn crud_object_store(store: &dyn Store) {
+    let test_key = "test_key";
+    let test_value = "test_value".as_bytes();
+
+    store
+        .put_object(test_key, test_value.to_vec(), false)
+        .await
+        .unwrap();
+
+    let returned_value = store.get_object(test_key).await.unwrap();
+    assert_eq!(test_value, returned_value);
+
+    store.delete_object(test_key).await.unwrap();
+
+    let returned_err = store.get_object(test_key).await.unwrap_err();
+    assert_eq!(ObjectStoreError::NotFound, returned_err);
+}
+
+/// Large objects can be streamed in and out of the store and their metadata listed.
+async fn stream_object_store(store: &dyn Store) {
+    // Spans multiple chunks and multipart upload parts and isn't aligned to either.
+    let test_value: Vec<u8> = (0..(super::s3::PART_SIZE + CHUNK_SIZE + 100))
+        .map(|i| (i % 251) as u8)
+        .collect();
+    let input: Vec<Result<Bytes, ObjectStoreError>> = test_value
+        .chunks(64 * 1024)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect();
+
+    let metadata = store
+        .put_object_stream(
+            "artifacts/large",
+            Box::pin(futures::stream::iter(input)),
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(test_value.len() as u64, metadata.size);
+    assert_eq!(64, metadata.hash.len());
+
+    let returned_metadata = store.get_object_metadata("artifacts/large").await.unwrap();
+    assert_eq!(metadata, returned_metadata);
+
+    let mut stream = store.get_object_stream("artifacts/large").await.unwrap();
+    let mut returned_value = vec![];
+    let mut chunks = 0;
+    while let Some(chunk) = stream.next().await {
+        returned_value.extend_from_slice(&chunk.unwrap());
+        chunks += 1;
+    }
+    assert!(chunks > 1);
+    assert_eq!(test_value, returned_value);
+
+    // Identical content hashes identically regardless of how it was written.
+    store
+        .put_object("artifacts/small", test_value.clone(), false)
+        .await
+        .unwrap();
+    let small_metadata = store.get_object_metadata("artifacts/small").await.unwrap();
+    assert_eq!(metadata.hash, small_metadata.hash);
+
+    store
+        .put_object("other", b"other".to_vec(), false)
+        .await
+        .unwrap();
+
+    let returned_err = store
+        .put_object("other", b"again".to_vec(), false)
+        .await
+        .unwrap_err();
+    assert_eq!(ObjectStoreError::Exists, returned_err);
+
+    store
+        .put_object("artifacts/empty", vec![], false)
+        .await
+        .unwrap();
+
+    let objects = store.list_objects("artifacts/").await.unwrap();
+    let keys: Vec<String> = objects.into_iter().map(|object| object.key).collect();
+    assert_eq!(
+        vec![
+            "artifacts/empty".to_string(),
+            "artifacts/large".to_string(),
+            "artifacts/small".to_string()
+        ],
+        keys
+    );
+
+    // Overwriting an object replaces its content entirely.
+    store
+        .put_object("artifacts/large", b"replaced".to_vec(), true)
+        .await
+        .unwrap();
+    let returned_value = store.get_object("artifacts/large").await.unwrap();
+    assert_eq!(b"replaced".to_vec(), returned_value);
+    let returned_metadata = store.get_object_metadata("artifacts/large").await.unwrap();
+    assert_eq!(8, returned_metadata.size);
+
+    store.delete_object("artifacts/large").await.unwrap();
+    let returned_err = store
+        .get_object_stream("artifacts/large")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(ObjectStoreError::NotFound, returned_err);
+    assert_eq!(2, store.list_objects("artifacts/").await.unwrap().len());
+}
 
 #[tokio::test]
 /// Basic CRUD can be accomplished for the embedded object store.
 async fn crud_object_store_embedded() {
-
     let mut rng = rand::thread_rng();
     let append_num: u8 = rng.gen();
     let path = format!("/tmp/gofer_tests_embedded_object_store{}", append_num);
-    defer!{std::fs::remove_dir_all(&path).unwrap();};
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
 
     let store = super::embedded::Engine::new(&path).await.unwrap();
 
-    let test_key = "test_key";
-    let test_value = "test_value".as_bytes();
+    crud_object_store(&store).await;
+}
 
-    store.put_object(test_key, test_value.to_vec(), false).await.unwrap();
+/// Sled's background threads can keep the file lock for a moment after a database is dropped, so opening the
+/// engine is retried for a few seconds while the lock is still taken. Any other error fails right away.
+async fn reopen_embedded(path: &str) -> super::embedded::Engine {
+    for _ in 0..500 {
+        match super::embedded::Engine::new(path).await {
+            Ok(store) => return store,
+            Err(ObjectStoreError::FailedInitPrecondition(e))
+                if e.contains("could not acquire lock") =>
+            {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await
+            }
+            Err(e) => panic!("could not reopen embedded object store; {}", e),
+        }
+    }
 
-    let returned_value = store.get_object(test_key).await.unwrap();
-    assert_eq!(test_value, returned_value);
+    panic!("could not reopen embedded object store; the database is still locked");
+}
 
-    store.delete_object(test_key).await.unwrap();
+#[tokio::test]
+/// Objects written by older versions of the embedded object store, which kept each object whole in the default
+/// tree, are migrated on startup so they can still be read, listed and removed.
+async fn legacy_layout_object_store_embedded() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let path = format!(
+        "/tmp/gofer_tests_embedded_object_store_legacy{}",
+        append_num
+    );
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
 
-    let returned_err = store.get_object(test_key).await.unwrap_err();
-    assert_eq!(super::ObjectStoreError::NotFound, returned_err);
+    let legacy_value: Vec<u8> = (0..(CHUNK_SIZE + 100)).map(|i| (i % 251) as u8).collect();
+
+    {
+        let db = sled::open(&path).unwrap();
+        db.insert("pipeline_key", legacy_value.clone()).unwrap();
+        db.insert("run_key", "run_value".as_bytes()).unwrap();
+        db.flush().unwrap();
+    }
+
+    let store = reopen_embedded(&path).await;
+
+    assert_eq!(
+        legacy_value,
+        store.get_object("pipeline_key").await.unwrap()
+    );
+    assert_eq!(
+        "run_value".as_bytes(),
+        store.get_object("run_key").await.unwrap()
+    );
+
+    let metadata = store.get_object_metadata("pipeline_key").await.unwrap();
+    assert_eq!(legacy_value.len() as u64, metadata.size);
+    assert_eq!(2, store.list_objects("").await.unwrap().len());
+
+    store.delete_object("run_key").await.unwrap();
+    drop(store);
+
+    // The objects were moved rather than copied, so they don't come back when the store is opened again.
+    let store = reopen_embedded(&path).await;
+    assert_eq!(
+        ObjectStoreError::NotFound,
+        store.get_object("run_key").await.unwrap_err()
+    );
+    assert_eq!(
+        legacy_value,
+        store.get_object("pipeline_key").await.unwrap()
+    );
+}
+
+#[tokio::test]
+/// Large objects are split into chunks and can be streamed in and out of the embedded object store.
+async fn stream_object_store_embedded() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let path = format!(
+        "/tmp/gofer_tests_embedded_object_store_stream{}",
+        append_num
+    );
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
+
+    let store = super::embedded::Engine::new(&path).await.unwrap();
+
+    stream_object_store(&store).await;
+}
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for the filesystem object store.
+async fn crud_object_store_filesystem() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let path = format!("/tmp/gofer_tests_filesystem_object_store{}", append_num);
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
+
+    let store = super::filesystem::Engine::new(&path).await.unwrap();
+
+    crud_object_store(&store).await;
+}
+
+#[tokio::test]
+/// Large objects can be streamed in and out of the filesystem object store.
+async fn stream_object_store_filesystem() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let path = format!(
+        "/tmp/gofer_tests_filesystem_object_store_stream{}",
+        append_num
+    );
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
+
+    let store = super::filesystem::Engine::new(&path).await.unwrap();
+
+    stream_object_store(&store).await;
+
+    // Objects are stored as plain files named after their keys.
+    let metadata = std::fs::metadata(format!("{}/objects/artifacts/small", path)).unwrap();
+    assert!(metadata.is_file());
+    let content = std::fs::read(format!("{}/objects/other", path)).unwrap();
+    assert_eq!(b"other".to_vec(), content);
+}
+
+#[tokio::test]
+/// Keys that would point outside of the filesystem object store are rejected.
+async fn path_traversal_object_store_filesystem() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let path = format!(
+        "/tmp/gofer_tests_filesystem_object_store_traversal{}",
+        append_num
+    );
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
+
+    let store = super::filesystem::Engine::new(&path).await.unwrap();
+
+    for key in [
+        "",
+        "../escape",
+        "/etc/passwd",
+        "nested/../../escape",
+        "./here",
+        "double//slash",
+        "trailing/",
+    ] {
+        let returned_err = store
+            .put_object(key, b"value".to_vec(), false)
+            .await
+            .unwrap_err();
+        assert_eq!(ObjectStoreError::FailedPrecondition, returned_err);
+
+        let returned_err = store.get_object(key).await.unwrap_err();
+        assert_eq!(ObjectStoreError::FailedPrecondition, returned_err);
+
+        let returned_err = store.delete_object(key).await.unwrap_err();
+        assert_eq!(ObjectStoreError::FailedPrecondition, returned_err);
+    }
+
+    // Listing with a prefix that looks like traversal just finds nothing.
+    assert!(store.list_objects("../").await.unwrap().is_empty());
+}
+
+/// The maximum amount of keys the fake S3 service returns per listing, kept small so paging is exercised.
+const FAKE_S3_MAX_KEYS: usize = 2;
+
+/// An in-memory stand-in for an S3 compatible service like MinIO, so the S3 engine can be tested without
+/// one. It only understands the requests the engine makes and doesn't verify signatures; the ignored tests
+/// against a real service below cover those.
+#[derive(Debug, Default)]
+struct FakeS3 {
+    objects: BTreeMap<String, Vec<u8>>,
+    uploads: HashMap<String, BTreeMap<u32, Vec<u8>>>,
+}
+
+async fn fake_s3_handler(
+    Extension(state): Extension<Arc<Mutex<FakeS3>>>,
+    method: Method,
+    uri: Uri,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> axum::response::Response {
+    let mut state = state.lock().unwrap();
+
+    if !headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .starts_with("AWS4-HMAC-SHA256 Credential=")
+    {
+        return fake_server::response(StatusCode::FORBIDDEN, &[], vec![]);
+    }
+
+    let exists_condition = headers.get("if-none-match").is_some();
+
+    // Requests are path style; the first segment is the bucket and the rest is the key.
+    let key = match uri.path().trim_start_matches('/').split_once('/') {
+        Some((_, key)) => key.to_string(),
+        None => {
+            let prefix = query.get("prefix").cloned().unwrap_or_default();
+            let start_after = query.get("continuation-token").cloned().unwrap_or_default();
+
+            let keys: Vec<&String> = state
+                .objects
+                .keys()
+                .filter(|key| key.starts_with(&prefix) && **key > start_after)
+                .collect();
+            let truncated = keys.len() > FAKE_S3_MAX_KEYS;
+            let keys = &keys[..keys.len().min(FAKE_S3_MAX_KEYS)];
+
+            let mut result = format!("<ListBucketResult><IsTruncated>{}</IsTruncated>", truncated);
+            for key in keys {
+                result.push_str(&format!("<Contents><Key>{}</Key></Contents>", key));
+            }
+            if truncated {
+                result.push_str(&format!(
+                    "<NextContinuationToken>{}</NextContinuationToken>",
+                    keys.last().unwrap()
+                ));
+            }
+            result.push_str("</ListBucketResult>");
+
+            return fake_server::response(StatusCode::OK, &[], result.into_bytes());
+        }
+    };
+
+    match method {
+        Method::GET => match state.objects.get(&key) {
+            Some(object) => fake_server::response(StatusCode::OK, &[], object.clone()),
+            None => fake_server::response(StatusCode::NOT_FOUND, &[], vec![]),
+        },
+        Method::HEAD => match state.objects.get(&key) {
+            Some(_) => fake_server::response(StatusCode::OK, &[], vec![]),
+            None => fake_server::response(StatusCode::NOT_FOUND, &[], vec![]),
+        },
+        Method::PUT => {
+            if let (Some(part_number), Some(upload_id)) =
+                (query.get("partNumber"), query.get("uploadId"))
+            {
+                let part_number: u32 = part_number.parse().unwrap();
+                state
+                    .uploads
+                    .get_mut(upload_id)
+                    .unwrap()
+                    .insert(part_number, body.to_vec());
+                let etag = format!("\"{}\"", part_number);
+                return fake_server::response(StatusCode::OK, &[("etag", &etag)], vec![]);
+            }
+
+            if exists_condition && state.objects.contains_key(&key) {
+                return fake_server::response(StatusCode::PRECONDITION_FAILED, &[], vec![]);
+            }
+
+            state.objects.insert(key, body.to_vec());
+            fake_server::response(StatusCode::OK, &[], vec![])
+        }
+        Method::POST if query.contains_key("uploads") => {
+            let upload_id = nanoid::nanoid!();
+            state.uploads.insert(upload_id.clone(), BTreeMap::new());
+            let result = format!(
+                "<InitiateMultipartUploadResult><Key>{}</Key><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                key, upload_id
+            );
+            fake_server::response(StatusCode::OK, &[], result.into_bytes())
+        }
+        Method::POST => {
+            let upload_id = query.get("uploadId").unwrap();
+
+            if exists_condition && state.objects.contains_key(&key) {
+                return fake_server::response(StatusCode::PRECONDITION_FAILED, &[], vec![]);
+            }
+
+            let parts = state.uploads.remove(upload_id).unwrap();
+            state
+                .objects
+                .insert(key.clone(), parts.into_values().flatten().collect());
+
+            let result = format!(
+                "<CompleteMultipartUploadResult><Key>{}</Key></CompleteMultipartUploadResult>",
+                key
+            );
+            fake_server::response(StatusCode::OK, &[], result.into_bytes())
+        }
+        Method::DELETE => {
+            match query.get("uploadId") {
+                Some(upload_id) => {
+                    state.uploads.remove(upload_id);
+                }
+                None => {
+                    state.objects.remove(&key);
+                }
+            };
+            fake_server::response(StatusCode::NO_CONTENT, &[], vec![])
+        }
+        _ => fake_server::response(StatusCode::METHOD_NOT_ALLOWED, &[], vec![]),
+    }
+}
+
+/// Starts a fake S3 service on a random port and returns an S3 engine configured to use it.
+async fn new_fake_s3_store() -> super::s3::Engine {
+    let address = fake_server::start(fake_s3_handler, Arc::new(Mutex::new(FakeS3::default())));
+
+    super::s3::Engine::new(&conf::api::S3ObjectStore {
+        bucket: "gofer".to_string(),
+        prefix: "tests/".to_string(),
+        region: "us-east-1".to_string(),
+        endpoint: address,
+        path_style: true,
+        access_key_id: "access_key_id".to_string(),
+        secret_access_key: "secret_access_key".to_string(),
+    })
+    .await
+    .unwrap()
+}
+
+/// Connects to a real S3 compatible service. By default this expects a local MinIO started with
+/// `minio server <dir>` and a bucket named "gofer" created in it; set GOFER_TEST_S3_ENDPOINT,
+/// GOFER_TEST_S3_BUCKET, GOFER_TEST_S3_ACCESS_KEY_ID and GOFER_TEST_S3_SECRET_ACCESS_KEY to use another one.
+/// Every store gets its own prefix so runs don't see each other's objects.
+async fn new_real_s3_store(secret_access_key: Option<&str>) -> super::s3::Engine {
+    super::s3::Engine::new(&conf::api::S3ObjectStore {
+        bucket: fake_server::env_or("GOFER_TEST_S3_BUCKET", "gofer"),
+        prefix: format!("gofer_tests/{}/", nanoid::nanoid!()),
+        region: "us-east-1".to_string(),
+        endpoint: fake_server::env_or("GOFER_TEST_S3_ENDPOINT", "http://127.0.0.1:9000"),
+        path_style: true,
+        access_key_id: fake_server::env_or("GOFER_TEST_S3_ACCESS_KEY_ID", "minioadmin"),
+        secret_access_key: match secret_access_key {
+            Some(secret_access_key) => secret_access_key.to_string(),
+            None => fake_server::env_or("GOFER_TEST_S3_SECRET_ACCESS_KEY", "minioadmin"),
+        },
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for the S3 object store.
+async fn crud_object_store_s3() {
+    let store = new_fake_s3_store().await;
+
+    crud_object_store(&store).await;
+}
+
+#[tokio::test]
+/// Large objects are uploaded in parts and can be streamed in and out of the S3 object store.
+async fn stream_object_store_s3() {
+    let store = new_fake_s3_store().await;
+
+    stream_object_store(&store).await;
+}
+
+#[tokio::test]
+#[ignore]
+/// Basic CRUD can be accomplished against a real S3 compatible service.
+async fn crud_object_store_s3_real_service() {
+    let store = new_real_s3_store(None).await;
+
+    crud_object_store(&store).await;
+}
+
+#[tokio::test]
+#[ignore]
+/// Multipart uploads and streaming work against a real S3 compatible service.
+async fn stream_object_store_s3_real_service() {
+    let store = new_real_s3_store(None).await;
+
+    stream_object_store(&store).await;
+}
+
+#[tokio::test]
+#[ignore]
+/// A real S3 compatible service rejects requests that aren't signed with the right secret.
+async fn bad_signature_object_store_s3_real_service() {
+    let store = new_real_s3_store(Some("wrong_secret_access_key")).await;
+
+    let returned_err = store
+        .put_object("test_key", b"test_value".to_vec(), false)
+        .await
+        .unwrap_err();
+    assert!(matches!(returned_err, ObjectStoreError::Unknown(_)));
 }
@@ -10,6 +10,25 @@ fn format_env_var(key: &str, value: &str) -> String {
     return format!("{}={}", key, value);
 }
 
+/// Converts a task's resource amount into the units docker expects by multiplying and then dividing it. Amounts
+/// too large for docker to represent are rejected instead of silently wrapping.
+fn convert_resource(
+    name: &str,
+    amount: u64,
+    multiplier: u64,
+    divisor: u64,
+) -> Result<i64, SchedulerError> {
+    amount
+        .checked_mul(multiplier)
+        .and_then(|value| i64::try_from(value / divisor).ok())
+        .ok_or_else(|| {
+            SchedulerError::FailedContainerPrecondition(format!(
+                "{} of '{}' is too large",
+                name, amount
+            ))
+        })
+}
+
 #[derive(Debug)]
 pub struct Docker {
     client: Arc<bollard::Docker>,
@@ -154,6 +173,37 @@ impl Scheduler for Docker {
             container_config.cmd = Some(req.command);
         }
 
+        let mut host_config = bollard::models::HostConfig::default();
+
+        // Docker has no notion of a hard CPU request, so we translate the request into a relative
+        // cpu share weight instead (1024 shares being equal to a single core).
+        if let Some(cpu_request) = req.cpu_request {
+            host_config.cpu_shares =
+                Some(convert_resource("cpu request", cpu_request, 1024, 1000)?);
+        }
+
+        if let Some(cpu_limit) = req.cpu_limit {
+            host_config.nano_cpus = Some(convert_resource("cpu limit", cpu_limit, 1_000_000, 1)?);
+        }
+
+        if let Some(memory_request) = req.memory_request {
+            host_config.memory_reservation = Some(convert_resource(
+                "memory request",
+                memory_request,
+                1024 * 1024,
+                1,
+            )?);
+        }
+
+        if let Some(memory_limit) = req.memory_limit {
+            host_config.memory = Some(convert_resource(
+                "memory limit",
+                memory_limit,
+                1024 * 1024,
+                1,
+            )?);
+        }
+
         // In order to properly set up a container such that we can talk to it we need several things:
         // 1) We need to expose the port that the container is listening on. We've hardcoded this in the
         // sdk to be tcp/port 8080.
@@ -175,12 +225,11 @@ impl Scheduler for Docker {
             let mut port_bindings = HashMap::new();
             port_bindings.insert("8080/tcp".to_string(), Some(vec![host_port_binding]));
 
-            container_config.host_config = Some(bollard::models::HostConfig {
-                port_bindings: Some(port_bindings),
-                ..Default::default()
-            })
+            host_config.port_bindings = Some(port_bindings);
         }
 
+        container_config.host_config = Some(host_config);
+
         let created_container = self
             .client
             .create_container(
@@ -299,3 +348,34 @@ impl Scheduler for Docker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Resources are converted into docker's units.
+    fn convert_resource_units() {
+        assert_eq!(
+            convert_resource("cpu request", 500, 1024, 1000).unwrap(),
+            512
+        );
+        assert_eq!(
+            convert_resource("memory limit", 256, 1024 * 1024, 1).unwrap(),
+            256 * 1024 * 1024
+        );
+    }
+
+    #[test]
+    /// Resources too large for docker are rejected instead of wrapping around.
+    fn convert_resource_overflow() {
+        assert!(matches!(
+            convert_resource("memory limit", u64::MAX, 1024 * 1024, 1),
+            Err(SchedulerError::FailedContainerPrecondition(_))
+        ));
+        assert!(matches!(
+            convert_resource("cpu limit", u64::MAX / 1_000_000, 1_000_000, 1),
+            Err(SchedulerError::FailedContainerPrecondition(_))
+        ));
+    }
+}
@@ -0,0 +1,815 @@
+use super::*;
+use async_trait::async_trait;
+use futures::StreamExt;
+use hyper::{client::HttpConnector, Body, Client, Method, Request, Response, StatusCode};
+use hyper_rustls::HttpsConnector;
+use serde_json::json;
+use slog_scope::debug;
+use std::time::Duration;
+
+/// Where Kubernetes mounts the credentials of the service account a pod runs as.
+const SERVICE_ACCOUNT_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// The address of the API server from inside the cluster.
+const IN_CLUSTER_API_SERVER: &str = "https://kubernetes.default.svc";
+
+/// Every pod runs a single container under this name.
+const CONTAINER_NAME: &str = "gofer";
+
+/// Trigger containers listen on this port; it is hardcoded in the sdk.
+const TRIGGER_PORT: u16 = 8080;
+
+/// Resource names are DNS labels, which are limited to 63 characters. We leave room for the suffixes of
+/// the secrets created alongside each pod.
+const MAX_NAME_LENGTH: usize = 53;
+
+/// Deleting a pod takes a while, so recreating one under the same name has to wait for it. This is how many
+/// times (a second apart) we try before giving up.
+const CREATE_ATTEMPTS: u64 = 60;
+
+const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+const CONTAINER_LABEL: &str = "gofer.dev/container";
+const CONTAINER_NAME_ANNOTATION: &str = "gofer.dev/container-name";
+
+/// Waiting reasons that mean the container is never going to start on its own.
+const FAILED_IMAGE_REASONS: [&str; 3] = ["ErrImagePull", "ImagePullBackOff", "InvalidImageName"];
+const FAILED_CONFIG_REASONS: [&str; 2] = ["CreateContainerConfigError", "CreateContainerError"];
+
+#[derive(Debug)]
+enum Auth {
+    None,
+    Token(String),
+    /// Kubernetes rotates service account tokens, so the token file is read again for every request.
+    ServiceAccount,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    #[serde(rename = "gitVersion", default)]
+    git_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiStatus {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pod {
+    metadata: PodMetadata,
+    #[serde(default)]
+    status: PodStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodMetadata {
+    #[serde(default)]
+    uid: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodStatus {
+    #[serde(default)]
+    phase: String,
+    #[serde(default)]
+    container_statuses: Vec<ContainerStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerStatus {
+    name: String,
+    #[serde(default)]
+    state: ContainerStatusState,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ContainerStatusState {
+    waiting: Option<ContainerStateWaiting>,
+    terminated: Option<ContainerStateTerminated>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerStateWaiting {
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContainerStateTerminated {
+    exit_code: i32,
+}
+
+impl Pod {
+    fn container_state(&self) -> Option<&ContainerStatusState> {
+        self.status
+            .container_statuses
+            .iter()
+            .find(|status| status.name == CONTAINER_NAME)
+            .map(|status| &status.state)
+    }
+
+    /// Returns an error if the pod's container is stuck waiting on something that won't resolve by itself,
+    /// like an image that can't be pulled.
+    fn check_waiting(&self) -> Result<(), SchedulerError> {
+        if let Some(waiting) = self
+            .container_state()
+            .and_then(|state| state.waiting.as_ref())
+        {
+            if FAILED_IMAGE_REASONS.contains(&waiting.reason.as_str()) {
+                return Err(SchedulerError::NoSuchImage(format!(
+                    "{}; {}",
+                    waiting.reason, waiting.message
+                )));
+            }
+
+            if FAILED_CONFIG_REASONS.contains(&waiting.reason.as_str()) {
+                return Err(SchedulerError::FailedContainerPrecondition(format!(
+                    "{}; {}",
+                    waiting.reason, waiting.message
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Turns a container name into a valid Kubernetes resource name. Names that had to be changed get a hash
+/// of the original appended so that different containers can't end up with the same name.
+pub(super) fn resource_name(name: &str) -> String {
+    let sanitized: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let sanitized = sanitized.trim_matches('-');
+
+    if sanitized == name && sanitized.len() <= MAX_NAME_LENGTH {
+        return sanitized.to_string();
+    }
+
+    let digest = orion::hash::digest(name.as_bytes()).expect("could not hash container name");
+    let hash: String = digest.as_ref()[..4]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let mut truncated = sanitized.to_string();
+    truncated.truncate(MAX_NAME_LENGTH - hash.len() - 1);
+
+    format!("{}-{}", truncated, hash)
+}
+
+/// Returns the registry an image is pulled from, in the form image pull secrets expect it.
+fn image_registry(image: &str) -> String {
+    match image.split_once('/') {
+        Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+            host.to_string()
+        }
+        _ => "https://index.docker.io/v1/".to_string(),
+    }
+}
+
+fn unexpected_response(status: StatusCode, body: &[u8]) -> SchedulerError {
+    let message = serde_json::from_slice::<ApiStatus>(body)
+        .map(|status| status.message)
+        .unwrap_or_else(|_| String::from_utf8_lossy(body).to_string());
+
+    SchedulerError::Unknown(format!("unexpected response {}; {}", status, message))
+}
+
+fn tls_config(ca_cert_path: &str) -> Result<rustls::ClientConfig, SchedulerError> {
+    let ca_cert = std::fs::read(ca_cert_path).map_err(|e| {
+        SchedulerError::FailedSchedulerPrecondition(format!(
+            "could not read CA certificate '{}'; {}",
+            ca_cert_path, e
+        ))
+    })?;
+
+    let certs = rustls_pemfile::certs(&mut ca_cert.as_slice()).map_err(|e| {
+        SchedulerError::FailedSchedulerPrecondition(format!(
+            "could not parse CA certificate '{}'; {}",
+            ca_cert_path, e
+        ))
+    })?;
+
+    let mut root_store = rustls::RootCertStore::empty();
+    let (added, _) = root_store.add_parsable_certificates(&certs);
+    if added == 0 {
+        return Err(SchedulerError::FailedSchedulerPrecondition(format!(
+            "no valid certificates found in '{}'",
+            ca_cert_path
+        )));
+    }
+
+    Ok(rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth())
+}
+
+/// Talks to the Kubernetes API. Kept separate from the scheduler so log streams can hold onto it.
+#[derive(Debug)]
+struct ApiClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+    api_server: String,
+    namespace: String,
+    auth: Auth,
+}
+
+impl ApiClient {
+    fn path(&self, resource: &str, name: Option<&str>) -> String {
+        match name {
+            Some(name) => format!(
+                "/api/v1/namespaces/{}/{}/{}",
+                self.namespace, resource, name
+            ),
+            None => format!("/api/v1/namespaces/{}/{}", self.namespace, resource),
+        }
+    }
+
+    /// Makes a request to the Kubernetes API, returning the response with its body unread.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        content_type: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<Response<Body>, SchedulerError> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("{}{}", self.api_server, path))
+            .header("content-type", content_type)
+            .header("accept", "application/json");
+
+        let token = match &self.auth {
+            Auth::None => None,
+            Auth::Token(token) => Some(token.clone()),
+            Auth::ServiceAccount => Some(
+                tokio::fs::read_to_string(format!("{}/token", SERVICE_ACCOUNT_PATH))
+                    .await
+                    .map_err(|e| SchedulerError::Connection(e.to_string()))?
+                    .trim()
+                    .to_string(),
+            ),
+        };
+
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+
+        let body = match body {
+            Some(body) => Body::from(body.to_string()),
+            None => Body::empty(),
+        };
+
+        let request = request
+            .body(body)
+            .map_err(|e| SchedulerError::Unknown(e.to_string()))?;
+
+        self.client
+            .request(request)
+            .await
+            .map_err(|e| SchedulerError::Connection(e.to_string()))
+    }
+
+    /// Makes a request to the Kubernetes API, returning the response status and body.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<(StatusCode, Vec<u8>), SchedulerError> {
+        let response = self.request(method, path, "application/json", body).await?;
+
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| SchedulerError::Connection(e.to_string()))?;
+
+        Ok((status, body.to_vec()))
+    }
+
+    /// Creates a resource. If one by the same name is still being deleted we wait for it to be gone first.
+    async fn create(
+        &self,
+        resource: &str,
+        body: serde_json::Value,
+    ) -> Result<Vec<u8>, SchedulerError> {
+        for _ in 0..CREATE_ATTEMPTS {
+            let (status, response_body) = self
+                .send(Method::POST, &self.path(resource, None), Some(body.clone()))
+                .await?;
+
+            match status {
+                status if status.is_success() => return Ok(response_body),
+                StatusCode::CONFLICT => {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+                StatusCode::UNPROCESSABLE_ENTITY => {
+                    return Err(SchedulerError::FailedContainerPrecondition(
+                        unexpected_response(status, &response_body).to_string(),
+                    ))
+                }
+                status => return Err(unexpected_response(status, &response_body)),
+            }
+        }
+
+        Err(SchedulerError::Unknown(format!(
+            "timed out waiting for previous {} to be deleted",
+            resource
+        )))
+    }
+
+    /// Deletes a resource immediately. Resources that don't exist are ignored.
+    async fn delete(&self, resource: &str, name: &str) -> Result<(), SchedulerError> {
+        let (status, body) = self
+            .send(
+                Method::DELETE,
+                &self.path(resource, Some(name)),
+                Some(json!({ "gracePeriodSeconds": 0, "propagationPolicy": "Background" })),
+            )
+            .await?;
+
+        match status {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Ok(()),
+            status => Err(unexpected_response(status, &body)),
+        }
+    }
+
+    /// Applies a JSON merge patch to a resource.
+    async fn patch(
+        &self,
+        resource: &str,
+        name: &str,
+        patch: serde_json::Value,
+    ) -> Result<(), SchedulerError> {
+        let response = self
+            .request(
+                Method::PATCH,
+                &self.path(resource, Some(name)),
+                "application/merge-patch+json",
+                Some(patch),
+            )
+            .await?;
+
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| SchedulerError::Connection(e.to_string()))?;
+
+        match status {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Err(SchedulerError::NoSuchContainer(name.to_string())),
+            status => Err(unexpected_response(status, &body)),
+        }
+    }
+
+    async fn get_pod(&self, name: &str) -> Result<Pod, SchedulerError> {
+        let (status, body) = self
+            .send(Method::GET, &self.path("pods", Some(name)), None)
+            .await?;
+
+        match status {
+            StatusCode::OK => {
+                serde_json::from_slice(&body).map_err(|e| SchedulerError::Unknown(e.to_string()))
+            }
+            StatusCode::NOT_FOUND => Err(SchedulerError::NoSuchContainer(name.to_string())),
+            status => Err(unexpected_response(status, &body)),
+        }
+    }
+
+    /// Logs can only be read once the container has started, so we wait until the pod is past pending.
+    async fn wait_for_start(&self, name: &str) -> Result<(), SchedulerError> {
+        loop {
+            let pod = self.get_pod(name).await?;
+
+            if pod.status.phase != "Pending" {
+                return Ok(());
+            }
+
+            pod.check_waiting()?;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
+
+/// Runs containers as pods within a single Kubernetes namespace. Each pod gets a Secret holding its
+/// environment variables and, if needed, another holding its registry credentials. Trigger containers are
+/// also given a Service so Gofer can reach them.
+///
+/// Gofer is expected to run inside the cluster so it can reach trigger services by their cluster DNS name.
+#[derive(Debug)]
+pub struct Kubernetes {
+    client: Arc<ApiClient>,
+}
+
+impl Kubernetes {
+    pub async fn new(config: &conf::api::KubernetesScheduler) -> Result<Self, SchedulerError> {
+        let in_cluster = config.api_server.is_empty();
+
+        let api_server = if in_cluster {
+            IN_CLUSTER_API_SERVER.to_string()
+        } else {
+            config.api_server.trim_end_matches('/').to_string()
+        };
+
+        let service_account_token = format!("{}/token", SERVICE_ACCOUNT_PATH);
+        let auth = if !config.token.is_empty() {
+            Auth::Token(config.token.clone())
+        } else if std::path::Path::new(&service_account_token).exists() {
+            Auth::ServiceAccount
+        } else {
+            Auth::None
+        };
+
+        let namespace = if !config.namespace.is_empty() {
+            config.namespace.clone()
+        } else {
+            std::fs::read_to_string(format!("{}/namespace", SERVICE_ACCOUNT_PATH))
+                .map(|namespace| namespace.trim().to_string())
+                .unwrap_or_else(|_| "default".to_string())
+        };
+
+        let ca_cert = if !config.ca_cert.is_empty() {
+            Some(config.ca_cert.clone())
+        } else if in_cluster {
+            Some(format!("{}/ca.crt", SERVICE_ACCOUNT_PATH))
+        } else {
+            None
+        };
+
+        let connector = match ca_cert {
+            Some(ca_cert) => hyper_rustls::HttpsConnectorBuilder::new()
+                .with_tls_config(tls_config(&ca_cert)?)
+                .https_or_http()
+                .enable_http1()
+                .build(),
+            None => hyper_rustls::HttpsConnectorBuilder::new()
+                .with_native_roots()
+                .https_or_http()
+                .enable_http1()
+                .build(),
+        };
+
+        let client = ApiClient {
+            client: Client::builder().build(connector),
+            api_server,
+            namespace,
+            auth,
+        };
+
+        // Check that we can actually get a connection.
+        let (status, body) = client.send(Method::GET, "/version", None).await?;
+        if !status.is_success() {
+            return Err(SchedulerError::Connection(
+                unexpected_response(status, &body).to_string(),
+            ));
+        }
+
+        let version: Version =
+            serde_json::from_slice(&body).map_err(|e| SchedulerError::Connection(e.to_string()))?;
+
+        debug!("Kubernetes scheduler successfully connected"; "version" => version.git_version,
+            "namespace" => &client.namespace);
+
+        Ok(Self {
+            client: Arc::new(client),
+        })
+    }
+
+    /// Removes everything left over from a previous container of the same name.
+    async fn remove_container(&self, name: &str) -> Result<(), SchedulerError> {
+        self.client.delete("pods", name).await?;
+        self.client.delete("services", name).await?;
+        self.client
+            .delete("secrets", &format!("{}-env", name))
+            .await?;
+        self.client
+            .delete("secrets", &format!("{}-registry", name))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Scheduler for Kubernetes {
+    async fn start_container(
+        &self,
+        req: StartContainerRequest,
+    ) -> Result<StartContainerResponse, SchedulerError> {
+        let name = resource_name(&req.name);
+
+        if let Err(e) = self.remove_container(&name).await {
+            debug!("could not remove previous container"; "name" => &req.name, "error" => e.to_string());
+        }
+
+        let labels = json!({
+            MANAGED_BY_LABEL: "gofer",
+            CONTAINER_LABEL: &name,
+        });
+
+        let mut secrets = vec![];
+
+        // Variables often hold secrets, so rather than putting them in the pod spec for anyone who can read
+        // pods to see, they're kept in a Secret the container reads them from.
+        let mut env = vec![];
+        if !req.variables.is_empty() {
+            let env_secret = format!("{}-env", name);
+            let mut data = serde_json::Map::new();
+
+            for (key, value) in &req.variables {
+                data.insert(key.clone(), json!(base64::encode(value)));
+                env.push(json!({
+                    "name": key,
+                    "valueFrom": { "secretKeyRef": { "name": &env_secret, "key": key } },
+                }));
+            }
+
+            self.client
+                .create(
+                    "secrets",
+                    json!({
+                        "apiVersion": "v1",
+                        "kind": "Secret",
+                        "metadata": { "name": &env_secret, "labels": &labels },
+                        "type": "Opaque",
+                        "data": data,
+                    }),
+                )
+                .await?;
+
+            secrets.push(env_secret);
+        }
+
+        let mut image_pull_secrets = vec![];
+        if let Some(registry_auth) = &req.registry_auth {
+            let registry_secret = format!("{}-registry", name);
+            let docker_config = json!({
+                "auths": {
+                    image_registry(&req.image): {
+                        "username": &registry_auth.user,
+                        "password": &registry_auth.pass,
+                        "auth": base64::encode(format!("{}:{}", registry_auth.user, registry_auth.pass)),
+                    }
+                }
+            });
+
+            self.client
+                .create(
+                    "secrets",
+                    json!({
+                        "apiVersion": "v1",
+                        "kind": "Secret",
+                        "metadata": { "name": &registry_secret, "labels": &labels },
+                        "type": "kubernetes.io/dockerconfigjson",
+                        "data": { ".dockerconfigjson": base64::encode(docker_config.to_string()) },
+                    }),
+                )
+                .await?;
+
+            image_pull_secrets.push(json!({ "name": &registry_secret }));
+            secrets.push(registry_secret);
+        }
+
+        let mut requests = serde_json::Map::new();
+        let mut limits = serde_json::Map::new();
+
+        if let Some(cpu_request) = req.cpu_request {
+            requests.insert("cpu".into(), json!(format!("{}m", cpu_request)));
+        }
+
+        if let Some(cpu_limit) = req.cpu_limit {
+            limits.insert("cpu".into(), json!(format!("{}m", cpu_limit)));
+        }
+
+        if let Some(memory_request) = req.memory_request {
+            requests.insert("memory".into(), json!(format!("{}Mi", memory_request)));
+        }
+
+        if let Some(memory_limit) = req.memory_limit {
+            limits.insert("memory".into(), json!(format!("{}Mi", memory_limit)));
+        }
+
+        let mut container = json!({
+            "name": CONTAINER_NAME,
+            "image": &req.image,
+            "imagePullPolicy": if req.always_pull { "Always" } else { "IfNotPresent" },
+            "env": env,
+            "resources": { "requests": requests, "limits": limits },
+        });
+
+        // Kubernetes calls the entrypoint the command and the command the args.
+        if !req.entrypoint.is_empty() {
+            container["command"] = json!(req.entrypoint);
+        }
+
+        if !req.command.is_empty() {
+            container["args"] = json!(req.command);
+        }
+
+        if req.enable_networking {
+            container["ports"] = json!([{ "containerPort": TRIGGER_PORT, "protocol": "TCP" }]);
+        }
+
+        let pod = self
+            .client
+            .create(
+                "pods",
+                json!({
+                    "apiVersion": "v1",
+                    "kind": "Pod",
+                    "metadata": {
+                        "name": &name,
+                        "labels": &labels,
+                        "annotations": { CONTAINER_NAME_ANNOTATION: &req.name },
+                    },
+                    "spec": {
+                        "restartPolicy": "Never",
+                        "containers": [container],
+                        "imagePullSecrets": image_pull_secrets,
+                    },
+                }),
+            )
+            .await?;
+
+        let pod: Pod =
+            serde_json::from_slice(&pod).map_err(|e| SchedulerError::Unknown(e.to_string()))?;
+
+        // Resources owned by the pod are cleaned up by Kubernetes once the pod is deleted.
+        let owner_references = json!([{
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "name": &name,
+            "uid": &pod.metadata.uid,
+        }]);
+
+        for secret in secrets {
+            if let Err(e) = self
+                .client
+                .patch(
+                    "secrets",
+                    &secret,
+                    json!({ "metadata": { "ownerReferences": &owner_references } }),
+                )
+                .await
+            {
+                debug!("could not set owner of container secret"; "name" => &req.name, "secret" => secret,
+                    "error" => e.to_string());
+            }
+        }
+
+        let mut response = StartContainerResponse {
+            scheduler_id: Some(pod.metadata.uid.clone()),
+            url: None,
+        };
+
+        if req.enable_networking {
+            self.client
+                .create(
+                    "services",
+                    json!({
+                        "apiVersion": "v1",
+                        "kind": "Service",
+                        "metadata": {
+                            "name": &name,
+                            "labels": &labels,
+                            "ownerReferences": &owner_references,
+                        },
+                        "spec": {
+                            "selector": { CONTAINER_LABEL: &name },
+                            "ports": [{ "port": TRIGGER_PORT, "targetPort": TRIGGER_PORT, "protocol": "TCP" }],
+                        },
+                    }),
+                )
+                .await?;
+
+            response.url = Some(format!(
+                "https://{}.{}.svc:{}",
+                name, self.client.namespace, TRIGGER_PORT
+            ));
+        }
+
+        Ok(response)
+    }
+
+    /// Kubernetes can't stop a pod without deleting it, so instead we set the pod's deadline to have already
+    /// passed. The container is stopped but the pod sticks around so its final state can still be read.
+    ///
+    /// The grace period given to the container is the pod's own termination grace period; the timeout passed
+    /// in can't be applied to a pod that's already running.
+    async fn stop_container(&self, req: StopContainerRequest) -> Result<(), SchedulerError> {
+        self.client
+            .patch(
+                "pods",
+                &resource_name(&req.name),
+                json!({ "spec": { "activeDeadlineSeconds": 1 } }),
+            )
+            .await
+    }
+
+    fn get_logs(
+        &self,
+        req: GetLogsRequest,
+    ) -> Pin<Box<dyn Stream<Item = Result<Log, SchedulerError>> + Send>> {
+        let client = self.client.clone();
+        let name = resource_name(&req.name);
+
+        let stream = async_stream::stream! {
+            if let Err(e) = client.wait_for_start(&name).await {
+                yield Err(e);
+                return;
+            }
+
+            let path = format!(
+                "{}/log?follow=true&container={}",
+                client.path("pods", Some(&name)),
+                CONTAINER_NAME
+            );
+
+            let response = match client.request(Method::GET, &path, "application/json", None).await {
+                Ok(response) => response,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+                yield Err(match status {
+                    StatusCode::NOT_FOUND => SchedulerError::NoSuchContainer(req.name.clone()),
+                    status => unexpected_response(status, &body),
+                });
+                return;
+            }
+
+            // Kubernetes doesn't separate stdout from stderr, so everything is treated as stdout. The log
+            // arrives in arbitrary chunks which we split back into lines.
+            let mut body = response.into_body();
+            let mut buffer: Vec<u8> = vec![];
+
+            while let Some(chunk) = body.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(SchedulerError::Connection(e.to_string()));
+                        return;
+                    }
+                };
+
+                buffer.extend_from_slice(&chunk);
+
+                while let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=position).collect();
+                    yield Ok(Log::Stdout(bytes::Bytes::from(line)));
+                }
+            }
+
+            if !buffer.is_empty() {
+                yield Ok(Log::Stdout(bytes::Bytes::from(buffer)));
+            }
+        };
+
+        Box::pin(stream)
+    }
+
+    async fn get_state(&self, req: GetStateRequest) -> Result<GetStateResponse, SchedulerError> {
+        let pod = self.client.get_pod(&resource_name(&req.name)).await?;
+
+        match pod.status.phase.as_str() {
+            "Pending" => {
+                pod.check_waiting()?;
+
+                Ok(GetStateResponse {
+                    exit_code: None,
+                    state: ContainerState::Running,
+                })
+            }
+            "Running" => Ok(GetStateResponse {
+                exit_code: None,
+                state: ContainerState::Running,
+            }),
+            "Succeeded" | "Failed" => Ok(GetStateResponse {
+                exit_code: pod
+                    .container_state()
+                    .and_then(|state| state.terminated.as_ref())
+                    .and_then(|terminated| u8::try_from(terminated.exit_code).ok()),
+                state: ContainerState::Exited,
+            }),
+            _ => Ok(GetStateResponse {
+                exit_code: None,
+                state: ContainerState::Unknown,
+            }),
+        }
+    }
+}
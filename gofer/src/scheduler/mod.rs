@@ -1,4 +1,5 @@
 mod docker;
+mod kubernetes;
 
 use crate::conf;
 use async_trait::async_trait;
@@ -12,6 +13,9 @@ use std::sync::Arc;
 use std::{collections::HashMap, pin::Pin};
 use strum::{Display, EnumString};
 
+#[cfg(test)]
+mod tests;
+
 /// Represents different scheduler failure possibilities.
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum SchedulerError {
@@ -105,6 +109,14 @@ pub struct StartContainerRequest {
     pub entrypoint: Vec<String>,
     /// Replaces container's cmd instruction with a custom one.
     pub command: Vec<String>,
+    /// The amount of CPU (in millicores) the container expects to use.
+    pub cpu_request: Option<u64>,
+    /// The maximum amount of CPU (in millicores) the container is allowed to use.
+    pub cpu_limit: Option<u64>,
+    /// The amount of memory (in megabytes) the container expects to use.
+    pub memory_request: Option<u64>,
+    /// The maximum amount of memory (in megabytes) the container is allowed to use.
+    pub memory_limit: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -180,6 +192,7 @@ pub trait Scheduler: Debug {
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq, Display, EnumString, LoadEnv)]
 pub enum Engine {
     Docker,
+    Kubernetes,
 }
 
 impl Default for Engine {
@@ -191,7 +204,6 @@ impl Default for Engine {
 pub async fn init_scheduler(
     config: &conf::api::Scheduler,
 ) -> Result<Arc<dyn Scheduler + Send + Sync>, SchedulerError> {
-    #[allow(clippy::match_single_binding)]
     match config.engine {
         Engine::Docker => {
             if let Some(config) = &config.docker {
@@ -203,5 +215,15 @@ pub async fn init_scheduler(
                 ))
             }
         }
+        Engine::Kubernetes => {
+            if let Some(config) = &config.kubernetes {
+                let engine = kubernetes::Kubernetes::new(config).await?;
+                Ok(Arc::new(engine))
+            } else {
+                Err(SchedulerError::FailedSchedulerPrecondition(
+                    "kubernetes engine settings not found in config".into(),
+                ))
+            }
+        }
     }
 }
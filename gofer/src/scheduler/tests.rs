@@ -0,0 +1,599 @@
+use super::kubernetes::{resource_name, Kubernetes};
+use super::*;
+use crate::fake_server;
+use axum::extract::Extension;
+use bytes::Bytes;
+use futures::StreamExt;
+use http::{HeaderMap, Method, StatusCode, Uri};
+use serde_json::json;
+use std::sync::Mutex;
+
+const FAKE_KUBERNETES_TOKEN: &str = "gofer_token";
+const FAKE_KUBERNETES_NAMESPACE: &str = "gofer";
+
+/// An in-memory stand-in for the Kubernetes API server, so the Kubernetes scheduler can be tested without a
+/// cluster. It only understands the requests the scheduler makes. Nothing runs the pods; tests move them
+/// through their lifecycle by editing their status.
+#[derive(Debug, Default)]
+struct FakeKubernetes {
+    pods: HashMap<String, serde_json::Value>,
+    services: HashMap<String, serde_json::Value>,
+    secrets: HashMap<String, serde_json::Value>,
+    logs: HashMap<String, String>,
+}
+
+impl FakeKubernetes {
+    fn resources(&mut self, resource: &str) -> Option<&mut HashMap<String, serde_json::Value>> {
+        match resource {
+            "pods" => Some(&mut self.pods),
+            "services" => Some(&mut self.services),
+            "secrets" => Some(&mut self.secrets),
+            _ => None,
+        }
+    }
+}
+
+fn fake_kubernetes_status(status: StatusCode, message: &str) -> axum::response::Response {
+    fake_server::json_response(
+        status,
+        json!({ "kind": "Status", "status": "Failure", "message": message, "code": status.as_u16() }),
+    )
+}
+
+/// Merges a JSON merge patch (RFC 7386) into a value. Null members remove the field and anything other than an
+/// object, arrays included, replaces the value outright.
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    match patch {
+        serde_json::Value::Object(patch) => {
+            if !target.is_object() {
+                *target = json!({});
+            }
+
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.as_object_mut().unwrap().remove(key);
+                } else {
+                    merge_patch(&mut target[key], value);
+                }
+            }
+        }
+        patch => *target = patch.clone(),
+    }
+}
+
+async fn fake_kubernetes_handler(
+    Extension(state): Extension<Arc<Mutex<FakeKubernetes>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> axum::response::Response {
+    let mut state = state.lock().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+
+    let authorization = headers
+        .get("authorization")
+        .and_then(|authorization| authorization.to_str().ok())
+        .unwrap_or_default();
+    if authorization != format!("Bearer {}", FAKE_KUBERNETES_TOKEN) {
+        return fake_kubernetes_status(StatusCode::UNAUTHORIZED, "Unauthorized");
+    }
+
+    if uri.path() == "/version" {
+        return fake_server::json_response(StatusCode::OK, json!({ "gitVersion": "v1.24.0" }));
+    }
+
+    let path = match uri.path().strip_prefix(&format!(
+        "/api/v1/namespaces/{}/",
+        FAKE_KUBERNETES_NAMESPACE
+    )) {
+        Some(path) => path.to_string(),
+        None => return fake_kubernetes_status(StatusCode::NOT_FOUND, "namespace not found"),
+    };
+    let segments: Vec<&str> = path.split('/').collect();
+
+    if let ["pods", name, "log"] = segments.as_slice() {
+        return match state.logs.get(*name) {
+            Some(logs) => fake_server::response(StatusCode::OK, &[], logs.clone()),
+            None => fake_kubernetes_status(StatusCode::NOT_FOUND, "pod not found"),
+        };
+    }
+
+    let (resource, name) = match segments.as_slice() {
+        [resource] => (resource.to_string(), None),
+        [resource, name] => (resource.to_string(), Some(name.to_string())),
+        _ => return fake_kubernetes_status(StatusCode::NOT_FOUND, "not found"),
+    };
+
+    let resources = match state.resources(&resource) {
+        Some(resources) => resources,
+        None => return fake_kubernetes_status(StatusCode::NOT_FOUND, "not found"),
+    };
+
+    match (method, name) {
+        (Method::POST, None) => {
+            let name = body["metadata"]["name"].as_str().unwrap().to_string();
+            if resources.contains_key(&name) {
+                return fake_kubernetes_status(StatusCode::CONFLICT, "already exists");
+            }
+
+            let mut object = body;
+            object["metadata"]["uid"] = json!(nanoid::nanoid!());
+            if resource == "pods" {
+                object["status"] = json!({ "phase": "Pending" });
+            }
+
+            resources.insert(name, object.clone());
+            fake_server::json_response(StatusCode::CREATED, object)
+        }
+        (Method::GET, Some(name)) => match resources.get(&name) {
+            Some(object) => fake_server::json_response(StatusCode::OK, object.clone()),
+            None => fake_kubernetes_status(StatusCode::NOT_FOUND, "not found"),
+        },
+        (Method::PATCH, Some(name)) => {
+            if headers.get("content-type").map(|value| value.as_bytes())
+                != Some(b"application/merge-patch+json")
+            {
+                return fake_kubernetes_status(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "the body of the request was in an unknown format",
+                );
+            }
+
+            let object = match resources.get_mut(&name) {
+                Some(object) => object,
+                None => return fake_kubernetes_status(StatusCode::NOT_FOUND, "not found"),
+            };
+
+            merge_patch(object, &body);
+
+            // The kubelet kills pods that are past their deadline.
+            if resource == "pods" && body["spec"]["activeDeadlineSeconds"].is_number() {
+                object["status"] = json!({
+                    "phase": "Failed",
+                    "reason": "DeadlineExceeded",
+                    "containerStatuses": [{
+                        "name": "gofer",
+                        "state": { "terminated": { "exitCode": 137 } },
+                    }],
+                });
+            }
+
+            fake_server::json_response(StatusCode::OK, object.clone())
+        }
+        (Method::DELETE, Some(name)) => match resources.remove(&name) {
+            Some(object) => fake_server::json_response(StatusCode::OK, object),
+            None => fake_kubernetes_status(StatusCode::NOT_FOUND, "not found"),
+        },
+        _ => fake_kubernetes_status(StatusCode::METHOD_NOT_ALLOWED, "method not allowed"),
+    }
+}
+
+/// Starts a fake Kubernetes API server on a random port and returns its address along with its state.
+fn start_fake_kubernetes() -> (String, Arc<Mutex<FakeKubernetes>>) {
+    let state = Arc::new(Mutex::new(FakeKubernetes::default()));
+    let address = fake_server::start(fake_kubernetes_handler, state.clone());
+
+    (address, state)
+}
+
+async fn new_kubernetes_scheduler(address: &str) -> Kubernetes {
+    Kubernetes::new(&conf::api::KubernetesScheduler {
+        api_server: address.to_string(),
+        token: FAKE_KUBERNETES_TOKEN.to_string(),
+        namespace: FAKE_KUBERNETES_NAMESPACE.to_string(),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+fn container_request(name: &str) -> StartContainerRequest {
+    StartContainerRequest {
+        name: name.to_string(),
+        image: "registry.example.com/gofer/task:latest".to_string(),
+        variables: HashMap::from([("GOFER_TASK_ID".to_string(), "task".to_string())]),
+        registry_auth: None,
+        always_pull: false,
+        enable_networking: false,
+        entrypoint: vec![],
+        command: vec![],
+        cpu_request: None,
+        cpu_limit: None,
+        memory_request: None,
+        memory_limit: None,
+    }
+}
+
+fn set_pod_status(state: &Mutex<FakeKubernetes>, name: &str, status: serde_json::Value) {
+    state.lock().unwrap().pods.get_mut(name).unwrap()["status"] = status;
+}
+
+#[test]
+/// Container names are turned into valid, unique resource names.
+fn kubernetes_resource_names() {
+    assert_eq!(resource_name("valid-name"), "valid-name");
+
+    let name = resource_name("Namespace_pipeline_1_task_1");
+    assert!(name.starts_with("namespace-pipeline-1-task-1-"));
+    assert_ne!(name, resource_name("namespace_pipeline_1_task_1"));
+
+    let long_name = resource_name(&"a".repeat(100));
+    assert!(long_name.len() <= 53);
+    assert_ne!(long_name, resource_name(&"a".repeat(101)));
+}
+
+#[tokio::test]
+/// Task containers are run as pods and their state and logs can be followed.
+async fn kubernetes_task_container() {
+    let (address, state) = start_fake_kubernetes();
+    let scheduler = new_kubernetes_scheduler(&address).await;
+
+    let mut request = container_request("namespace_pipeline_1_task_1");
+    request.registry_auth = Some(RegistryAuth {
+        user: "user".to_string(),
+        pass: "pass".to_string(),
+    });
+    request.entrypoint = vec!["/bin/sh".to_string()];
+    request.command = vec!["-c".to_string(), "echo hello".to_string()];
+    request.cpu_limit = Some(500);
+    request.memory_request = Some(256);
+
+    let response = scheduler.start_container(request).await.unwrap();
+    assert!(response.scheduler_id.is_some());
+    assert!(response.url.is_none());
+
+    let name = resource_name("namespace_pipeline_1_task_1");
+    {
+        let state = state.lock().unwrap();
+        let pod = &state.pods[&name];
+        let container = &pod["spec"]["containers"][0];
+
+        assert_eq!(container["image"], "registry.example.com/gofer/task:latest");
+        assert_eq!(container["command"], json!(["/bin/sh"]));
+        assert_eq!(container["args"], json!(["-c", "echo hello"]));
+        assert_eq!(container["resources"]["limits"]["cpu"], "500m");
+        assert_eq!(container["resources"]["requests"]["memory"], "256Mi");
+        assert_eq!(
+            pod["metadata"]["annotations"]["gofer.dev/container-name"],
+            "namespace_pipeline_1_task_1"
+        );
+
+        // Variables are read from a secret rather than kept in the pod spec.
+        let env_secret = format!("{}-env", name);
+        assert_eq!(
+            container["env"][0]["valueFrom"]["secretKeyRef"]["name"],
+            env_secret.as_str()
+        );
+        assert_eq!(
+            state.secrets[&env_secret]["data"]["GOFER_TASK_ID"],
+            base64::encode("task")
+        );
+        assert_eq!(
+            state.secrets[&env_secret]["metadata"]["ownerReferences"][0]["uid"],
+            pod["metadata"]["uid"]
+        );
+
+        let registry_secret = format!("{}-registry", name);
+        assert_eq!(
+            pod["spec"]["imagePullSecrets"][0]["name"],
+            registry_secret.as_str()
+        );
+        let docker_config = base64::decode(
+            state.secrets[&registry_secret]["data"][".dockerconfigjson"]
+                .as_str()
+                .unwrap(),
+        )
+        .unwrap();
+        let docker_config: serde_json::Value = serde_json::from_slice(&docker_config).unwrap();
+        assert_eq!(
+            docker_config["auths"]["registry.example.com"]["username"],
+            "user"
+        );
+    }
+
+    let response = scheduler
+        .get_state(GetStateRequest {
+            name: "namespace_pipeline_1_task_1".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.state, ContainerState::Running);
+
+    set_pod_status(&state, &name, json!({ "phase": "Running" }));
+    state.lock().unwrap().logs.insert(
+        name.clone(),
+        "first line\nsecond line\nno newline".to_string(),
+    );
+
+    let logs: Vec<String> = scheduler
+        .get_logs(GetLogsRequest {
+            name: "namespace_pipeline_1_task_1".to_string(),
+        })
+        .map(|log| match log.unwrap() {
+            Log::Stdout(log) => String::from_utf8(log.to_vec()).unwrap(),
+            _ => panic!("logs should be stdout"),
+        })
+        .collect()
+        .await;
+    assert_eq!(logs, vec!["first line\n", "second line\n", "no newline"]);
+
+    set_pod_status(
+        &state,
+        &name,
+        json!({
+            "phase": "Failed",
+            "containerStatuses": [{ "name": "gofer", "state": { "terminated": { "exitCode": 2 } } }],
+        }),
+    );
+
+    let response = scheduler
+        .get_state(GetStateRequest {
+            name: "namespace_pipeline_1_task_1".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.state, ContainerState::Exited);
+    assert_eq!(response.exit_code, Some(2));
+
+    // Exit codes that don't fit are reported as unknown rather than wrapped.
+    set_pod_status(
+        &state,
+        &name,
+        json!({
+            "phase": "Failed",
+            "containerStatuses": [{ "name": "gofer", "state": { "terminated": { "exitCode": -1 } } }],
+        }),
+    );
+
+    let response = scheduler
+        .get_state(GetStateRequest {
+            name: "namespace_pipeline_1_task_1".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.state, ContainerState::Exited);
+    assert_eq!(response.exit_code, None);
+
+    // Starting a container with the same name replaces the previous one.
+    let previous_uid = state.lock().unwrap().pods[&name]["metadata"]["uid"].clone();
+    scheduler
+        .start_container(container_request("namespace_pipeline_1_task_1"))
+        .await
+        .unwrap();
+    {
+        let state = state.lock().unwrap();
+        assert_ne!(state.pods[&name]["metadata"]["uid"], previous_uid);
+        assert!(!state.secrets.contains_key(&format!("{}-registry", name)));
+    }
+}
+
+#[tokio::test]
+/// Trigger containers are given a service so Gofer can reach them.
+async fn kubernetes_trigger_container() {
+    let (address, state) = start_fake_kubernetes();
+    let scheduler = new_kubernetes_scheduler(&address).await;
+
+    let mut request = container_request("trigger_cron");
+    request.enable_networking = true;
+
+    let response = scheduler.start_container(request).await.unwrap();
+
+    let name = resource_name("trigger_cron");
+    assert_eq!(
+        response.url,
+        Some(format!(
+            "https://{}.{}.svc:8080",
+            name, FAKE_KUBERNETES_NAMESPACE
+        ))
+    );
+
+    let state = state.lock().unwrap();
+    let service = &state.services[&name];
+    assert_eq!(
+        service["spec"]["selector"]["gofer.dev/container"],
+        name.as_str()
+    );
+    assert_eq!(service["spec"]["ports"][0]["targetPort"], 8080);
+    assert_eq!(
+        state.pods[&name]["spec"]["containers"][0]["ports"][0]["containerPort"],
+        8080
+    );
+}
+
+#[tokio::test]
+/// Stopped containers are killed but their final state can still be read.
+async fn kubernetes_stop_container() {
+    let (address, state) = start_fake_kubernetes();
+    let scheduler = new_kubernetes_scheduler(&address).await;
+
+    scheduler
+        .start_container(container_request("stop_me"))
+        .await
+        .unwrap();
+    set_pod_status(
+        &state,
+        &resource_name("stop_me"),
+        json!({ "phase": "Running" }),
+    );
+
+    scheduler
+        .stop_container(StopContainerRequest {
+            name: "stop_me".to_string(),
+            timeout: 10,
+        })
+        .await
+        .unwrap();
+
+    let response = scheduler
+        .get_state(GetStateRequest {
+            name: "stop_me".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.state, ContainerState::Exited);
+    assert_eq!(response.exit_code, Some(137));
+
+    let returned_err = scheduler
+        .stop_container(StopContainerRequest {
+            name: "does_not_exist".to_string(),
+            timeout: 10,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(returned_err, SchedulerError::NoSuchContainer(_)));
+}
+
+#[tokio::test]
+/// Containers whose image can't be pulled are reported instead of waiting forever.
+async fn kubernetes_failed_image_pull() {
+    let (address, state) = start_fake_kubernetes();
+    let scheduler = new_kubernetes_scheduler(&address).await;
+
+    scheduler
+        .start_container(container_request("bad_image"))
+        .await
+        .unwrap();
+    set_pod_status(
+        &state,
+        &resource_name("bad_image"),
+        json!({
+            "phase": "Pending",
+            "containerStatuses": [{
+                "name": "gofer",
+                "state": { "waiting": { "reason": "ImagePullBackOff", "message": "image not found" } },
+            }],
+        }),
+    );
+
+    let returned_err = scheduler
+        .get_state(GetStateRequest {
+            name: "bad_image".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(returned_err, SchedulerError::NoSuchImage(_)));
+
+    let log = scheduler
+        .get_logs(GetLogsRequest {
+            name: "bad_image".to_string(),
+        })
+        .next()
+        .await
+        .unwrap();
+    assert!(matches!(log, Err(SchedulerError::NoSuchImage(_))));
+
+    let returned_err = scheduler
+        .get_state(GetStateRequest {
+            name: "does_not_exist".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(returned_err, SchedulerError::NoSuchContainer(_)));
+}
+
+#[tokio::test]
+/// The scheduler refuses to start if it can't authenticate with the API server.
+async fn kubernetes_bad_credentials() {
+    let (address, _) = start_fake_kubernetes();
+
+    let returned_err = Kubernetes::new(&conf::api::KubernetesScheduler {
+        api_server: address,
+        token: "wrong".to_string(),
+        namespace: FAKE_KUBERNETES_NAMESPACE.to_string(),
+        ..Default::default()
+    })
+    .await
+    .unwrap_err();
+    assert!(matches!(returned_err, SchedulerError::Connection(_)));
+}
+
+/// Connects to a real Kubernetes API server. By default this expects `kubectl proxy --port=8001` to be running
+/// against a local cluster (kind, minikube or similar); set GOFER_TEST_KUBERNETES_API_SERVER,
+/// GOFER_TEST_KUBERNETES_TOKEN and GOFER_TEST_KUBERNETES_NAMESPACE to use another one.
+async fn new_real_kubernetes_scheduler() -> Kubernetes {
+    Kubernetes::new(&conf::api::KubernetesScheduler {
+        api_server: fake_server::env_or(
+            "GOFER_TEST_KUBERNETES_API_SERVER",
+            "http://127.0.0.1:8001",
+        ),
+        token: fake_server::env_or("GOFER_TEST_KUBERNETES_TOKEN", ""),
+        namespace: fake_server::env_or("GOFER_TEST_KUBERNETES_NAMESPACE", "default"),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+/// Waits for a container on a real cluster to exit, giving up after two minutes.
+async fn wait_for_exit(scheduler: &Kubernetes, name: &str) -> GetStateResponse {
+    for _ in 0..120 {
+        let response = scheduler
+            .get_state(GetStateRequest {
+                name: name.to_string(),
+            })
+            .await
+            .unwrap();
+        if response.state == ContainerState::Exited {
+            return response;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    panic!("container {} never exited", name);
+}
+
+#[tokio::test]
+#[ignore]
+/// Task containers run to completion on a real cluster and their logs and exit code can be read.
+async fn kubernetes_task_container_real_api_server() {
+    let scheduler = new_real_kubernetes_scheduler().await;
+
+    let mut request = container_request("gofer_test_task");
+    request.image = "busybox:latest".to_string();
+    request.entrypoint = vec!["/bin/sh".to_string()];
+    request.command = vec!["-c".to_string(), "echo $GOFER_TASK_ID; exit 3".to_string()];
+    request.cpu_limit = Some(100);
+    request.memory_limit = Some(64);
+    scheduler.start_container(request).await.unwrap();
+
+    let logs: Vec<String> = scheduler
+        .get_logs(GetLogsRequest {
+            name: "gofer_test_task".to_string(),
+        })
+        .map(|log| match log.unwrap() {
+            Log::Stdout(log) => String::from_utf8(log.to_vec()).unwrap(),
+            _ => panic!("logs should be stdout"),
+        })
+        .collect()
+        .await;
+    assert_eq!(logs, vec!["task\n"]);
+
+    let response = wait_for_exit(&scheduler, "gofer_test_task").await;
+    assert_eq!(response.exit_code, Some(3));
+}
+
+#[tokio::test]
+#[ignore]
+/// Running containers on a real cluster can be stopped, which relies on the API server accepting the scheduler's
+/// merge patch.
+async fn kubernetes_stop_container_real_api_server() {
+    let scheduler = new_real_kubernetes_scheduler().await;
+
+    let mut request = container_request("gofer_test_stop");
+    request.image = "busybox:latest".to_string();
+    request.entrypoint = vec!["/bin/sh".to_string()];
+    request.command = vec!["-c".to_string(), "sleep 600".to_string()];
+    scheduler.start_container(request).await.unwrap();
+
+    scheduler
+        .stop_container(StopContainerRequest {
+            name: "gofer_test_stop".to_string(),
+            timeout: 10,
+        })
+        .await
+        .unwrap();
+
+    wait_for_exit(&scheduler, "gofer_test_stop").await;
+}
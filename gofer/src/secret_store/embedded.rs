@@ -1,12 +1,20 @@
 use super::*;
 use async_trait::async_trait;
 use orion::aead;
+use std::collections::HashMap;
 use std::{fs::create_dir_all, io, path::Path};
 
+/// Stored secrets start with this marker followed by the id of the key they were encrypted with (prefixed
+/// by its length) and then the cipher text. Secrets stored before keys had ids are just cipher text.
+const KEY_ID_MARKER: &[u8] = b"gofer_key_id:";
+
 #[derive(Debug, Clone)]
 pub struct Engine {
     db: sled::Db,
-    encryption_key: String,
+    /// The id of the key new secrets are encrypted with.
+    active_key: String,
+    /// All keys secrets can be decrypted with, by id.
+    encryption_keys: HashMap<String, String>,
 }
 
 // Create folder if not exists.
@@ -18,44 +26,125 @@ fn touch_folder(path: &Path) -> io::Result<()> {
     Ok(())
 }
 
+/// Splits a stored secret into the id of the key it was encrypted with and its cipher text. Secrets stored
+/// before keys had ids have no key id.
+fn split_key_id(stored: &[u8]) -> (Option<String>, &[u8]) {
+    let rest = match stored.strip_prefix(KEY_ID_MARKER) {
+        Some(rest) => rest,
+        None => return (None, stored),
+    };
+
+    let id_len = match rest.first() {
+        Some(id_len) => *id_len as usize,
+        None => return (None, stored),
+    };
+
+    match rest.get(1..1 + id_len) {
+        Some(id) => (
+            Some(String::from_utf8_lossy(id).to_string()),
+            &rest[1 + id_len..],
+        ),
+        None => (None, stored),
+    }
+}
+
 impl Engine {
-    pub async fn new(path: &str, encryption_key: &str) -> Result<Self, SecretStoreError> {
+    pub async fn new(
+        path: &str,
+        active_key: &str,
+        encryption_keys: &HashMap<String, String>,
+    ) -> Result<Self, SecretStoreError> {
         touch_folder(Path::new(path)).unwrap();
 
-        if encryption_key.len() != 32 {
-            return Err(SecretStoreError::FailedInitPrecondition(
-                "encryption key length must be 32 characters".to_string(),
-            ));
+        if !encryption_keys.contains_key(active_key) {
+            return Err(SecretStoreError::FailedInitPrecondition(format!(
+                "active encryption key '{}' not found in encryption keys; set secret_store.embedded.active_key \
+                to the id of a key in secret_store.embedded.encryption_keys",
+                active_key
+            )));
+        }
+
+        for (id, key) in encryption_keys {
+            if id.is_empty() || id.len() > u8::MAX as usize {
+                return Err(SecretStoreError::FailedInitPrecondition(
+                    "encryption key ids must be between 1 and 255 characters".to_string(),
+                ));
+            }
+
+            if key.len() != 32 {
+                return Err(SecretStoreError::FailedInitPrecondition(format!(
+                    "encryption key '{}' length must be 32 characters",
+                    id
+                )));
+            }
         }
 
         Ok(Self {
             db: sled::open(path).expect("could not open object database"),
-            encryption_key: encryption_key.to_string(),
+            active_key: active_key.to_string(),
+            encryption_keys: encryption_keys.clone(),
         })
     }
 
-    /// Used to encrypt the plaintext secret before storing. The key provided
-    /// MUST be 32 characters.
-    fn encrypt(&self, plaintext: &str) -> Result<Vec<u8>, SecretStoreError> {
-        let key = aead::SecretKey::from_slice(self.encryption_key.as_bytes())
+    /// Used to encrypt the plaintext secret with the active key before storing. The key id is stored along
+    /// with the cipher text so the secret can still be decrypted once the active key changes.
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, SecretStoreError> {
+        let key = aead::SecretKey::from_slice(self.encryption_keys[&self.active_key].as_bytes())
             .map_err(|e| SecretStoreError::FailedEncryption(format!("{:?}", e)))?;
 
-        let cipher_text = aead::seal(&key, plaintext.as_bytes())
+        let cipher_text = aead::seal(&key, plaintext)
             .map_err(|e| SecretStoreError::FailedEncryption(format!("{:?}", e)))?;
 
-        Ok(cipher_text)
+        let mut stored = KEY_ID_MARKER.to_vec();
+        stored.push(self.active_key.len() as u8);
+        stored.extend_from_slice(self.active_key.as_bytes());
+        stored.extend_from_slice(&cipher_text);
+
+        Ok(stored)
     }
 
-    /// Used to decrypt the encrypted secret before passing back to user. The key
-    /// provided MUST be 32 characters.
-    fn decrypt(&self, cipher_text: Vec<u8>) -> Result<Vec<u8>, SecretStoreError> {
-        let key = aead::SecretKey::from_slice(self.encryption_key.as_bytes())
+    /// Used to decrypt the stored secret before passing back to user. Returns the plaintext along with the
+    /// id of the key that decrypted it.
+    fn decrypt(&self, stored: &[u8]) -> Result<(Vec<u8>, Option<String>), SecretStoreError> {
+        let (key_id, cipher_text) = split_key_id(stored);
+
+        let key_id = match key_id {
+            Some(key_id) if self.encryption_keys.contains_key(&key_id) => key_id,
+            Some(key_id) => {
+                return Err(SecretStoreError::FailedEncryption(format!(
+                    "secret was encrypted with key '{}' which is not configured",
+                    key_id
+                )))
+            }
+            // Secrets stored before keys had ids could have been encrypted with any of them.
+            None => return self.decrypt_without_key_id(stored),
+        };
+
+        let key = aead::SecretKey::from_slice(self.encryption_keys[&key_id].as_bytes())
             .map_err(|e| SecretStoreError::FailedEncryption(format!("{:?}", e)))?;
 
-        let decryption_text = aead::open(&key, &cipher_text)
+        let plaintext = aead::open(&key, cipher_text)
             .map_err(|e| SecretStoreError::FailedEncryption(format!("{:?}", e)))?;
 
-        Ok(decryption_text)
+        Ok((plaintext, Some(key_id)))
+    }
+
+    fn decrypt_without_key_id(
+        &self,
+        cipher_text: &[u8],
+    ) -> Result<(Vec<u8>, Option<String>), SecretStoreError> {
+        for key in self.encryption_keys.values() {
+            let key = aead::SecretKey::from_slice(key.as_bytes())
+                .map_err(|e| SecretStoreError::FailedEncryption(format!("{:?}", e)))?;
+
+            if let Ok(plaintext) = aead::open(&key, cipher_text) {
+                return Ok((plaintext, None));
+            }
+        }
+
+        Err(SecretStoreError::FailedEncryption(
+            "secret could not be decrypted with any configured key".to_string(),
+        ))
     }
 }
 
@@ -71,7 +160,7 @@ impl Store for Engine {
             return Err(SecretStoreError::NotFound);
         };
 
-        let secret = self.decrypt(value.unwrap().to_vec())?;
+        let (secret, _) = self.decrypt(&value.unwrap())?;
 
         Ok(secret)
     }
@@ -86,7 +175,7 @@ impl Store for Engine {
             return Err(SecretStoreError::FailedPrecondition);
         };
 
-        let secret = self.encrypt(value).unwrap();
+        let secret = self.encrypt(value.as_bytes())?;
 
         if force {
             self.db
@@ -110,4 +199,37 @@ impl Store for Engine {
 
         Ok(())
     }
+
+    async fn rotate_encryption_keys(&self) -> Result<u64, SecretStoreError> {
+        let mut rotated = 0;
+
+        for entry in self.db.iter() {
+            let (key, stored) = entry.map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+            let (secret, key_id) = self.decrypt(&stored)?;
+            if key_id.as_deref() == Some(self.active_key.as_str()) {
+                continue;
+            }
+
+            let secret = self.encrypt(&secret)?;
+
+            // If the swap fails the secret was changed since we read it, which means it was already
+            // encrypted with the active key.
+            let swapped = self
+                .db
+                .compare_and_swap(key, Some(stored), Some(secret))
+                .map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+            if swapped.is_ok() {
+                rotated += 1;
+            }
+        }
+
+        self.db
+            .flush_async()
+            .await
+            .map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+        Ok(rotated)
+    }
 }
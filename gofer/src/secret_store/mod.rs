@@ -1,10 +1,11 @@
 mod embedded;
+mod vault;
 
 use crate::conf;
 use async_trait::async_trait;
 use econf::LoadEnv;
 use serde::Deserialize;
-use slog_scope::error;
+use slog_scope::{error, warn};
 use std::fmt::Debug;
 use std::sync::Arc;
 use strum::{Display, EnumString};
@@ -41,11 +42,18 @@ pub trait Store: Debug {
     async fn put_secret(&self, key: &str, value: &str, force: bool)
         -> Result<(), SecretStoreError>;
     async fn delete_secret(&self, key: &str) -> Result<(), SecretStoreError>;
+
+    /// Re-encrypts every secret that isn't already encrypted with the store's active encryption key,
+    /// returning how many were re-encrypted. Only stores that encrypt secrets themselves support this.
+    async fn rotate_encryption_keys(&self) -> Result<u64, SecretStoreError> {
+        Err(SecretStoreError::FailedPrecondition)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq, Display, EnumString, LoadEnv)]
 pub enum Engine {
     Embedded,
+    Vault,
 }
 
 impl Default for Engine {
@@ -57,11 +65,28 @@ impl Default for Engine {
 pub async fn init_secret_store(
     config: &conf::api::SecretStore,
 ) -> Result<Arc<dyn Store + Send + Sync>, SecretStoreError> {
-    #[allow(clippy::match_single_binding)]
     match config.engine {
         Engine::Embedded => {
             if let Some(config) = &config.embedded {
-                let engine = embedded::Engine::new(&config.path, &config.encryption_key).await?;
+                if config.encryption_key.is_some() {
+                    warn!("secret_store.embedded.encryption_key is deprecated and is used as the encryption key \
+                        with id 'default'; move it to secret_store.embedded.encryption_keys and set \
+                        secret_store.embedded.active_key instead");
+                }
+
+                let (active_key, encryption_keys) = config.keys();
+                let engine =
+                    embedded::Engine::new(&config.path, &active_key, &encryption_keys).await?;
+                Ok(Arc::new(engine))
+            } else {
+                Err(SecretStoreError::FailedInitPrecondition(
+                    "engine settings not found in config".into(),
+                ))
+            }
+        }
+        Engine::Vault => {
+            if let Some(config) = &config.vault {
+                let engine = vault::Engine::new(config).await?;
                 Ok(Arc::new(engine))
             } else {
                 Err(SecretStoreError::FailedInitPrecondition(
@@ -1,6 +1,48 @@
-use crate::secret_store::Store;
+use crate::conf;
+use crate::fake_server;
+use crate::secret_store::{SecretStoreError, Store};
+use axum::extract::Extension;
+use bytes::Bytes;
 use defer_lite::defer;
+use http::{HeaderMap, Method, StatusCode, Uri};
 use rand::prelude::*;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Basic CRUD that every secret store engine should support.
+async fn crud_secret_store(store: &dyn Store) {
+    let test_key = "test_key";
+    let test_value = "test_value";
+
+    store.put_secret(test_key, test_value, false).await.unwrap();
+
+    let returned_value = store.get_secret(test_key).await.unwrap();
+    assert_eq!(test_value, String::from_utf8_lossy(&returned_value));
+
+    let returned_err = store
+        .put_secret(test_key, "other_value", false)
+        .await
+        .unwrap_err();
+    assert_eq!(SecretStoreError::Exists, returned_err);
+
+    store
+        .put_secret(test_key, "other_value", true)
+        .await
+        .unwrap();
+
+    let returned_value = store.get_secret(test_key).await.unwrap();
+    assert_eq!("other_value", String::from_utf8_lossy(&returned_value));
+
+    store.delete_secret(test_key).await.unwrap();
+
+    let returned_err = store.get_secret(test_key).await.unwrap_err();
+    assert_eq!(SecretStoreError::NotFound, returned_err);
+
+    // Once deleted a secret can be created again.
+    store.put_secret(test_key, test_value, false).await.unwrap();
+    store.delete_secret(test_key).await.unwrap();
+}
 
 #[tokio::test]
 /// Basic CRUD can be accomplished for the embedded secret store.
@@ -10,20 +52,251 @@ async fn crud_secret_store_embedded() {
     let path = format!("/tmp/gofer_tests_embedded_secret_store{}", append_num);
     defer! {std::fs::remove_dir_all(&path).unwrap();};
 
-    let store = super::embedded::Engine::new(&path, "changemechangemechangemechangeme")
+    let keys = HashMap::from([(
+        "default".to_string(),
+        "changemechangemechangemechangeme".to_string(),
+    )]);
+    let store = super::embedded::Engine::new(&path, "default", &keys)
         .await
         .unwrap();
 
-    let test_key = "test_key";
-    let test_value = "test_value";
+    crud_secret_store(&store).await;
+}
 
-    store.put_secret(test_key, test_value, false).await.unwrap();
+#[tokio::test]
+/// Secrets stay readable while the embedded secret store's active key changes and can then be re-encrypted
+/// with the new key so the old one can be removed.
+async fn rotate_keys_secret_store_embedded() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let path = format!(
+        "/tmp/gofer_tests_embedded_secret_store_rotate{}",
+        append_num
+    );
+    defer! {std::fs::remove_dir_all(&path).unwrap();};
 
-    let returned_value = store.get_secret(test_key).await.unwrap();
-    assert_eq!(test_value, String::from_utf8_lossy(&returned_value));
+    let old_key = (
+        "old".to_string(),
+        "oldoldoldoldoldoldoldoldoldoldol".to_string(),
+    );
+    let new_key = (
+        "new".to_string(),
+        "newnewnewnewnewnewnewnewnewnewne".to_string(),
+    );
 
-    store.delete_secret(test_key).await.unwrap();
+    let store = super::embedded::Engine::new(&path, "old", &HashMap::from([old_key.clone()]))
+        .await
+        .unwrap();
+    store
+        .put_secret("test_key", "test_value", false)
+        .await
+        .unwrap();
+    drop(store);
 
-    let returned_err = store.get_secret(test_key).await.unwrap_err();
-    assert_eq!(super::SecretStoreError::NotFound, returned_err);
+    let store = super::embedded::Engine::new(
+        &path,
+        "new",
+        &HashMap::from([old_key.clone(), new_key.clone()]),
+    )
+    .await
+    .unwrap();
+
+    let returned_value = store.get_secret("test_key").await.unwrap();
+    assert_eq!("test_value", String::from_utf8_lossy(&returned_value));
+
+    assert_eq!(1, store.rotate_encryption_keys().await.unwrap());
+    assert_eq!(0, store.rotate_encryption_keys().await.unwrap());
+    drop(store);
+
+    let store = super::embedded::Engine::new(&path, "new", &HashMap::from([new_key]))
+        .await
+        .unwrap();
+
+    let returned_value = store.get_secret("test_key").await.unwrap();
+    assert_eq!("test_value", String::from_utf8_lossy(&returned_value));
+
+    let returned_err = super::embedded::Engine::new(&path, "old", &HashMap::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        returned_err,
+        SecretStoreError::FailedInitPrecondition(_)
+    ));
+}
+
+const FAKE_VAULT_ROOT_TOKEN: &str = "root";
+const FAKE_VAULT_ROLE_ID: &str = "gofer_role";
+const FAKE_VAULT_SECRET_ID: &str = "gofer_secret";
+
+/// An in-memory stand-in for a Vault dev server, so the Vault engine can be tested without one. It only
+/// understands the requests the engine makes to a KV version 2 mount named "secret" and to AppRole login.
+#[derive(Debug, Default)]
+struct FakeVault {
+    tokens: HashSet<String>,
+    /// The value and current version of each secret.
+    secrets: HashMap<String, (String, u64)>,
+}
+
+async fn fake_vault_handler(
+    Extension(state): Extension<Arc<Mutex<FakeVault>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> axum::response::Response {
+    let mut state = state.lock().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+
+    if uri.path() == "/v1/auth/approle/login" {
+        if body["role_id"] != FAKE_VAULT_ROLE_ID || body["secret_id"] != FAKE_VAULT_SECRET_ID {
+            return fake_server::json_response(
+                StatusCode::BAD_REQUEST,
+                json!({ "errors": ["invalid role or secret ID"] }),
+            );
+        }
+
+        let token = nanoid::nanoid!();
+        state.tokens.insert(token.clone());
+        return fake_server::json_response(
+            StatusCode::OK,
+            json!({ "auth": { "client_token": token, "lease_duration": 3600 } }),
+        );
+    }
+
+    let token = headers
+        .get("x-vault-token")
+        .and_then(|token| token.to_str().ok())
+        .unwrap_or_default();
+    if token != FAKE_VAULT_ROOT_TOKEN && !state.tokens.contains(token) {
+        return fake_server::json_response(
+            StatusCode::FORBIDDEN,
+            json!({ "errors": ["permission denied"] }),
+        );
+    }
+
+    if let Some(key) = uri.path().strip_prefix("/v1/secret/data/") {
+        return match method {
+            Method::GET => match state.secrets.get(key) {
+                Some((value, version)) => fake_server::json_response(
+                    StatusCode::OK,
+                    json!({ "data": { "data": { "value": value }, "metadata": { "version": version } } }),
+                ),
+                None => fake_server::json_response(StatusCode::NOT_FOUND, json!({ "errors": [] })),
+            },
+            Method::POST => {
+                // With check-and-set the write only goes through if the given version is the current one, where
+                // 0 means the secret doesn't exist yet.
+                let version = state
+                    .secrets
+                    .get(key)
+                    .map(|(_, version)| *version)
+                    .unwrap_or(0);
+                if let Some(cas) = body["options"]["cas"].as_u64() {
+                    if cas != version {
+                        return fake_server::json_response(
+                            StatusCode::BAD_REQUEST,
+                            json!({ "errors": ["check-and-set parameter did not match the current version"] }),
+                        );
+                    }
+                }
+
+                let value = body["data"]["value"].as_str().unwrap().to_string();
+                state.secrets.insert(key.to_string(), (value, version + 1));
+                fake_server::json_response(
+                    StatusCode::OK,
+                    json!({ "data": { "version": version + 1 } }),
+                )
+            }
+            _ => {
+                fake_server::json_response(StatusCode::METHOD_NOT_ALLOWED, json!({ "errors": [] }))
+            }
+        };
+    }
+
+    if let Some(key) = uri.path().strip_prefix("/v1/secret/metadata/") {
+        if method == Method::DELETE {
+            state.secrets.remove(key);
+            return fake_server::response(StatusCode::NO_CONTENT, &[], Bytes::new());
+        }
+    }
+
+    fake_server::json_response(StatusCode::NOT_FOUND, json!({ "errors": [] }))
+}
+
+/// Starts a fake Vault server on a random port and returns its address.
+fn start_fake_vault() -> String {
+    fake_server::start(
+        fake_vault_handler,
+        Arc::new(Mutex::new(FakeVault::default())),
+    )
+}
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for the Vault secret store authenticating with a token.
+async fn crud_secret_store_vault_token() {
+    let store = super::vault::Engine::new(&conf::api::VaultSecretStore {
+        address: start_fake_vault(),
+        mount: "secret".to_string(),
+        prefix: "gofer/".to_string(),
+        token: Some(FAKE_VAULT_ROOT_TOKEN.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    crud_secret_store(&store).await;
+}
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for the Vault secret store authenticating with AppRole.
+async fn crud_secret_store_vault_approle() {
+    let store = super::vault::Engine::new(&conf::api::VaultSecretStore {
+        address: start_fake_vault(),
+        mount: "secret".to_string(),
+        prefix: "gofer/".to_string(),
+        role_id: Some(FAKE_VAULT_ROLE_ID.to_string()),
+        secret_id: Some(FAKE_VAULT_SECRET_ID.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    crud_secret_store(&store).await;
+
+    let bad_store = super::vault::Engine::new(&conf::api::VaultSecretStore {
+        address: start_fake_vault(),
+        mount: "secret".to_string(),
+        prefix: "gofer/".to_string(),
+        role_id: Some(FAKE_VAULT_ROLE_ID.to_string()),
+        secret_id: Some("wrong".to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let returned_err = bad_store.get_secret("test_key").await.unwrap_err();
+    assert!(matches!(returned_err, SecretStoreError::Unknown(_)));
+}
+
+#[tokio::test]
+#[ignore]
+/// Basic CRUD can be accomplished against a real Vault server. By default this expects a dev server started with
+/// `vault server -dev -dev-root-token-id=root`, which mounts KV version 2 at "secret"; set
+/// GOFER_TEST_VAULT_ADDRESS and GOFER_TEST_VAULT_TOKEN to use another one. Every run gets its own prefix so runs
+/// don't see each other's secrets.
+async fn crud_secret_store_vault_real_server() {
+    let store = super::vault::Engine::new(&conf::api::VaultSecretStore {
+        address: fake_server::env_or("GOFER_TEST_VAULT_ADDRESS", "http://127.0.0.1:8200"),
+        mount: "secret".to_string(),
+        prefix: format!("gofer_tests/{}/", nanoid::nanoid!()),
+        token: Some(fake_server::env_or(
+            "GOFER_TEST_VAULT_TOKEN",
+            FAKE_VAULT_ROOT_TOKEN,
+        )),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    crud_secret_store(&store).await;
 }
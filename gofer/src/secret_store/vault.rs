@@ -0,0 +1,328 @@
+use super::*;
+use async_trait::async_trait;
+use hyper::{client::HttpConnector, Body, Client, Method, Request, StatusCode};
+use hyper_rustls::HttpsConnector;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long before a token expires that it will be replaced, so that it doesn't expire mid request.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// The default mount path of the AppRole auth method.
+const DEFAULT_APPROLE_MOUNT: &str = "approle";
+
+#[derive(Debug, Clone)]
+enum Auth {
+    Token(String),
+    AppRole {
+        mount: String,
+        role_id: String,
+        secret_id: String,
+    },
+}
+
+/// A token retrieved by logging in through AppRole.
+#[derive(Debug)]
+struct LeasedToken {
+    value: String,
+    /// None if the token never expires.
+    expires: Option<Instant>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginAuth {
+    client_token: String,
+    lease_duration: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadResponse {
+    data: ReadData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadData {
+    data: SecretData,
+}
+
+#[derive(Debug, Deserialize)]
+struct SecretData {
+    value: String,
+}
+
+/// Percent encodes a key so that it can be used as part of a path.
+fn path_encode(value: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+fn unexpected_response(status: StatusCode, body: &[u8]) -> SecretStoreError {
+    SecretStoreError::Unknown(format!(
+        "unexpected response {}; {}",
+        status,
+        String::from_utf8_lossy(body)
+    ))
+}
+
+/// Stores secrets in a HashiCorp Vault KV version 2 secrets engine. Each secret is kept at
+/// <mount>/<prefix><key> with its value stored under the "value" field.
+#[derive(Debug)]
+pub struct Engine {
+    client: Client<HttpsConnector<HttpConnector>>,
+    address: String,
+    mount: String,
+    prefix: String,
+    auth: Auth,
+    leased_token: Mutex<Option<LeasedToken>>,
+}
+
+impl Engine {
+    pub async fn new(config: &conf::api::VaultSecretStore) -> Result<Self, SecretStoreError> {
+        if config.address.is_empty() || config.mount.is_empty() {
+            return Err(SecretStoreError::FailedInitPrecondition(
+                "address and mount must be set".into(),
+            ));
+        }
+
+        let auth = match (&config.token, &config.role_id, &config.secret_id) {
+            (Some(token), _, _) if !token.is_empty() => Auth::Token(token.clone()),
+            (_, Some(role_id), Some(secret_id)) => Auth::AppRole {
+                mount: config
+                    .approle_mount
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_APPROLE_MOUNT.to_string()),
+                role_id: role_id.clone(),
+                secret_id: secret_id.clone(),
+            },
+            _ => {
+                return Err(SecretStoreError::FailedInitPrecondition(
+                    "either a token or an AppRole role_id and secret_id must be set".into(),
+                ))
+            }
+        };
+
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Ok(Self {
+            client: Client::builder().build(connector),
+            address: config.address.trim_end_matches('/').to_string(),
+            mount: config.mount.trim_matches('/').to_string(),
+            prefix: config.prefix.clone(),
+            auth,
+            leased_token: Mutex::new(None),
+        })
+    }
+
+    fn data_path(&self, key: &str) -> String {
+        format!(
+            "{}/data/{}",
+            self.mount,
+            path_encode(&format!("{}{}", self.prefix, key))
+        )
+    }
+
+    fn metadata_path(&self, key: &str) -> String {
+        format!(
+            "{}/metadata/{}",
+            self.mount,
+            path_encode(&format!("{}{}", self.prefix, key))
+        )
+    }
+
+    /// Makes a request to the Vault API, returning the response status and body.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        token: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> Result<(StatusCode, Vec<u8>), SecretStoreError> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("{}/v1/{}", self.address, path))
+            .header("content-type", "application/json");
+
+        if let Some(token) = token {
+            request = request.header("x-vault-token", token);
+        }
+
+        let body = match body {
+            Some(body) => Body::from(body.to_string()),
+            None => Body::empty(),
+        };
+
+        let request = request
+            .body(body)
+            .map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+        Ok((status, body.to_vec()))
+    }
+
+    /// Returns a token to authenticate requests with, logging in through AppRole if there is no current
+    /// token or it is about to expire.
+    async fn token(&self) -> Result<String, SecretStoreError> {
+        let (mount, role_id, secret_id) = match &self.auth {
+            Auth::Token(token) => return Ok(token.clone()),
+            Auth::AppRole {
+                mount,
+                role_id,
+                secret_id,
+            } => (mount, role_id, secret_id),
+        };
+
+        let mut leased_token = self.leased_token.lock().await;
+
+        if let Some(token) = leased_token.as_ref() {
+            match token.expires {
+                Some(expires) if Instant::now() + TOKEN_EXPIRY_MARGIN >= expires => {}
+                _ => return Ok(token.value.clone()),
+            }
+        }
+
+        let (status, body) = self
+            .send(
+                Method::POST,
+                &format!("auth/{}/login", mount),
+                None,
+                Some(json!({ "role_id": role_id, "secret_id": secret_id })),
+            )
+            .await?;
+
+        if !status.is_success() {
+            return Err(unexpected_response(status, &body));
+        }
+
+        let login: LoginResponse =
+            serde_json::from_slice(&body).map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+
+        let expires = match login.auth.lease_duration {
+            0 => None,
+            duration => Some(Instant::now() + Duration::from_secs(duration)),
+        };
+
+        *leased_token = Some(LeasedToken {
+            value: login.auth.client_token.clone(),
+            expires,
+        });
+
+        Ok(login.auth.client_token)
+    }
+
+    /// Makes an authenticated request to the Vault API. If a token obtained through AppRole is rejected
+    /// (for instance because it was revoked) we log in again and retry once.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<(StatusCode, Vec<u8>), SecretStoreError> {
+        let token = self.token().await?;
+        let (status, response_body) = self
+            .send(method.clone(), path, Some(&token), body.clone())
+            .await?;
+
+        if status != StatusCode::FORBIDDEN || matches!(self.auth, Auth::Token(_)) {
+            return Ok((status, response_body));
+        }
+
+        *self.leased_token.lock().await = None;
+        let token = self.token().await?;
+        self.send(method, path, Some(&token), body).await
+    }
+}
+
+#[async_trait]
+impl Store for Engine {
+    async fn get_secret(&self, key: &str) -> Result<Vec<u8>, SecretStoreError> {
+        let (status, body) = self
+            .request(Method::GET, &self.data_path(key), None)
+            .await?;
+
+        match status {
+            StatusCode::OK => {
+                let response: ReadResponse = serde_json::from_slice(&body)
+                    .map_err(|e| SecretStoreError::Unknown(e.to_string()))?;
+                Ok(response.data.data.value.into_bytes())
+            }
+            StatusCode::NOT_FOUND => Err(SecretStoreError::NotFound),
+            status => Err(unexpected_response(status, &body)),
+        }
+    }
+
+    async fn put_secret(
+        &self,
+        key: &str,
+        value: &str,
+        force: bool,
+    ) -> Result<(), SecretStoreError> {
+        if key.is_empty() {
+            return Err(SecretStoreError::FailedPrecondition);
+        };
+
+        // A check-and-set version of 0 tells Vault to only write the secret if it doesn't already exist.
+        let body = if force {
+            json!({ "data": { "value": value } })
+        } else {
+            json!({ "options": { "cas": 0 }, "data": { "value": value } })
+        };
+
+        let (status, body) = self
+            .request(Method::POST, &self.data_path(key), Some(body))
+            .await?;
+
+        match status {
+            status if status.is_success() => Ok(()),
+            StatusCode::BAD_REQUEST
+                if !force && String::from_utf8_lossy(&body).contains("check-and-set") =>
+            {
+                Err(SecretStoreError::Exists)
+            }
+            status => Err(unexpected_response(status, &body)),
+        }
+    }
+
+    async fn delete_secret(&self, key: &str) -> Result<(), SecretStoreError> {
+        // Deleting the metadata removes every version of the secret. Deleting only the latest version would
+        // leave the key behind and stop it from being created again without force.
+        let (status, body) = self
+            .request(Method::DELETE, &self.metadata_path(key), None)
+            .await?;
+
+        match status {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND => Ok(()),
+            status => Err(unexpected_response(status, &body)),
+        }
+    }
+}
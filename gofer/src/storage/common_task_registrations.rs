@@ -1,4 +1,4 @@
-use crate::storage::{SqliteErrors, StorageError, MAX_ROW_LIMIT};
+use crate::storage::{encryption::Cipher, Conn, SqliteErrors, StorageError, MAX_ROW_LIMIT};
 use futures::TryFutureExt;
 use gofer_models::common_task::{Registration, Status};
 use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqliteConnection};
@@ -11,11 +11,25 @@ pub struct UpdatableFields {
     pub user: Option<String>,
     pub pass: Option<String>,
     pub variables: Option<String>,
+    pub status: Option<Status>,
 }
 
-/// Return all notifiers; limited to 200 rows in any one response.
+/// Registry passwords are encrypted at rest.
+fn decrypt_registration(
+    cipher: &Cipher,
+    mut registration: Registration,
+) -> Result<Registration, StorageError> {
+    registration.pass = registration
+        .pass
+        .map(|pass| cipher.decrypt("pass", &pass))
+        .transpose()?;
+
+    Ok(registration)
+}
+
+/// Return all common tasks; limited to 200 rows in any one response.
 pub async fn list(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     offset: u64,
     limit: u64,
 ) -> Result<Vec<Registration>, StorageError> {
@@ -25,10 +39,12 @@ pub async fn list(
         limit = MAX_ROW_LIMIT;
     }
 
-    sqlx::query(
+    let cipher = conn.cipher();
+
+    let registrations = sqlx::query(
         r#"
 SELECT name, image, user, pass, variables, created, status
-FROM notifier_registrations
+FROM common_task_registrations
 LIMIT ?
 OFFSET ?;"#,
     )
@@ -48,33 +64,44 @@ OFFSET ?;"#,
             .map_err(|_| StorageError::Parse {
                 value: row.get("status"),
                 column: "status".to_string(),
-                err: "could not parse value into notifier status enum".to_string(),
+                err: "could not parse value into common task status enum".to_string(),
             })
             .unwrap(),
     })
-    .fetch_all(conn)
+    .fetch_all(&mut **conn)
     .await
-    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .map_err(|e| StorageError::Unknown(e.to_string()))?;
+
+    registrations
+        .into_iter()
+        .map(|registration| decrypt_registration(&cipher, registration))
+        .collect()
 }
 
-/// Insert a new notifier registration.
+/// Insert a new common task registration.
 pub async fn insert(
-    conn: &mut SqliteConnection,
-    notifier_registration: &Registration,
+    conn: &mut Conn<'_>,
+    common_task_registration: &Registration,
 ) -> Result<(), StorageError> {
     sqlx::query(
         r#"
-INSERT INTO notifier_registrations (name, image, user, pass, variables, created, status)
+INSERT INTO common_task_registrations (name, image, user, pass, variables, created, status)
 VALUES (?, ?, ?, ?, ?, ?, ?);"#,
     )
-    .bind(&notifier_registration.name)
-    .bind(&notifier_registration.image)
-    .bind(&notifier_registration.user)
-    .bind(&notifier_registration.pass)
-    .bind(serde_json::to_string(&notifier_registration.variables).unwrap())
-    .bind(notifier_registration.created as i64)
-    .bind(&notifier_registration.status.to_string())
-    .execute(conn)
+    .bind(&common_task_registration.name)
+    .bind(&common_task_registration.image)
+    .bind(&common_task_registration.user)
+    .bind(
+        common_task_registration
+            .pass
+            .as_deref()
+            .map(|pass| conn.cipher().encrypt(pass))
+            .transpose()?,
+    )
+    .bind(serde_json::to_string(&common_task_registration.variables).unwrap())
+    .bind(common_task_registration.created as i64)
+    .bind(&common_task_registration.status.to_string())
+    .execute(&mut **conn)
     .map_err(|e| match e {
         sqlx::Error::Database(database_err) => {
             if let Some(err_code) = database_err.code() {
@@ -91,12 +118,14 @@ VALUES (?, ?, ?, ?, ?, ?, ?);"#,
     Ok(())
 }
 
-/// Get details on a specific notifier_registration.
-pub async fn get(conn: &mut SqliteConnection, name: &str) -> Result<Registration, StorageError> {
-    sqlx::query(
+/// Get details on a specific common task registration.
+pub async fn get(conn: &mut Conn<'_>, name: &str) -> Result<Registration, StorageError> {
+    let cipher = conn.cipher();
+
+    let registration = sqlx::query(
         r#"
 SELECT name, image, user, pass, variables, created, status
-FROM notifier_registrations
+FROM common_task_registrations
 WHERE name = ?;"#,
     )
     .bind(name)
@@ -114,26 +143,28 @@ WHERE name = ?;"#,
             .map_err(|_| StorageError::Parse {
                 value: row.get("status"),
                 column: "status".to_string(),
-                err: "could not parse value into notifier status enum".to_string(),
+                err: "could not parse value into common task status enum".to_string(),
             })
             .unwrap(),
     })
-    .fetch_one(conn)
+    .fetch_one(&mut **conn)
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => StorageError::NotFound,
         _ => StorageError::Unknown(e.to_string()),
     })
-    .await
+    .await?;
+
+    decrypt_registration(&cipher, registration)
 }
 
-/// Update a specific trigger_registration.
+/// Update a specific common task registration.
 pub async fn update(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     name: &str,
     fields: UpdatableFields,
 ) -> Result<(), StorageError> {
     let mut update_query: QueryBuilder<Sqlite> =
-        QueryBuilder::new(r#"UPDATE notifier_registrations SET "#);
+        QueryBuilder::new(r#"UPDATE common_task_registrations SET "#);
 
     let mut updated_fields_total = 0;
 
@@ -157,7 +188,7 @@ pub async fn update(
             update_query.push(", ");
         }
         update_query.push("pass = ");
-        update_query.push_bind(pass);
+        update_query.push_bind(conn.cipher().encrypt(&pass)?);
         updated_fields_total += 1;
     }
 
@@ -167,6 +198,15 @@ pub async fn update(
         }
         update_query.push("variables = ");
         update_query.push_bind(variables);
+        updated_fields_total += 1;
+    }
+
+    if let Some(status) = fields.status {
+        if updated_fields_total > 0 {
+            update_query.push(", ");
+        }
+        update_query.push("status = ");
+        update_query.push_bind(status.to_string());
     }
 
     update_query.push(" WHERE name = ");
@@ -175,7 +215,7 @@ pub async fn update(
 
     let update_query = update_query.build();
     update_query
-        .execute(conn)
+        .execute(&mut **conn)
         .map_ok(|_| ())
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => StorageError::NotFound,
@@ -187,7 +227,7 @@ pub async fn update(
 pub async fn delete(conn: &mut SqliteConnection, name: &str) -> Result<(), StorageError> {
     sqlx::query(
         r#"
-DELETE FROM notifier_registrations
+DELETE FROM common_task_registrations
 WHERE name = ?;"#,
     )
     .bind(name)
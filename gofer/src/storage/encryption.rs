@@ -0,0 +1,275 @@
+use crate::storage::StorageError;
+use futures::TryFutureExt;
+use gofer_models::{task::Task, Variable, VariableSensitivity};
+use orion::aead;
+use sqlx::{sqlite::SqliteRow, Acquire, Row, SqliteConnection};
+
+/// Marks a column value as encrypted. The cipher text that follows is base64 encoded so that it can be kept in
+/// TEXT columns.
+const ENCRYPTED_PREFIX: &str = "encrypted:";
+
+/// Encrypts and decrypts sensitive column values with the key the database was opened with. Values encrypted
+/// with one key can't be read with another.
+#[derive(Debug)]
+pub struct Cipher {
+    key: aead::SecretKey,
+}
+
+impl Cipher {
+    pub(super) fn new(key: &str) -> Result<Self, StorageError> {
+        if key.len() != 32 {
+            return Err(StorageError::Unknown(
+                "encryption key length must be 32 characters".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            key: aead::SecretKey::from_slice(key.as_bytes())
+                .map_err(|e| StorageError::Unknown(format!("{:?}", e)))?,
+        })
+    }
+
+    /// Encrypts a value before it is written to the database.
+    pub(super) fn encrypt(&self, plaintext: &str) -> Result<String, StorageError> {
+        let cipher_text = aead::seal(&self.key, plaintext.as_bytes())
+            .map_err(|e| StorageError::Unknown(format!("could not encrypt value; {:?}", e)))?;
+
+        Ok(format!(
+            "{}{}",
+            ENCRYPTED_PREFIX,
+            base64::encode(cipher_text)
+        ))
+    }
+
+    /// Decrypts a value read from the given column.
+    pub(super) fn decrypt(&self, column: &str, value: &str) -> Result<String, StorageError> {
+        let decrypt_err = |err: String| StorageError::Parse {
+            // The value is left out since it is either cipher text or something that should have been.
+            value: String::new(),
+            column: column.to_string(),
+            err,
+        };
+
+        let cipher_text = value
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| decrypt_err("value is not encrypted".to_string()))?;
+
+        let cipher_text = base64::decode(cipher_text).map_err(|e| decrypt_err(e.to_string()))?;
+
+        let plaintext = aead::open(&self.key, &cipher_text)
+            .map_err(|e| decrypt_err(format!("could not decrypt value; {:?}", e)))?;
+
+        String::from_utf8(plaintext).map_err(|e| decrypt_err(e.to_string()))
+    }
+
+    /// Returns the variables with the values of private variables encrypted.
+    pub(super) fn encrypt_variables(
+        &self,
+        variables: &[Variable],
+    ) -> Result<Vec<Variable>, StorageError> {
+        variables
+            .iter()
+            .map(|variable| {
+                let mut variable = variable.clone();
+                if variable.sensitivity == VariableSensitivity::Private {
+                    variable.value = self.encrypt(&variable.value)?;
+                }
+                Ok(variable)
+            })
+            .collect()
+    }
+
+    /// Returns the variables with the values of private variables decrypted.
+    pub(super) fn decrypt_variables(
+        &self,
+        column: &str,
+        variables: Vec<Variable>,
+    ) -> Result<Vec<Variable>, StorageError> {
+        variables
+            .into_iter()
+            .map(|mut variable| {
+                if variable.sensitivity == VariableSensitivity::Private {
+                    variable.value = self.decrypt(column, &variable.value)?;
+                }
+                Ok(variable)
+            })
+            .collect()
+    }
+
+    /// Returns the task with its registry password encrypted. Task runs keep their own copy of the task they
+    /// ran, so the password is encrypted there too.
+    pub(super) fn encrypt_task(&self, task: &Task) -> Result<Task, StorageError> {
+        let mut task = task.clone();
+        if let Some(registry_auth) = task.registry_auth.as_mut() {
+            registry_auth.pass = self.encrypt(&registry_auth.pass)?;
+        }
+
+        Ok(task)
+    }
+
+    /// Returns the task with its registry password decrypted.
+    pub(super) fn decrypt_task(&self, column: &str, mut task: Task) -> Result<Task, StorageError> {
+        if let Some(registry_auth) = task.registry_auth.as_mut() {
+            registry_auth.pass = self.decrypt(column, &registry_auth.pass)?;
+        }
+
+        Ok(task)
+    }
+}
+
+fn json_err(e: serde_json::Error) -> StorageError {
+    StorageError::Unknown(e.to_string())
+}
+
+/// Encrypts sensitive values that were written before they were encrypted. Encryption needs the server's key,
+/// which SQL migrations don't have, so this runs after them and marks itself as done in the
+/// encrypted_columns_migration table.
+pub(super) async fn encrypt_existing_rows(
+    conn: &mut SqliteConnection,
+    cipher: &Cipher,
+) -> Result<(), StorageError> {
+    let mut tx = conn
+        .begin()
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+
+    let migrated = sqlx::query("SELECT id FROM encrypted_columns_migration;")
+        .fetch_optional(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+
+    if migrated.is_some() {
+        return Ok(());
+    }
+
+    for table in ["trigger_registrations", "common_task_registrations"] {
+        let rows = sqlx::query(&format!(
+            "SELECT name, pass FROM {} WHERE pass IS NOT NULL;",
+            table
+        ))
+        .map(|row: SqliteRow| (row.get::<String, _>("name"), row.get::<String, _>("pass")))
+        .fetch_all(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+
+        for (name, pass) in rows {
+            sqlx::query(&format!("UPDATE {} SET pass = ? WHERE name = ?;", table))
+                .bind(cipher.encrypt(&pass)?)
+                .bind(name)
+                .execute(&mut tx)
+                .map_err(|e| StorageError::Unknown(e.to_string()))
+                .await?;
+        }
+    }
+
+    let tasks = sqlx::query(
+        r#"
+SELECT namespace, pipeline, id, registry_auth
+FROM tasks
+WHERE registry_auth IS NOT NULL AND registry_auth != '';"#,
+    )
+    .map(|row: SqliteRow| {
+        (
+            row.get::<String, _>("namespace"),
+            row.get::<String, _>("pipeline"),
+            row.get::<String, _>("id"),
+            row.get::<String, _>("registry_auth"),
+        )
+    })
+    .fetch_all(&mut tx)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    for (namespace, pipeline, id, registry_auth) in tasks {
+        sqlx::query(
+            r#"
+UPDATE tasks SET registry_auth = ?
+WHERE namespace = ? AND pipeline = ? AND id = ?;"#,
+        )
+        .bind(cipher.encrypt(&registry_auth)?)
+        .bind(namespace)
+        .bind(pipeline)
+        .bind(id)
+        .execute(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+    }
+
+    let runs = sqlx::query("SELECT namespace, pipeline, id, variables FROM runs;")
+        .map(|row: SqliteRow| {
+            (
+                row.get::<String, _>("namespace"),
+                row.get::<String, _>("pipeline"),
+                row.get::<i64, _>("id"),
+                row.get::<String, _>("variables"),
+            )
+        })
+        .fetch_all(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+
+    for (namespace, pipeline, id, variables) in runs {
+        let variables: Vec<Variable> = serde_json::from_str(&variables).map_err(json_err)?;
+
+        sqlx::query(
+            r#"
+UPDATE runs SET variables = ?
+WHERE namespace = ? AND pipeline = ? AND id = ?;"#,
+        )
+        .bind(serde_json::to_string(&cipher.encrypt_variables(&variables)?).map_err(json_err)?)
+        .bind(namespace)
+        .bind(pipeline)
+        .bind(id)
+        .execute(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+    }
+
+    let task_runs = sqlx::query(
+        "SELECT namespace, pipeline, run, id, attempt, task, variables FROM task_runs;",
+    )
+    .map(|row: SqliteRow| {
+        (
+            row.get::<String, _>("namespace"),
+            row.get::<String, _>("pipeline"),
+            row.get::<i64, _>("run"),
+            row.get::<String, _>("id"),
+            row.get::<i64, _>("attempt"),
+            row.get::<String, _>("task"),
+            row.get::<String, _>("variables"),
+        )
+    })
+    .fetch_all(&mut tx)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    for (namespace, pipeline, run, id, attempt, task, variables) in task_runs {
+        let task: Task = serde_json::from_str(&task).map_err(json_err)?;
+        let variables: Vec<Variable> = serde_json::from_str(&variables).map_err(json_err)?;
+
+        sqlx::query(
+            r#"
+UPDATE task_runs SET task = ?, variables = ?
+WHERE namespace = ? AND pipeline = ? AND run = ? AND id = ? AND attempt = ?;"#,
+        )
+        .bind(serde_json::to_string(&cipher.encrypt_task(&task)?).map_err(json_err)?)
+        .bind(serde_json::to_string(&cipher.encrypt_variables(&variables)?).map_err(json_err)?)
+        .bind(namespace)
+        .bind(pipeline)
+        .bind(run)
+        .bind(id)
+        .bind(attempt)
+        .execute(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+    }
+
+    sqlx::query("INSERT INTO encrypted_columns_migration (id) VALUES (0);")
+        .execute(&mut tx)
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+        .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| StorageError::Unknown(e.to_string()))
+}
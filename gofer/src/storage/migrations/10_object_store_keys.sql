@@ -0,0 +1,43 @@
+-- The keys of objects stored for each pipeline and run. The objects themselves live in the object store.
+-- The tables these replace were never written to.
+DROP TABLE IF EXISTS object_store_pipeline_keys;
+DROP TABLE IF EXISTS object_store_run_keys;
+
+CREATE TABLE IF NOT EXISTS object_store_pipeline_keys (
+    namespace TEXT    NOT NULL,
+    pipeline  TEXT    NOT NULL,
+    key       TEXT    NOT NULL,
+    created   INTEGER NOT NULL,
+    FOREIGN KEY (namespace) REFERENCES namespaces(id) ON DELETE CASCADE,
+    FOREIGN KEY (namespace, pipeline) REFERENCES pipelines(namespace, id) ON DELETE CASCADE,
+    PRIMARY KEY (namespace, pipeline, key)
+) STRICT;
+
+CREATE INDEX idx_object_store_pipeline_keys_created ON object_store_pipeline_keys (created);
+
+CREATE TABLE IF NOT EXISTS object_store_run_keys (
+    namespace TEXT    NOT NULL,
+    pipeline  TEXT    NOT NULL,
+    run       INTEGER NOT NULL,
+    key       TEXT    NOT NULL,
+    created   INTEGER NOT NULL,
+    FOREIGN KEY (namespace) REFERENCES namespaces(id) ON DELETE CASCADE,
+    FOREIGN KEY (namespace, pipeline) REFERENCES pipelines(namespace, id) ON DELETE CASCADE,
+    FOREIGN KEY (namespace, pipeline, run) REFERENCES runs(namespace, pipeline, id) ON DELETE CASCADE,
+    PRIMARY KEY (namespace, pipeline, run, key)
+) STRICT;
+
+-- Runs used to keep their object keys and whether they had expired as JSON in store_info. The keys move to
+-- object_store_run_keys and the expiry to its own column.
+INSERT OR IGNORE INTO object_store_run_keys (namespace, pipeline, run, key, created)
+SELECT runs.namespace, runs.pipeline, runs.id, keys.value, runs.started
+FROM runs, json_each(runs.store_info, '$.keys') AS keys
+WHERE runs.store_info IS NOT NULL;
+
+ALTER TABLE runs ADD COLUMN store_objects_expired INTEGER NOT NULL DEFAULT 0
+    CHECK (store_objects_expired IN (0, 1));
+
+UPDATE runs SET store_objects_expired = 1
+WHERE store_info IS NOT NULL AND json_extract(store_info, '$.is_expired') = 1;
+
+ALTER TABLE runs DROP COLUMN store_info;
@@ -0,0 +1,2 @@
+-- The time (in seconds) a pipeline's runs are allowed to take. 0 means only the global run timeout applies.
+ALTER TABLE pipelines ADD COLUMN run_timeout INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Registration passwords, task registry auth and private run/task run variables are encrypted with the
+-- server's encryption key. Rows written before that are encrypted by Gofer on startup, since SQL has no
+-- access to the key. This table records that it has been done so it only happens once.
+CREATE TABLE IF NOT EXISTS encrypted_columns_migration (
+    id INTEGER NOT NULL CHECK (id = 0),
+    PRIMARY KEY (id)
+) STRICT;
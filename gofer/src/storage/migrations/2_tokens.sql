@@ -0,0 +1,9 @@
+-- API tokens. Only a hash of each token is kept so that the database can't be used to authenticate.
+-- kind is one of 'Management', 'Client' or 'Run'. Run tokens are created by Gofer itself for the tasks of a run.
+CREATE TABLE IF NOT EXISTS tokens (
+    id       INTEGER PRIMARY KEY,
+    hash     TEXT    NOT NULL UNIQUE,
+    created  INTEGER NOT NULL,
+    kind     TEXT    NOT NULL,
+    metadata TEXT    NOT NULL
+) STRICT;
@@ -0,0 +1,2 @@
+-- The namespaces a client token is allowed to act on, as a JSON array of namespace ids.
+ALTER TABLE tokens ADD COLUMN namespaces TEXT NOT NULL DEFAULT '[]';
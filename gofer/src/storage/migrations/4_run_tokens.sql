@@ -0,0 +1,6 @@
+-- Tokens minted for runs have the 'Run' kind. They are limited to the run's pipeline and only last as long as
+-- the run is allowed to.
+-- pipelines is a JSON array of pipeline ids; an empty array means every pipeline in the token's namespaces.
+-- expires is in epoch milli; zero means the token never expires.
+ALTER TABLE tokens ADD COLUMN pipelines TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE tokens ADD COLUMN expires INTEGER NOT NULL DEFAULT 0;
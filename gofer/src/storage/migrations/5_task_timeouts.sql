@@ -0,0 +1,2 @@
+-- The time (in seconds) a task's task runs are allowed to run for. 0 means there is no limit.
+ALTER TABLE tasks ADD COLUMN timeout INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- CPU (in millicores) and memory (in megabytes) requested by and allowed for a task's containers.
+-- 0 means the task doesn't set its own and the scheduler defaults apply.
+ALTER TABLE tasks ADD COLUMN cpu_request INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN cpu_limit INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN memory_request INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN memory_limit INTEGER NOT NULL DEFAULT 0;
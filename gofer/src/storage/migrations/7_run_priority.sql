@@ -0,0 +1,6 @@
+-- Runs waiting to be started are started in order of highest priority first. Existing runs get the default
+-- priority.
+ALTER TABLE runs ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+-- Queued runs are looked up by state whenever the dispatcher runs.
+CREATE INDEX idx_runs_state ON runs (state);
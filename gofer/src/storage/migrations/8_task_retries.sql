@@ -0,0 +1,38 @@
+-- Describes if and how a task is retried when one of its task runs fails. Existing tasks are never retried.
+ALTER TABLE tasks ADD COLUMN retry_policy TEXT NOT NULL
+    DEFAULT '{"retries":0,"backoff":"Constant","delay":0,"max_delay":0}';
+
+-- Each attempt of a task gets its own task run, so the attempt becomes part of the primary key. SQLite can't
+-- change a primary key in place, so the table is copied. Task runs from before retries existed get attempt 0.
+CREATE TABLE task_runs_new (
+    namespace     TEXT    NOT NULL,
+    pipeline      TEXT    NOT NULL,
+    run           INTEGER NOT NULL,
+    id            TEXT    NOT NULL,
+    task          TEXT    NOT NULL,
+    created       INTEGER NOT NULL,
+    started       INTEGER NOT NULL,
+    ended         INTEGER NOT NULL,
+    exit_code     INTEGER,
+    failure       TEXT,
+    logs_expired  INTEGER NOT NULL CHECK (logs_expired IN (0, 1)),
+    logs_removed  INTEGER NOT NULL CHECK (logs_removed IN (0, 1)),
+    state         TEXT    NOT NULL,
+    status        TEXT    NOT NULL,
+    scheduler_id  TEXT,
+    variables     TEXT NOT NULL,
+    attempt       INTEGER NOT NULL,
+    FOREIGN KEY (namespace) REFERENCES namespaces(id) ON DELETE CASCADE,
+    FOREIGN KEY (namespace, pipeline) REFERENCES pipelines(namespace, id) ON DELETE CASCADE,
+    PRIMARY KEY (namespace, pipeline, run, id, attempt)
+) STRICT;
+
+INSERT INTO task_runs_new (namespace, pipeline, run, id, task, created, started, ended, exit_code, failure,
+    logs_expired, logs_removed, state, status, scheduler_id, variables, attempt)
+SELECT namespace, pipeline, run, id, task, created, started, ended, exit_code, failure, logs_expired,
+    logs_removed, state, status, scheduler_id, variables, 0
+FROM task_runs;
+
+DROP TABLE task_runs;
+
+ALTER TABLE task_runs_new RENAME TO task_runs;
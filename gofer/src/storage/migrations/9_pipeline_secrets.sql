@@ -0,0 +1,10 @@
+-- The keys of secrets stored for each pipeline. The values themselves live in the secret store.
+CREATE TABLE IF NOT EXISTS pipeline_secrets (
+    namespace TEXT    NOT NULL,
+    pipeline  TEXT    NOT NULL,
+    key       TEXT    NOT NULL,
+    created   INTEGER NOT NULL,
+    FOREIGN KEY (namespace) REFERENCES namespaces(id) ON DELETE CASCADE,
+    FOREIGN KEY (namespace, pipeline) REFERENCES pipelines(namespace, id) ON DELETE CASCADE,
+    PRIMARY KEY (namespace, pipeline, key)
+) STRICT;
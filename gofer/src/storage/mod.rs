@@ -1,16 +1,32 @@
 pub mod common_task_registrations;
+mod encryption;
 pub mod events;
 pub mod namespaces;
+pub mod objects;
 pub mod pipelines;
 pub mod runs;
+pub mod secrets;
 pub mod task_runs;
+pub mod tokens;
 pub mod trigger_registrations;
 
 #[cfg(test)]
 mod tests;
 
-use sqlx::{migrate, pool::PoolConnection, Pool, Sqlite, SqlitePool};
-use std::{error::Error, fmt, fs::File, io, path::Path};
+use encryption::Cipher;
+use sqlx::{
+    migrate, pool::PoolConnection, Acquire, Pool, Sqlite, SqliteConnection, SqlitePool,
+    Transaction,
+};
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io,
+    ops::{Deref, DerefMut},
+    path::Path,
+    sync::Arc,
+};
 
 /// The maximum amount of rows that can be returned by any single query.
 const MAX_ROW_LIMIT: u64 = 200;
@@ -40,6 +56,7 @@ pub enum StorageError {
 #[derive(Debug)]
 pub enum SqliteErrors {
     Constraint,
+    Unique,
 }
 
 /// Sqlite Errors are determined by database error code. We map these to the specific code so that
@@ -49,6 +66,7 @@ impl SqliteErrors {
     fn value(&self) -> String {
         match *self {
             SqliteErrors::Constraint => "1555".to_string(),
+            SqliteErrors::Unique => "2067".to_string(),
         }
     }
 }
@@ -62,6 +80,75 @@ impl fmt::Display for SqliteErrors {
 #[derive(Debug, Clone)]
 pub struct Db {
     pool: Pool<Sqlite>,
+    cipher: Arc<Cipher>,
+}
+
+#[derive(Debug)]
+enum ConnInner<'c> {
+    Pool(PoolConnection<Sqlite>),
+    Transaction(Transaction<'c, Sqlite>),
+}
+
+/// A database connection that carries the cipher sensitive columns are encrypted with. It dereferences to a
+/// plain [`SqliteConnection`] so storage functions that don't touch sensitive columns can take one of those
+/// instead.
+#[derive(Debug)]
+pub struct Conn<'c> {
+    inner: ConnInner<'c>,
+    cipher: Arc<Cipher>,
+}
+
+impl Conn<'_> {
+    /// Starts a transaction, or a savepoint if this is already a transaction. The transaction is rolled back
+    /// if it is dropped before being committed.
+    pub async fn begin(&mut self) -> Result<Conn<'_>, StorageError> {
+        let cipher = self.cipher.clone();
+        let tx = self
+            .deref_mut()
+            .begin()
+            .await
+            .map_err(|e| StorageError::Unknown(e.to_string()))?;
+
+        Ok(Conn {
+            inner: ConnInner::Transaction(tx),
+            cipher,
+        })
+    }
+
+    /// Commits the transaction. Connections that aren't a transaction have nothing to commit.
+    pub async fn commit(self) -> Result<(), StorageError> {
+        match self.inner {
+            ConnInner::Pool(_) => Ok(()),
+            ConnInner::Transaction(tx) => tx
+                .commit()
+                .await
+                .map_err(|e| StorageError::Unknown(e.to_string())),
+        }
+    }
+
+    fn cipher(&self) -> Arc<Cipher> {
+        self.cipher.clone()
+    }
+}
+
+impl Deref for Conn<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.inner {
+            ConnInner::Pool(conn) => conn,
+            ConnInner::Transaction(tx) => tx,
+        }
+    }
+}
+
+impl DerefMut for Conn<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.inner {
+            ConnInner::Pool(conn) => conn,
+            ConnInner::Transaction(tx) => tx,
+        }
+    }
 }
 
 // Create file if not exists.
@@ -74,9 +161,12 @@ fn touch_file(path: &Path) -> io::Result<()> {
 }
 
 impl Db {
-    pub async fn new(path: &str) -> Result<Self, Box<dyn Error>> {
+    /// Opens the database, encrypting sensitive values with the given key. The key MUST be 32 characters long.
+    pub async fn new(path: &str, encryption_key: &str) -> Result<Self, Box<dyn Error>> {
         touch_file(Path::new(path)).unwrap();
 
+        let cipher = Cipher::new(encryption_key)?;
+
         let connection_pool = SqlitePool::connect(&format!("file:{}", path))
             .await
             .unwrap();
@@ -86,15 +176,25 @@ impl Db {
             .await
             .unwrap();
 
+        let mut conn = connection_pool.acquire().await?;
+        encryption::encrypt_existing_rows(&mut conn, &cipher).await?;
+
         Ok(Db {
             pool: connection_pool,
+            cipher: Arc::new(cipher),
         })
     }
 
-    pub async fn conn(&self) -> Result<PoolConnection<Sqlite>, StorageError> {
-        self.pool
+    pub async fn conn(&self) -> Result<Conn<'static>, StorageError> {
+        let conn = self
+            .pool
             .acquire()
             .await
-            .map_err(|e| StorageError::Connection(format!("{:?}", e)))
+            .map_err(|e| StorageError::Connection(format!("{:?}", e)))?;
+
+        Ok(Conn {
+            inner: ConnInner::Pool(conn),
+            cipher: self.cipher.clone(),
+        })
     }
 }
@@ -0,0 +1,198 @@
+use crate::storage::{SqliteErrors, StorageError};
+use futures::TryFutureExt;
+use gofer_models::object::ObjectKey;
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
+use std::ops::Deref;
+
+/// Return all object keys for a pipeline, oldest first.
+pub async fn list_pipeline_keys(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+) -> Result<Vec<ObjectKey>, StorageError> {
+    sqlx::query(
+        r#"
+SELECT key, created
+FROM object_store_pipeline_keys
+WHERE namespace = ? AND pipeline = ?
+ORDER BY created ASC, key ASC;"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .map(|row: SqliteRow| ObjectKey {
+        key: row.get("key"),
+        created: row.get::<i64, _>("created") as u64,
+    })
+    .fetch_all(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await
+}
+
+/// Insert a new pipeline object key.
+pub async fn insert_pipeline_key(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+    object_key: &ObjectKey,
+) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+INSERT INTO object_store_pipeline_keys (namespace, pipeline, key, created)
+VALUES (?, ?, ?, ?);"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(&object_key.key)
+    .bind(object_key.created as i64)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::Database(database_err) => {
+            if let Some(err_code) = database_err.code() {
+                if err_code.deref() == SqliteErrors::Constraint.value() {
+                    return StorageError::Exists;
+                }
+            }
+            StorageError::Unknown(database_err.message().to_string())
+        }
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
+pub async fn delete_pipeline_key(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+    key: &str,
+) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+DELETE FROM object_store_pipeline_keys
+WHERE namespace = ? AND pipeline = ? AND key = ?;"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(key)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
+/// Return all object keys for a run, oldest first.
+pub async fn list_run_keys(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+    run_id: u64,
+) -> Result<Vec<ObjectKey>, StorageError> {
+    sqlx::query(
+        r#"
+SELECT key, created
+FROM object_store_run_keys
+WHERE namespace = ? AND pipeline = ? AND run = ?
+ORDER BY created ASC, key ASC;"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(run_id as i64)
+    .map(|row: SqliteRow| ObjectKey {
+        key: row.get("key"),
+        created: row.get::<i64, _>("created") as u64,
+    })
+    .fetch_all(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await
+}
+
+/// Insert a new run object key.
+pub async fn insert_run_key(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+    run_id: u64,
+    object_key: &ObjectKey,
+) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+INSERT INTO object_store_run_keys (namespace, pipeline, run, key, created)
+VALUES (?, ?, ?, ?, ?);"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(run_id as i64)
+    .bind(&object_key.key)
+    .bind(object_key.created as i64)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::Database(database_err) => {
+            if let Some(err_code) = database_err.code() {
+                if err_code.deref() == SqliteErrors::Constraint.value() {
+                    return StorageError::Exists;
+                }
+            }
+            StorageError::Unknown(database_err.message().to_string())
+        }
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
+pub async fn delete_run_key(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+    run_id: u64,
+    key: &str,
+) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+DELETE FROM object_store_run_keys
+WHERE namespace = ? AND pipeline = ? AND run = ? AND key = ?;"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(run_id as i64)
+    .bind(key)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
+/// Return all object keys for every run of a pipeline along with the run they belong to.
+pub async fn list_pipeline_run_keys(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+) -> Result<Vec<(u64, ObjectKey)>, StorageError> {
+    sqlx::query(
+        r#"
+SELECT run, key, created
+FROM object_store_run_keys
+WHERE namespace = ? AND pipeline = ?
+ORDER BY run ASC, created ASC, key ASC;"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .map(|row: SqliteRow| {
+        (
+            row.get::<i64, _>("run") as u64,
+            ObjectKey {
+                key: row.get("key"),
+                created: row.get::<i64, _>("created") as u64,
+            },
+        )
+    })
+    .fetch_all(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await
+}
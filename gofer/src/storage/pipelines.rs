@@ -1,7 +1,7 @@
-use crate::storage::{SqliteErrors, StorageError, MAX_ROW_LIMIT};
+use crate::storage::{objects, Conn, SqliteErrors, StorageError, MAX_ROW_LIMIT};
 use futures::TryFutureExt;
 use gofer_models::{pipeline, task};
-use sqlx::{sqlite::SqliteRow, Acquire, QueryBuilder, Row, Sqlite, SqliteConnection};
+use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqliteConnection};
 use std::{collections::HashMap, ops::Deref};
 use std::{ops::Not, str::FromStr};
 
@@ -10,54 +10,86 @@ pub struct UpdatableFields {
     pub name: Option<String>,
     pub description: Option<String>,
     pub parallelism: Option<u64>,
+    pub run_timeout: Option<u64>,
     pub modified: Option<u64>,
     pub state: Option<pipeline::State>,
 }
 
 pub async fn list_tasks(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     namespace_id: &str,
     pipeline_id: &str,
 ) -> Result<Vec<task::Task>, StorageError> {
-    sqlx::query(
+    let cipher = conn.cipher();
+
+    let tasks = sqlx::query(
         r#"
-SELECT id, description, image, registry_auth, depends_on, variables, entrypoint, command
+SELECT id, description, image, registry_auth, depends_on, variables, entrypoint, command, timeout,
+    cpu_request, cpu_limit, memory_request, memory_limit, retry_policy
 FROM tasks
 WHERE namespace = ? AND pipeline = ?;"#,
     )
     .bind(namespace_id)
     .bind(pipeline_id)
-    .map(|row: SqliteRow| task::Task {
-        id: row.get("id"),
-        description: row.get("description"),
-        image: row.get("image"),
-        registry_auth: {
-            let registry_auth = row.get::<String, _>("registry_auth");
-            registry_auth
-                .is_empty()
-                .not()
-                .then(|| serde_json::from_str(&registry_auth).unwrap())
-        },
-        depends_on: {
-            let depends_on = row.get::<String, _>("depends_on");
-            serde_json::from_str(&depends_on).unwrap()
-        },
-        variables: {
-            let variables = row.get::<String, _>("variables");
-            serde_json::from_str(&variables).unwrap()
-        },
-        entrypoint: {
-            let entrypoint = row.get::<String, _>("entrypoint");
-            serde_json::from_str(&entrypoint).unwrap()
-        },
-        command: {
-            let command = row.get::<String, _>("command");
-            serde_json::from_str(&command).unwrap()
-        },
+    .map(|row: SqliteRow| {
+        // Registry auth is encrypted at rest, so it is decrypted once all rows have been read.
+        let registry_auth = row.get::<String, _>("registry_auth");
+
+        let task = task::Task {
+            id: row.get("id"),
+            description: row.get("description"),
+            image: row.get("image"),
+            registry_auth: None,
+            depends_on: {
+                let depends_on = row.get::<String, _>("depends_on");
+                serde_json::from_str(&depends_on).unwrap()
+            },
+            variables: {
+                let variables = row.get::<String, _>("variables");
+                serde_json::from_str(&variables).unwrap()
+            },
+            entrypoint: {
+                let entrypoint = row.get::<String, _>("entrypoint");
+                serde_json::from_str(&entrypoint).unwrap()
+            },
+            command: {
+                let command = row.get::<String, _>("command");
+                serde_json::from_str(&command).unwrap()
+            },
+            timeout: row.get::<i64, _>("timeout") as u64,
+            cpu_request: row.get::<i64, _>("cpu_request") as u64,
+            cpu_limit: row.get::<i64, _>("cpu_limit") as u64,
+            memory_request: row.get::<i64, _>("memory_request") as u64,
+            memory_limit: row.get::<i64, _>("memory_limit") as u64,
+            retry_policy: {
+                let retry_policy = row.get::<String, _>("retry_policy");
+                serde_json::from_str(&retry_policy).unwrap()
+            },
+        };
+
+        (registry_auth, task)
     })
-    .fetch_all(conn)
+    .fetch_all(&mut **conn)
     .map_err(|e| StorageError::Unknown(e.to_string()))
-    .await
+    .await?;
+
+    tasks
+        .into_iter()
+        .map(|(registry_auth, mut task)| {
+            if !registry_auth.is_empty() {
+                let registry_auth = cipher.decrypt("registry_auth", &registry_auth)?;
+                task.registry_auth =
+                    serde_json::from_str(&registry_auth).map_err(|e| StorageError::Parse {
+                        // Left out since it holds the decrypted registry password.
+                        value: String::new(),
+                        column: "registry_auth".to_string(),
+                        err: e.to_string(),
+                    })?;
+            }
+
+            Ok(task)
+        })
+        .collect()
 }
 
 pub async fn list_trigger_settings(
@@ -114,17 +146,50 @@ WHERE namespace = ? AND pipeline = ?;"#,
     .await
 }
 
+/// Returns the trigger settings, along with the namespace and pipeline they belong to, for every active
+/// pipeline subscribed to the given trigger kind.
+pub async fn list_active_trigger_subscriptions(
+    conn: &mut SqliteConnection,
+    kind: &str,
+) -> Result<Vec<(String, String, pipeline::TriggerSettings)>, StorageError> {
+    sqlx::query(
+        r#"
+SELECT settings.namespace, settings.pipeline, settings.kind, settings.label, settings.settings,
+    settings.error
+FROM pipeline_trigger_settings settings
+INNER JOIN pipelines ON settings.namespace = pipelines.namespace AND settings.pipeline = pipelines.id
+WHERE settings.kind = ? AND pipelines.state = ?;"#,
+    )
+    .bind(kind)
+    .bind(pipeline::State::Active.to_string())
+    .map(|row: SqliteRow| {
+        (
+            row.get("namespace"),
+            row.get("pipeline"),
+            pipeline::TriggerSettings {
+                name: row.get("kind"),
+                label: row.get("label"),
+                settings: {
+                    let value = row.get::<String, _>("settings");
+                    serde_json::from_str(&value).unwrap()
+                },
+                error: row.get("error"),
+            },
+        )
+    })
+    .fetch_all(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await
+}
+
 /// Return all pipeline for a given namespace; limited to 200 rows per response.
 pub async fn list(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     offset: u64,
     limit: u64,
     namespace_id: &str,
 ) -> Result<Vec<pipeline::Pipeline>, StorageError> {
-    let mut tx = conn
-        .begin()
-        .map_err(|e| StorageError::Unknown(e.to_string()))
-        .await?;
+    let mut tx = conn.begin().await?;
 
     let mut limit = limit;
 
@@ -135,7 +200,7 @@ pub async fn list(
     // First we need to get the general pipeline information.
     let mut pipelines = sqlx::query(
         r#"
-SELECT namespace, id, name, description, parallelism, created, modified, state
+SELECT namespace, id, name, description, parallelism, run_timeout, created, modified, state
 FROM pipelines
 WHERE namespace = ?
 ORDER BY created
@@ -151,6 +216,7 @@ OFFSET ?;"#,
         name: row.get("name"),
         description: row.get("description"),
         parallelism: row.get::<i64, _>("parallelism") as u64,
+        run_timeout: row.get::<i64, _>("run_timeout") as u64,
         created: row.get::<i64, _>("created") as u64,
         modified: row.get::<i64, _>("modified") as u64,
         state: pipeline::State::from_str(row.get("state"))
@@ -165,7 +231,7 @@ OFFSET ?;"#,
         common_tasks: HashMap::new(),
         store_keys: vec![],
     })
-    .fetch_all(&mut tx)
+    .fetch_all(&mut *tx)
     .map_err(|e| StorageError::Unknown(e.to_string()))
     .await?;
 
@@ -193,17 +259,19 @@ OFFSET ?;"#,
             .into_iter()
             .map(|value| (value.label.clone(), value))
             .collect();
+
+        let store_keys = objects::list_pipeline_keys(&mut tx, namespace_id, &pipeline.id).await?;
+
+        pipeline.store_keys = store_keys.into_iter().map(|value| value.key).collect();
     }
 
-    tx.commit()
-        .await
-        .map_err(|e| StorageError::Unknown(e.to_string()))?;
+    tx.commit().await?;
 
     Ok(pipelines)
 }
 
 pub async fn insert_task(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     namespace_id: &str,
     pipeline_id: &str,
     task: &task::Task,
@@ -211,8 +279,9 @@ pub async fn insert_task(
     sqlx::query(
         r#"
 INSERT INTO tasks (namespace, pipeline, id, description, image, registry_auth,
-    depends_on, variables, entrypoint, command)
-VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
+    depends_on, variables, entrypoint, command, timeout, cpu_request, cpu_limit, memory_request,
+    memory_limit, retry_policy)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     )
     .bind(namespace_id)
     .bind(pipeline_id)
@@ -223,13 +292,23 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
         task.registry_auth
             .is_none()
             .not()
-            .then(|| Some(serde_json::to_string(&task.registry_auth).unwrap())),
+            .then(|| {
+                conn.cipher()
+                    .encrypt(&serde_json::to_string(&task.registry_auth).unwrap())
+            })
+            .transpose()?,
     )
     .bind(serde_json::to_string(&task.depends_on).unwrap())
     .bind(serde_json::to_string(&task.variables).unwrap())
     .bind(serde_json::to_string(&task.entrypoint).unwrap())
     .bind(serde_json::to_string(&task.command).unwrap())
-    .execute(conn)
+    .bind(task.timeout as i64)
+    .bind(task.cpu_request as i64)
+    .bind(task.cpu_limit as i64)
+    .bind(task.memory_request as i64)
+    .bind(task.memory_limit as i64)
+    .bind(serde_json::to_string(&task.retry_policy).unwrap())
+    .execute(&mut **conn)
     .map_ok(|_| ())
     .map_err(|e| match e {
         sqlx::Error::Database(database_err) => {
@@ -277,6 +356,34 @@ INSERT INTO pipeline_trigger_settings (namespace, pipeline, kind, label, setting
     .await
 }
 
+/// Replaces the stored trigger settings for the label given with the new settings; used mostly to record
+/// whether the pipeline's subscription to the trigger was successful.
+pub async fn update_trigger_settings(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+    settings: &pipeline::TriggerSettings,
+) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+UPDATE pipeline_trigger_settings SET kind = ?, settings = ?, error = ?
+WHERE namespace = ? AND pipeline = ? AND label = ?;"#,
+    )
+    .bind(&settings.name)
+    .bind(serde_json::to_string(&settings.settings).unwrap())
+    .bind(&settings.error)
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(&settings.label)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
 pub async fn insert_common_task_settings(
     conn: &mut SqliteConnection,
     namespace_id: &str,
@@ -311,29 +418,27 @@ INSERT INTO pipeline_common_task_settings (namespace, pipeline, kind, label, set
 
 /// Insert a new pipeline.
 pub async fn insert(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     pipeline: &pipeline::Pipeline,
 ) -> Result<(), StorageError> {
-    let mut tx = conn
-        .begin()
-        .map_err(|e| StorageError::Unknown(e.to_string()))
-        .await?;
+    let mut tx = conn.begin().await?;
 
     sqlx::query(
         r#"
-INSERT INTO pipelines (namespace, id, name, description, parallelism, state,
+INSERT INTO pipelines (namespace, id, name, description, parallelism, run_timeout, state,
     created, modified)
-VALUES (?, ?, ?, ?, ?, ?, ?, ?);"#,
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     )
     .bind(&pipeline.namespace)
     .bind(&pipeline.id)
     .bind(&pipeline.name)
     .bind(&pipeline.description)
     .bind(pipeline.parallelism as i64)
+    .bind(pipeline.run_timeout as i64)
     .bind(pipeline.state.to_string())
     .bind(pipeline.created as i64)
     .bind(pipeline.modified as i64)
-    .execute(&mut tx)
+    .execute(&mut *tx)
     .map_err(|e| match e {
         sqlx::Error::Database(database_err) => {
             if let Some(err_code) = database_err.code() {
@@ -359,25 +464,20 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?);"#,
         insert_common_task_settings(&mut tx, &pipeline.namespace, &pipeline.id, settings).await?;
     }
 
-    tx.commit()
-        .await
-        .map_err(|e| StorageError::Unknown(e.to_string()))
+    tx.commit().await
 }
 
 /// Get details on a specific pipeline.
 pub async fn get(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     namespace_id: &str,
     pipeline_id: &str,
 ) -> Result<pipeline::Pipeline, StorageError> {
-    let mut tx = conn
-        .begin()
-        .map_err(|e| StorageError::Unknown(e.to_string()))
-        .await?;
+    let mut tx = conn.begin().await?;
 
     let mut pipeline = sqlx::query(
         r#"
-SELECT namespace, id, name, description, parallelism, created, modified, state
+SELECT namespace, id, name, description, parallelism, run_timeout, created, modified, state
 FROM pipelines
 WHERE namespace = ? AND id = ?
 ORDER BY id
@@ -391,6 +491,7 @@ LIMIT 1;"#,
         name: row.get("name"),
         description: row.get("description"),
         parallelism: row.get::<i64, _>("parallelism") as u64,
+        run_timeout: row.get::<i64, _>("run_timeout") as u64,
         created: row.get::<i64, _>("created") as u64,
         modified: row.get::<i64, _>("modified") as u64,
         state: pipeline::State::from_str(row.get("state"))
@@ -405,7 +506,7 @@ LIMIT 1;"#,
         common_tasks: HashMap::new(),
         store_keys: vec![],
     })
-    .fetch_one(&mut tx)
+    .fetch_one(&mut *tx)
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => StorageError::NotFound,
         _ => StorageError::Unknown(e.to_string()),
@@ -430,9 +531,10 @@ LIMIT 1;"#,
         .map(|value| (value.label.clone(), value))
         .collect();
 
-    tx.commit()
-        .await
-        .map_err(|e| StorageError::Unknown(e.to_string()))?;
+    let store_keys = objects::list_pipeline_keys(&mut tx, namespace_id, pipeline_id).await?;
+    pipeline.store_keys = store_keys.into_iter().map(|value| value.key).collect();
+
+    tx.commit().await?;
 
     Ok(pipeline)
 }
@@ -508,15 +610,12 @@ WHERE namespace = ? AND pipeline = ? AND label = ?;"#,
 
 /// Update a specific pipeline.
 pub async fn update(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     namespace_id: &str,
     id: &str,
     fields: UpdatableFields,
 ) -> Result<(), StorageError> {
-    let mut tx = conn
-        .begin()
-        .map_err(|e| StorageError::Unknown(e.to_string()))
-        .await?;
+    let mut tx = conn.begin().await?;
 
     let pipeline = get(&mut tx, namespace_id, id).await?;
 
@@ -548,6 +647,15 @@ pub async fn update(
         updated_fields_total += 1;
     }
 
+    if let Some(run_timeout) = fields.run_timeout {
+        if updated_fields_total > 0 {
+            update_query.push(", ");
+        }
+        update_query.push("run_timeout = ");
+        update_query.push_bind(run_timeout as i64);
+        updated_fields_total += 1;
+    }
+
     if let Some(state) = fields.state {
         if updated_fields_total > 0 {
             update_query.push(", ");
@@ -575,7 +683,7 @@ pub async fn update(
     let update_query = update_query.build();
 
     update_query
-        .execute(&mut tx)
+        .execute(&mut *tx)
         .map_ok(|_| ())
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => StorageError::NotFound,
@@ -600,9 +708,7 @@ pub async fn update(
         insert_common_task_settings(&mut tx, &pipeline.namespace, &pipeline.id, settings).await?;
     }
 
-    tx.commit()
-        .await
-        .map_err(|e| StorageError::Unknown(e.to_string()))
+    tx.commit().await
 }
 
 pub async fn delete(
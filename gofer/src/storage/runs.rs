@@ -1,25 +1,33 @@
-use crate::storage::{SqliteErrors, StorageError, MAX_ROW_LIMIT};
+use crate::storage::{encryption::Cipher, Conn, SqliteErrors, StorageError, MAX_ROW_LIMIT};
 use futures::TryFutureExt;
 use gofer_models::run::{Run, State, Status, StatusReason, StoreInfo};
 use gofer_models::Variable;
-use sqlx::{sqlite::SqliteRow, Acquire, QueryBuilder, Row, Sqlite, SqliteConnection};
+use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqliteConnection};
 use std::ops::{Deref, Not};
 use std::str::FromStr;
 
 #[derive(Debug, Default)]
 pub struct UpdatableFields {
+    pub started: Option<u64>,
     pub ended: Option<u64>,
     pub state: Option<State>,
     pub status: Option<Status>,
     pub failure_info: Option<StatusReason>,
     pub variables: Option<Vec<Variable>>,
-    pub store_info: Option<StoreInfo>,
+    pub store_objects_expired: Option<bool>,
+}
+
+/// Private variable values are encrypted at rest.
+fn decrypt_run(cipher: &Cipher, mut run: Run) -> Result<Run, StorageError> {
+    run.variables = cipher.decrypt_variables("variables", run.variables)?;
+
+    Ok(run)
 }
 
 /// Return all runs for a given namespace/pipeline; limited to 200 rows per response.
 /// Returns runs by id(which is sequential) in descending order.
 pub async fn list(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     offset: u64,
     limit: u64,
     namespace_id: &str,
@@ -31,12 +39,19 @@ pub async fn list(
         limit = MAX_ROW_LIMIT;
     }
 
+    let cipher = conn.cipher();
+
     let runs = sqlx::query(
         r#"
-SELECT namespace, pipeline, id, started, ended, state, status, failure_info, trigger, variables, store_info
+SELECT namespace, pipeline, id, started, ended, state, status, failure_info, trigger, variables,
+    store_objects_expired, priority,
+    (SELECT json_group_array(key) FROM (
+        SELECT key FROM object_store_run_keys
+        WHERE namespace = runs.namespace AND pipeline = runs.pipeline AND run = runs.id
+        ORDER BY key)) AS store_keys
 FROM runs
 WHERE namespace = ? AND pipeline = ?
-ORDER BY started DESC
+ORDER BY id DESC
 LIMIT ?
 OFFSET ?;"#,
     )
@@ -80,45 +95,226 @@ OFFSET ?;"#,
             let variables_json = row.get::<String, _>("variables");
             serde_json::from_str(&variables_json).unwrap()
         },
-        store_info: {
-            let store_info = row.get::<String, _>("store_info");
-            store_info
+        store_info: StoreInfo {
+            is_expired: row.get::<bool, _>("store_objects_expired"),
+            keys: {
+                let store_keys_json = row.get::<String, _>("store_keys");
+                serde_json::from_str(&store_keys_json).unwrap()
+            },
+        },
+        priority: row.get::<i64, _>("priority"),
+    })
+    .fetch_all(&mut **conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    runs.into_iter()
+        .map(|run| decrypt_run(&cipher, run))
+        .collect()
+}
+
+/// Return all runs, across every namespace and pipeline, that are currently running.
+/// This is used on startup to pick back up runs that were in progress when Gofer last shut down.
+pub async fn list_running(conn: &mut Conn<'_>) -> Result<Vec<Run>, StorageError> {
+    let cipher = conn.cipher();
+
+    let runs = sqlx::query(
+        r#"
+SELECT namespace, pipeline, id, started, ended, state, status, failure_info, trigger, variables,
+    store_objects_expired, priority,
+    (SELECT json_group_array(key) FROM (
+        SELECT key FROM object_store_run_keys
+        WHERE namespace = runs.namespace AND pipeline = runs.pipeline AND run = runs.id
+        ORDER BY key)) AS store_keys
+FROM runs
+WHERE state = ?
+ORDER BY started ASC;"#,
+    )
+    .bind(State::Running.to_string())
+    .map(|row: SqliteRow| Run {
+        namespace: row.get("namespace"),
+        pipeline: row.get("pipeline"),
+        started: row.get::<i64, _>("started") as u64,
+        ended: row.get::<i64, _>("ended") as u64,
+        id: row.get::<i64, _>("id") as u64,
+        state: State::from_str(row.get("state"))
+            .map_err(|_| StorageError::Parse {
+                value: row.get("state"),
+                column: "state".to_string(),
+                err: "could not parse value into run state enum".to_string(),
+            })
+            .unwrap(),
+        status: Status::from_str(row.get("status"))
+            .map_err(|_| StorageError::Parse {
+                value: row.get("status"),
+                column: "status".to_string(),
+                err: "could not parse value into run status enum".to_string(),
+            })
+            .unwrap(),
+        status_reason: {
+            let failure_info = row.get::<String, _>("failure_info");
+            failure_info
                 .is_empty()
                 .not()
-                .then(|| serde_json::from_str(&store_info).unwrap())
+                .then(|| serde_json::from_str(&failure_info).unwrap())
+        },
+        task_runs: vec![],
+        trigger: {
+            let trigger_info_json = row.get::<String, _>("trigger");
+            serde_json::from_str(&trigger_info_json).unwrap()
         },
+        variables: {
+            let variables_json = row.get::<String, _>("variables");
+            serde_json::from_str(&variables_json).unwrap()
+        },
+        store_info: StoreInfo {
+            is_expired: row.get::<bool, _>("store_objects_expired"),
+            keys: {
+                let store_keys_json = row.get::<String, _>("store_keys");
+                serde_json::from_str(&store_keys_json).unwrap()
+            },
+        },
+        priority: row.get::<i64, _>("priority"),
     })
-    .fetch_all(conn)
+    .fetch_all(&mut **conn)
     .map_err(|e| StorageError::Unknown(e.to_string()))
     .await?;
 
-    Ok(runs)
+    runs.into_iter()
+        .map(|run| decrypt_run(&cipher, run))
+        .collect()
 }
 
-/// Insert a new run.
-pub async fn insert(conn: &mut SqliteConnection, run: &Run) -> Result<u64, StorageError> {
-    let mut tx = conn
-        .begin()
-        .map_err(|e| StorageError::Unknown(e.to_string()))
-        .await?;
+/// Return all runs, across every namespace and pipeline, that are waiting to be started. This is the run queue;
+/// runs are returned in the order they should be started: highest priority first and then oldest first.
+pub async fn list_pending(conn: &mut Conn<'_>) -> Result<Vec<Run>, StorageError> {
+    let cipher = conn.cipher();
 
-    let last_run = list(&mut tx, 0, 1, &run.namespace, &run.pipeline).await?;
+    let runs = sqlx::query(
+        r#"
+SELECT namespace, pipeline, id, started, ended, state, status, failure_info, trigger, variables,
+    store_objects_expired, priority,
+    (SELECT json_group_array(key) FROM (
+        SELECT key FROM object_store_run_keys
+        WHERE namespace = runs.namespace AND pipeline = runs.pipeline AND run = runs.id
+        ORDER BY key)) AS store_keys
+FROM runs
+WHERE state = ?
+ORDER BY priority DESC, started ASC, id ASC;"#,
+    )
+    .bind(State::Pending.to_string())
+    .map(|row: SqliteRow| Run {
+        namespace: row.get("namespace"),
+        pipeline: row.get("pipeline"),
+        started: row.get::<i64, _>("started") as u64,
+        ended: row.get::<i64, _>("ended") as u64,
+        id: row.get::<i64, _>("id") as u64,
+        state: State::from_str(row.get("state"))
+            .map_err(|_| StorageError::Parse {
+                value: row.get("state"),
+                column: "state".to_string(),
+                err: "could not parse value into run state enum".to_string(),
+            })
+            .unwrap(),
+        status: Status::from_str(row.get("status"))
+            .map_err(|_| StorageError::Parse {
+                value: row.get("status"),
+                column: "status".to_string(),
+                err: "could not parse value into run status enum".to_string(),
+            })
+            .unwrap(),
+        status_reason: {
+            let failure_info = row.get::<String, _>("failure_info");
+            failure_info
+                .is_empty()
+                .not()
+                .then(|| serde_json::from_str(&failure_info).unwrap())
+        },
+        task_runs: vec![],
+        trigger: {
+            let trigger_info_json = row.get::<String, _>("trigger");
+            serde_json::from_str(&trigger_info_json).unwrap()
+        },
+        variables: {
+            let variables_json = row.get::<String, _>("variables");
+            serde_json::from_str(&variables_json).unwrap()
+        },
+        store_info: StoreInfo {
+            is_expired: row.get::<bool, _>("store_objects_expired"),
+            keys: {
+                let store_keys_json = row.get::<String, _>("store_keys");
+                serde_json::from_str(&store_keys_json).unwrap()
+            },
+        },
+        priority: row.get::<i64, _>("priority"),
+    })
+    .fetch_all(&mut **conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
 
-    let mut next_id = 1;
+    runs.into_iter()
+        .map(|run| decrypt_run(&cipher, run))
+        .collect()
+}
 
-    if !last_run.is_empty() {
-        next_id = last_run[0].id + 1;
-    }
+/// Return the number of runs, across every namespace and pipeline, that are currently running.
+pub async fn count_all_running(conn: &mut SqliteConnection) -> Result<u64, StorageError> {
+    let count = sqlx::query(
+        r#"
+SELECT COUNT(*) AS total
+FROM runs
+WHERE state = ?;"#,
+    )
+    .bind(State::Running.to_string())
+    .map(|row: SqliteRow| row.get::<i64, _>("total") as u64)
+    .fetch_one(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    Ok(count)
+}
+
+/// Return the number of runs for a given pipeline that are currently running.
+pub async fn count_running(
+    conn: &mut SqliteConnection,
+    namespace_id: &str,
+    pipeline_id: &str,
+) -> Result<u64, StorageError> {
+    let count = sqlx::query(
+        r#"
+SELECT COUNT(*) AS total
+FROM runs
+WHERE namespace = ? AND pipeline = ? AND state = ?;"#,
+    )
+    .bind(namespace_id)
+    .bind(pipeline_id)
+    .bind(State::Running.to_string())
+    .map(|row: SqliteRow| row.get::<i64, _>("total") as u64)
+    .fetch_one(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    Ok(count)
+}
 
+/// Insert a new run.
+pub async fn insert(conn: &mut Conn<'_>, run: &Run) -> Result<u64, StorageError> {
+    let mut tx = conn.begin().await?;
+
+    // Runs are numbered after the highest id rather than the latest start time, since queued runs have their
+    // start time reset when they're dispatched. Working out the id within the insert means the transaction
+    // takes the write lock before it reads anything.
     sqlx::query(
         r#"
 INSERT INTO runs (namespace, pipeline, id, started, ended, state, status, failure_info,
-    trigger, variables, store_info)
-VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
+    trigger, variables, store_objects_expired, priority)
+VALUES (?, ?, (SELECT COALESCE(MAX(id), 0) + 1 FROM runs WHERE namespace = ? AND pipeline = ?),
+    ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     )
     .bind(&run.namespace)
     .bind(&run.pipeline)
-    .bind(next_id as i64)
+    .bind(&run.namespace)
+    .bind(&run.pipeline)
     .bind(run.started as i64)
     .bind(run.ended as i64)
     .bind(run.state.to_string())
@@ -130,14 +326,10 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
             .then(|| serde_json::to_string(&run.status_reason).unwrap()),
     )
     .bind(serde_json::to_string(&run.trigger).unwrap())
-    .bind(serde_json::to_string(&run.variables).unwrap())
-    .bind(
-        run.store_info
-            .is_none()
-            .not()
-            .then(|| serde_json::to_string(&run.store_info).unwrap()),
-    )
-    .execute(&mut tx)
+    .bind(serde_json::to_string(&tx.cipher().encrypt_variables(&run.variables)?).unwrap())
+    .bind(run.store_info.is_expired)
+    .bind(run.priority)
+    .execute(&mut *tx)
     .map_err(|e| match e {
         sqlx::Error::Database(database_err) => {
             if let Some(err_code) = database_err.code() {
@@ -151,23 +343,41 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     })
     .await?;
 
-    tx.commit()
-        .await
-        .map_err(|e| StorageError::Unknown(e.to_string()))?;
+    let id = sqlx::query(
+        r#"
+SELECT MAX(id) AS id
+FROM runs
+WHERE namespace = ? AND pipeline = ?;"#,
+    )
+    .bind(&run.namespace)
+    .bind(&run.pipeline)
+    .map(|row: SqliteRow| row.get::<i64, _>("id") as u64)
+    .fetch_one(&mut *tx)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    tx.commit().await?;
 
-    Ok(next_id)
+    Ok(id)
 }
 
 /// Get details on a specific run.
 pub async fn get(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     namespace_id: &str,
     pipeline_id: &str,
     id: u64,
 ) -> Result<Run, StorageError> {
-    sqlx::query(
+    let cipher = conn.cipher();
+
+    let run = sqlx::query(
         r#"
-SELECT namespace, pipeline, id, started, ended, state, status, failure_info, trigger, variables, store_info
+SELECT namespace, pipeline, id, started, ended, state, status, failure_info, trigger, variables,
+    store_objects_expired, priority,
+    (SELECT json_group_array(key) FROM (
+        SELECT key FROM object_store_run_keys
+        WHERE namespace = runs.namespace AND pipeline = runs.pipeline AND run = runs.id
+        ORDER BY key)) AS store_keys
 FROM runs
 WHERE namespace = ? AND pipeline = ? AND id = ?;"#,
     )
@@ -210,25 +420,28 @@ WHERE namespace = ? AND pipeline = ? AND id = ?;"#,
             let variables_json = row.get::<String, _>("variables");
             serde_json::from_str(&variables_json).unwrap()
         },
-        store_info: {
-            let store_info = row.get::<String, _>("store_info");
-            store_info
-                .is_empty()
-                .not()
-                .then(|| serde_json::from_str(&store_info).unwrap())
+        store_info: StoreInfo {
+            is_expired: row.get::<bool, _>("store_objects_expired"),
+            keys: {
+                let store_keys_json = row.get::<String, _>("store_keys");
+                serde_json::from_str(&store_keys_json).unwrap()
+            },
         },
+        priority: row.get::<i64, _>("priority"),
     })
-    .fetch_one(conn)
+    .fetch_one(&mut **conn)
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => StorageError::NotFound,
         _ => StorageError::Unknown(e.to_string()),
     })
-    .await
+    .await?;
+
+    decrypt_run(&cipher, run)
 }
 
 /// Update a specific run.
 pub async fn update(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     run: &Run,
     fields: UpdatableFields,
 ) -> Result<(), StorageError> {
@@ -236,7 +449,16 @@ pub async fn update(
 
     let mut updated_fields_total = 0;
 
+    if let Some(started) = fields.started {
+        update_query.push("started = ");
+        update_query.push_bind(started as i64);
+        updated_fields_total += 1;
+    }
+
     if let Some(ended) = fields.ended {
+        if updated_fields_total > 0 {
+            update_query.push(", ");
+        }
         update_query.push("ended = ");
         update_query.push_bind(ended as i64);
         updated_fields_total += 1;
@@ -274,16 +496,18 @@ pub async fn update(
             update_query.push(", ");
         }
         update_query.push("variables = ");
-        update_query.push_bind(serde_json::to_string(&variables).unwrap());
+        update_query.push_bind(
+            serde_json::to_string(&conn.cipher().encrypt_variables(&variables)?).unwrap(),
+        );
         updated_fields_total += 1;
     }
 
-    if let Some(store_info) = fields.store_info {
+    if let Some(store_objects_expired) = fields.store_objects_expired {
         if updated_fields_total > 0 {
             update_query.push(", ");
         }
-        update_query.push("store_info = ");
-        update_query.push_bind(serde_json::to_string(&store_info).unwrap());
+        update_query.push("store_objects_expired = ");
+        update_query.push_bind(store_objects_expired);
     }
 
     update_query.push(" WHERE namespace = ");
@@ -299,7 +523,7 @@ pub async fn update(
     let update_query = update_query.build();
 
     update_query
-        .execute(conn)
+        .execute(&mut **conn)
         .map_ok(|_| ())
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => StorageError::NotFound,
@@ -0,0 +1,111 @@
+use crate::storage::{SqliteErrors, StorageError};
+use futures::TryFutureExt;
+use gofer_models::secret::Secret;
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
+use std::ops::Deref;
+
+/// Return all secret keys for a pipeline. The secret values themselves live in the secret store.
+pub async fn list(
+    conn: &mut SqliteConnection,
+    namespace: &str,
+    pipeline: &str,
+) -> Result<Vec<Secret>, StorageError> {
+    sqlx::query(
+        r#"
+SELECT namespace, pipeline, key, created
+FROM pipeline_secrets
+WHERE namespace = ? AND pipeline = ?
+ORDER BY key;"#,
+    )
+    .bind(namespace)
+    .bind(pipeline)
+    .map(|row: SqliteRow| Secret {
+        namespace: row.get("namespace"),
+        pipeline: row.get("pipeline"),
+        key: row.get("key"),
+        created: row.get::<i64, _>("created") as u64,
+    })
+    .fetch_all(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await
+}
+
+/// Insert a new secret key.
+pub async fn insert(conn: &mut SqliteConnection, secret: &Secret) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+INSERT INTO pipeline_secrets (namespace, pipeline, key, created)
+VALUES (?, ?, ?, ?);"#,
+    )
+    .bind(&secret.namespace)
+    .bind(&secret.pipeline)
+    .bind(&secret.key)
+    .bind(secret.created as i64)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::Database(database_err) => {
+            if let Some(err_code) = database_err.code() {
+                if err_code.deref() == SqliteErrors::Constraint.value() {
+                    return StorageError::Exists;
+                }
+            }
+            StorageError::Unknown(database_err.message().to_string())
+        }
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
+/// Get details on a specific secret key.
+pub async fn get(
+    conn: &mut SqliteConnection,
+    namespace: &str,
+    pipeline: &str,
+    key: &str,
+) -> Result<Secret, StorageError> {
+    sqlx::query(
+        r#"
+SELECT namespace, pipeline, key, created
+FROM pipeline_secrets
+WHERE namespace = ? AND pipeline = ? AND key = ?;"#,
+    )
+    .bind(namespace)
+    .bind(pipeline)
+    .bind(key)
+    .map(|row: SqliteRow| Secret {
+        namespace: row.get("namespace"),
+        pipeline: row.get("pipeline"),
+        key: row.get("key"),
+        created: row.get::<i64, _>("created") as u64,
+    })
+    .fetch_one(conn)
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
+
+pub async fn delete(
+    conn: &mut SqliteConnection,
+    namespace: &str,
+    pipeline: &str,
+    key: &str,
+) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+DELETE FROM pipeline_secrets
+WHERE namespace = ? AND pipeline = ? AND key = ?;"#,
+    )
+    .bind(namespace)
+    .bind(pipeline)
+    .bind(key)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await
+}
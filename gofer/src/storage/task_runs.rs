@@ -1,12 +1,19 @@
 use std::ops::{Deref, Not};
 
-use crate::storage::{SqliteErrors, StorageError, MAX_ROW_LIMIT};
+use crate::storage::{encryption::Cipher, Conn, SqliteErrors, StorageError, MAX_ROW_LIMIT};
 use futures::TryFutureExt;
 use gofer_models::task_run::{State, Status, StatusReason, TaskRun};
 use gofer_models::Variable;
 use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqliteConnection};
 use std::str::FromStr;
 
+fn decrypt_task_run(cipher: &Cipher, mut task_run: TaskRun) -> Result<TaskRun, StorageError> {
+    task_run.task = cipher.decrypt_task("task", task_run.task)?;
+    task_run.variables = cipher.decrypt_variables("variables", task_run.variables)?;
+
+    Ok(task_run)
+}
+
 #[derive(Debug, Default)]
 pub struct UpdatableFields {
     pub started: Option<u64>,
@@ -22,8 +29,9 @@ pub struct UpdatableFields {
 }
 
 /// Return all task_run for a given namespace/pipeline/run; limited to 200 rows per response.
+/// Every attempt of a task run is returned, ordered by task run id and then attempt.
 pub async fn list(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     offset: u64,
     limit: u64,
     namespace_id: &str,
@@ -36,13 +44,16 @@ pub async fn list(
         limit = MAX_ROW_LIMIT;
     }
 
+    let cipher = conn.cipher();
+
     // First we need to get the general task_run information.
-    let task_runs = sqlx::query(
+    let task_runs: Vec<TaskRun> = sqlx::query(
             r#"
 SELECT namespace, pipeline, run, id, task, created, started, ended, exit_code, failure,
-logs_expired, logs_removed, state, status, scheduler_id, variables
+logs_expired, logs_removed, state, status, scheduler_id, variables, attempt
 FROM task_runs
 WHERE namespace = ? AND pipeline = ? AND run = ?
+ORDER BY id ASC, attempt ASC
 LIMIT ?
 OFFSET ?;"#,
         )
@@ -103,27 +114,31 @@ OFFSET ?;"#,
                 let variables_json = row.get::<String, _>("variables");
                 serde_json::from_str(&variables_json).unwrap()
             },
+            attempt: row.get::<i64, _>("attempt") as u64,
         })
-        .fetch_all(conn)
+        .fetch_all(&mut **conn)
         .map_err(|e| StorageError::Unknown(e.to_string()))
         .await?;
 
-    Ok(task_runs)
+    task_runs
+        .into_iter()
+        .map(|task_run| decrypt_task_run(&cipher, task_run))
+        .collect()
 }
 
 /// Insert a new task_run.
-pub async fn insert(conn: &mut SqliteConnection, task_run: &TaskRun) -> Result<(), StorageError> {
+pub async fn insert(conn: &mut Conn<'_>, task_run: &TaskRun) -> Result<(), StorageError> {
     sqlx::query(
         r#"
 INSERT INTO task_runs (namespace, pipeline, run, id, task, created, started, ended,
-    exit_code, failure, logs_expired, logs_removed, state, status, scheduler_id, variables)
-VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
+    exit_code, failure, logs_expired, logs_removed, state, status, scheduler_id, variables, attempt)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     )
     .bind(&task_run.namespace)
     .bind(&task_run.pipeline)
     .bind(task_run.run as i64)
     .bind(&task_run.id)
-    .bind(serde_json::to_string(&task_run.task).unwrap())
+    .bind(serde_json::to_string(&conn.cipher().encrypt_task(&task_run.task)?).unwrap())
     .bind(task_run.created as i64)
     .bind(task_run.started as i64)
     .bind(task_run.ended as i64)
@@ -154,8 +169,9 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     .bind(task_run.state.to_string())
     .bind(task_run.status.to_string())
     .bind(&task_run.scheduler_id)
-    .bind(serde_json::to_string(&task_run.variables).unwrap())
-    .execute(conn)
+    .bind(serde_json::to_string(&conn.cipher().encrypt_variables(&task_run.variables)?).unwrap())
+    .bind(task_run.attempt as i64)
+    .execute(&mut **conn)
     .map_ok(|_| ())
     .map_err(|e| match e {
         sqlx::Error::Database(database_err) => {
@@ -171,25 +187,32 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
     .await
 }
 
-/// Get details on a specific task_run.
+/// Get details on a specific task_run attempt. An attempt of 0 returns the latest attempt.
 pub async fn get(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     namespace_id: &str,
     pipeline_id: &str,
     run_id: u64,
     id: &str,
+    attempt: u64,
 ) -> Result<TaskRun, StorageError> {
+    let cipher = conn.cipher();
+
     let task_run = sqlx::query(
             r#"
 SELECT namespace, pipeline, run, id, task, created, started, ended, exit_code, failure,
-logs_expired, logs_removed, state, status, scheduler_id, variables
+logs_expired, logs_removed, state, status, scheduler_id, variables, attempt
 FROM task_runs
-WHERE namespace = ? AND pipeline = ? AND run = ? AND id = ?;"#,
+WHERE namespace = ? AND pipeline = ? AND run = ? AND id = ? AND (? = 0 OR attempt = ?)
+ORDER BY attempt DESC
+LIMIT 1;"#,
         )
         .bind(namespace_id)
         .bind(pipeline_id)
         .bind(run_id as i64)
         .bind(id)
+        .bind(attempt as i64)
+        .bind(attempt as i64)
         .map(|row: SqliteRow| TaskRun {
             namespace: row.get("namespace"),
             pipeline: row.get("pipeline"),
@@ -242,20 +265,21 @@ WHERE namespace = ? AND pipeline = ? AND run = ? AND id = ?;"#,
                 let variables_json = row.get::<String, _>("variables");
                 serde_json::from_str(&variables_json).unwrap()
             },
+            attempt: row.get::<i64, _>("attempt") as u64,
         })
-        .fetch_one(conn)
+        .fetch_one(&mut **conn)
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => StorageError::NotFound,
             _ => StorageError::Unknown(e.to_string()),
         })
         .await?;
 
-    Ok(task_run)
+    decrypt_task_run(&cipher, task_run)
 }
 
 /// Update a specific task_run.
 pub async fn update(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     task_run: &TaskRun,
     fields: UpdatableFields,
 ) -> Result<(), StorageError> {
@@ -356,7 +380,9 @@ pub async fn update(
             update_query.push(", ");
         }
         update_query.push("variables = ");
-        update_query.push_bind(serde_json::to_string(&variables).unwrap());
+        update_query.push_bind(
+            serde_json::to_string(&conn.cipher().encrypt_variables(&variables)?).unwrap(),
+        );
     }
 
     update_query.push(" WHERE namespace = ");
@@ -370,12 +396,15 @@ pub async fn update(
 
     update_query.push(" AND id = ");
     update_query.push_bind(&task_run.id);
+
+    update_query.push(" AND attempt = ");
+    update_query.push_bind(task_run.attempt as i64);
     update_query.push(";");
 
     let update_query = update_query.build();
 
     update_query
-        .execute(conn)
+        .execute(&mut **conn)
         .map_ok(|_| ())
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => StorageError::NotFound,
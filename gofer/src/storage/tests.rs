@@ -2,8 +2,11 @@ use super::*;
 use gofer_models::*;
 use pretty_assertions::assert_eq;
 use rand::prelude::*;
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
 use std::{collections::HashMap, ops::Deref};
 
+const TEST_ENCRYPTION_KEY: &str = "changemechangemechangemechangeme";
+
 struct TestHarness {
     db: Db,
     storage_path: String,
@@ -15,7 +18,7 @@ impl TestHarness {
         let append_num: u8 = rng.gen();
         let storage_path = format!("/tmp/gofer_tests_storage{}.db", append_num);
 
-        let db = Db::new(&storage_path).await.unwrap();
+        let db = Db::new(&storage_path, TEST_ENCRYPTION_KEY).await.unwrap();
 
         Self { db, storage_path }
     }
@@ -107,10 +110,14 @@ async fn crud_pipelines() {
         gofer_sdk::config::Pipeline::new("test_pipeline_full", "Test Pipeline")
             .description("a fully loaded pipeline config for testing")
             .parallelism(10)
+            .run_timeout(std::time::Duration::from_secs(3600))
             .tasks(vec![gofer_sdk::config::Task::new(
                 "test_task",
                 "test_image",
-            )])
+            )
+            .timeout(std::time::Duration::from_secs(300))
+            .cpu(500, 1000)
+            .memory(256, 512)])
             .triggers(vec![gofer_sdk::config::PipelineTriggerConfig::new(
                 "test_trigger",
                 "test_trigger",
@@ -139,7 +146,33 @@ async fn crud_pipelines() {
 
     assert_eq!(pipeline, test_pipeline);
 
+    let mut test_trigger_settings = test_pipeline_full.triggers["test_trigger"].clone();
+    test_trigger_settings.error = Some("could not subscribe".to_string());
+
+    pipelines::update_trigger_settings(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline_full.id,
+        &test_trigger_settings,
+    )
+    .await
+    .unwrap();
+
+    let subscriptions = pipelines::list_active_trigger_subscriptions(&mut conn, "test_trigger")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        subscriptions,
+        vec![(
+            test_namespace.id.clone(),
+            test_pipeline_full.id.clone(),
+            test_trigger_settings
+        )]
+    );
+
     test_pipeline.name = "Test Pipeline Updated".to_string();
+    test_pipeline.run_timeout = 600;
 
     pipelines::update(
         &mut conn,
@@ -147,6 +180,7 @@ async fn crud_pipelines() {
         &test_pipeline.id,
         pipelines::UpdatableFields {
             name: Some(test_pipeline.name.clone()),
+            run_timeout: Some(test_pipeline.run_timeout),
             ..Default::default()
         },
     )
@@ -208,6 +242,7 @@ async fn crud_runs() {
         },
         vec![],
     );
+    test_run_2.priority = 5;
     runs::insert(&mut conn, &test_run_2).await.unwrap();
 
     let runs = runs::list(&mut conn, 0, 0, &test_namespace.id, &test_pipeline.id)
@@ -255,6 +290,31 @@ async fn crud_runs() {
     .unwrap();
     assert_eq!(run, test_run);
 
+    let pending_runs = runs::list_pending(&mut conn).await.unwrap();
+    assert_eq!(pending_runs, vec![test_run_2.clone()]);
+
+    runs::update(
+        &mut conn,
+        &test_run_2,
+        runs::UpdatableFields {
+            state: Some(run::State::Running),
+            started: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    test_run_2.state = run::State::Running;
+    test_run_2.started = 2;
+
+    let running_runs = runs::list_running(&mut conn).await.unwrap();
+    assert_eq!(running_runs, vec![test_run_2]);
+
+    let running_total = runs::count_running(&mut conn, &test_namespace.id, &test_pipeline.id)
+        .await
+        .unwrap();
+    assert_eq!(running_total, 1);
+
     runs::delete(
         &mut conn,
         &test_namespace.id,
@@ -341,6 +401,7 @@ async fn crud_task_runs() {
         &test_pipeline.id,
         test_run.id,
         &test_task_run.id,
+        0,
     )
     .await
     .unwrap();
@@ -365,12 +426,60 @@ async fn crud_task_runs() {
         &test_pipeline.id,
         test_run.id,
         &test_task_run.id,
+        0,
     )
     .await
     .unwrap();
 
     assert_eq!(task_run, test_task_run);
 
+    let mut test_task_run_retry = test_task_run.clone();
+    test_task_run_retry.attempt = 2;
+    test_task_run_retry.state = task_run::State::Processing;
+
+    task_runs::insert(&mut conn, &test_task_run_retry)
+        .await
+        .unwrap();
+
+    let task_run = task_runs::get(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        test_run.id,
+        &test_task_run.id,
+        0,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(task_run, test_task_run_retry);
+
+    let task_run = task_runs::get(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        test_run.id,
+        &test_task_run.id,
+        1,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(task_run, test_task_run);
+
+    let task_runs = task_runs::list(
+        &mut conn,
+        0,
+        0,
+        &test_namespace.id,
+        &test_pipeline.id,
+        test_run.id,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(task_runs, vec![test_task_run.clone(), test_task_run_retry]);
+
     task_runs::delete(
         &mut conn,
         &test_namespace.id,
@@ -387,6 +496,7 @@ async fn crud_task_runs() {
         &test_pipeline.id,
         test_run.id,
         &test_task_run.id,
+        0,
     )
     .await
     .unwrap_err();
@@ -512,3 +622,597 @@ async fn crud_common_task_registrations() {
 
     assert_eq!(common_task, StorageError::NotFound);
 }
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for pipeline secrets.
+async fn crud_secrets() {
+    let harness = TestHarness::new().await;
+    let mut conn = harness.conn().await.unwrap();
+
+    let test_namespace =
+        namespace::Namespace::new("test_namespace", "Test Namespace", "Test Description");
+    namespaces::insert(&mut conn, &test_namespace)
+        .await
+        .unwrap();
+
+    let test_pipeline_config = gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline");
+    let test_pipeline = pipeline::Pipeline::new(&test_namespace.id, test_pipeline_config);
+    pipelines::insert(&mut conn, &test_pipeline).await.unwrap();
+
+    let test_secret = secret::Secret::new(&test_namespace.id, &test_pipeline.id, "test_key");
+
+    secrets::insert(&mut conn, &test_secret).await.unwrap();
+
+    let err = secrets::insert(&mut conn, &test_secret).await.unwrap_err();
+    assert_eq!(err, StorageError::Exists);
+
+    let secrets = secrets::list(&mut conn, &test_namespace.id, &test_pipeline.id)
+        .await
+        .unwrap();
+
+    assert_eq!(secrets.len(), 1);
+    assert_eq!(secrets[0], test_secret);
+
+    let secret = secrets::get(&mut conn, &test_namespace.id, &test_pipeline.id, "test_key")
+        .await
+        .unwrap();
+    assert_eq!(secret, test_secret);
+
+    secrets::delete(&mut conn, &test_namespace.id, &test_pipeline.id, "test_key")
+        .await
+        .unwrap();
+
+    let secret = secrets::get(&mut conn, &test_namespace.id, &test_pipeline.id, "test_key")
+        .await
+        .unwrap_err();
+
+    assert_eq!(secret, StorageError::NotFound);
+}
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for pipeline and run object keys.
+async fn crud_objects() {
+    let harness = TestHarness::new().await;
+    let mut conn = harness.conn().await.unwrap();
+
+    let test_namespace =
+        namespace::Namespace::new("test_namespace", "Test Namespace", "Test Description");
+    namespaces::insert(&mut conn, &test_namespace)
+        .await
+        .unwrap();
+
+    let test_pipeline_config = gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline");
+    let test_pipeline = pipeline::Pipeline::new(&test_namespace.id, test_pipeline_config);
+    pipelines::insert(&mut conn, &test_pipeline).await.unwrap();
+
+    let test_run = run::Run::new(
+        &test_namespace.id,
+        &test_pipeline.id,
+        run::TriggerInfo {
+            name: "test_trigger".to_string(),
+            label: "my_test_trigger".to_string(),
+        },
+        vec![],
+    );
+    let run_id = runs::insert(&mut conn, &test_run).await.unwrap();
+
+    let pipeline_key = object::ObjectKey {
+        key: "pipeline_key".to_string(),
+        created: 1,
+    };
+    let newer_pipeline_key = object::ObjectKey {
+        key: "newer_pipeline_key".to_string(),
+        created: 2,
+    };
+
+    objects::insert_pipeline_key(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        &pipeline_key,
+    )
+    .await
+    .unwrap();
+    objects::insert_pipeline_key(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        &newer_pipeline_key,
+    )
+    .await
+    .unwrap();
+
+    let err = objects::insert_pipeline_key(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        &pipeline_key,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err, StorageError::Exists);
+
+    // Keys are listed oldest first.
+    let pipeline_keys =
+        objects::list_pipeline_keys(&mut conn, &test_namespace.id, &test_pipeline.id)
+            .await
+            .unwrap();
+    assert_eq!(
+        pipeline_keys,
+        vec![pipeline_key.clone(), newer_pipeline_key.clone()]
+    );
+
+    let pipeline = pipelines::get(&mut conn, &test_namespace.id, &test_pipeline.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        pipeline.store_keys,
+        vec![pipeline_key.key.clone(), newer_pipeline_key.key.clone()]
+    );
+
+    objects::delete_pipeline_key(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        "pipeline_key",
+    )
+    .await
+    .unwrap();
+
+    let pipeline_keys =
+        objects::list_pipeline_keys(&mut conn, &test_namespace.id, &test_pipeline.id)
+            .await
+            .unwrap();
+    assert_eq!(pipeline_keys, vec![newer_pipeline_key]);
+
+    let run_key = object::ObjectKey::new("run_key");
+
+    objects::insert_run_key(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        run_id,
+        &run_key,
+    )
+    .await
+    .unwrap();
+
+    let run_keys = objects::list_run_keys(&mut conn, &test_namespace.id, &test_pipeline.id, run_id)
+        .await
+        .unwrap();
+    assert_eq!(run_keys, vec![run_key.clone()]);
+
+    let pipeline_run_keys =
+        objects::list_pipeline_run_keys(&mut conn, &test_namespace.id, &test_pipeline.id)
+            .await
+            .unwrap();
+    assert_eq!(pipeline_run_keys, vec![(run_id, run_key.clone())]);
+
+    // A run's store info is derived from its object keys.
+    let mut run = runs::get(&mut conn, &test_namespace.id, &test_pipeline.id, run_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        run.store_info,
+        run::StoreInfo {
+            is_expired: false,
+            keys: vec![run_key.key.clone()],
+        }
+    );
+
+    runs::update(
+        &mut conn,
+        &run,
+        runs::UpdatableFields {
+            store_objects_expired: Some(true),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    run = runs::get(&mut conn, &test_namespace.id, &test_pipeline.id, run_id)
+        .await
+        .unwrap();
+    assert!(run.store_info.is_expired);
+
+    objects::delete_run_key(
+        &mut conn,
+        &test_namespace.id,
+        &test_pipeline.id,
+        run_id,
+        "run_key",
+    )
+    .await
+    .unwrap();
+
+    let run_keys = objects::list_run_keys(&mut conn, &test_namespace.id, &test_pipeline.id, run_id)
+        .await
+        .unwrap();
+    assert!(run_keys.is_empty());
+}
+
+#[tokio::test]
+/// Basic CRUD can be accomplished for tokens.
+async fn crud_tokens() {
+    let harness = TestHarness::new().await;
+    let mut conn = harness.conn().await.unwrap();
+
+    let mut test_token = token::Token::new(
+        "test_hash",
+        token::Kind::Client,
+        HashMap::from([("user".to_string(), "obama".to_string())]),
+        vec!["test_namespace".to_string()],
+    )
+    .pipelines(vec!["test_pipeline".to_string()])
+    .expires(1);
+
+    test_token.id = tokens::insert(&mut conn, &test_token).await.unwrap();
+
+    let err = tokens::insert(&mut conn, &test_token).await.unwrap_err();
+    assert_eq!(err, StorageError::Exists);
+
+    let tokens = tokens::list(&mut conn).await.unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0], test_token);
+
+    let token = tokens::get(&mut conn, test_token.id).await.unwrap();
+    assert_eq!(token, test_token);
+
+    tokens::delete(&mut conn, test_token.id).await.unwrap();
+
+    let err = tokens::get(&mut conn, test_token.id).await.unwrap_err();
+    assert_eq!(err, StorageError::NotFound);
+}
+
+/// Returns the raw value of the first column of the first row returned by the query.
+async fn raw_value(conn: &mut SqliteConnection, query: &str) -> String {
+    sqlx::query(query)
+        .map(|row: SqliteRow| row.get::<String, _>(0))
+        .fetch_one(conn)
+        .await
+        .unwrap()
+}
+
+/// Inserts a run, task run and trigger registration that all carry sensitive values.
+async fn insert_sensitive_values(
+    conn: &mut Conn<'_>,
+) -> (
+    pipeline::Pipeline,
+    run::Run,
+    task_run::TaskRun,
+    trigger::Registration,
+) {
+    let test_namespace =
+        namespace::Namespace::new("test_namespace", "Test Namespace", "Test Description");
+    namespaces::insert(conn, &test_namespace).await.unwrap();
+
+    let mut test_task = task::Task::new("test_task", "test_image");
+    test_task.registry_auth = Some(task::RegistryAuth {
+        user: "registry_user".to_string(),
+        pass: "registry_pass".to_string(),
+    });
+
+    let test_pipeline_config = gofer_sdk::config::Pipeline::new("test_pipeline", "Test Pipeline");
+    let mut test_pipeline = pipeline::Pipeline::new(&test_namespace.id, test_pipeline_config);
+    test_pipeline.tasks = HashMap::from([("test_task".to_string(), test_task.clone())]);
+    pipelines::insert(conn, &test_pipeline).await.unwrap();
+
+    let variables = vec![
+        Variable {
+            key: "private_key".to_string(),
+            value: "private_value".to_string(),
+            owner: VariableOwner::User,
+            sensitivity: VariableSensitivity::Private,
+        },
+        Variable {
+            key: "public_key".to_string(),
+            value: "public_value".to_string(),
+            owner: VariableOwner::User,
+            sensitivity: VariableSensitivity::Public,
+        },
+    ];
+
+    let mut test_run = run::Run::new(
+        &test_namespace.id,
+        &test_pipeline.id,
+        run::TriggerInfo {
+            name: "test_trigger".to_string(),
+            label: "my_test_trigger".to_string(),
+        },
+        variables.clone(),
+    );
+    test_run.id = runs::insert(conn, &test_run).await.unwrap();
+
+    let mut test_task_run = task_run::TaskRun::new(
+        &test_namespace.id,
+        &test_pipeline.id,
+        test_run.id,
+        test_task,
+    );
+    test_task_run.variables = variables;
+    task_runs::insert(conn, &test_task_run).await.unwrap();
+
+    let test_trigger_registration = trigger::Registration {
+        name: "test_trigger".to_string(),
+        image: "docker/test".to_string(),
+        user: Some("trigger_user".to_string()),
+        pass: Some("trigger_pass".to_string()),
+        variables: HashMap::new(),
+        created: 0,
+        status: trigger::Status::Enabled,
+    };
+    trigger_registrations::insert(conn, &test_trigger_registration)
+        .await
+        .unwrap();
+
+    (
+        test_pipeline,
+        test_run,
+        test_task_run,
+        test_trigger_registration,
+    )
+}
+
+/// Checks that sensitive values aren't stored in plaintext and are read back decrypted.
+async fn assert_sensitive_values_encrypted(
+    conn: &mut Conn<'_>,
+    test_pipeline: &pipeline::Pipeline,
+    test_run: &run::Run,
+    test_task_run: &task_run::TaskRun,
+    test_trigger_registration: &trigger::Registration,
+) {
+    let registry_auth = raw_value(conn, "SELECT registry_auth FROM tasks;").await;
+    assert!(!registry_auth.contains("registry_pass"));
+
+    let run_variables = raw_value(conn, "SELECT variables FROM runs;").await;
+    assert!(!run_variables.contains("private_value"));
+    assert!(run_variables.contains("public_value"));
+
+    let task_run_task = raw_value(conn, "SELECT task FROM task_runs;").await;
+    assert!(!task_run_task.contains("registry_pass"));
+
+    let task_run_variables = raw_value(conn, "SELECT variables FROM task_runs;").await;
+    assert!(!task_run_variables.contains("private_value"));
+
+    let trigger_pass = raw_value(conn, "SELECT pass FROM trigger_registrations;").await;
+    assert!(!trigger_pass.contains("trigger_pass"));
+
+    let pipeline = pipelines::get(conn, &test_pipeline.namespace, &test_pipeline.id)
+        .await
+        .unwrap();
+    assert_eq!(&pipeline, test_pipeline);
+
+    let run = runs::get(conn, &test_run.namespace, &test_run.pipeline, test_run.id)
+        .await
+        .unwrap();
+    assert_eq!(&run, test_run);
+
+    let task_run = task_runs::get(
+        conn,
+        &test_task_run.namespace,
+        &test_task_run.pipeline,
+        test_task_run.run,
+        &test_task_run.id,
+        0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(&task_run, test_task_run);
+
+    let trigger = trigger_registrations::get(conn, &test_trigger_registration.name)
+        .await
+        .unwrap();
+    assert_eq!(&trigger, test_trigger_registration);
+}
+
+#[tokio::test]
+/// Sensitive values are encrypted at rest and decrypted when read.
+async fn encrypted_columns() {
+    let harness = TestHarness::new().await;
+    let mut conn = harness.conn().await.unwrap();
+
+    let (test_pipeline, test_run, test_task_run, test_trigger_registration) =
+        insert_sensitive_values(&mut conn).await;
+
+    assert_sensitive_values_encrypted(
+        &mut conn,
+        &test_pipeline,
+        &test_run,
+        &test_task_run,
+        &test_trigger_registration,
+    )
+    .await;
+}
+
+#[tokio::test]
+/// Sensitive values that can't be decrypted are returned as errors instead of panicking.
+async fn undecryptable_columns() {
+    let harness = TestHarness::new().await;
+    let mut conn = harness.conn().await.unwrap();
+
+    let (_, test_run, _, test_trigger_registration) = insert_sensitive_values(&mut conn).await;
+
+    sqlx::query("UPDATE runs SET variables = ?;")
+        .bind(serde_json::to_string(&test_run.variables).unwrap())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE trigger_registrations SET pass = 'trigger_pass';")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+    let err = runs::get(
+        &mut conn,
+        &test_run.namespace,
+        &test_run.pipeline,
+        test_run.id,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, StorageError::Parse { column, .. } if column == "variables"));
+
+    let err = trigger_registrations::get(&mut conn, &test_trigger_registration.name)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, StorageError::Parse { column, .. } if column == "pass"));
+}
+
+#[tokio::test]
+/// Sensitive values stored before they were encrypted are encrypted by the migration.
+async fn encrypt_existing_rows() {
+    let harness = TestHarness::new().await;
+    let mut conn = harness.conn().await.unwrap();
+    let cipher = conn.cipher();
+
+    let (test_pipeline, test_run, test_task_run, test_trigger_registration) =
+        insert_sensitive_values(&mut conn).await;
+
+    // Put the rows back the way they were stored before encryption and forget the migration ran.
+    let task = test_pipeline.tasks["test_task"].clone();
+    sqlx::query("UPDATE tasks SET registry_auth = ?;")
+        .bind(serde_json::to_string(&task.registry_auth).unwrap())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE runs SET variables = ?;")
+        .bind(serde_json::to_string(&test_run.variables).unwrap())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE task_runs SET task = ?, variables = ?;")
+        .bind(serde_json::to_string(&test_task_run.task).unwrap())
+        .bind(serde_json::to_string(&test_task_run.variables).unwrap())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE trigger_registrations SET pass = 'trigger_pass';")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM encrypted_columns_migration;")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+    encryption::encrypt_existing_rows(&mut conn, &cipher)
+        .await
+        .unwrap();
+
+    assert_sensitive_values_encrypted(
+        &mut conn,
+        &test_pipeline,
+        &test_run,
+        &test_task_run,
+        &test_trigger_registration,
+    )
+    .await;
+
+    // Running it again does nothing, so values aren't encrypted twice.
+    encryption::encrypt_existing_rows(&mut conn, &cipher)
+        .await
+        .unwrap();
+
+    assert_sensitive_values_encrypted(
+        &mut conn,
+        &test_pipeline,
+        &test_run,
+        &test_task_run,
+        &test_trigger_registration,
+    )
+    .await;
+}
+
+#[tokio::test]
+/// Databases created before the schema changed are migrated without losing their rows.
+async fn migrate_baseline_database() {
+    let mut rng = rand::thread_rng();
+    let append_num: u8 = rng.gen();
+    let storage_path = format!("/tmp/gofer_tests_storage_baseline{}.db", append_num);
+    let migrations_path = format!("/tmp/gofer_tests_baseline_migrations{}", append_num);
+
+    // Set up the database with only the first migration, the way it was originally released.
+    std::fs::create_dir_all(&migrations_path).unwrap();
+    std::fs::write(
+        format!("{}/0_init.sql", migrations_path),
+        include_str!("migrations/0_init.sql"),
+    )
+    .unwrap();
+    touch_file(Path::new(&storage_path)).unwrap();
+
+    let pool = SqlitePool::connect(&format!("file:{}", storage_path))
+        .await
+        .unwrap();
+    sqlx::migrate::Migrator::new(Path::new(&migrations_path))
+        .await
+        .unwrap()
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let test_task = task::Task::new("test_task", "test_image");
+    let mut baseline_task = serde_json::to_value(&test_task).unwrap();
+    for field in [
+        "timeout",
+        "cpu_request",
+        "cpu_limit",
+        "memory_request",
+        "memory_limit",
+        "retry_policy",
+    ] {
+        baseline_task.as_object_mut().unwrap().remove(field);
+    }
+
+    let statements = [
+        "INSERT INTO namespaces VALUES ('test_namespace', 'Test Namespace', '', 0, 0);".to_string(),
+        "INSERT INTO pipelines VALUES ('test_namespace', 'test_pipeline', 'Test Pipeline', '', 0, 0, 0, 'Active');"
+            .to_string(),
+        r#"INSERT INTO tasks VALUES ('test_namespace', 'test_pipeline', 'test_task', NULL, 'test_image', NULL, '{}', '[]', '[]', '[]');"#
+            .to_string(),
+        r#"INSERT INTO runs VALUES ('test_namespace', 'test_pipeline', 1, 0, 0, 'Complete', 'Successful', NULL, '{"name":"test_trigger","label":"my_test_trigger"}', '[]', '{"is_expired":true,"keys":["first_key","second_key"]}');"#
+            .to_string(),
+        format!(
+            "INSERT INTO task_runs VALUES ('test_namespace', 'test_pipeline', 1, 'test_task', '{}', 0, 0, 0, 0, NULL, 0, 0, 'Complete', 'Successful', NULL, '[]');",
+            baseline_task
+        ),
+    ];
+    for statement in statements {
+        sqlx::query(&statement).execute(&pool).await.unwrap();
+    }
+    pool.close().await;
+
+    let harness = TestHarness {
+        db: Db::new(&storage_path, TEST_ENCRYPTION_KEY).await.unwrap(),
+        storage_path,
+    };
+    let mut conn = harness.conn().await.unwrap();
+
+    let pipeline = pipelines::get(&mut conn, "test_namespace", "test_pipeline")
+        .await
+        .unwrap();
+    assert_eq!(pipeline.tasks["test_task"], test_task);
+
+    let run = runs::get(&mut conn, "test_namespace", "test_pipeline", 1)
+        .await
+        .unwrap();
+    assert!(run.store_info.is_expired);
+    assert_eq!(run.priority, 0);
+
+    let keys: Vec<String> = objects::list_run_keys(&mut conn, "test_namespace", "test_pipeline", 1)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|key| key.key)
+        .collect();
+    assert_eq!(keys, vec!["first_key", "second_key"]);
+
+    let task_runs = task_runs::list(&mut conn, 0, 0, "test_namespace", "test_pipeline", 1)
+        .await
+        .unwrap();
+    assert_eq!(task_runs.len(), 1);
+    assert_eq!(task_runs[0].attempt, 0);
+    assert_eq!(task_runs[0].task, test_task);
+
+    std::fs::remove_dir_all(&migrations_path).unwrap();
+}
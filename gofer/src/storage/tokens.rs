@@ -0,0 +1,119 @@
+use crate::storage::{SqliteErrors, StorageError};
+use futures::TryFutureExt;
+use gofer_models::token::{Kind, Token};
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection};
+use std::{ops::Deref, str::FromStr};
+
+fn from_row(row: SqliteRow) -> Result<Token, StorageError> {
+    let kind: String = row.get("kind");
+    let metadata: String = row.get("metadata");
+    let namespaces: String = row.get("namespaces");
+    let pipelines: String = row.get("pipelines");
+
+    Ok(Token {
+        id: row.get::<i64, _>("id") as u64,
+        hash: row.get("hash"),
+        created: row.get::<i64, _>("created") as u64,
+        kind: Kind::from_str(&kind).map_err(|_| StorageError::Parse {
+            value: kind.clone(),
+            column: "kind".to_string(),
+            err: "could not parse value into token kind enum".to_string(),
+        })?,
+        metadata: serde_json::from_str(&metadata).map_err(|e| StorageError::Parse {
+            value: metadata.clone(),
+            column: "metadata".to_string(),
+            err: e.to_string(),
+        })?,
+        namespaces: serde_json::from_str(&namespaces).map_err(|e| StorageError::Parse {
+            value: namespaces.clone(),
+            column: "namespaces".to_string(),
+            err: e.to_string(),
+        })?,
+        pipelines: serde_json::from_str(&pipelines).map_err(|e| StorageError::Parse {
+            value: pipelines.clone(),
+            column: "pipelines".to_string(),
+            err: e.to_string(),
+        })?,
+        expires: row.get::<i64, _>("expires") as u64,
+    })
+}
+
+/// Return all tokens, oldest first.
+pub async fn list(conn: &mut SqliteConnection) -> Result<Vec<Token>, StorageError> {
+    let rows = sqlx::query(
+        r#"
+SELECT id, hash, created, kind, metadata, namespaces, pipelines, expires
+FROM tokens
+ORDER BY id;"#,
+    )
+    .fetch_all(conn)
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await?;
+
+    rows.into_iter().map(from_row).collect()
+}
+
+/// Insert a new token, returning its id.
+pub async fn insert(conn: &mut SqliteConnection, token: &Token) -> Result<u64, StorageError> {
+    let result = sqlx::query(
+        r#"
+INSERT INTO tokens (hash, created, kind, metadata, namespaces, pipelines, expires)
+VALUES (?, ?, ?, ?, ?, ?, ?);"#,
+    )
+    .bind(&token.hash)
+    .bind(token.created as i64)
+    .bind(token.kind.to_string())
+    .bind(serde_json::to_string(&token.metadata).unwrap())
+    .bind(serde_json::to_string(&token.namespaces).unwrap())
+    .bind(serde_json::to_string(&token.pipelines).unwrap())
+    .bind(token.expires as i64)
+    .execute(conn)
+    .map_err(|e| match e {
+        sqlx::Error::Database(database_err) => {
+            if let Some(err_code) = database_err.code() {
+                if err_code.deref() == SqliteErrors::Constraint.value()
+                    || err_code.deref() == SqliteErrors::Unique.value()
+                {
+                    return StorageError::Exists;
+                }
+            }
+            StorageError::Unknown(database_err.message().to_string())
+        }
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await?;
+
+    Ok(result.last_insert_rowid() as u64)
+}
+
+/// Get details on a specific token.
+pub async fn get(conn: &mut SqliteConnection, id: u64) -> Result<Token, StorageError> {
+    let row = sqlx::query(
+        r#"
+SELECT id, hash, created, kind, metadata, namespaces, pipelines, expires
+FROM tokens
+WHERE id = ?;"#,
+    )
+    .bind(id as i64)
+    .fetch_one(conn)
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => StorageError::NotFound,
+        _ => StorageError::Unknown(e.to_string()),
+    })
+    .await?;
+
+    from_row(row)
+}
+
+pub async fn delete(conn: &mut SqliteConnection, id: u64) -> Result<(), StorageError> {
+    sqlx::query(
+        r#"
+DELETE FROM tokens
+WHERE id = ?;"#,
+    )
+    .bind(id as i64)
+    .execute(conn)
+    .map_ok(|_| ())
+    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .await
+}
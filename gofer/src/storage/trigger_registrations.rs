@@ -1,6 +1,6 @@
 use std::{ops::Deref, str::FromStr};
 
-use crate::storage::{SqliteErrors, StorageError, MAX_ROW_LIMIT};
+use crate::storage::{encryption::Cipher, Conn, SqliteErrors, StorageError, MAX_ROW_LIMIT};
 use futures::TryFutureExt;
 use gofer_models::trigger::{Registration, Status};
 use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite, SqliteConnection};
@@ -14,9 +14,22 @@ pub struct UpdatableFields {
     pub status: Option<Status>,
 }
 
+/// Registry passwords are encrypted at rest.
+fn decrypt_registration(
+    cipher: &Cipher,
+    mut registration: Registration,
+) -> Result<Registration, StorageError> {
+    registration.pass = registration
+        .pass
+        .map(|pass| cipher.decrypt("pass", &pass))
+        .transpose()?;
+
+    Ok(registration)
+}
+
 /// Return all triggers; limited to 200 rows in any one response.
 pub async fn list(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     offset: u64,
     limit: u64,
 ) -> Result<Vec<Registration>, StorageError> {
@@ -26,7 +39,9 @@ pub async fn list(
         limit = MAX_ROW_LIMIT;
     }
 
-    sqlx::query(
+    let cipher = conn.cipher();
+
+    let registrations = sqlx::query(
         r#"
 SELECT name, image, user, pass, variables, created, status
 FROM trigger_registrations
@@ -53,14 +68,19 @@ OFFSET ?;"#,
             })
             .unwrap(),
     })
-    .fetch_all(conn)
+    .fetch_all(&mut **conn)
     .await
-    .map_err(|e| StorageError::Unknown(e.to_string()))
+    .map_err(|e| StorageError::Unknown(e.to_string()))?;
+
+    registrations
+        .into_iter()
+        .map(|registration| decrypt_registration(&cipher, registration))
+        .collect()
 }
 
 /// Insert a new trigger registration.
 pub async fn insert(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     trigger_registration: &Registration,
 ) -> Result<(), StorageError> {
     sqlx::query(
@@ -71,11 +91,17 @@ VALUES (?, ?, ?, ?, ?, ?, ?);"#,
     .bind(&trigger_registration.name)
     .bind(&trigger_registration.image)
     .bind(&trigger_registration.user)
-    .bind(&trigger_registration.pass)
+    .bind(
+        trigger_registration
+            .pass
+            .as_deref()
+            .map(|pass| conn.cipher().encrypt(pass))
+            .transpose()?,
+    )
     .bind(serde_json::to_string(&trigger_registration.variables).unwrap())
     .bind(trigger_registration.created as i64)
     .bind(&trigger_registration.status.to_string())
-    .execute(conn)
+    .execute(&mut **conn)
     .map_ok(|_| ())
     .map_err(|e| match e {
         sqlx::Error::Database(database_err) => {
@@ -92,8 +118,10 @@ VALUES (?, ?, ?, ?, ?, ?, ?);"#,
 }
 
 /// Get details on a specific trigger_registration.
-pub async fn get(conn: &mut SqliteConnection, name: &str) -> Result<Registration, StorageError> {
-    sqlx::query(
+pub async fn get(conn: &mut Conn<'_>, name: &str) -> Result<Registration, StorageError> {
+    let cipher = conn.cipher();
+
+    let registration = sqlx::query(
         r#"
 SELECT name, image, user, pass, variables, created, status
 FROM trigger_registrations
@@ -118,17 +146,19 @@ WHERE name = ?;"#,
             })
             .unwrap(),
     })
-    .fetch_one(conn)
+    .fetch_one(&mut **conn)
     .map_err(|e| match e {
         sqlx::Error::RowNotFound => StorageError::NotFound,
         _ => StorageError::Unknown(e.to_string()),
     })
-    .await
+    .await?;
+
+    decrypt_registration(&cipher, registration)
 }
 
 /// Update a specific trigger_registration.
 pub async fn update(
-    conn: &mut SqliteConnection,
+    conn: &mut Conn<'_>,
     name: &str,
     fields: UpdatableFields,
 ) -> Result<(), StorageError> {
@@ -157,7 +187,7 @@ pub async fn update(
             update_query.push(", ");
         }
         update_query.push("pass = ");
-        update_query.push_bind(pass);
+        update_query.push_bind(conn.cipher().encrypt(&pass)?);
         updated_fields_total += 1;
     }
 
@@ -184,7 +214,7 @@ pub async fn update(
 
     let update_query = update_query.build();
     update_query
-        .execute(conn)
+        .execute(&mut **conn)
         .map_ok(|_| ())
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => StorageError::NotFound,
@@ -1,3 +1,4 @@
+use gofer_proto::common_task::Status as CommonTaskStatus;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use strum::{Display, EnumString};
@@ -14,6 +15,26 @@ pub enum Status {
     Disabled,
 }
 
+impl From<CommonTaskStatus> for Status {
+    fn from(r: CommonTaskStatus) -> Self {
+        match r {
+            CommonTaskStatus::Unknown => Status::Unknown,
+            CommonTaskStatus::Enabled => Status::Enabled,
+            CommonTaskStatus::Disabled => Status::Disabled,
+        }
+    }
+}
+
+impl From<Status> for CommonTaskStatus {
+    fn from(r: Status) -> Self {
+        match r {
+            Status::Unknown => CommonTaskStatus::Unknown,
+            Status::Enabled => CommonTaskStatus::Enabled,
+            Status::Disabled => CommonTaskStatus::Disabled,
+        }
+    }
+}
+
 /// The in-memory representation of a common task.
 #[derive(Debug, Clone)]
 pub struct CommonTask {
@@ -24,6 +45,34 @@ pub struct CommonTask {
     pub registry_auth: Option<super::RegistryAuth>,
     pub variables: HashMap<String, String>,
     pub documentation: Option<String>,
+    pub status: Status,
+}
+
+impl From<Registration> for CommonTask {
+    fn from(r: Registration) -> Self {
+        Self {
+            name: r.name,
+            image: r.image,
+            registry_auth: r.user.map(|user| super::RegistryAuth {
+                user,
+                pass: r.pass.unwrap_or_default(),
+            }),
+            variables: r.variables,
+            documentation: None,
+            status: r.status,
+        }
+    }
+}
+
+impl From<CommonTask> for gofer_proto::CommonTask {
+    fn from(c: CommonTask) -> Self {
+        gofer_proto::CommonTask {
+            name: c.name,
+            image: c.image,
+            documentation: c.documentation.unwrap_or_default(),
+            status: CommonTaskStatus::from(c.status) as i32,
+        }
+    }
 }
 
 /// When installing a new common task, we allow the common task installer to pass a bunch of settings that
@@ -57,6 +57,18 @@ pub enum Kind {
         pipeline_id: String,
     },
 
+    // Pipeline secret events
+    PutPipelineSecret {
+        namespace_id: String,
+        pipeline_id: String,
+        key: String,
+    },
+    DeletedPipelineSecret {
+        namespace_id: String,
+        pipeline_id: String,
+        key: String,
+    },
+
     // Run events
     StartedRun {
         namespace_id: String,
@@ -76,18 +88,21 @@ pub enum Kind {
         pipeline_id: String,
         run_id: u64,
         task_run_id: String,
+        attempt: u64,
     },
     StartedTaskRun {
         namespace_id: String,
         pipeline_id: String,
         run_id: u64,
         task_run_id: String,
+        attempt: u64,
     },
     CompletedTaskRun {
         namespace_id: String,
         pipeline_id: String,
         run_id: u64,
         task_run_id: String,
+        attempt: u64,
         status: task_run::Status,
     },
 
@@ -109,6 +124,24 @@ pub enum Kind {
         image: String,
     },
 
+    // Common task events
+    InstalledCommonTask {
+        name: String,
+        image: String,
+    },
+    UninstalledCommonTask {
+        name: String,
+        image: String,
+    },
+    EnabledCommonTask {
+        name: String,
+        image: String,
+    },
+    DisabledCommonTask {
+        name: String,
+        image: String,
+    },
+
     // Trigger event events
     FiredTriggerEvent {
         namespace_id: String,
@@ -1,10 +1,13 @@
 pub mod common_task;
 pub mod event;
 pub mod namespace;
+pub mod object;
 pub mod pipeline;
 pub mod run;
+pub mod secret;
 pub mod task;
 pub mod task_run;
+pub mod token;
 pub mod trigger;
 
 use serde::{Deserialize, Serialize};
@@ -149,6 +152,7 @@ impl From<Variable> for gofer_proto::Variable {
             owner: Into::<gofer_proto::variable::VariableOwner>::into(p.owner) as i32,
             sensitivity: Into::<gofer_proto::variable::VariableSensitivity>::into(p.sensitivity)
                 as i32,
+            redacted: false,
         }
     }
 }
@@ -0,0 +1,38 @@
+use super::epoch;
+
+/// A key for an object stored in Gofer's object store on behalf of a pipeline or run. The object itself
+/// lives only in the object store.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ObjectKey {
+    /// The key the object is referenced by within pipeline configurations.
+    pub key: String,
+    /// The creation time in epoch milli.
+    pub created: u64,
+}
+
+impl ObjectKey {
+    pub fn new(key: &str) -> Self {
+        ObjectKey {
+            key: key.to_string(),
+            created: epoch(),
+        }
+    }
+}
+
+impl From<ObjectKey> for gofer_proto::ObjectKey {
+    fn from(object_key: ObjectKey) -> Self {
+        gofer_proto::ObjectKey {
+            key: object_key.key,
+            created: object_key.created,
+        }
+    }
+}
+
+impl From<gofer_proto::ObjectKey> for ObjectKey {
+    fn from(object_key: gofer_proto::ObjectKey) -> Self {
+        ObjectKey {
+            key: object_key.key,
+            created: object_key.created,
+        }
+    }
+}
@@ -50,6 +50,9 @@ pub struct Pipeline {
     /// Controls how many runs can be active at any single time. 0 indicates unbounded with respect to bounds
     /// enforced by Gofer.
     pub parallelism: u64,
+    /// The maximum amount of time (in seconds) a run of this pipeline is allowed to take. It can only
+    /// shorten the global run timeout. 0 means only the global run timeout applies.
+    pub run_timeout: u64,
     /// The creation time in epoch milli.
     pub created: u64,
     /// The last modified time in epoch milli. Only updates on changes to the pipeline attributes, not tangential
@@ -75,6 +78,7 @@ impl From<Pipeline> for gofer_proto::Pipeline {
             name: p.name,
             description: p.description,
             parallelism: p.parallelism,
+            run_timeout: p.run_timeout,
             created: p.created,
             modified: p.modified,
             state: gofer_proto::pipeline::PipelineState::from(p.state) as i32,
@@ -106,6 +110,7 @@ impl From<gofer_proto::Pipeline> for Pipeline {
             name: p.name,
             description: p.description,
             parallelism: p.parallelism,
+            run_timeout: p.run_timeout,
             created: p.created,
             modified: p.modified,
             state: gofer_proto::pipeline::PipelineState::from_i32(p.state)
@@ -139,6 +144,7 @@ impl Pipeline {
             name: config.name,
             description: config.description.unwrap_or_default(),
             parallelism: config.parallelism,
+            run_timeout: config.run_timeout,
             created: epoch(),
             modified: epoch(),
             state: State::Active,
@@ -99,6 +99,8 @@ pub enum Reason {
     UserCancelled,
     /// One or more tasks could not be completed due to the system or admin cancelling the run.
     AdminCancelled,
+    /// One or more tasks were stopped because they exceeded their task or run timeout.
+    Timeout,
 }
 
 impl From<RunStatusReason> for Reason {
@@ -110,6 +112,7 @@ impl From<RunStatusReason> for Reason {
             RunStatusReason::FailedPrecondition => Reason::FailedPrecondition,
             RunStatusReason::UserCancelled => Reason::UserCancelled,
             RunStatusReason::AdminCancelled => Reason::AdminCancelled,
+            RunStatusReason::Timeout => Reason::Timeout,
         }
     }
 }
@@ -123,6 +126,7 @@ impl From<Reason> for RunStatusReason {
             Reason::FailedPrecondition => RunStatusReason::FailedPrecondition,
             Reason::UserCancelled => RunStatusReason::UserCancelled,
             Reason::AdminCancelled => RunStatusReason::AdminCancelled,
+            Reason::Timeout => RunStatusReason::Timeout,
         }
     }
 }
@@ -185,7 +189,7 @@ impl From<gofer_proto::RunTriggerInfo> for TriggerInfo {
 }
 
 /// Information about the run's store keys as they pertain to Gofer's object store.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct StoreInfo {
     /// After a certain number of runs Gofer's run objects are removed.
     pub is_expired: bool,
@@ -238,7 +242,9 @@ pub struct Run {
     /// Environment variables to be injected into each child task run. These are usually injected by the trigger.
     pub variables: Vec<Variable>,
     /// Information about the object keys that were stored in Gofer's run object store for this run.
-    pub store_info: Option<StoreInfo>,
+    pub store_info: StoreInfo,
+    /// Runs waiting to be started are started in order of highest priority first.
+    pub priority: i64,
 }
 
 impl Run {
@@ -260,7 +266,8 @@ impl Run {
             task_runs: vec![],
             trigger,
             variables,
-            store_info: None,
+            store_info: StoreInfo::default(),
+            priority: 0,
         }
     }
 }
@@ -279,7 +286,8 @@ impl From<Run> for gofer_proto::Run {
             task_runs: r.task_runs,
             trigger: Some(r.trigger.into()),
             variables: r.variables.into_iter().map(|value| value.into()).collect(),
-            store_info: r.store_info.map(|si| si.into()),
+            store_info: Some(r.store_info.into()),
+            priority: r.priority,
         }
     }
 }
@@ -302,7 +310,8 @@ impl From<gofer_proto::Run> for Run {
             task_runs: r.task_runs,
             trigger: r.trigger.unwrap().into(),
             variables: r.variables.into_iter().map(|value| value.into()).collect(),
-            store_info: r.store_info.map(|si| si.into()),
+            store_info: r.store_info.map(|si| si.into()).unwrap_or_default(),
+            priority: r.priority,
         }
     }
 }
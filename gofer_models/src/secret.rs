@@ -0,0 +1,48 @@
+use super::epoch;
+
+/// Metadata about a secret stored for a pipeline. The secret's value never lives here; it is kept only in
+/// the secret store.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Secret {
+    /// Unique identifier of the target namespace.
+    pub namespace: String,
+    /// Unique identifier of the target pipeline.
+    pub pipeline: String,
+    /// The key the secret is referenced by within pipeline configurations.
+    pub key: String,
+    /// The creation time in epoch milli.
+    pub created: u64,
+}
+
+impl Secret {
+    pub fn new(namespace: &str, pipeline: &str, key: &str) -> Self {
+        Secret {
+            namespace: namespace.to_string(),
+            pipeline: pipeline.to_string(),
+            key: key.to_string(),
+            created: epoch(),
+        }
+    }
+}
+
+impl From<Secret> for gofer_proto::Secret {
+    fn from(secret: Secret) -> Self {
+        gofer_proto::Secret {
+            namespace: secret.namespace,
+            pipeline: secret.pipeline,
+            key: secret.key,
+            created: secret.created,
+        }
+    }
+}
+
+impl From<gofer_proto::Secret> for Secret {
+    fn from(secret: gofer_proto::Secret) -> Self {
+        Secret {
+            namespace: secret.namespace,
+            pipeline: secret.pipeline,
+            key: secret.key,
+            created: secret.created,
+        }
+    }
+}
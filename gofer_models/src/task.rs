@@ -97,6 +97,120 @@ impl From<config::RegistryAuth> for RegistryAuth {
     }
 }
 
+/// Controls how the wait between attempts of a failed task grows.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub enum Backoff {
+    /// Always wait the same amount of time between attempts.
+    Constant,
+    /// Double the wait after every attempt.
+    Exponential,
+}
+
+impl From<gofer_proto::retry_policy::Backoff> for Backoff {
+    fn from(b: gofer_proto::retry_policy::Backoff) -> Self {
+        match b {
+            gofer_proto::retry_policy::Backoff::Unknown
+            | gofer_proto::retry_policy::Backoff::Constant => Backoff::Constant,
+            gofer_proto::retry_policy::Backoff::Exponential => Backoff::Exponential,
+        }
+    }
+}
+
+impl From<Backoff> for gofer_proto::retry_policy::Backoff {
+    fn from(b: Backoff) -> Self {
+        match b {
+            Backoff::Constant => gofer_proto::retry_policy::Backoff::Constant,
+            Backoff::Exponential => gofer_proto::retry_policy::Backoff::Exponential,
+        }
+    }
+}
+
+impl From<config::Backoff> for Backoff {
+    fn from(b: config::Backoff) -> Self {
+        match b {
+            config::Backoff::Constant => Backoff::Constant,
+            config::Backoff::Exponential => Backoff::Exponential,
+        }
+    }
+}
+
+/// Describes if and how a task is retried when one of its task runs fails.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct RetryPolicy {
+    /// The amount of times a failed task run is retried. 0 means the task is never retried.
+    pub retries: u64,
+    pub backoff: Backoff,
+    /// The time (in seconds) to wait before the first retry.
+    pub delay: u64,
+    /// The longest time (in seconds) to wait between retries. 0 means there is no maximum.
+    pub max_delay: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Backoff::Constant,
+            delay: 0,
+            max_delay: 0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the time (in seconds) to wait before starting the given attempt. The first retry is attempt 2.
+    pub fn delay_before(&self, attempt: u64) -> u64 {
+        let delay = match self.backoff {
+            Backoff::Constant => self.delay,
+            Backoff::Exponential => {
+                let exponent = attempt.saturating_sub(2).min(63) as u32;
+                self.delay.saturating_mul(2u64.saturating_pow(exponent))
+            }
+        };
+
+        if self.max_delay != 0 {
+            delay.min(self.max_delay)
+        } else {
+            delay
+        }
+    }
+}
+
+impl From<gofer_proto::RetryPolicy> for RetryPolicy {
+    fn from(p: gofer_proto::RetryPolicy) -> Self {
+        RetryPolicy {
+            retries: p.retries,
+            backoff: gofer_proto::retry_policy::Backoff::from_i32(p.backoff)
+                .unwrap_or(gofer_proto::retry_policy::Backoff::Unknown)
+                .into(),
+            delay: p.delay,
+            max_delay: p.max_delay,
+        }
+    }
+}
+
+impl From<RetryPolicy> for gofer_proto::RetryPolicy {
+    fn from(p: RetryPolicy) -> Self {
+        gofer_proto::RetryPolicy {
+            retries: p.retries,
+            backoff: gofer_proto::retry_policy::Backoff::from(p.backoff) as i32,
+            delay: p.delay,
+            max_delay: p.max_delay,
+        }
+    }
+}
+
+impl From<config::RetryPolicy> for RetryPolicy {
+    fn from(p: config::RetryPolicy) -> Self {
+        RetryPolicy {
+            retries: p.retries,
+            backoff: p.backoff.into(),
+            delay: p.delay,
+            max_delay: p.max_delay,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct Task {
     pub id: String,
@@ -107,6 +221,25 @@ pub struct Task {
     pub variables: Vec<Variable>,
     pub entrypoint: Vec<String>,
     pub command: Vec<String>,
+    /// The maximum amount of time (in seconds) a task run for this task is allowed to run before
+    /// it is stopped. 0 means the task is only limited by the overall run timeout.
+    #[serde(default)]
+    pub timeout: u64,
+    /// The amount of CPU (in millicores) the task expects to use. 0 means unset.
+    #[serde(default)]
+    pub cpu_request: u64,
+    /// The maximum amount of CPU (in millicores) the task is allowed to use. 0 means unset.
+    #[serde(default)]
+    pub cpu_limit: u64,
+    /// The amount of memory (in megabytes) the task expects to use. 0 means unset.
+    #[serde(default)]
+    pub memory_request: u64,
+    /// The maximum amount of memory (in megabytes) the task is allowed to use. 0 means unset.
+    #[serde(default)]
+    pub memory_limit: u64,
+    /// How the task should be retried when one of its task runs fails.
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
 }
 
 impl Task {
@@ -120,6 +253,12 @@ impl Task {
             variables: Vec::new(),
             entrypoint: Vec::new(),
             command: Vec::new(),
+            timeout: 0,
+            cpu_request: 0,
+            cpu_limit: 0,
+            memory_request: 0,
+            memory_limit: 0,
+            retry_policy: RetryPolicy::default(),
         }
     }
 }
@@ -148,6 +287,12 @@ impl From<gofer_proto::Task> for Task {
             variables: { p.variables.into_iter().map(Variable::from).collect() },
             entrypoint: p.entrypoint,
             command: p.command,
+            timeout: p.timeout,
+            cpu_request: p.cpu_request,
+            cpu_limit: p.cpu_limit,
+            memory_request: p.memory_request,
+            memory_limit: p.memory_limit,
+            retry_policy: p.retry_policy.map(RetryPolicy::from).unwrap_or_default(),
         }
     }
 }
@@ -172,6 +317,12 @@ impl From<Task> for gofer_proto::Task {
             variables: { p.variables.into_iter().map(|var| var.into()).collect() },
             entrypoint: p.entrypoint,
             command: p.command,
+            timeout: p.timeout,
+            cpu_request: p.cpu_request,
+            cpu_limit: p.cpu_limit,
+            memory_request: p.memory_request,
+            memory_limit: p.memory_limit,
+            retry_policy: Some(p.retry_policy.into()),
         }
     }
 }
@@ -201,6 +352,12 @@ impl From<config::Task> for Task {
             },
             entrypoint: p.entrypoint,
             command: p.command,
+            timeout: p.timeout,
+            cpu_request: p.cpu_request,
+            cpu_limit: p.cpu_limit,
+            memory_request: p.memory_request,
+            memory_limit: p.memory_limit,
+            retry_policy: p.retry_policy.into(),
         }
     }
 }
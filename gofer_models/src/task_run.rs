@@ -107,6 +107,8 @@ pub enum Reason {
     Cancelled,
     /// Task run was lost due to internal error in tracking.
     Orphaned,
+    /// Task run was stopped because it ran longer than its task or run timeout allowed.
+    Timeout,
 }
 
 impl From<Reason> for task_run_status_reason::Reason {
@@ -118,6 +120,7 @@ impl From<Reason> for task_run_status_reason::Reason {
             Reason::FailedPrecondition => task_run_status_reason::Reason::FailedPrecondition,
             Reason::Cancelled => task_run_status_reason::Reason::Cancelled,
             Reason::Orphaned => task_run_status_reason::Reason::Orphaned,
+            Reason::Timeout => task_run_status_reason::Reason::Timeout,
         }
     }
 }
@@ -131,6 +134,7 @@ impl From<task_run_status_reason::Reason> for Reason {
             task_run_status_reason::Reason::FailedPrecondition => Reason::FailedPrecondition,
             task_run_status_reason::Reason::Cancelled => Reason::Cancelled,
             task_run_status_reason::Reason::Orphaned => Reason::Orphaned,
+            task_run_status_reason::Reason::Timeout => Reason::Timeout,
         }
     }
 }
@@ -202,6 +206,9 @@ pub struct TaskRun {
     pub scheduler_id: Option<String>,
     /// The environment variables injected during this particular task run.
     pub variables: Vec<Variable>,
+    /// Which attempt of the task this task run represents. Tasks that are retried get a new task run
+    /// per attempt; the first attempt is 1.
+    pub attempt: u64,
 }
 
 impl TaskRun {
@@ -223,6 +230,7 @@ impl TaskRun {
             status: Status::Unknown,
             scheduler_id: None,
             variables: vec![],
+            attempt: 1,
         }
     }
 }
@@ -246,6 +254,7 @@ impl From<TaskRun> for gofer_proto::TaskRun {
             status: TaskRunStatus::from(r.status) as i32,
             scheduler_id: r.scheduler_id.unwrap_or_default(),
             variables: r.variables.into_iter().map(|value| value.into()).collect(),
+            attempt: r.attempt,
         }
     }
 }
@@ -273,6 +282,7 @@ impl From<gofer_proto::TaskRun> for TaskRun {
                 .into(),
             scheduler_id: Some(r.scheduler_id),
             variables: r.variables.into_iter().map(|v| v.into()).collect(),
+            attempt: r.attempt,
         }
     }
 }
@@ -0,0 +1,123 @@
+use super::epoch;
+use gofer_proto::token::Kind as ProtoKind;
+use std::collections::HashMap;
+use strum::{Display, EnumString};
+
+/// The kind of token determines what it is allowed to do.
+#[derive(Debug, Display, EnumString, PartialEq, Eq, Clone)]
+pub enum Kind {
+    /// Cannot determine kind, should never be in this kind.
+    Unknown,
+    /// Can perform any action, including managing other tokens.
+    Management,
+    /// Meant for users and automation. Can only act on the namespaces it was granted.
+    Client,
+    /// Created for a single run so its tasks can call back into Gofer. Can only access the objects and secrets
+    /// of the run's pipeline.
+    Run,
+}
+
+impl From<ProtoKind> for Kind {
+    fn from(k: ProtoKind) -> Self {
+        match k {
+            ProtoKind::Unknown => Kind::Unknown,
+            ProtoKind::Management => Kind::Management,
+            ProtoKind::Client => Kind::Client,
+            ProtoKind::Run => Kind::Run,
+        }
+    }
+}
+
+impl From<Kind> for ProtoKind {
+    fn from(k: Kind) -> Self {
+        match k {
+            Kind::Unknown => ProtoKind::Unknown,
+            Kind::Management => ProtoKind::Management,
+            Kind::Client => ProtoKind::Client,
+            Kind::Run => ProtoKind::Run,
+        }
+    }
+}
+
+/// An API token used to authenticate with Gofer. The token itself is never stored, only a hash of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Unique identifier, assigned when the token is stored.
+    pub id: u64,
+    /// Hash of the token, used to look it up when presented.
+    pub hash: String,
+    /// The creation time in epoch milli.
+    pub created: u64,
+    pub kind: Kind,
+    /// Extra information about the token, like who or what it was created for.
+    pub metadata: HashMap<String, String>,
+    /// The namespaces a client token is allowed to act on. Management tokens can act on every namespace.
+    pub namespaces: Vec<String>,
+    /// The pipelines a client token is allowed to act on within its namespaces. Empty means all of them.
+    pub pipelines: Vec<String>,
+    /// The time the token stops being valid in epoch milli. Zero means it never expires.
+    pub expires: u64,
+}
+
+impl Token {
+    pub fn new(
+        hash: &str,
+        kind: Kind,
+        metadata: HashMap<String, String>,
+        namespaces: Vec<String>,
+    ) -> Self {
+        Token {
+            id: 0,
+            hash: hash.to_string(),
+            created: epoch(),
+            kind,
+            metadata,
+            namespaces,
+            pipelines: vec![],
+            expires: 0,
+        }
+    }
+
+    pub fn pipelines(mut self, pipelines: Vec<String>) -> Self {
+        self.pipelines = pipelines;
+        self
+    }
+
+    pub fn expires(mut self, expires: u64) -> Self {
+        self.expires = expires;
+        self
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires != 0 && epoch() >= self.expires
+    }
+
+    /// Returns whether the token is allowed to act on the given namespace.
+    pub fn has_namespace_access(&self, namespace: &str) -> bool {
+        self.kind == Kind::Management || self.namespaces.iter().any(|id| id == namespace)
+    }
+
+    /// Returns whether the token is allowed to act on the given pipeline.
+    pub fn has_pipeline_access(&self, namespace: &str, pipeline: &str) -> bool {
+        if self.kind == Kind::Management {
+            return true;
+        }
+
+        self.has_namespace_access(namespace)
+            && (self.pipelines.is_empty() || self.pipelines.iter().any(|id| id == pipeline))
+    }
+}
+
+impl From<Token> for gofer_proto::Token {
+    fn from(token: Token) -> Self {
+        gofer_proto::Token {
+            id: token.id,
+            created: token.created,
+            kind: ProtoKind::from(token.kind) as i32,
+            metadata: token.metadata,
+            namespaces: token.namespaces,
+            pipelines: token.pipelines,
+            expires: token.expires,
+        }
+    }
+}
@@ -37,6 +37,9 @@ pub struct Variable {
     pub owner: i32,
     #[prost(enumeration="variable::VariableSensitivity", tag="4")]
     pub sensitivity: i32,
+    /// The value was removed because the variable is private.
+    #[prost(bool, tag="5")]
+    pub redacted: bool,
 }
 /// Nested message and enum types in `Variable`.
 pub mod variable {
@@ -81,6 +84,9 @@ pub struct Pipeline {
     pub common_tasks: ::std::collections::HashMap<::prost::alloc::string::String, PipelineCommonTaskSettings>,
     #[prost(string, repeated, tag="12")]
     pub store_keys: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// in seconds; 0 means only the global run timeout applies.
+    #[prost(uint64, tag="13")]
+    pub run_timeout: u64,
 }
 /// Nested message and enum types in `Pipeline`.
 pub mod pipeline {
@@ -108,6 +114,9 @@ pub struct PipelineConfig {
     pub triggers: ::prost::alloc::vec::Vec<PipelineTriggerConfig>,
     #[prost(message, repeated, tag="7")]
     pub common_tasks: ::prost::alloc::vec::Vec<PipelineCommonTaskConfig>,
+    /// in seconds; 0 means only the global run timeout applies.
+    #[prost(uint64, tag="8")]
+    pub run_timeout: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Run {
@@ -135,6 +144,8 @@ pub struct Run {
     pub variables: ::prost::alloc::vec::Vec<Variable>,
     #[prost(message, optional, tag="12")]
     pub store_info: ::core::option::Option<RunStoreInfo>,
+    #[prost(int64, tag="13")]
+    pub priority: i64,
 }
 /// Nested message and enum types in `Run`.
 pub mod run {
@@ -173,6 +184,7 @@ pub mod run_status_reason {
         FailedPrecondition = 3,
         UserCancelled = 4,
         AdminCancelled = 5,
+        Timeout = 6,
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -197,6 +209,30 @@ pub struct RegistryAuth {
     pub pass: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RetryPolicy {
+    /// Amount of times a failed task run is retried; 0 means no retries.
+    #[prost(uint64, tag="1")]
+    pub retries: u64,
+    #[prost(enumeration="retry_policy::Backoff", tag="2")]
+    pub backoff: i32,
+    /// in seconds; the wait before the first retry.
+    #[prost(uint64, tag="3")]
+    pub delay: u64,
+    /// in seconds; caps exponential backoff. 0 means no cap.
+    #[prost(uint64, tag="4")]
+    pub max_delay: u64,
+}
+/// Nested message and enum types in `RetryPolicy`.
+pub mod retry_policy {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Backoff {
+        Unknown = 0,
+        Constant = 1,
+        Exponential = 2,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Task {
     #[prost(string, tag="1")]
     pub id: ::prost::alloc::string::String,
@@ -214,6 +250,23 @@ pub struct Task {
     pub entrypoint: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     #[prost(string, repeated, tag="8")]
     pub command: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// in seconds; 0 means no task specific timeout.
+    #[prost(uint64, tag="9")]
+    pub timeout: u64,
+    /// in millicores; 0 means unset.
+    #[prost(uint64, tag="10")]
+    pub cpu_request: u64,
+    /// in millicores; 0 means unset.
+    #[prost(uint64, tag="11")]
+    pub cpu_limit: u64,
+    /// in megabytes; 0 means unset.
+    #[prost(uint64, tag="12")]
+    pub memory_request: u64,
+    /// in megabytes; 0 means unset.
+    #[prost(uint64, tag="13")]
+    pub memory_limit: u64,
+    #[prost(message, optional, tag="14")]
+    pub retry_policy: ::core::option::Option<RetryPolicy>,
 }
 /// Nested message and enum types in `Task`.
 pub mod task {
@@ -266,6 +319,23 @@ pub struct TaskConfig {
     pub entrypoint: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     #[prost(string, repeated, tag="8")]
     pub command: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// in seconds; 0 means no task specific timeout.
+    #[prost(uint64, tag="9")]
+    pub timeout: u64,
+    /// in millicores; 0 means unset.
+    #[prost(uint64, tag="10")]
+    pub cpu_request: u64,
+    /// in millicores; 0 means unset.
+    #[prost(uint64, tag="11")]
+    pub cpu_limit: u64,
+    /// in megabytes; 0 means unset.
+    #[prost(uint64, tag="12")]
+    pub memory_request: u64,
+    /// in megabytes; 0 means unset.
+    #[prost(uint64, tag="13")]
+    pub memory_limit: u64,
+    #[prost(message, optional, tag="14")]
+    pub retry_policy: ::core::option::Option<RetryPolicy>,
 }
 /// Nested message and enum types in `TaskConfig`.
 pub mod task_config {
@@ -314,6 +384,7 @@ pub mod task_run_status_reason {
         FailedPrecondition = 3,
         Cancelled = 4,
         Orphaned = 5,
+        Timeout = 6,
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -350,6 +421,9 @@ pub struct TaskRun {
     pub task: ::core::option::Option<Task>,
     #[prost(message, repeated, tag="16")]
     pub variables: ::prost::alloc::vec::Vec<Variable>,
+    /// Which attempt of the task this is; starts at 1.
+    #[prost(uint64, tag="17")]
+    pub attempt: u64,
 }
 /// Nested message and enum types in `TaskRun`.
 pub mod task_run {
@@ -485,6 +559,24 @@ pub mod common_task_registration {
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ObjectKey {
+    #[prost(string, tag="1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub created: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Secret {
+    #[prost(string, tag="1")]
+    pub namespace: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(uint64, tag="4")]
+    pub created: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Event {
     #[prost(uint64, tag="1")]
     pub id: u64,
@@ -497,6 +589,39 @@ pub struct Event {
     #[prost(uint64, tag="4")]
     pub emitted: u64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Token {
+    #[prost(uint64, tag="1")]
+    pub id: u64,
+    #[prost(uint64, tag="2")]
+    pub created: u64,
+    #[prost(enumeration="token::Kind", tag="3")]
+    pub kind: i32,
+    #[prost(map="string, string", tag="4")]
+    pub metadata: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    /// The namespaces a client token is allowed to act on. Management tokens can
+    /// act on every namespace.
+    #[prost(string, repeated, tag="5")]
+    pub namespaces: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The pipelines a client token is allowed to act on within its namespaces.
+    /// Empty means all of them.
+    #[prost(string, repeated, tag="6")]
+    pub pipelines: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Epoch milli; zero means the token never expires.
+    #[prost(uint64, tag="7")]
+    pub expires: u64,
+}
+/// Nested message and enum types in `Token`.
+pub mod token {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Kind {
+        Unknown = 0,
+        Management = 1,
+        Client = 2,
+        Run = 3,
+    }
+}
 ////////////// System Transport Models //////////////
 
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -511,6 +636,15 @@ pub struct GetSystemInfoResponse {
     #[prost(string, tag="3")]
     pub semver: ::prost::alloc::string::String,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RotateSecretStoreKeysRequest {
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RotateSecretStoreKeysResponse {
+    /// The number of secrets re-encrypted.
+    #[prost(uint64, tag="1")]
+    pub secrets_reencrypted: u64,
+}
 ////////////// Namespace Transport Models //////////////
 
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -684,6 +818,10 @@ pub struct GetRunRequest {
     /// Run ID
     #[prost(uint64, tag="3")]
     pub id: u64,
+    /// Return the values of private variables instead of redacting them.
+    /// Requires a management token.
+    #[prost(bool, tag="4")]
+    pub reveal_private_variables: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetRunResponse {
@@ -738,6 +876,10 @@ pub struct StartRunRequest {
     /// overrides all other environment variables if there is a name collision.
     #[prost(map="string, string", tag="3")]
     pub variables: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    /// priority controls the order in which queued runs are started; runs with a
+    /// higher priority are started first. Defaults to 0.
+    #[prost(int64, tag="4")]
+    pub priority: i64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StartRunResponse {
@@ -806,6 +948,10 @@ pub struct ListTaskRunsRequest {
     pub pipeline_id: ::prost::alloc::string::String,
     #[prost(uint64, tag="3")]
     pub run_id: u64,
+    /// Return the values of private variables instead of redacting them.
+    /// Requires a management token.
+    #[prost(bool, tag="4")]
+    pub reveal_private_variables: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListTaskRunsResponse {
@@ -824,6 +970,13 @@ pub struct GetTaskRunRequest {
     /// Task Run ID
     #[prost(string, tag="4")]
     pub id: ::prost::alloc::string::String,
+    /// Task run attempt; 0 means the latest attempt.
+    #[prost(uint64, tag="5")]
+    pub attempt: u64,
+    /// Return the values of private variables instead of redacting them.
+    /// Requires a management token.
+    #[prost(bool, tag="6")]
+    pub reveal_private_variables: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetTaskRunResponse {
@@ -862,6 +1015,9 @@ pub struct GetTaskRunLogsRequest {
     /// Task Run ID
     #[prost(string, tag="4")]
     pub id: ::prost::alloc::string::String,
+    /// Task run attempt; 0 means the latest attempt.
+    #[prost(uint64, tag="5")]
+    pub attempt: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetTaskRunLogsResponse {
@@ -884,10 +1040,231 @@ pub struct DeleteTaskRunLogsRequest {
     /// Task Run ID
     #[prost(string, tag="4")]
     pub id: ::prost::alloc::string::String,
+    /// Task run attempt; 0 means the latest attempt.
+    #[prost(uint64, tag="5")]
+    pub attempt: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DeleteTaskRunLogsResponse {
 }
+////////////// Object Store Transport Models //////////////
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineObjectRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineObjectResponse {
+    /// The next chunk of the object's content.
+    #[prost(bytes="vec", tag="1")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPipelineObjectsRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPipelineObjectsResponse {
+    #[prost(message, repeated, tag="1")]
+    pub keys: ::prost::alloc::vec::Vec<ObjectKey>,
+}
+/// Objects are sent as a stream of requests. The first request must contain the
+/// object's details; later requests only carry the next chunk of content and
+/// their other fields are ignored.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PutPipelineObjectRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+    /// The next chunk of the object's content.
+    #[prost(bytes="vec", tag="4")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+    /// force will cause Gofer to overwrite the object if it already exists.
+    #[prost(bool, tag="5")]
+    pub force: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PutPipelineObjectResponse {
+    /// The size of the object stored.
+    #[prost(uint64, tag="1")]
+    pub bytes: u64,
+    /// The maximum number of objects a pipeline can hold.
+    #[prost(uint64, tag="2")]
+    pub object_limit: u64,
+    /// The keys of any objects removed to stay within the object limit.
+    #[prost(string, repeated, tag="3")]
+    pub objects_evicted: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletePipelineObjectRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletePipelineObjectResponse {
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRunObjectRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub run_id: u64,
+    #[prost(string, tag="4")]
+    pub key: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRunObjectResponse {
+    /// The next chunk of the object's content.
+    #[prost(bytes="vec", tag="1")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRunObjectsRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub run_id: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRunObjectsResponse {
+    #[prost(message, repeated, tag="1")]
+    pub keys: ::prost::alloc::vec::Vec<ObjectKey>,
+}
+/// Objects are sent as a stream of requests. The first request must contain the
+/// object's details; later requests only carry the next chunk of content and
+/// their other fields are ignored.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PutRunObjectRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub run_id: u64,
+    #[prost(string, tag="4")]
+    pub key: ::prost::alloc::string::String,
+    /// The next chunk of the object's content.
+    #[prost(bytes="vec", tag="5")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+    /// force will cause Gofer to overwrite the object if it already exists.
+    #[prost(bool, tag="6")]
+    pub force: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PutRunObjectResponse {
+    /// The size of the object stored.
+    #[prost(uint64, tag="1")]
+    pub bytes: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteRunObjectRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub run_id: u64,
+    #[prost(string, tag="4")]
+    pub key: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteRunObjectResponse {
+}
+////////////// Secret Transport Models //////////////
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineSecretRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+    /// Whether to return the secret value. By default only the secret's metadata
+    /// is returned.
+    #[prost(bool, tag="4")]
+    pub include_secret: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineSecretResponse {
+    #[prost(message, optional, tag="1")]
+    pub secret: ::core::option::Option<Secret>,
+    /// Only populated if include_secret was set.
+    #[prost(string, tag="2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPipelineSecretsRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPipelineSecretsResponse {
+    #[prost(message, repeated, tag="1")]
+    pub secrets: ::prost::alloc::vec::Vec<Secret>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PutPipelineSecretRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub value: ::prost::alloc::string::String,
+    /// force will cause Gofer to overwrite the secret if it already exists.
+    #[prost(bool, tag="5")]
+    pub force: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PutPipelineSecretResponse {
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletePipelineSecretRequest {
+    /// Unique namespace identifier
+    #[prost(string, tag="1")]
+    pub namespace_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pipeline_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub key: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletePipelineSecretResponse {
+}
 ////////////// Trigger Transport Models //////////////
 
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1141,11 +1518,19 @@ pub struct TriggerShutdownResponse {
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TriggerExternalEventRequest {
+    /// The raw body of the external request.
     #[prost(bytes="vec", tag="1")]
     pub payload: ::prost::alloc::vec::Vec<u8>,
+    /// The headers of the external request. Useful for verifying webhook
+    /// signatures or determining the type of event sent.
+    #[prost(map="string, string", tag="2")]
+    pub headers: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TriggerExternalEventResponse {
+    /// Optional body to respond to the external caller with.
+    #[prost(bytes="vec", tag="1")]
+    pub payload: ::prost::alloc::vec::Vec<u8>,
 }
 ////////////// Events Transport Models //////////////
 
@@ -1174,6 +1559,47 @@ pub struct ListEventsResponse {
     #[prost(message, optional, tag="1")]
     pub event: ::core::option::Option<Event>,
 }
+////////////// Token Transport Models //////////////
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateTokenRequest {
+    #[prost(enumeration="token::Kind", tag="1")]
+    pub kind: i32,
+    /// Extra information about the token, like who or what it was created for.
+    #[prost(map="string, string", tag="2")]
+    pub metadata: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    /// The namespaces a client token is allowed to act on.
+    #[prost(string, repeated, tag="3")]
+    pub namespaces: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The pipelines a client token is allowed to act on within its namespaces.
+    /// Empty means all of them.
+    #[prost(string, repeated, tag="4")]
+    pub pipelines: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateTokenResponse {
+    #[prost(message, optional, tag="1")]
+    pub details: ::core::option::Option<Token>,
+    /// The token itself. It cannot be retrieved again.
+    #[prost(string, tag="2")]
+    pub token: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTokensRequest {
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTokensResponse {
+    #[prost(message, repeated, tag="1")]
+    pub tokens: ::prost::alloc::vec::Vec<Token>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteTokenRequest {
+    #[prost(uint64, tag="1")]
+    pub id: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteTokenResponse {
+}
 /// Generated client implementations.
 pub mod gofer_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -1258,6 +1684,31 @@ pub mod gofer_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
+        /// RotateSecretStoreKeys re-encrypts all secrets in the secret store with the
+        /// active encryption key. Only supported by secret stores that encrypt secrets
+        /// themselves.
+        pub async fn rotate_secret_store_keys(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RotateSecretStoreKeysRequest>,
+        ) -> Result<
+            tonic::Response<super::RotateSecretStoreKeysResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/RotateSecretStoreKeys",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
         /// ListNamespaces returns all registered namespaces.
         pub async fn list_namespaces(
             &mut self,
@@ -1731,10 +2182,272 @@ pub mod gofer_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// GetTrigger returns details about a specific trigger.
-        pub async fn get_trigger(
+        /// GetPipelineObject streams the content of a single pipeline object in
+        /// chunks.
+        pub async fn get_pipeline_object(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetTriggerRequest>,
+            request: impl tonic::IntoRequest<super::GetPipelineObjectRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::GetPipelineObjectResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/GetPipelineObject",
+            );
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// ListPipelineObjects returns the keys of all objects stored for a pipeline.
+        pub async fn list_pipeline_objects(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListPipelineObjectsRequest>,
+        ) -> Result<tonic::Response<super::ListPipelineObjectsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/ListPipelineObjects",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// PutPipelineObject stores an object for a pipeline, streamed in chunks. If
+        /// this causes the pipeline to go over its object limit the oldest object is
+        /// evicted.
+        pub async fn put_pipeline_object(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::PutPipelineObjectRequest,
+            >,
+        ) -> Result<tonic::Response<super::PutPipelineObjectResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/PutPipelineObject",
+            );
+            self.inner
+                .client_streaming(request.into_streaming_request(), path, codec)
+                .await
+        }
+        /// DeletePipelineObject removes a pipeline object.
+        pub async fn delete_pipeline_object(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeletePipelineObjectRequest>,
+        ) -> Result<
+            tonic::Response<super::DeletePipelineObjectResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/DeletePipelineObject",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetRunObject streams the content of a single run object in chunks.
+        pub async fn get_run_object(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRunObjectRequest>,
+        ) -> Result<
+            tonic::Response<tonic::codec::Streaming<super::GetRunObjectResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Gofer/GetRunObject");
+            self.inner.server_streaming(request.into_request(), path, codec).await
+        }
+        /// ListRunObjects returns the keys of all objects stored for a run.
+        pub async fn list_run_objects(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRunObjectsRequest>,
+        ) -> Result<tonic::Response<super::ListRunObjectsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/ListRunObjects",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// PutRunObject stores an object for a run, streamed in chunks. Objects can no
+        /// longer be stored once a run's objects have expired.
+        pub async fn put_run_object(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::PutRunObjectRequest,
+            >,
+        ) -> Result<tonic::Response<super::PutRunObjectResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Gofer/PutRunObject");
+            self.inner
+                .client_streaming(request.into_streaming_request(), path, codec)
+                .await
+        }
+        /// DeleteRunObject removes a run object.
+        pub async fn delete_run_object(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteRunObjectRequest>,
+        ) -> Result<tonic::Response<super::DeleteRunObjectResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/DeleteRunObject",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetPipelineSecret returns the details of a single pipeline secret. The
+        /// secret value is only included when explicitly requested.
+        pub async fn get_pipeline_secret(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetPipelineSecretRequest>,
+        ) -> Result<tonic::Response<super::GetPipelineSecretResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/GetPipelineSecret",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ListPipelineSecrets returns the keys of all secrets stored for a pipeline.
+        pub async fn list_pipeline_secrets(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListPipelineSecretsRequest>,
+        ) -> Result<tonic::Response<super::ListPipelineSecretsResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/ListPipelineSecrets",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// PutPipelineSecret stores a secret for a pipeline. Existing secrets are only
+        /// replaced if the force flag is used.
+        pub async fn put_pipeline_secret(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PutPipelineSecretRequest>,
+        ) -> Result<tonic::Response<super::PutPipelineSecretResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/PutPipelineSecret",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// DeletePipelineSecret removes a secret from a pipeline.
+        pub async fn delete_pipeline_secret(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeletePipelineSecretRequest>,
+        ) -> Result<
+            tonic::Response<super::DeletePipelineSecretResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/proto.Gofer/DeletePipelineSecret",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// GetTrigger returns details about a specific trigger.
+        pub async fn get_trigger(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetTriggerRequest>,
         ) -> Result<tonic::Response<super::GetTriggerResponse>, tonic::Status> {
             self.inner
                 .ready()
@@ -2031,6 +2744,63 @@ pub mod gofer_client {
             let path = http::uri::PathAndQuery::from_static("/proto.Gofer/ListEvents");
             self.inner.server_streaming(request.into_request(), path, codec).await
         }
+        /// CreateToken creates a new API token. The token itself is only returned
+        /// here; Gofer only stores a hash of it. Requires a management token.
+        pub async fn create_token(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateTokenRequest>,
+        ) -> Result<tonic::Response<super::CreateTokenResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Gofer/CreateToken");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// ListTokens returns details about all API tokens. Requires a management
+        /// token.
+        pub async fn list_tokens(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListTokensRequest>,
+        ) -> Result<tonic::Response<super::ListTokensResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Gofer/ListTokens");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// DeleteToken removes an API token by id, revoking access for anyone using
+        /// it. Requires a management token.
+        pub async fn delete_token(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteTokenRequest>,
+        ) -> Result<tonic::Response<super::DeleteTokenResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/proto.Gofer/DeleteToken");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
     }
 }
 /// Generated client implementations.
@@ -2204,8 +2974,8 @@ pub mod trigger_service_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        /// ExternalEvent are json blobs of gofer's /events endpoint. Normally
-        /// webhooks.
+        /// ExternalEvent are requests made to gofer's /api/external/<trigger_name>
+        /// endpoint passed along to the named trigger. Normally webhooks.
         pub async fn external_event(
             &mut self,
             request: impl tonic::IntoRequest<super::TriggerExternalEventRequest>,
@@ -2242,6 +3012,16 @@ pub mod gofer_server {
             &self,
             request: tonic::Request<super::GetSystemInfoRequest>,
         ) -> Result<tonic::Response<super::GetSystemInfoResponse>, tonic::Status>;
+        /// RotateSecretStoreKeys re-encrypts all secrets in the secret store with the
+        /// active encryption key. Only supported by secret stores that encrypt secrets
+        /// themselves.
+        async fn rotate_secret_store_keys(
+            &self,
+            request: tonic::Request<super::RotateSecretStoreKeysRequest>,
+        ) -> Result<
+            tonic::Response<super::RotateSecretStoreKeysResponse>,
+            tonic::Status,
+        >;
         /// ListNamespaces returns all registered namespaces.
         async fn list_namespaces(
             &self,
@@ -2391,6 +3171,84 @@ pub mod gofer_server {
             &self,
             request: tonic::Request<super::DeleteTaskRunLogsRequest>,
         ) -> Result<tonic::Response<super::DeleteTaskRunLogsResponse>, tonic::Status>;
+        ///Server streaming response type for the GetPipelineObject method.
+        type GetPipelineObjectStream: futures_core::Stream<
+                Item = Result<super::GetPipelineObjectResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// GetPipelineObject streams the content of a single pipeline object in
+        /// chunks.
+        async fn get_pipeline_object(
+            &self,
+            request: tonic::Request<super::GetPipelineObjectRequest>,
+        ) -> Result<tonic::Response<Self::GetPipelineObjectStream>, tonic::Status>;
+        /// ListPipelineObjects returns the keys of all objects stored for a pipeline.
+        async fn list_pipeline_objects(
+            &self,
+            request: tonic::Request<super::ListPipelineObjectsRequest>,
+        ) -> Result<tonic::Response<super::ListPipelineObjectsResponse>, tonic::Status>;
+        /// PutPipelineObject stores an object for a pipeline, streamed in chunks. If
+        /// this causes the pipeline to go over its object limit the oldest object is
+        /// evicted.
+        async fn put_pipeline_object(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::PutPipelineObjectRequest>>,
+        ) -> Result<tonic::Response<super::PutPipelineObjectResponse>, tonic::Status>;
+        /// DeletePipelineObject removes a pipeline object.
+        async fn delete_pipeline_object(
+            &self,
+            request: tonic::Request<super::DeletePipelineObjectRequest>,
+        ) -> Result<tonic::Response<super::DeletePipelineObjectResponse>, tonic::Status>;
+        ///Server streaming response type for the GetRunObject method.
+        type GetRunObjectStream: futures_core::Stream<
+                Item = Result<super::GetRunObjectResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// GetRunObject streams the content of a single run object in chunks.
+        async fn get_run_object(
+            &self,
+            request: tonic::Request<super::GetRunObjectRequest>,
+        ) -> Result<tonic::Response<Self::GetRunObjectStream>, tonic::Status>;
+        /// ListRunObjects returns the keys of all objects stored for a run.
+        async fn list_run_objects(
+            &self,
+            request: tonic::Request<super::ListRunObjectsRequest>,
+        ) -> Result<tonic::Response<super::ListRunObjectsResponse>, tonic::Status>;
+        /// PutRunObject stores an object for a run, streamed in chunks. Objects can no
+        /// longer be stored once a run's objects have expired.
+        async fn put_run_object(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::PutRunObjectRequest>>,
+        ) -> Result<tonic::Response<super::PutRunObjectResponse>, tonic::Status>;
+        /// DeleteRunObject removes a run object.
+        async fn delete_run_object(
+            &self,
+            request: tonic::Request<super::DeleteRunObjectRequest>,
+        ) -> Result<tonic::Response<super::DeleteRunObjectResponse>, tonic::Status>;
+        /// GetPipelineSecret returns the details of a single pipeline secret. The
+        /// secret value is only included when explicitly requested.
+        async fn get_pipeline_secret(
+            &self,
+            request: tonic::Request<super::GetPipelineSecretRequest>,
+        ) -> Result<tonic::Response<super::GetPipelineSecretResponse>, tonic::Status>;
+        /// ListPipelineSecrets returns the keys of all secrets stored for a pipeline.
+        async fn list_pipeline_secrets(
+            &self,
+            request: tonic::Request<super::ListPipelineSecretsRequest>,
+        ) -> Result<tonic::Response<super::ListPipelineSecretsResponse>, tonic::Status>;
+        /// PutPipelineSecret stores a secret for a pipeline. Existing secrets are only
+        /// replaced if the force flag is used.
+        async fn put_pipeline_secret(
+            &self,
+            request: tonic::Request<super::PutPipelineSecretRequest>,
+        ) -> Result<tonic::Response<super::PutPipelineSecretResponse>, tonic::Status>;
+        /// DeletePipelineSecret removes a secret from a pipeline.
+        async fn delete_pipeline_secret(
+            &self,
+            request: tonic::Request<super::DeletePipelineSecretRequest>,
+        ) -> Result<tonic::Response<super::DeletePipelineSecretResponse>, tonic::Status>;
         /// GetTrigger returns details about a specific trigger.
         async fn get_trigger(
             &self,
@@ -2477,6 +3335,24 @@ pub mod gofer_server {
             &self,
             request: tonic::Request<super::ListEventsRequest>,
         ) -> Result<tonic::Response<Self::ListEventsStream>, tonic::Status>;
+        /// CreateToken creates a new API token. The token itself is only returned
+        /// here; Gofer only stores a hash of it. Requires a management token.
+        async fn create_token(
+            &self,
+            request: tonic::Request<super::CreateTokenRequest>,
+        ) -> Result<tonic::Response<super::CreateTokenResponse>, tonic::Status>;
+        /// ListTokens returns details about all API tokens. Requires a management
+        /// token.
+        async fn list_tokens(
+            &self,
+            request: tonic::Request<super::ListTokensRequest>,
+        ) -> Result<tonic::Response<super::ListTokensResponse>, tonic::Status>;
+        /// DeleteToken removes an API token by id, revoking access for anyone using
+        /// it. Requires a management token.
+        async fn delete_token(
+            &self,
+            request: tonic::Request<super::DeleteTokenRequest>,
+        ) -> Result<tonic::Response<super::DeleteTokenResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct GoferServer<T: Gofer> {
@@ -2565,6 +3441,46 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
+                "/proto.Gofer/RotateSecretStoreKeys" => {
+                    #[allow(non_camel_case_types)]
+                    struct RotateSecretStoreKeysSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::RotateSecretStoreKeysRequest>
+                    for RotateSecretStoreKeysSvc<T> {
+                        type Response = super::RotateSecretStoreKeysResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RotateSecretStoreKeysRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).rotate_secret_store_keys(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RotateSecretStoreKeysSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/proto.Gofer/ListNamespaces" => {
                     #[allow(non_camel_case_types)]
                     struct ListNamespacesSvc<T: Gofer>(pub Arc<T>);
@@ -2932,16 +3848,475 @@ pub mod gofer_server {
                     for CreatePipelineSvc<T> {
                         type Response = super::CreatePipelineResponse;
                         type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreatePipelineRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).create_pipeline(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreatePipelineSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/UpdatePipeline" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdatePipelineSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::UpdatePipelineRequest>
+                    for UpdatePipelineSvc<T> {
+                        type Response = super::UpdatePipelineResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdatePipelineRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).update_pipeline(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdatePipelineSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/DeletePipeline" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeletePipelineSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::DeletePipelineRequest>
+                    for DeletePipelineSvc<T> {
+                        type Response = super::DeletePipelineResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeletePipelineRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).delete_pipeline(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeletePipelineSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/GetRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetRunSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::GetRunRequest>
+                    for GetRunSvc<T> {
+                        type Response = super::GetRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRunRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_run(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/ListRuns" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListRunsSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::ListRunsRequest>
+                    for ListRunsSvc<T> {
+                        type Response = super::ListRunsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRunsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list_runs(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListRunsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/StartRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct StartRunSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::StartRunRequest>
+                    for StartRunSvc<T> {
+                        type Response = super::StartRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StartRunRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).start_run(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StartRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/RetryRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct RetryRunSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::RetryRunRequest>
+                    for RetryRunSvc<T> {
+                        type Response = super::RetryRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RetryRunRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).retry_run(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RetryRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/CancelRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelRunSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::CancelRunRequest>
+                    for CancelRunSvc<T> {
+                        type Response = super::CancelRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelRunRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).cancel_run(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/CancelAllRuns" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelAllRunsSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::CancelAllRunsRequest>
+                    for CancelAllRunsSvc<T> {
+                        type Response = super::CancelAllRunsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelAllRunsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).cancel_all_runs(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelAllRunsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/GetTaskRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTaskRunSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::GetTaskRunRequest>
+                    for GetTaskRunSvc<T> {
+                        type Response = super::GetTaskRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetTaskRunRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).get_task_run(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTaskRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/ListTaskRuns" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListTaskRunsSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::ListTaskRunsRequest>
+                    for ListTaskRunsSvc<T> {
+                        type Response = super::ListTaskRunsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListTaskRunsRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).list_task_runs(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListTaskRunsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/CancelTaskRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelTaskRunSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::CancelTaskRunRequest>
+                    for CancelTaskRunSvc<T> {
+                        type Response = super::CancelTaskRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelTaskRunRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).cancel_task_run(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelTaskRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/GetTaskRunLogs" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTaskRunLogsSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::ServerStreamingService<super::GetTaskRunLogsRequest>
+                    for GetTaskRunLogsSvc<T> {
+                        type Response = super::GetTaskRunLogsResponse;
+                        type ResponseStream = T::GetTaskRunLogsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::CreatePipelineRequest>,
+                            request: tonic::Request<super::GetTaskRunLogsRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).create_pipeline(request).await
+                                (*inner).get_task_run_logs(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2951,37 +4326,37 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CreatePipelineSvc(inner);
+                        let method = GetTaskRunLogsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.server_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/UpdatePipeline" => {
+                "/proto.Gofer/DeleteTaskRunLogs" => {
                     #[allow(non_camel_case_types)]
-                    struct UpdatePipelineSvc<T: Gofer>(pub Arc<T>);
+                    struct DeleteTaskRunLogsSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::UnaryService<super::UpdatePipelineRequest>
-                    for UpdatePipelineSvc<T> {
-                        type Response = super::UpdatePipelineResponse;
+                    > tonic::server::UnaryService<super::DeleteTaskRunLogsRequest>
+                    for DeleteTaskRunLogsSvc<T> {
+                        type Response = super::DeleteTaskRunLogsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::UpdatePipelineRequest>,
+                            request: tonic::Request<super::DeleteTaskRunLogsRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).update_pipeline(request).await
+                                (*inner).delete_task_run_logs(request).await
                             };
                             Box::pin(fut)
                         }
@@ -2991,7 +4366,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = UpdatePipelineSvc(inner);
+                        let method = DeleteTaskRunLogsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3003,25 +4378,27 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/DeletePipeline" => {
+                "/proto.Gofer/GetPipelineObject" => {
                     #[allow(non_camel_case_types)]
-                    struct DeletePipelineSvc<T: Gofer>(pub Arc<T>);
+                    struct GetPipelineObjectSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::UnaryService<super::DeletePipelineRequest>
-                    for DeletePipelineSvc<T> {
-                        type Response = super::DeletePipelineResponse;
+                    > tonic::server::ServerStreamingService<
+                        super::GetPipelineObjectRequest,
+                    > for GetPipelineObjectSvc<T> {
+                        type Response = super::GetPipelineObjectResponse;
+                        type ResponseStream = T::GetPipelineObjectStream;
                         type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
+                            tonic::Response<Self::ResponseStream>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::DeletePipelineRequest>,
+                            request: tonic::Request<super::GetPipelineObjectRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).delete_pipeline(request).await
+                                (*inner).get_pipeline_object(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3031,34 +4408,38 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = DeletePipelineSvc(inner);
+                        let method = GetPipelineObjectSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.server_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/GetRun" => {
+                "/proto.Gofer/ListPipelineObjects" => {
                     #[allow(non_camel_case_types)]
-                    struct GetRunSvc<T: Gofer>(pub Arc<T>);
-                    impl<T: Gofer> tonic::server::UnaryService<super::GetRunRequest>
-                    for GetRunSvc<T> {
-                        type Response = super::GetRunResponse;
+                    struct ListPipelineObjectsSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::ListPipelineObjectsRequest>
+                    for ListPipelineObjectsSvc<T> {
+                        type Response = super::ListPipelineObjectsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetRunRequest>,
+                            request: tonic::Request<super::ListPipelineObjectsRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).get_run(request).await };
+                            let fut = async move {
+                                (*inner).list_pipeline_objects(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -3067,7 +4448,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetRunSvc(inner);
+                        let method = ListPipelineObjectsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3079,22 +4460,29 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/ListRuns" => {
+                "/proto.Gofer/PutPipelineObject" => {
                     #[allow(non_camel_case_types)]
-                    struct ListRunsSvc<T: Gofer>(pub Arc<T>);
-                    impl<T: Gofer> tonic::server::UnaryService<super::ListRunsRequest>
-                    for ListRunsSvc<T> {
-                        type Response = super::ListRunsResponse;
+                    struct PutPipelineObjectSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::ClientStreamingService<
+                        super::PutPipelineObjectRequest,
+                    > for PutPipelineObjectSvc<T> {
+                        type Response = super::PutPipelineObjectResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ListRunsRequest>,
+                            request: tonic::Request<
+                                tonic::Streaming<super::PutPipelineObjectRequest>,
+                            >,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).list_runs(request).await };
+                            let fut = async move {
+                                (*inner).put_pipeline_object(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -3103,34 +4491,38 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = ListRunsSvc(inner);
+                        let method = PutPipelineObjectSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.client_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/StartRun" => {
+                "/proto.Gofer/DeletePipelineObject" => {
                     #[allow(non_camel_case_types)]
-                    struct StartRunSvc<T: Gofer>(pub Arc<T>);
-                    impl<T: Gofer> tonic::server::UnaryService<super::StartRunRequest>
-                    for StartRunSvc<T> {
-                        type Response = super::StartRunResponse;
+                    struct DeletePipelineObjectSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::DeletePipelineObjectRequest>
+                    for DeletePipelineObjectSvc<T> {
+                        type Response = super::DeletePipelineObjectResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::StartRunRequest>,
+                            request: tonic::Request<super::DeletePipelineObjectRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).start_run(request).await };
+                            let fut = async move {
+                                (*inner).delete_pipeline_object(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -3139,7 +4531,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = StartRunSvc(inner);
+                        let method = DeletePipelineObjectSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3151,22 +4543,27 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/RetryRun" => {
+                "/proto.Gofer/GetRunObject" => {
                     #[allow(non_camel_case_types)]
-                    struct RetryRunSvc<T: Gofer>(pub Arc<T>);
-                    impl<T: Gofer> tonic::server::UnaryService<super::RetryRunRequest>
-                    for RetryRunSvc<T> {
-                        type Response = super::RetryRunResponse;
+                    struct GetRunObjectSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::ServerStreamingService<super::GetRunObjectRequest>
+                    for GetRunObjectSvc<T> {
+                        type Response = super::GetRunObjectResponse;
+                        type ResponseStream = T::GetRunObjectStream;
                         type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
+                            tonic::Response<Self::ResponseStream>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::RetryRunRequest>,
+                            request: tonic::Request<super::GetRunObjectRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).retry_run(request).await };
+                            let fut = async move {
+                                (*inner).get_run_object(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -3175,34 +4572,38 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = RetryRunSvc(inner);
+                        let method = GetRunObjectSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.server_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/CancelRun" => {
+                "/proto.Gofer/ListRunObjects" => {
                     #[allow(non_camel_case_types)]
-                    struct CancelRunSvc<T: Gofer>(pub Arc<T>);
-                    impl<T: Gofer> tonic::server::UnaryService<super::CancelRunRequest>
-                    for CancelRunSvc<T> {
-                        type Response = super::CancelRunResponse;
+                    struct ListRunObjectsSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::ListRunObjectsRequest>
+                    for ListRunObjectsSvc<T> {
+                        type Response = super::ListRunObjectsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::CancelRunRequest>,
+                            request: tonic::Request<super::ListRunObjectsRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).cancel_run(request).await };
+                            let fut = async move {
+                                (*inner).list_run_objects(request).await
+                            };
                             Box::pin(fut)
                         }
                     }
@@ -3211,7 +4612,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CancelRunSvc(inner);
+                        let method = ListRunObjectsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3223,25 +4624,27 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/CancelAllRuns" => {
+                "/proto.Gofer/PutRunObject" => {
                     #[allow(non_camel_case_types)]
-                    struct CancelAllRunsSvc<T: Gofer>(pub Arc<T>);
+                    struct PutRunObjectSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::UnaryService<super::CancelAllRunsRequest>
-                    for CancelAllRunsSvc<T> {
-                        type Response = super::CancelAllRunsResponse;
+                    > tonic::server::ClientStreamingService<super::PutRunObjectRequest>
+                    for PutRunObjectSvc<T> {
+                        type Response = super::PutRunObjectResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::CancelAllRunsRequest>,
+                            request: tonic::Request<
+                                tonic::Streaming<super::PutRunObjectRequest>,
+                            >,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).cancel_all_runs(request).await
+                                (*inner).put_run_object(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3251,35 +4654,37 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CancelAllRunsSvc(inner);
+                        let method = PutRunObjectSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.client_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/GetTaskRun" => {
+                "/proto.Gofer/DeleteRunObject" => {
                     #[allow(non_camel_case_types)]
-                    struct GetTaskRunSvc<T: Gofer>(pub Arc<T>);
-                    impl<T: Gofer> tonic::server::UnaryService<super::GetTaskRunRequest>
-                    for GetTaskRunSvc<T> {
-                        type Response = super::GetTaskRunResponse;
+                    struct DeleteRunObjectSvc<T: Gofer>(pub Arc<T>);
+                    impl<
+                        T: Gofer,
+                    > tonic::server::UnaryService<super::DeleteRunObjectRequest>
+                    for DeleteRunObjectSvc<T> {
+                        type Response = super::DeleteRunObjectResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetTaskRunRequest>,
+                            request: tonic::Request<super::DeleteRunObjectRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).get_task_run(request).await
+                                (*inner).delete_run_object(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3289,7 +4694,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetTaskRunSvc(inner);
+                        let method = DeleteRunObjectSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3301,25 +4706,25 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/ListTaskRuns" => {
+                "/proto.Gofer/GetPipelineSecret" => {
                     #[allow(non_camel_case_types)]
-                    struct ListTaskRunsSvc<T: Gofer>(pub Arc<T>);
+                    struct GetPipelineSecretSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::UnaryService<super::ListTaskRunsRequest>
-                    for ListTaskRunsSvc<T> {
-                        type Response = super::ListTaskRunsResponse;
+                    > tonic::server::UnaryService<super::GetPipelineSecretRequest>
+                    for GetPipelineSecretSvc<T> {
+                        type Response = super::GetPipelineSecretResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ListTaskRunsRequest>,
+                            request: tonic::Request<super::GetPipelineSecretRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).list_task_runs(request).await
+                                (*inner).get_pipeline_secret(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3329,7 +4734,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = ListTaskRunsSvc(inner);
+                        let method = GetPipelineSecretSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3341,25 +4746,25 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/CancelTaskRun" => {
+                "/proto.Gofer/ListPipelineSecrets" => {
                     #[allow(non_camel_case_types)]
-                    struct CancelTaskRunSvc<T: Gofer>(pub Arc<T>);
+                    struct ListPipelineSecretsSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::UnaryService<super::CancelTaskRunRequest>
-                    for CancelTaskRunSvc<T> {
-                        type Response = super::CancelTaskRunResponse;
+                    > tonic::server::UnaryService<super::ListPipelineSecretsRequest>
+                    for ListPipelineSecretsSvc<T> {
+                        type Response = super::ListPipelineSecretsResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::CancelTaskRunRequest>,
+                            request: tonic::Request<super::ListPipelineSecretsRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).cancel_task_run(request).await
+                                (*inner).list_pipeline_secrets(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3369,7 +4774,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = CancelTaskRunSvc(inner);
+                        let method = ListPipelineSecretsSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -3381,26 +4786,25 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/GetTaskRunLogs" => {
+                "/proto.Gofer/PutPipelineSecret" => {
                     #[allow(non_camel_case_types)]
-                    struct GetTaskRunLogsSvc<T: Gofer>(pub Arc<T>);
+                    struct PutPipelineSecretSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::ServerStreamingService<super::GetTaskRunLogsRequest>
-                    for GetTaskRunLogsSvc<T> {
-                        type Response = super::GetTaskRunLogsResponse;
-                        type ResponseStream = T::GetTaskRunLogsStream;
+                    > tonic::server::UnaryService<super::PutPipelineSecretRequest>
+                    for PutPipelineSecretSvc<T> {
+                        type Response = super::PutPipelineSecretResponse;
                         type Future = BoxFuture<
-                            tonic::Response<Self::ResponseStream>,
+                            tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetTaskRunLogsRequest>,
+                            request: tonic::Request<super::PutPipelineSecretRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).get_task_run_logs(request).await
+                                (*inner).put_pipeline_secret(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3410,37 +4814,37 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetTaskRunLogsSvc(inner);
+                        let method = PutPipelineSecretSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
                                 send_compression_encodings,
                             );
-                        let res = grpc.server_streaming(method, req).await;
+                        let res = grpc.unary(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                "/proto.Gofer/DeleteTaskRunLogs" => {
+                "/proto.Gofer/DeletePipelineSecret" => {
                     #[allow(non_camel_case_types)]
-                    struct DeleteTaskRunLogsSvc<T: Gofer>(pub Arc<T>);
+                    struct DeletePipelineSecretSvc<T: Gofer>(pub Arc<T>);
                     impl<
                         T: Gofer,
-                    > tonic::server::UnaryService<super::DeleteTaskRunLogsRequest>
-                    for DeleteTaskRunLogsSvc<T> {
-                        type Response = super::DeleteTaskRunLogsResponse;
+                    > tonic::server::UnaryService<super::DeletePipelineSecretRequest>
+                    for DeletePipelineSecretSvc<T> {
+                        type Response = super::DeletePipelineSecretResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::DeleteTaskRunLogsRequest>,
+                            request: tonic::Request<super::DeletePipelineSecretRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).delete_task_run_logs(request).await
+                                (*inner).delete_pipeline_secret(request).await
                             };
                             Box::pin(fut)
                         }
@@ -3450,7 +4854,7 @@ pub mod gofer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = DeleteTaskRunLogsSvc(inner);
+                        let method = DeletePipelineSecretSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -4056,6 +5460,118 @@ pub mod gofer_server {
                     };
                     Box::pin(fut)
                 }
+                "/proto.Gofer/CreateToken" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateTokenSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::CreateTokenRequest>
+                    for CreateTokenSvc<T> {
+                        type Response = super::CreateTokenResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateTokenRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).create_token(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateTokenSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/ListTokens" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListTokensSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::ListTokensRequest>
+                    for ListTokensSvc<T> {
+                        type Response = super::ListTokensResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListTokensRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).list_tokens(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListTokensSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/proto.Gofer/DeleteToken" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteTokenSvc<T: Gofer>(pub Arc<T>);
+                    impl<T: Gofer> tonic::server::UnaryService<super::DeleteTokenRequest>
+                    for DeleteTokenSvc<T> {
+                        type Response = super::DeleteTokenResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteTokenRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).delete_token(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteTokenSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -4134,8 +5650,8 @@ pub mod trigger_service_server {
             &self,
             request: tonic::Request<super::TriggerShutdownRequest>,
         ) -> Result<tonic::Response<super::TriggerShutdownResponse>, tonic::Status>;
-        /// ExternalEvent are json blobs of gofer's /events endpoint. Normally
-        /// webhooks.
+        /// ExternalEvent are requests made to gofer's /api/external/<trigger_name>
+        /// endpoint passed along to the named trigger. Normally webhooks.
         async fn external_event(
             &self,
             request: tonic::Request<super::TriggerExternalEventRequest>,
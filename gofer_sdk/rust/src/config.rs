@@ -1,8 +1,21 @@
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    time::Duration,
+};
 
 use crate::{validate_identifier, ConfigError};
 
+/// The maximum amount of tasks (including common tasks) a single pipeline can contain.
+pub const MAX_TASK_COUNT: usize = 100;
+
+/// The maximum amount of CPU (in millicores) a single task can request or be limited to.
+pub const MAX_TASK_CPU: u64 = 1_000_000;
+
+/// The maximum amount of memory (in megabytes) a single task can request or be limited to.
+pub const MAX_TASK_MEMORY: u64 = 16 * 1024 * 1024;
+
 #[must_use = "complete pipeline config with the .finish() method"]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -16,6 +29,10 @@ pub struct Pipeline {
     /// Controls how many runs can be active at any single time.
     /// 0 defaults to whatever the global Gofer setting is.
     pub parallelism: u64,
+    /// Maximum time (in seconds) a run of the pipeline is allowed to take before it is stopped.
+    /// This can only shorten Gofer's global run timeout, never extend it. 0 means only the global
+    /// run timeout applies.
+    pub run_timeout: u64,
     /// A mapping of pipeline owned tasks.
     pub tasks: Vec<Task>,
     /// A mapping of pipeline owned triggers to their settings.
@@ -37,6 +54,7 @@ impl From<gofer_proto::PipelineConfig> for Pipeline {
                 }
             },
             parallelism: p.parallelism,
+            run_timeout: p.run_timeout,
             tasks: p.tasks.into_iter().map(|value| value.into()).collect(),
             triggers: p.triggers.into_iter().map(|value| value.into()).collect(),
             common_tasks: p
@@ -55,6 +73,7 @@ impl From<Pipeline> for gofer_proto::PipelineConfig {
             name: p.name,
             description: p.description.unwrap_or_default(),
             parallelism: p.parallelism,
+            run_timeout: p.run_timeout,
             tasks: p.tasks.into_iter().map(|value| value.into()).collect(),
             triggers: p.triggers.into_iter().map(|value| value.into()).collect(),
             common_tasks: p
@@ -73,6 +92,7 @@ impl Pipeline {
             name: name.to_string(),
             description: None,
             parallelism: 0,
+            run_timeout: 0,
             tasks: Vec::new(),
             triggers: Vec::new(),
             common_tasks: Vec::new(),
@@ -94,6 +114,71 @@ impl Pipeline {
             common_task.validate()?;
         }
 
+        self.validate_dag()
+    }
+
+    /// Makes sure the tasks of a pipeline form a graph that Gofer can actually run: every task id is unique,
+    /// every dependency points at a task that exists, and there are no dependency cycles. Common tasks are
+    /// referenced by their label.
+    fn validate_dag(&self) -> Result<(), ConfigError> {
+        let count = self.tasks.len() + self.common_tasks.len();
+
+        if count == 0 {
+            return Err(ConfigError::NoTasks);
+        }
+
+        if count > MAX_TASK_COUNT {
+            return Err(ConfigError::TooManyTasks {
+                count,
+                max: MAX_TASK_COUNT,
+            });
+        }
+
+        let mut ids = HashSet::new();
+        let ids_in_order = self.tasks.iter().map(|task| &task.id).chain(
+            self.common_tasks
+                .iter()
+                .map(|common_task| &common_task.label),
+        );
+
+        for id in ids_in_order {
+            if !ids.insert(id.as_str()) {
+                return Err(ConfigError::DuplicateTask(id.clone()));
+            }
+        }
+
+        for task in &self.tasks {
+            let mut parents: Vec<&String> = task.depends_on.keys().collect();
+            parents.sort();
+
+            for parent in parents {
+                if !ids.contains(parent.as_str()) {
+                    return Err(ConfigError::UnknownParent {
+                        task: task.id.clone(),
+                        parent: parent.clone(),
+                    });
+                }
+            }
+        }
+
+        let dependencies: HashMap<&str, Vec<&str>> = self
+            .tasks
+            .iter()
+            .map(|task| {
+                let mut parents: Vec<&str> = task.depends_on.keys().map(|p| p.as_str()).collect();
+                parents.sort_unstable();
+                (task.id.as_str(), parents)
+            })
+            .collect();
+
+        let mut finished = HashSet::new();
+        for task in &self.tasks {
+            let mut path = vec![];
+            if let Some(cycle) = find_cycle(&task.id, &dependencies, &mut path, &mut finished) {
+                return Err(ConfigError::DependencyCycle(cycle));
+            }
+        }
+
         Ok(())
     }
 
@@ -107,6 +192,11 @@ impl Pipeline {
         self
     }
 
+    pub fn run_timeout(mut self, run_timeout: Duration) -> Self {
+        self.run_timeout = run_timeout.as_secs();
+        self
+    }
+
     pub fn tasks(mut self, tasks: Vec<Task>) -> Self {
         self.tasks = tasks;
         self
@@ -132,6 +222,42 @@ impl Pipeline {
     }
 }
 
+/// Depth first search through a task's dependencies. Returns the tasks making up the cycle (starting and
+/// ending with the same task) if one is reachable from the given task. Tasks that have already been fully
+/// explored are kept in `finished` so that they're only ever searched once.
+fn find_cycle<'a>(
+    id: &'a str,
+    dependencies: &HashMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    finished: &mut HashSet<&'a str>,
+) -> Option<Vec<String>> {
+    if finished.contains(id) {
+        return None;
+    }
+
+    if let Some(position) = path.iter().position(|task| *task == id) {
+        let mut cycle: Vec<String> = path[position..]
+            .iter()
+            .map(|task| task.to_string())
+            .collect();
+        cycle.push(id.to_string());
+        return Some(cycle);
+    }
+
+    path.push(id);
+
+    for parent in dependencies.get(id).into_iter().flatten() {
+        if let Some(cycle) = find_cycle(parent, dependencies, path, finished) {
+            return Some(cycle);
+        }
+    }
+
+    path.pop();
+    finished.insert(id);
+
+    None
+}
+
 /// Every time a pipeline attempts to subscribe to a trigger, it passes certain
 /// values back to that trigger for certain functionality. Since triggers keep no
 /// permanent state, these settings are kept here so that when triggers are restarted
@@ -310,6 +436,106 @@ impl From<RegistryAuth> for gofer_proto::RegistryAuth {
     }
 }
 
+/// Controls how the wait between retries of a task grows.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum Backoff {
+    /// Wait the same amount of time before every retry.
+    Constant,
+    /// Double the wait after every retry.
+    Exponential,
+}
+
+impl From<gofer_proto::retry_policy::Backoff> for Backoff {
+    fn from(b: gofer_proto::retry_policy::Backoff) -> Self {
+        match b {
+            gofer_proto::retry_policy::Backoff::Unknown
+            | gofer_proto::retry_policy::Backoff::Constant => Backoff::Constant,
+            gofer_proto::retry_policy::Backoff::Exponential => Backoff::Exponential,
+        }
+    }
+}
+
+impl From<Backoff> for gofer_proto::retry_policy::Backoff {
+    fn from(b: Backoff) -> Self {
+        match b {
+            Backoff::Constant => gofer_proto::retry_policy::Backoff::Constant,
+            Backoff::Exponential => gofer_proto::retry_policy::Backoff::Exponential,
+        }
+    }
+}
+
+/// Controls if and how a task is retried when it fails.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub struct RetryPolicy {
+    /// Amount of times a failed task is retried. 0 means the task is never retried.
+    pub retries: u64,
+    pub backoff: Backoff,
+    /// Time (in seconds) to wait before the first retry.
+    pub delay: u64,
+    /// Longest time (in seconds) to wait between retries. 0 means there is no maximum.
+    pub max_delay: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Backoff::Constant,
+            delay: 0,
+            max_delay: 0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retry a failed task up to `retries` times, waiting `delay` before every retry.
+    pub fn constant(retries: u64, delay: Duration) -> Self {
+        Self {
+            retries,
+            backoff: Backoff::Constant,
+            delay: delay.as_secs(),
+            max_delay: 0,
+        }
+    }
+
+    /// Retry a failed task up to `retries` times, waiting `initial_delay` before the first retry and doubling
+    /// the wait for every retry after that up to `max_delay`.
+    pub fn exponential(retries: u64, initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            retries,
+            backoff: Backoff::Exponential,
+            delay: initial_delay.as_secs(),
+            max_delay: max_delay.as_secs(),
+        }
+    }
+}
+
+impl From<gofer_proto::RetryPolicy> for RetryPolicy {
+    fn from(p: gofer_proto::RetryPolicy) -> Self {
+        RetryPolicy {
+            retries: p.retries,
+            backoff: gofer_proto::retry_policy::Backoff::from_i32(p.backoff)
+                .unwrap_or(gofer_proto::retry_policy::Backoff::Unknown)
+                .into(),
+            delay: p.delay,
+            max_delay: p.max_delay,
+        }
+    }
+}
+
+impl From<RetryPolicy> for gofer_proto::RetryPolicy {
+    fn from(p: RetryPolicy) -> Self {
+        gofer_proto::RetryPolicy {
+            retries: p.retries,
+            backoff: gofer_proto::retry_policy::Backoff::from(p.backoff) as i32,
+            delay: p.delay,
+            max_delay: p.max_delay,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct Task {
@@ -321,6 +547,19 @@ pub struct Task {
     pub variables: HashMap<String, String>,
     pub entrypoint: Vec<String>,
     pub command: Vec<String>,
+    /// Maximum time (in seconds) the task is allowed to run before it is stopped.
+    /// 0 means the task is only limited by Gofer's global run timeout.
+    pub timeout: u64,
+    /// CPU (in millicores) the task expects to use. 0 leaves it up to the server default.
+    pub cpu_request: u64,
+    /// Maximum CPU (in millicores) the task may use. 0 leaves it up to the server default.
+    pub cpu_limit: u64,
+    /// Memory (in megabytes) the task expects to use. 0 leaves it up to the server default.
+    pub memory_request: u64,
+    /// Maximum memory (in megabytes) the task may use. 0 leaves it up to the server default.
+    pub memory_limit: u64,
+    /// How the task is retried when it fails. By default tasks are not retried.
+    pub retry_policy: RetryPolicy,
 }
 
 impl Task {
@@ -334,11 +573,70 @@ impl Task {
             variables: HashMap::new(),
             entrypoint: Vec::new(),
             command: Vec::new(),
+            timeout: 0,
+            cpu_request: 0,
+            cpu_limit: 0,
+            memory_request: 0,
+            memory_limit: 0,
+            retry_policy: RetryPolicy::default(),
         }
     }
 
     pub fn validate(&self) -> Result<(), ConfigError> {
         validate_identifier("id", &self.id)?;
+
+        for (argument, value, max) in [
+            ("cpu_request", self.cpu_request, MAX_TASK_CPU),
+            ("cpu_limit", self.cpu_limit, MAX_TASK_CPU),
+            ("memory_request", self.memory_request, MAX_TASK_MEMORY),
+            ("memory_limit", self.memory_limit, MAX_TASK_MEMORY),
+        ] {
+            if value > max {
+                return Err(ConfigError::InvalidArgument {
+                    argument: argument.to_string(),
+                    value: value.to_string(),
+                    description: format!(
+                        "task '{}' {} cannot be larger than {}",
+                        self.id, argument, max
+                    ),
+                });
+            }
+        }
+
+        if self.cpu_limit != 0 && self.cpu_request > self.cpu_limit {
+            return Err(ConfigError::InvalidArgument {
+                argument: "cpu_request".to_string(),
+                value: self.cpu_request.to_string(),
+                description: format!(
+                    "task '{}' cpu request cannot be larger than its cpu limit",
+                    self.id
+                ),
+            });
+        }
+
+        if self.memory_limit != 0 && self.memory_request > self.memory_limit {
+            return Err(ConfigError::InvalidArgument {
+                argument: "memory_request".to_string(),
+                value: self.memory_request.to_string(),
+                description: format!(
+                    "task '{}' memory request cannot be larger than its memory limit",
+                    self.id
+                ),
+            });
+        }
+
+        if self.retry_policy.max_delay != 0 && self.retry_policy.delay > self.retry_policy.max_delay
+        {
+            return Err(ConfigError::InvalidArgument {
+                argument: "retry_policy".to_string(),
+                value: self.retry_policy.delay.to_string(),
+                description: format!(
+                    "task '{}' retry delay cannot be larger than its maximum retry delay",
+                    self.id
+                ),
+            });
+        }
+
         Ok(())
     }
 
@@ -388,6 +686,31 @@ impl Task {
         self.command = command.into_iter().map(|s| s.to_string()).collect();
         self
     }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout.as_secs();
+        self
+    }
+
+    /// Sets the CPU request and limit for the task in millicores (1000 millicores equals one CPU core).
+    pub fn cpu(mut self, request: u64, limit: u64) -> Self {
+        self.cpu_request = request;
+        self.cpu_limit = limit;
+        self
+    }
+
+    /// Sets the memory request and limit for the task in megabytes.
+    pub fn memory(mut self, request: u64, limit: u64) -> Self {
+        self.memory_request = request;
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Sets how the task is retried when it fails.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
 }
 
 impl From<gofer_proto::TaskConfig> for Task {
@@ -414,6 +737,12 @@ impl From<gofer_proto::TaskConfig> for Task {
             variables: p.variables,
             entrypoint: p.entrypoint,
             command: p.command,
+            timeout: p.timeout,
+            cpu_request: p.cpu_request,
+            cpu_limit: p.cpu_limit,
+            memory_request: p.memory_request,
+            memory_limit: p.memory_limit,
+            retry_policy: p.retry_policy.map(RetryPolicy::from).unwrap_or_default(),
         }
     }
 }
@@ -438,6 +767,12 @@ impl From<Task> for gofer_proto::TaskConfig {
             variables: p.variables,
             entrypoint: p.entrypoint,
             command: p.command,
+            timeout: p.timeout,
+            cpu_request: p.cpu_request,
+            cpu_limit: p.cpu_limit,
+            memory_request: p.memory_request,
+            memory_limit: p.memory_limit,
+            retry_policy: Some(p.retry_policy.into()),
         }
     }
 }
@@ -450,8 +785,123 @@ mod tests {
     fn test_pipeline() {
         Pipeline::new("simple_pipeline", "Simple Pipeline")
             .description("Test Description")
-            .tasks(vec![])
+            .tasks(vec![Task::new("simple_task", "ubuntu:latest")])
             .finish()
             .expect("config failed");
     }
+
+    #[test]
+    fn test_invalid_identifier() {
+        let err = Pipeline::new("simple-pipeline", "Simple Pipeline")
+            .tasks(vec![Task::new("simple_task", "ubuntu:latest")])
+            .validate()
+            .unwrap_err();
+
+        assert!(matches!(err, ConfigError::InvalidArgument { .. }));
+    }
+
+    #[test]
+    fn test_resources_too_large() {
+        let err = Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(vec![
+                Task::new("simple_task", "ubuntu:latest").memory(0, MAX_TASK_MEMORY + 1)
+            ])
+            .validate()
+            .unwrap_err();
+
+        assert!(matches!(err, ConfigError::InvalidArgument { argument, .. }
+            if argument == "memory_limit"));
+
+        Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(vec![Task::new("simple_task", "ubuntu:latest")
+                .cpu(MAX_TASK_CPU, MAX_TASK_CPU)
+                .memory(MAX_TASK_MEMORY, MAX_TASK_MEMORY)])
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_no_tasks() {
+        let err = Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .validate()
+            .unwrap_err();
+
+        assert!(matches!(err, ConfigError::NoTasks));
+    }
+
+    #[test]
+    fn test_too_many_tasks() {
+        let tasks = (0..=MAX_TASK_COUNT)
+            .map(|i| Task::new(&format!("task_{}", i), "ubuntu:latest"))
+            .collect();
+
+        let err = Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(tasks)
+            .validate()
+            .unwrap_err();
+
+        assert!(
+            matches!(err, ConfigError::TooManyTasks { count, .. } if count == MAX_TASK_COUNT + 1)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_tasks() {
+        let err = Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(vec![
+                Task::new("simple_task", "ubuntu:latest"),
+                Task::new("simple_task", "ubuntu:latest"),
+            ])
+            .validate()
+            .unwrap_err();
+
+        assert!(matches!(err, ConfigError::DuplicateTask(id) if id == "simple_task"));
+    }
+
+    #[test]
+    fn test_unknown_parent() {
+        let err = Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(vec![Task::new("simple_task", "ubuntu:latest")
+                .depends_on_one("missing_task", RequiredParentStatus::Success)])
+            .validate()
+            .unwrap_err();
+
+        assert!(matches!(err, ConfigError::UnknownParent { task, parent }
+            if task == "simple_task" && parent == "missing_task"));
+    }
+
+    #[test]
+    fn test_dependency_cycle() {
+        let err = Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(vec![
+                Task::new("task_one", "ubuntu:latest")
+                    .depends_on_one("task_three", RequiredParentStatus::Any),
+                Task::new("task_two", "ubuntu:latest")
+                    .depends_on_one("task_one", RequiredParentStatus::Any),
+                Task::new("task_three", "ubuntu:latest")
+                    .depends_on_one("task_two", RequiredParentStatus::Any),
+                Task::new("task_four", "ubuntu:latest")
+                    .depends_on_one("task_one", RequiredParentStatus::Any),
+            ])
+            .validate()
+            .unwrap_err();
+
+        assert!(matches!(err, ConfigError::DependencyCycle(cycle)
+            if cycle == vec!["task_one", "task_three", "task_two", "task_one"]));
+    }
+
+    #[test]
+    fn test_valid_dag() {
+        Pipeline::new("simple_pipeline", "Simple Pipeline")
+            .tasks(vec![
+                Task::new("task_one", "ubuntu:latest"),
+                Task::new("task_two", "ubuntu:latest")
+                    .depends_on_one("task_one", RequiredParentStatus::Success),
+                Task::new("task_three", "ubuntu:latest")
+                    .depends_on_one("task_one", RequiredParentStatus::Any)
+                    .depends_on_one("task_two", RequiredParentStatus::Failure),
+            ])
+            .validate()
+            .unwrap();
+    }
 }
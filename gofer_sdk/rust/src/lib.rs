@@ -13,6 +13,16 @@ pub enum ConfigError {
     },
     #[error("could not parse config; {0}")]
     Parsing(String),
+    #[error("pipeline must contain at least one task")]
+    NoTasks,
+    #[error("pipeline contains {count} tasks; pipelines are limited to {max} tasks")]
+    TooManyTasks { count: usize, max: usize },
+    #[error("task id '{0}' is used more than once; task ids must be unique")]
+    DuplicateTask(String),
+    #[error("task '{task}' depends on '{parent}' which is not a task in this pipeline")]
+    UnknownParent { task: String, parent: String },
+    #[error("task dependencies contain a cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
 }
 
 /// Identifiers are used as the primary key in most of gofer's resources.
@@ -39,7 +49,7 @@ fn validate_identifier(arg: &str, value: &str) -> Result<(), ConfigError> {
         });
     }
 
-    if !alphanumeric_w_underscores.is_match(arg) {
+    if !alphanumeric_w_underscores.is_match(value) {
         return Err(ConfigError::InvalidArgument {
             argument: arg.to_string(),
             value: value.to_string(),
@@ -37,7 +37,7 @@ trait Trigger {
     /// triggers should lean toward quick cleanups and shutdowns.
     fn shutdown(req: TriggerShutdownRequest) -> Result<TriggerShutdownResponse, TriggerError>;
 
-    /// Json blobs of Gofer's external /events endpoint. Normally webhooks.
+    /// Requests sent to Gofer's external /api/external/<trigger_name> endpoint. Normally webhooks.
     fn external_event(
         req: TriggerExternalEventRequest,
     ) -> Result<TriggerExternalEventResponse, TriggerError>;